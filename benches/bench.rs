@@ -310,7 +310,7 @@ fn attributes(c: &mut Criterion) {
             let mut buf = Vec::new();
             loop {
                 match r.read_event(&mut buf) {
-                    Ok(Event::Empty(e)) if e.name() == b"player" => {
+                    Ok(Event::Empty(e)) if e.name().as_ref() == b"player" => {
                         for name in ["num", "status", "avg"] {
                             if let Some(_attr) = e.try_get_attribute(name).unwrap() {
                                 count += 1
@@ -332,12 +332,93 @@ fn attributes(c: &mut Criterion) {
     group.finish();
 }
 
+/// Generates a document with `count` records which have long attribute values
+/// and are indented with long runs of whitespaces
+fn records(count: usize) -> String {
+    let mut xml = String::from("<records>\n");
+    let value = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod";
+    for i in 0..count {
+        xml.push_str(&format!(
+            "{indent}<record id=\"{i}\" title='{value}' note=\"a > b, 'quoted'\"\n\
+             {indent}        description=\"{value} {value}\"/>\n",
+            indent = "                ",
+            i = i,
+            value = value,
+        ));
+    }
+    xml.push_str("</records>\n");
+    xml
+}
+
+/// Benchmarks scanning of markup with long attribute values and whitespaces
+fn scanning(c: &mut Criterion) {
+    let xml = records(1000);
+    let mut group = c.benchmark_group("scanning");
+    group.throughput(criterion::Throughput::Bytes(xml.len() as u64));
+    group.bench_function("read_event", |b| {
+        b.iter(|| {
+            let mut r = Reader::from_reader(xml.as_bytes());
+            r.check_end_names(false).trim_text(true);
+            let mut count = criterion::black_box(0);
+            let mut buf = Vec::new();
+            loop {
+                match r.read_event(&mut buf) {
+                    Ok(Event::Empty(_)) => count += 1,
+                    Ok(Event::Eof) => break,
+                    _ => (),
+                }
+                buf.clear();
+            }
+            assert_eq!(count, 1000);
+        })
+    });
+
+    group.bench_function("read_event_unbuffered", |b| {
+        b.iter(|| {
+            let mut r = Reader::from_str(&xml);
+            r.check_end_names(false).trim_text(true);
+            let mut count = criterion::black_box(0);
+            loop {
+                match r.read_event_unbuffered() {
+                    Ok(Event::Empty(_)) => count += 1,
+                    Ok(Event::Eof) => break,
+                    _ => (),
+                }
+            }
+            assert_eq!(count, 1000);
+        })
+    });
+
+    group.bench_function("attributes", |b| {
+        b.iter(|| {
+            let mut r = Reader::from_str(&xml);
+            r.check_end_names(false).trim_text(true);
+            let mut count = criterion::black_box(0);
+            loop {
+                match r.read_event_unbuffered() {
+                    Ok(Event::Empty(e)) => {
+                        for attr in e.attributes() {
+                            let _attr = attr.unwrap();
+                            count += 1
+                        }
+                    }
+                    Ok(Event::Eof) => break,
+                    _ => (),
+                }
+            }
+            assert_eq!(count, 4000);
+        })
+    });
+    group.finish();
+}
+
 criterion_group!(
     benches,
     read_event,
     bytes_text_unescaped,
     read_namespaced_event,
     one_event,
-    attributes
+    attributes,
+    scanning
 );
 criterion_main!(benches);
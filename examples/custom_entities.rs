@@ -4,12 +4,11 @@
 //! NB: this example is deliberately kept simple:
 //! * it assumes that the XML file is UTF-8 encoded (custom_entities must only contain UTF-8 data)
 //! * it only handles internal entities;
-//! * the regex in this example is simple but brittle;
 //! * it does not support the use of entities in entity declaration.
 
+use quick_xml::dtd::{Declaration, EntityDef};
 use quick_xml::events::Event;
 use quick_xml::Reader;
-use regex::bytes::Regex;
 use std::collections::HashMap;
 
 const DATA: &str = r#"
@@ -28,13 +27,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut buf = Vec::new();
     let mut custom_entities = HashMap::new();
-    let entity_re = Regex::new(r#"<!ENTITY\s+([^ \t\r\n]+)\s+"([^"]*)"\s*>"#)?;
 
     loop {
         match reader.read_event(&mut buf) {
             Ok(Event::DocType(ref e)) => {
-                for cap in entity_re.captures_iter(&e) {
-                    custom_entities.insert(cap[1].to_vec(), cap[2].to_vec());
+                for decl in e.parse_doctype()?.declarations {
+                    if let Declaration::Entity(entity) = decl {
+                        if let EntityDef::Internal(value) = entity.def {
+                            if !entity.parameter {
+                                custom_entities.insert(entity.name.to_vec(), value.to_vec());
+                            }
+                        }
+                    }
                 }
             }
             Ok(Event::Start(ref e)) => match e.name().as_ref() {
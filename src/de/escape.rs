@@ -1,10 +1,11 @@
 //! Serde `Deserializer` module
 
 use crate::de::deserialize_bool;
-use crate::{errors::serialize::DeError, errors::Error, escape::unescape, reader::Decoder};
+use crate::{errors::serialize::DeError, errors::Error, escape::do_unescape, reader::Decoder};
 use serde::de::{DeserializeSeed, EnumAccess, VariantAccess, Visitor};
 use serde::{self, forward_to_deserialize_any, serde_if_integer128};
 use std::borrow::Cow;
+use std::collections::HashMap;
 
 /// A deserializer for a xml escaped and encoded value
 ///
@@ -20,6 +21,8 @@ pub struct EscapedDeserializer<'a> {
     escaped_value: Cow<'a, [u8]>,
     /// If `true`, value requires unescaping before using
     escaped: bool,
+    /// Entities declared in the DTD of the document, used for unescaping
+    entities: Option<&'a HashMap<Vec<u8>, Vec<u8>>>,
 }
 
 impl<'a> EscapedDeserializer<'a> {
@@ -28,11 +31,18 @@ impl<'a> EscapedDeserializer<'a> {
             decoder,
             escaped_value,
             escaped,
+            entities: None,
         }
     }
+    /// Sets entities declared in the DTD of the document, used for unescaping
+    pub fn with_entities(mut self, entities: &'a HashMap<Vec<u8>, Vec<u8>>) -> Self {
+        self.entities = Some(entities);
+        self
+    }
     fn unescaped(&self) -> Result<Cow<[u8]>, DeError> {
         if self.escaped {
-            unescape(&self.escaped_value).map_err(|e| DeError::InvalidXml(Error::EscapeError(e)))
+            do_unescape(&self.escaped_value, self.entities)
+                .map_err(|e| DeError::InvalidXml(Error::EscapeError(e)))
         } else {
             Ok(Cow::Borrowed(&self.escaped_value))
         }
@@ -309,11 +309,10 @@ where
                 let slice = self.start.attributes_raw();
                 let decoder = self.de.reader.decoder();
 
-                seed.deserialize(EscapedDeserializer::new(
-                    Cow::Borrowed(&slice[value]),
-                    decoder,
-                    true,
-                ))
+                seed.deserialize(
+                    EscapedDeserializer::new(Cow::Borrowed(&slice[value]), decoder, true)
+                        .with_entities(self.de.reader.entities()),
+                )
             }
             // This arm processes the following XML shape:
             // <any-tag>
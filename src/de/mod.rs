@@ -227,6 +227,7 @@ use crate::{
 };
 use serde::de::{self, Deserialize, DeserializeOwned, Visitor};
 use std::borrow::Cow;
+use std::collections::HashMap;
 #[cfg(feature = "overlapped-lists")]
 use std::collections::VecDeque;
 use std::io::BufRead;
@@ -624,14 +625,16 @@ where
         allow_start: bool,
     ) -> Result<BytesCData<'de>, DeError> {
         match self.next()? {
-            DeEvent::Text(e) if unescape => e.unescape().map_err(|e| DeError::InvalidXml(e.into())),
+            DeEvent::Text(e) if unescape => e
+                .unescape(self.reader.entities())
+                .map_err(|e| DeError::InvalidXml(e.into())),
             DeEvent::Text(e) => Ok(BytesCData::new(e.into_inner())),
             DeEvent::CData(e) => Ok(e),
             DeEvent::Start(e) if allow_start => {
                 // allow one nested level
                 let inner = self.next()?;
                 let t = match inner {
-                    DeEvent::Text(t) if unescape => t.unescape()?,
+                    DeEvent::Text(t) if unescape => t.unescape(self.reader.entities())?,
                     DeEvent::Text(t) => BytesCData::new(t.into_inner()),
                     DeEvent::CData(t) => t,
                     DeEvent::Start(s) => {
@@ -712,7 +715,9 @@ impl<'de> Deserializer<'de, SliceReader<'de>> {
         reader
             .expand_empty_elements(true)
             .check_end_names(true)
-            .trim_text(true);
+            .trim_text(true)
+            .coalesce_text(true)
+            .skip_comments_in_text(true);
         Self::new(SliceReader { reader })
     }
 }
@@ -730,7 +735,9 @@ where
         reader
             .expand_empty_elements(true)
             .check_end_names(true)
-            .trim_text(true);
+            .trim_text(true)
+            .coalesce_text(true)
+            .skip_comments_in_text(true);
 
         Self::new(IoReader {
             reader,
@@ -936,6 +943,9 @@ pub trait XmlRead<'i> {
 
     /// A copy of the reader's decoder used to decode strings.
     fn decoder(&self) -> Decoder;
+
+    /// Internal entities declared in the DTD of the document.
+    fn entities(&self) -> &HashMap<Vec<u8>, Vec<u8>>;
 }
 
 /// XML input source that reads from a std::io input stream.
@@ -969,7 +979,7 @@ impl<'i, R: BufRead> XmlRead<'i> for IoReader<R> {
 
     fn read_to_end(&mut self, name: QName) -> Result<(), DeError> {
         match self.reader.read_to_end(name, &mut self.buf) {
-            Err(Error::UnexpectedEof(_)) => Err(DeError::UnexpectedEof),
+            Err(Error::UnexpectedEof(..)) => Err(DeError::UnexpectedEof),
             other => Ok(other?),
         }
     }
@@ -977,6 +987,10 @@ impl<'i, R: BufRead> XmlRead<'i> for IoReader<R> {
     fn decoder(&self) -> Decoder {
         self.reader.decoder()
     }
+
+    fn entities(&self) -> &HashMap<Vec<u8>, Vec<u8>> {
+        self.reader.entities()
+    }
 }
 
 /// XML input source that reads from a slice of bytes and can borrow from it.
@@ -1005,7 +1019,7 @@ impl<'de> XmlRead<'de> for SliceReader<'de> {
 
     fn read_to_end(&mut self, name: QName) -> Result<(), DeError> {
         match self.reader.read_to_end_unbuffered(name) {
-            Err(Error::UnexpectedEof(_)) => Err(DeError::UnexpectedEof),
+            Err(Error::UnexpectedEof(..)) => Err(DeError::UnexpectedEof),
             other => Ok(other?),
         }
     }
@@ -1013,6 +1027,10 @@ impl<'de> XmlRead<'de> for SliceReader<'de> {
     fn decoder(&self) -> Decoder {
         self.reader.decoder()
     }
+
+    fn entities(&self) -> &HashMap<Vec<u8>, Vec<u8>> {
+        self.reader.entities()
+    }
 }
 
 #[cfg(test)]
@@ -1448,9 +1466,14 @@ mod tests {
     #[test]
     fn next_text() {
         match from_str::<String>(r#"</root>"#) {
-            Err(DeError::InvalidXml(Error::EndEventMismatch { expected, found })) => {
+            Err(DeError::InvalidXml(Error::EndEventMismatch {
+                expected,
+                found,
+                span,
+            })) => {
                 assert_eq!(expected, "");
                 assert_eq!(found, "root");
+                assert_eq!(span, 0..7);
             }
             x => panic!(
                 r#"Expected `Err(InvalidXml(EndEventMismatch("", "root")))`, but found {:?}"#,
@@ -1462,9 +1485,14 @@ mod tests {
         assert_eq!(s, "");
 
         match from_str::<String>(r#"<root></other>"#) {
-            Err(DeError::InvalidXml(Error::EndEventMismatch { expected, found })) => {
+            Err(DeError::InvalidXml(Error::EndEventMismatch {
+                expected,
+                found,
+                span,
+            })) => {
                 assert_eq!(expected, "root");
                 assert_eq!(found, "other");
+                assert_eq!(span, 6..14);
             }
             x => panic!(
                 r#"Expected `Err(InvalidXml(EndEventMismatch("root", "other")))`, but found {:?}"#,
@@ -0,0 +1,1067 @@
+//! A lightweight tree representation of XML documents.
+//!
+//! A [`Document`] is built from events of the [`NsReader`], so element and
+//! attribute namespaces are resolved while the document is read. The tree can
+//! be navigated and modified and written back with the [`Writer`].
+//!
+//! All names, texts and attribute values are decoded and unescaped into owned
+//! strings, and they are escaped again when the tree is written. The tree
+//! keeps all nodes inside the root element, including whitespace-only texts,
+//! so the content of a document, that was read with the default configuration
+//! of the reader, is written back without changes.
+//!
+//! # Examples
+//!
+//! ```
+//! # use pretty_assertions::assert_eq;
+//! use quick_xml::dom::{Document, Element};
+//!
+//! let xml = r#"<config xmlns:db="urn:db"><db:server port="5432">localhost</db:server></config>"#;
+//! let mut document: Document = xml.parse().unwrap();
+//!
+//! let server = document.root().child_ns("server", Some("urn:db")).unwrap();
+//! assert_eq!(server.text(), "localhost");
+//! assert_eq!(server.attribute("port"), Some("5432"));
+//!
+//! let root = document.root_mut();
+//! root.push(Element::new("debug").with_attribute("enabled", "true"));
+//!
+//! assert_eq!(
+//!     document.to_string(),
+//!     r#"<config xmlns:db="urn:db"><db:server port="5432">localhost</db:server><debug enabled="true"/></config>"#
+//! );
+//! ```
+//!
+//! [`NsReader`]: crate::NsReader
+//! [`Writer`]: crate::Writer
+
+use std::fmt::{self, Display, Formatter};
+use std::io::{BufRead, Write};
+use std::slice::Iter;
+use std::str::FromStr;
+
+use crate::errors::{Error, Result};
+use crate::events::{BytesCData, BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use crate::name::ResolveResult;
+use crate::reader::{NsReader, Reader};
+use crate::writer::Writer;
+
+/// The namespace of the reserved `xml` prefix, for example, of `xml:lang` attributes
+pub const XML: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// The namespace of namespace declarations: `xmlns` and `xmlns:prefix` attributes
+pub const XMLNS: &str = "http://www.w3.org/2000/xmlns/";
+
+/// Decodes bytes, that are not escaped, like names and the content of comments
+fn decode<R>(reader: &Reader<R>, bytes: &[u8]) -> Result<String> {
+    #[cfg(feature = "encoding")]
+    let decoded = reader.decode(bytes);
+
+    #[cfg(not(feature = "encoding"))]
+    let decoded = reader.decode(bytes)?;
+
+    Ok(decoded.into())
+}
+
+/// Converts a resolved namespace into an owned one. The `xml` prefix is bound
+/// to the [`XML`] namespace without a declaration
+fn namespace<R>(reader: &Reader<R>, resolved: ResolveResult) -> Result<Option<String>> {
+    match resolved {
+        ResolveResult::Bound(ns) => decode(reader, ns.as_ref()).map(Some),
+        ResolveResult::Unbound => Ok(None),
+        ResolveResult::Unknown(prefix) if prefix == b"xml" => Ok(Some(XML.to_string())),
+        ResolveResult::Unknown(prefix) => Err(Error::UnknownPrefix(prefix)),
+    }
+}
+
+/// Returns the part of the qualified name after the prefix
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Returns the prefix of the qualified name or an empty string, if the name
+/// has no prefix
+fn prefix(name: &str) -> &str {
+    match name.rfind(':') {
+        Some(i) => &name[..i],
+        None => "",
+    }
+}
+
+/// Returns `true` if the attribute name is a namespace declaration
+fn is_declaration(name: &str) -> bool {
+    name == "xmlns" || name.starts_with("xmlns:")
+}
+
+/// Namespace bindings in scope while elements are written: prefixes, empty for
+/// the default namespace, together with their namespaces
+type Scope<'a> = Vec<(&'a str, Option<&'a str>)>;
+
+/// Binds the prefix to the namespace, if it is not bound to it yet, and returns
+/// the declaration, that should be written. Bindings of the written element
+/// start at `start` in the scope
+fn bind<'a>(
+    scope: &mut Scope<'a>,
+    start: usize,
+    prefix: &'a str,
+    namespace: Option<&'a str>,
+) -> Result<Option<(String, &'a str)>> {
+    let unknown = || Error::UnknownPrefix(prefix.as_bytes().to_vec());
+    // Only the default namespace can be unbound
+    if namespace.is_none() && !prefix.is_empty() {
+        return Err(unknown());
+    }
+    let bound = match scope.iter().rev().find(|(p, _)| *p == prefix) {
+        Some((_, ns)) => *ns,
+        None if prefix == "xml" => Some(XML),
+        None => None,
+    };
+    if bound == namespace {
+        return Ok(None);
+    }
+    // A prefix can be bound only once in the same element
+    let declared = scope[start..].iter().any(|(p, _)| *p == prefix);
+    if declared || prefix == "xml" || prefix == "xmlns" {
+        return Err(unknown());
+    }
+    scope.push((prefix, namespace));
+    let name = match prefix {
+        "" => "xmlns".to_string(),
+        prefix => format!("xmlns:{}", prefix),
+    };
+    Ok(Some((name, namespace.unwrap_or(""))))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// An XML document: the root element together with the XML declaration,
+/// the document type declaration, comments and processing instructions
+/// around it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Document {
+    children: Vec<Node>,
+    /// Index of the root element in `children`
+    root: usize,
+}
+
+impl Document {
+    /// Creates a document with the specified root element.
+    pub fn new(root: Element) -> Self {
+        Self {
+            children: vec![Node::Element(root)],
+            root: 0,
+        }
+    }
+
+    /// Reads a document from a reader with the default configuration.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self> {
+        Self::read_from(&mut NsReader::from_reader(reader))
+    }
+
+    /// Reads a document from the configured reader until the end of input.
+    ///
+    /// Texts between the top-level nodes of the document are skipped. If the
+    /// document has several root elements, which is possible when the reader
+    /// is not [strict], the first one becomes the root and the others are
+    /// stored as [`children()`](Self::children) of the document.
+    ///
+    /// [strict]: crate::Reader::strict
+    pub fn read_from<R: BufRead>(reader: &mut NsReader<R>) -> Result<Self> {
+        let mut buf = Vec::new();
+        let mut children = Vec::new();
+        // Elements, which end tags are not read yet
+        let mut opened: Vec<Element> = Vec::new();
+        loop {
+            let node = match reader.read_event(&mut buf)? {
+                Event::Start(e) => {
+                    opened.push(Element::read(reader, &e)?);
+                    None
+                }
+                Event::Empty(e) => Some(Node::Element(Element::read(reader, &e)?)),
+                Event::End(_) => opened.pop().map(Node::Element),
+                Event::Text(e) if e.is_empty() || opened.is_empty() => None,
+                Event::Text(e) => Some(Node::Text(e.unescape_and_decode(reader)?)),
+                Event::CData(e) => Some(Node::CData(decode(reader, &e)?)),
+                Event::Comment(e) => Some(Node::Comment(decode(reader, &e)?)),
+                Event::PI(e) => Some(Node::ProcessingInstruction(decode(reader, &e)?)),
+                Event::DocType(e) => Some(Node::DocType(decode(reader, &e)?)),
+                Event::Decl(e) => Some(Node::Declaration(Declaration::read(reader, &e)?)),
+                Event::Eof => break,
+            };
+            if let Some(node) = node {
+                match opened.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => children.push(node),
+                }
+            }
+            buf.clear();
+        }
+        if !opened.is_empty() {
+            let end = reader.buffer_position();
+            return Err(Error::UnexpectedEof("Element".to_string(), end..end));
+        }
+        match children.iter().position(|n| matches!(n, Node::Element(_))) {
+            Some(root) => Ok(Self { children, root }),
+            None => {
+                let end = reader.buffer_position();
+                Err(Error::MissingRoot(end..end))
+            }
+        }
+    }
+
+    /// Returns the root element of the document.
+    pub fn root(&self) -> &Element {
+        match &self.children[self.root] {
+            Node::Element(e) => e,
+            _ => unreachable!("the root of a document is always an element"),
+        }
+    }
+
+    /// Returns the root element of the document for modification.
+    pub fn root_mut(&mut self) -> &mut Element {
+        match &mut self.children[self.root] {
+            Node::Element(e) => e,
+            _ => unreachable!("the root of a document is always an element"),
+        }
+    }
+
+    /// Returns all top-level nodes of the document, including the root element.
+    pub fn children(&self) -> &[Node] {
+        &self.children
+    }
+
+    /// Returns the XML declaration of the document, if any.
+    pub fn declaration(&self) -> Option<&Declaration> {
+        self.children.iter().find_map(|n| match n {
+            Node::Declaration(d) => Some(d),
+            _ => None,
+        })
+    }
+
+    /// Writes the document into the writer.
+    pub fn write_to<W: Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        for node in &self.children {
+            node.write_to(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Document {
+    type Err = Error;
+
+    /// Reads a document from a string with the default configuration of the reader.
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_reader(s.as_bytes())
+    }
+}
+
+impl Display for Document {
+    /// Writes the document without indentation.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut writer = Writer::new(Vec::new());
+        self.write_to(&mut writer).map_err(|_| fmt::Error)?;
+        f.write_str(&String::from_utf8_lossy(&writer.into_inner()))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A node of the document tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+    /// An element with its attributes and children
+    Element(Element),
+    /// Unescaped text
+    Text(String),
+    /// Content of a CDATA section
+    CData(String),
+    /// Content of a comment
+    Comment(String),
+    /// Content of a processing instruction, including its target
+    ProcessingInstruction(String),
+    /// Content of a document type declaration
+    DocType(String),
+    /// XML declaration
+    Declaration(Declaration),
+}
+
+impl Node {
+    /// Returns the element, if this node is an element.
+    pub fn as_element(&self) -> Option<&Element> {
+        match self {
+            Node::Element(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns the element for modification, if this node is an element.
+    pub fn as_element_mut(&mut self) -> Option<&mut Element> {
+        match self {
+            Node::Element(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns the text, if this node is a text or a CDATA section.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Node::Text(t) | Node::CData(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Writes the node into the writer.
+    pub fn write_to<W: Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        match self {
+            Node::Element(e) => e.write_to(writer),
+            Node::Text(t) => writer.write_event(Event::Text(BytesText::from_plain_str(t))),
+            Node::CData(t) => writer.write_event(Event::CData(BytesCData::from_str(t))),
+            Node::Comment(t) => writer.write_event(Event::Comment(BytesText::from_escaped_str(t))),
+            Node::ProcessingInstruction(t) => {
+                writer.write_event(Event::PI(BytesText::from_escaped_str(t)))
+            }
+            Node::DocType(t) => writer.write_event(Event::DocType(BytesText::from_escaped_str(t))),
+            Node::Declaration(d) => d.write_to(writer),
+        }
+    }
+}
+
+impl From<Element> for Node {
+    fn from(element: Element) -> Self {
+        Node::Element(element)
+    }
+}
+
+impl From<String> for Node {
+    fn from(text: String) -> Self {
+        Node::Text(text)
+    }
+}
+
+impl<'a> From<&'a str> for Node {
+    fn from(text: &'a str) -> Self {
+        Node::Text(text.to_string())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// An XML declaration: `<?xml version="1.0" encoding="UTF-8" standalone="yes"?>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Declaration {
+    /// Version of XML, usually `1.0`
+    pub version: String,
+    /// Encoding of the document
+    pub encoding: Option<String>,
+    /// Whether the document is standalone, `yes` or `no`
+    pub standalone: Option<String>,
+}
+
+impl Declaration {
+    fn read<R>(reader: &Reader<R>, decl: &BytesDecl) -> Result<Self> {
+        Ok(Self {
+            version: decode(reader, &decl.version()?)?,
+            encoding: match decl.encoding() {
+                Some(encoding) => Some(decode(reader, &encoding?)?),
+                None => None,
+            },
+            standalone: match decl.standalone() {
+                Some(standalone) => Some(decode(reader, &standalone?)?),
+                None => None,
+            },
+        })
+    }
+
+    fn write_to<W: Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        writer.write_event(Event::Decl(BytesDecl::new(
+            self.version.as_bytes(),
+            self.encoding.as_ref().map(|e| e.as_bytes()),
+            self.standalone.as_ref().map(|s| s.as_bytes()),
+        )))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// An attribute of an element.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attribute {
+    /// Qualified name of the attribute, as it is written in the document
+    pub name: String,
+    /// Namespace of the attribute. Attributes without a prefix have no namespace
+    pub namespace: Option<String>,
+    /// Unescaped value of the attribute
+    pub value: String,
+}
+
+impl Attribute {
+    /// Returns the name of the attribute without a prefix.
+    pub fn local_name(&self) -> &str {
+        local_name(&self.name)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// An element of the document tree.
+///
+/// Namespace declarations are stored as ordinary attributes and are written
+/// as is. Namespaces of elements and attributes are resolved when a document
+/// is read and are not changed when the tree is modified, so they should be
+/// set explicitly for new elements and attributes. Namespaces, that are not
+/// declared by the stored attributes, are declared when the element is
+/// written, see [`Self::write_to()`].
+///
+/// Elements are written, cloned, compared and dropped without recursion, so
+/// a tree of any depth can be processed. Only the [`Debug`] output is formatted
+/// recursively. Use [`ReaderLimits::max_depth`] to restrict the depth of trees
+/// read from untrusted documents.
+///
+/// [`ReaderLimits::max_depth`]: crate::ReaderLimits::max_depth
+#[derive(Debug, Eq)]
+pub struct Element {
+    name: String,
+    namespace: Option<String>,
+    attributes: Vec<Attribute>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    /// Creates an element with the specified qualified name and without
+    /// a namespace, attributes and children.
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        Self {
+            name: name.into(),
+            namespace: None,
+            attributes: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn read<R>(reader: &NsReader<R>, start: &BytesStart) -> Result<Self> {
+        let mut attributes = Vec::new();
+        for attr in start.attributes() {
+            let attr = attr?;
+            let namespace = match attr.key.as_namespace_binding() {
+                Some(_) => Some(XMLNS.to_string()),
+                None => self::namespace(reader, reader.resolve_attribute(attr.key).0)?,
+            };
+            attributes.push(Attribute {
+                name: decode(reader, attr.key.as_ref())?,
+                namespace,
+                value: attr.unescape_and_decode_value(reader)?,
+            });
+        }
+        Ok(Self {
+            name: decode(reader, start.name().as_ref())?,
+            namespace: self::namespace(reader, reader.resolve_element(start.name()).0)?,
+            attributes,
+            children: Vec::new(),
+        })
+    }
+
+    /// Adds an attribute to the element and returns it. See [`Self::set_attribute()`].
+    pub fn with_attribute<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        self.set_attribute(name, value);
+        self
+    }
+
+    /// Adds a child node to the element and returns it.
+    pub fn with_child<N: Into<Node>>(mut self, node: N) -> Self {
+        self.push(node);
+        self
+    }
+
+    /// Returns the qualified name of the element, as it is written in the document.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the name of the element without a prefix.
+    pub fn local_name(&self) -> &str {
+        local_name(&self.name)
+    }
+
+    /// Returns the namespace of the element.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// Sets the namespace of the element. The namespace is declared when the
+    /// element is written, if it is not bound to the prefix of the element.
+    pub fn set_namespace(&mut self, namespace: Option<String>) {
+        self.namespace = namespace;
+    }
+
+    /// Returns `true` if the element has the specified local name and namespace.
+    pub fn is(&self, local_name: &str, namespace: Option<&str>) -> bool {
+        self.local_name() == local_name && self.namespace() == namespace
+    }
+
+    //--------------------------------------------------------------------------------------------
+    // Attributes
+
+    /// Returns all attributes of the element, including namespace declarations.
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    /// Returns all attributes of the element for modification.
+    pub fn attributes_mut(&mut self) -> &mut Vec<Attribute> {
+        &mut self.attributes
+    }
+
+    /// Returns the value of the attribute with the specified qualified name.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| a.value.as_str())
+    }
+
+    /// Returns the value of the attribute with the specified local name and namespace.
+    pub fn attribute_ns(&self, local_name: &str, namespace: Option<&str>) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|a| a.local_name() == local_name && a.namespace.as_deref() == namespace)
+            .map(|a| a.value.as_str())
+    }
+
+    /// Sets the value of the attribute with the specified qualified name and
+    /// returns the previous value. A new attribute is added without a namespace.
+    pub fn set_attribute<N, V>(&mut self, name: N, value: V) -> Option<String>
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        let name = name.into();
+        let value = value.into();
+        match self.attributes.iter_mut().find(|a| a.name == name) {
+            Some(attr) => Some(std::mem::replace(&mut attr.value, value)),
+            None => {
+                self.attributes.push(Attribute {
+                    name,
+                    namespace: None,
+                    value,
+                });
+                None
+            }
+        }
+    }
+
+    /// Removes the attribute with the specified qualified name and returns its value.
+    pub fn remove_attribute(&mut self, name: &str) -> Option<String> {
+        let index = self.attributes.iter().position(|a| a.name == name)?;
+        Some(self.attributes.remove(index).value)
+    }
+
+    //--------------------------------------------------------------------------------------------
+    // Children
+
+    /// Returns all child nodes of the element.
+    pub fn children(&self) -> &[Node] {
+        &self.children
+    }
+
+    /// Returns all child nodes of the element for modification.
+    pub fn children_mut(&mut self) -> &mut Vec<Node> {
+        &mut self.children
+    }
+
+    /// Adds a child node to the end of the element.
+    pub fn push<N: Into<Node>>(&mut self, node: N) {
+        self.children.push(node.into());
+    }
+
+    /// Returns an iterator over the child elements.
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(Node::as_element)
+    }
+
+    /// Returns an iterator over the child elements for modification.
+    pub fn elements_mut(&mut self) -> impl Iterator<Item = &mut Element> {
+        self.children.iter_mut().filter_map(Node::as_element_mut)
+    }
+
+    /// Returns the first child element with the specified qualified name.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|e| e.name == name)
+    }
+
+    /// Returns the first child element with the specified qualified name for modification.
+    pub fn child_mut(&mut self, name: &str) -> Option<&mut Element> {
+        self.elements_mut().find(|e| e.name == name)
+    }
+
+    /// Returns the first child element with the specified local name and namespace.
+    pub fn child_ns(&self, local_name: &str, namespace: Option<&str>) -> Option<&Element> {
+        self.elements().find(|e| e.is(local_name, namespace))
+    }
+
+    /// Returns the first child element with the specified local name and
+    /// namespace for modification.
+    pub fn child_ns_mut(
+        &mut self,
+        local_name: &str,
+        namespace: Option<&str>,
+    ) -> Option<&mut Element> {
+        self.elements_mut().find(|e| e.is(local_name, namespace))
+    }
+
+    /// Returns an iterator over the child elements with the specified qualified name.
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.elements().filter(move |e| e.name == name)
+    }
+
+    /// Returns an iterator over the child elements with the specified local
+    /// name and namespace.
+    pub fn children_ns<'a>(
+        &'a self,
+        local_name: &'a str,
+        namespace: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a Element> {
+        self.elements().filter(move |e| e.is(local_name, namespace))
+    }
+
+    /// Returns the concatenated texts and CDATA sections of the element and
+    /// all its descendants.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        // Children of the element and its descendants, that are not visited yet
+        let mut opened = vec![self.children.iter()];
+        while let Some(children) = opened.last_mut() {
+            match children.next() {
+                Some(Node::Element(e)) => opened.push(e.children.iter()),
+                Some(Node::Text(t)) | Some(Node::CData(t)) => text.push_str(t),
+                Some(_) => {}
+                None => {
+                    opened.pop();
+                }
+            }
+        }
+        text
+    }
+
+    //--------------------------------------------------------------------------------------------
+
+    /// Writes the element with its descendants into the writer. An element
+    /// without children is written as an empty element tag.
+    ///
+    /// Namespaces of elements and prefixed attributes, that are not bound to
+    /// their prefixes by the namespace declarations of the element or its
+    /// ancestors, are declared with `xmlns` and `xmlns:prefix` attributes
+    /// before other attributes. Prefixed attributes without a namespace are
+    /// written as is.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPrefix`] if an element has a prefix, but does
+    /// not have a namespace, or a namespace cannot be bound to its prefix,
+    /// because the prefix is reserved or is already bound to another namespace
+    /// by the same element.
+    pub fn write_to<W: Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        let mut scope = Vec::new();
+        // Elements, which end tags are not written yet, with the length of the
+        // scope outside of them and their children, that are not written yet
+        let mut opened: Vec<(&Element, usize, Iter<Node>)> = Vec::new();
+        let mut next = Some(self);
+        loop {
+            if let Some(element) = next.take() {
+                let outer = scope.len();
+                if element.write_start(writer, &mut scope)? {
+                    opened.push((element, outer, element.children.iter()));
+                } else {
+                    scope.truncate(outer);
+                }
+            }
+            let node = match opened.last_mut() {
+                Some((_, _, children)) => children.next(),
+                None => return Ok(()),
+            };
+            match node {
+                Some(Node::Element(e)) => next = Some(e),
+                Some(node) => node.write_to(writer)?,
+                None => {
+                    if let Some((element, outer, _)) = opened.pop() {
+                        let end = BytesEnd::borrowed(element.name.as_bytes());
+                        writer.write_event(Event::End(end))?;
+                        scope.truncate(outer);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Writes the start tag of the element, or the empty element tag, if the
+    /// element does not have children, and adds namespace bindings of the element
+    /// to the scope. Returns `true` if the start tag was written.
+    fn write_start<'a, W: Write>(
+        &'a self,
+        writer: &mut Writer<W>,
+        scope: &mut Scope<'a>,
+    ) -> Result<bool> {
+        let outer = scope.len();
+        for attr in &self.attributes {
+            if is_declaration(&attr.name) {
+                let prefix = match attr.name.as_str() {
+                    "xmlns" => "",
+                    _ => attr.local_name(),
+                };
+                let namespace = Some(attr.value.as_str()).filter(|ns| !ns.is_empty());
+                scope.push((prefix, namespace));
+            }
+        }
+        let mut declarations = Vec::new();
+        declarations.extend(bind(
+            scope,
+            outer,
+            prefix(&self.name),
+            self.namespace.as_deref(),
+        )?);
+        for attr in &self.attributes {
+            // Attributes without a prefix are never in a namespace
+            let prefix = prefix(&attr.name);
+            if attr.namespace.is_some() && !prefix.is_empty() && !is_declaration(&attr.name) {
+                declarations.extend(bind(scope, outer, prefix, attr.namespace.as_deref())?);
+            }
+        }
+
+        let mut start = BytesStart::borrowed_name(self.name.as_bytes());
+        for (name, namespace) in &declarations {
+            start.push_attribute((name.as_str(), *namespace));
+        }
+        for attr in &self.attributes {
+            start.push_attribute((attr.name.as_str(), attr.value.as_str()));
+        }
+        if self.children.is_empty() {
+            writer.write_event(Event::Empty(start))?;
+            Ok(false)
+        } else {
+            writer.write_event(Event::Start(start))?;
+            Ok(true)
+        }
+    }
+}
+
+impl Clone for Element {
+    fn clone(&self) -> Self {
+        let copy = |e: &Element| Element {
+            name: e.name.clone(),
+            namespace: e.namespace.clone(),
+            attributes: e.attributes.clone(),
+            children: Vec::with_capacity(e.children.len()),
+        };
+        // Copies of elements, which children are not copied yet, and their children
+        let mut opened = vec![(copy(self), self.children.iter())];
+        loop {
+            let (element, children) = opened.last_mut().expect("the copy is not finished");
+            match children.next() {
+                Some(Node::Element(e)) => opened.push((copy(e), e.children.iter())),
+                Some(node) => element.children.push(node.clone()),
+                None => {
+                    let (element, _) = opened.pop().expect("the copy is not finished");
+                    match opened.last_mut() {
+                        Some((parent, _)) => parent.children.push(Node::Element(element)),
+                        None => return element,
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl PartialEq for Element {
+    fn eq(&self, other: &Self) -> bool {
+        let mut pairs = vec![(self, other)];
+        while let Some((left, right)) = pairs.pop() {
+            if left.name != right.name
+                || left.namespace != right.namespace
+                || left.attributes != right.attributes
+                || left.children.len() != right.children.len()
+            {
+                return false;
+            }
+            for pair in left.children.iter().zip(&right.children) {
+                match pair {
+                    (Node::Element(left), Node::Element(right)) => pairs.push((left, right)),
+                    (left, right) if left != right => return false,
+                    _ => {}
+                }
+            }
+        }
+        true
+    }
+}
+
+impl Drop for Element {
+    fn drop(&mut self) {
+        let mut nodes = std::mem::take(&mut self.children);
+        while let Some(node) = nodes.pop() {
+            if let Node::Element(mut e) = node {
+                nodes.append(&mut e.children);
+            }
+        }
+    }
+}
+
+impl Display for Element {
+    /// Writes the element without indentation.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut writer = Writer::new(Vec::new());
+        self.write_to(&mut writer).map_err(|_| fmt::Error)?;
+        f.write_str(&String::from_utf8_lossy(&writer.into_inner()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn navigation() {
+        let document: Document = "<root><a x='1'>one</a><b/><a>t<c>w</c>o</a></root>"
+            .parse()
+            .unwrap();
+        let root = document.root();
+
+        assert_eq!(root.name(), "root");
+        assert_eq!(root.elements().count(), 3);
+        assert_eq!(root.child("a").unwrap().attribute("x"), Some("1"));
+        assert_eq!(root.child("a").unwrap().attribute("y"), None);
+        assert_eq!(root.child("b").unwrap().children(), &[]);
+        assert_eq!(root.child("c"), None);
+        assert_eq!(
+            root.children_named("a")
+                .map(Element::text)
+                .collect::<Vec<_>>(),
+            vec!["one", "two"]
+        );
+        assert_eq!(root.text(), "onetwo");
+    }
+
+    #[test]
+    fn namespaces() {
+        let document: Document = r#"
+            <root xmlns="urn:default" xmlns:p="urn:p" a="1" p:a="2" xml:lang="en">
+                <p:child/>
+                <child xmlns=""/>
+                <child/>
+            </root>"#
+            .parse()
+            .unwrap();
+        let root = document.root();
+
+        assert_eq!(root.namespace(), Some("urn:default"));
+        assert!(root.is("root", Some("urn:default")));
+        // Unprefixed attributes are not in the default namespace
+        assert_eq!(root.attribute_ns("a", None), Some("1"));
+        assert_eq!(root.attribute_ns("a", Some("urn:p")), Some("2"));
+        assert_eq!(root.attribute("p:a"), Some("2"));
+        assert_eq!(root.attribute_ns("lang", Some(XML)), Some("en"));
+        // Namespace declarations are ordinary attributes
+        assert_eq!(root.attribute("xmlns:p"), Some("urn:p"));
+        assert_eq!(root.attribute_ns("p", Some(XMLNS)), Some("urn:p"));
+        assert_eq!(root.attribute_ns("xmlns", Some(XMLNS)), Some("urn:default"));
+
+        let child = root.child_ns("child", Some("urn:p")).unwrap();
+        assert_eq!(child.name(), "p:child");
+        assert_eq!(child.local_name(), "child");
+        assert_eq!(root.child_ns("child", None).unwrap().attributes().len(), 1);
+        assert_eq!(root.children_ns("child", Some("urn:default")).count(), 1);
+        assert_eq!(root.children_named("child").count(), 2);
+    }
+
+    #[test]
+    fn mutation() {
+        let mut document: Document = "<root a='1'><b/>text</root>".parse().unwrap();
+        let root = document.root_mut();
+
+        assert_eq!(root.set_attribute("a", "2"), Some("1".to_string()));
+        assert_eq!(root.set_attribute("c", "<&>"), None);
+        assert_eq!(root.remove_attribute("d"), None);
+        assert_eq!(root.remove_attribute("a"), Some("2".to_string()));
+
+        root.child_mut("b").unwrap().push("inner");
+        root.children_mut().remove(1);
+        root.push(
+            Element::new("p:d")
+                .with_attribute("e", "\"")
+                .with_child(Element::new("f")),
+        );
+        root.elements_mut()
+            .last()
+            .unwrap()
+            .set_namespace(Some("urn:p".into()));
+
+        assert!(document.root().child_ns("d", Some("urn:p")).is_some());
+        assert_eq!(
+            document.to_string(),
+            r#"<root c="&lt;&amp;&gt;"><b>inner</b><p:d xmlns:p="urn:p" e="&quot;"><f/></p:d></root>"#
+        );
+    }
+
+    /// Namespaces, that are not in scope, are declared
+    #[test]
+    fn write_namespaces() {
+        let mut document: Document =
+            r#"<root xmlns="urn:default" xmlns:p="urn:p"/>"#.parse().unwrap();
+        let mut a = Element::new("p:a");
+        a.set_namespace(Some("urn:p".into()));
+        let mut b = Element::new("b");
+        b.set_namespace(Some("urn:default".into()));
+        let mut q = Element::new("q:c").with_child(Element::new("q:d"));
+        q.set_namespace(Some("urn:q".into()));
+        q.elements_mut()
+            .next()
+            .unwrap()
+            .set_namespace(Some("urn:q".into()));
+        let mut r = Element::new("e").with_attribute("r:x", "1");
+        r.attributes_mut()[0].namespace = Some("urn:r".into());
+        document.root_mut().push(a);
+        document.root_mut().push(b);
+        document.root_mut().push(Element::new("no-namespace"));
+        document.root_mut().push(q);
+        document.root_mut().push(r);
+
+        assert_eq!(
+            document.to_string(),
+            concat!(
+                r#"<root xmlns="urn:default" xmlns:p="urn:p">"#,
+                "<p:a/>",
+                "<b/>",
+                r#"<no-namespace xmlns=""/>"#,
+                r#"<q:c xmlns:q="urn:q"><q:d/></q:c>"#,
+                r#"<e xmlns="" xmlns:r="urn:r" r:x="1"/>"#,
+                "</root>",
+            )
+        );
+        // The element is written with all declarations, that are needed by it
+        let child = document.root().child("p:a").unwrap();
+        assert_eq!(child.to_string(), r#"<p:a xmlns:p="urn:p"/>"#);
+    }
+
+    #[test]
+    fn write_errors() {
+        let write = |element: &Element| element.write_to(&mut Writer::new(Vec::new()));
+
+        // A prefix cannot be unbound
+        match write(&Element::new("p:a")) {
+            Err(Error::UnknownPrefix(prefix)) => assert_eq!(prefix, b"p"),
+            x => panic!("Expected `Err(UnknownPrefix(_))`, but got {:?}", x),
+        }
+        // The prefix is already bound to another namespace by the element
+        let mut element = Element::new("p:a").with_attribute("xmlns:p", "urn:other");
+        element.set_namespace(Some("urn:p".into()));
+        match write(&element) {
+            Err(Error::UnknownPrefix(prefix)) => assert_eq!(prefix, b"p"),
+            x => panic!("Expected `Err(UnknownPrefix(_))`, but got {:?}", x),
+        }
+        // The `xml` prefix is reserved
+        let mut element = Element::new("xml:a");
+        element.set_namespace(Some("urn:p".into()));
+        match write(&element) {
+            Err(Error::UnknownPrefix(prefix)) => assert_eq!(prefix, b"xml"),
+            x => panic!("Expected `Err(UnknownPrefix(_))`, but got {:?}", x),
+        }
+    }
+
+    /// Content of a document, that is read with the default configuration,
+    /// is written back without changes
+    #[test]
+    fn roundtrip() {
+        let xml = concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "<!DOCTYPE root>",
+            "<!-- comment -->",
+            r#"<root a="&lt;&amp;">"#,
+            "\n  text &amp; &lt;",
+            "\n  <![CDATA[<cdata>]]>",
+            "\n  <?pi content?>",
+            "\n  <!--inner-->",
+            "\n  <empty/>",
+            "\n</root>",
+            "<?after?>",
+        );
+        let document: Document = xml.parse().unwrap();
+        assert_eq!(document.to_string(), xml);
+
+        assert_eq!(
+            document.declaration(),
+            Some(&Declaration {
+                version: "1.0".into(),
+                encoding: Some("UTF-8".into()),
+                standalone: None,
+            })
+        );
+        assert_eq!(document.children().len(), 5);
+        assert_eq!(document.root().attribute("a"), Some("<&"));
+        assert_eq!(
+            document.root().children()[0].as_text(),
+            Some("\n  text & <\n  ")
+        );
+        assert_eq!(document.root().children()[1], Node::CData("<cdata>".into()));
+    }
+
+    #[test]
+    fn configured_reader() {
+        let mut reader = NsReader::from_str("<root>\n  <a> x </a>\n</root>");
+        reader.trim_text(true);
+        let document = Document::read_from(&mut reader).unwrap();
+
+        assert_eq!(document.root().children().len(), 1);
+        assert_eq!(document.root().text(), "x");
+    }
+
+    /// Deeply nested trees do not overflow the stack
+    #[test]
+    fn deep() {
+        let depth = 50_000;
+        let xml = format!("{}text{}", "<a>".repeat(depth), "</a>".repeat(depth));
+        let document: Document = xml.parse().unwrap();
+        assert_eq!(document.root().text(), "text");
+        assert_eq!(document.to_string(), xml);
+
+        let copy = document.clone();
+        assert!(copy == document);
+        let other = format!("{}other{}", "<a>".repeat(depth), "</a>".repeat(depth));
+        assert!(other.parse::<Document>().unwrap() != document);
+    }
+
+    #[test]
+    fn write_indented() {
+        let document = Document::new(
+            Element::new("root")
+                .with_child(Element::new("a").with_attribute("x", "1"))
+                .with_child(Element::new("b").with_child("text")),
+        );
+        let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+        document.write_to(&mut writer).unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "<root>\n  <a x=\"1\"/>\n  <b>text</b>\n</root>"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        match "<!-- no root -->".parse::<Document>() {
+            Err(Error::MissingRoot(span)) => assert_eq!(span, 16..16),
+            x => panic!("Expected `Err(MissingRoot)`, but got {:?}", x),
+        }
+        match "<p:a/>".parse::<Document>() {
+            Err(Error::UnknownPrefix(prefix)) => assert_eq!(prefix, b"p"),
+            x => panic!("Expected `Err(UnknownPrefix(_))`, but got {:?}", x),
+        }
+        match "<a><b></b>".parse::<Document>() {
+            Err(Error::UnexpectedEof(_, span)) => assert_eq!(span, 10..10),
+            x => panic!("Expected `Err(UnexpectedEof(_, _))`, but got {:?}", x),
+        }
+    }
+}
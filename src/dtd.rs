@@ -0,0 +1,1115 @@
+//! Module for parsing the content of the [`DocType`] event according to the
+//! [Document Type Declaration][spec] part of the XML specification.
+//!
+//! The parser turns the internal subset of the DTD into typed declarations.
+//! Parameter entity references in the internal subset are reported as is and
+//! are not expanded, and the external subset is never loaded.
+//!
+//! # Examples
+//!
+//! ```
+//! use quick_xml::dtd::{Declaration, EntityDef};
+//! use quick_xml::events::Event;
+//! use quick_xml::Reader;
+//!
+//! let mut reader = Reader::from_str(r#"<!DOCTYPE root [ <!ENTITY msg "hello"> ]><root/>"#);
+//! let mut buf = Vec::new();
+//! loop {
+//!     match reader.read_event(&mut buf).unwrap() {
+//!         Event::DocType(e) => {
+//!             let doctype = e.parse_doctype().unwrap();
+//!             assert_eq!(doctype.name, b"root");
+//!             match &doctype.declarations[0] {
+//!                 Declaration::Entity(entity) => {
+//!                     assert_eq!(entity.name, b"msg");
+//!                     assert_eq!(entity.def, EntityDef::Internal(b"hello"));
+//!                 }
+//!                 d => panic!("Unexpected declaration {:?}", d),
+//!             }
+//!             break;
+//!         }
+//!         Event::Eof => panic!("DOCTYPE not found"),
+//!         _ => {}
+//!     }
+//! }
+//! ```
+//!
+//! [`DocType`]: crate::events::Event::DocType
+//! [spec]: https://www.w3.org/TR/xml/#sec-prolog-dtd
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::reader::is_whitespace;
+
+/// An error returned by the DTD parser. Positions are relative to the start of
+/// the content of the [`DocType`] event (that is, after `<!DOCTYPE` and
+/// following whitespaces).
+///
+/// [`DocType`]: crate::events::Event::DocType
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DtdError {
+    /// The content ended while the parser expected the specified construct.
+    UnexpectedEnd(&'static str),
+    /// The parser expected the specified construct at the specified position,
+    /// but found something else.
+    Expected(&'static str, usize),
+    /// Groups of a content model are nested deeper than [`MAX_GROUP_DEPTH`].
+    /// Contains the position of the group that exceeds the limit.
+    TooDeep(usize),
+}
+
+impl Display for DtdError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd(expected) => {
+                write!(f, "unexpected end of DOCTYPE, expected {}", expected)
+            }
+            Self::Expected(expected, pos) => {
+                write!(f, "position {}: expected {}", pos, expected)
+            }
+            Self::TooDeep(pos) => write!(
+                f,
+                "position {}: content model groups are nested deeper than {}",
+                pos, MAX_GROUP_DEPTH
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DtdError {}
+
+/// The maximum nesting depth of groups in content models of element
+/// declarations. Deeper groups are an error, so malicious documents can not
+/// exhaust the stack of the parser
+pub const MAX_GROUP_DEPTH: usize = 128;
+
+/// Parsed document type declaration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocType<'a> {
+    /// Name of the root element
+    pub name: &'a [u8],
+    /// Reference to the external subset, if any
+    pub external_id: Option<ExternalId<'a>>,
+    /// Declarations of the internal subset in order of appearance. Comments
+    /// and processing instructions are skipped
+    pub declarations: Vec<Declaration<'a>>,
+}
+
+/// Reference to an external resource: an external subset of the DTD, an
+/// external entity or a notation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExternalId<'a> {
+    /// `SYSTEM "system-id"`
+    System(&'a [u8]),
+    /// `PUBLIC "public-id" "system-id"`. The system identifier can be omitted
+    /// only in the notation declarations
+    Public {
+        /// Public identifier
+        public_id: &'a [u8],
+        /// System identifier
+        system_id: Option<&'a [u8]>,
+    },
+}
+
+/// A markup declaration from the internal subset of the DTD.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Declaration<'a> {
+    /// `<!ELEMENT ...>`
+    Element(ElementDecl<'a>),
+    /// `<!ATTLIST ...>`
+    AttList(AttListDecl<'a>),
+    /// `<!ENTITY ...>`
+    Entity(EntityDecl<'a>),
+    /// `<!NOTATION ...>`
+    Notation(NotationDecl<'a>),
+    /// A parameter entity reference `%name;`. Contains the name of the entity
+    PEReference(&'a [u8]),
+}
+
+/// Element type declaration: `<!ELEMENT name content>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ElementDecl<'a> {
+    /// Name of the element
+    pub name: &'a [u8],
+    /// Allowed content of the element
+    pub content: ContentSpec<'a>,
+}
+
+/// Content model of an element.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContentSpec<'a> {
+    /// `EMPTY`
+    Empty,
+    /// `ANY`
+    Any,
+    /// `(#PCDATA | name1 | name2)*`. Contains the names of allowed elements,
+    /// which can be empty
+    Mixed(Vec<&'a [u8]>),
+    /// Element content, for example `(head, body?)`
+    Children(ContentParticle<'a>),
+}
+
+/// A content particle of the element content model: a name, a choice or a
+/// sequence with an optional repetition suffix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContentParticle<'a> {
+    /// The particle itself
+    pub kind: Particle<'a>,
+    /// How many times the particle can occur
+    pub repetition: Repetition,
+}
+
+/// Kind of the [`ContentParticle`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Particle<'a> {
+    /// Name of an element
+    Name(&'a [u8]),
+    /// `(a | b | c)`
+    Choice(Vec<ContentParticle<'a>>),
+    /// `(a, b, c)`. Group with a single particle `(a)` is also a sequence
+    Sequence(Vec<ContentParticle<'a>>),
+}
+
+/// Repetition suffix of the [`ContentParticle`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Repetition {
+    /// No suffix, exactly one occurrence
+    Once,
+    /// `?`, zero or one occurrence
+    Optional,
+    /// `*`, zero or more occurrences
+    ZeroOrMore,
+    /// `+`, one or more occurrences
+    OneOrMore,
+}
+
+/// Attribute-list declaration: `<!ATTLIST element attr type default ...>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttListDecl<'a> {
+    /// Name of the element
+    pub element: &'a [u8],
+    /// Definitions of the attributes
+    pub attributes: Vec<AttDef<'a>>,
+}
+
+/// Definition of an attribute in the [`AttListDecl`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttDef<'a> {
+    /// Name of the attribute
+    pub name: &'a [u8],
+    /// Type of the attribute
+    pub ty: AttType<'a>,
+    /// Default value of the attribute
+    pub default: DefaultDecl<'a>,
+}
+
+/// Type of an attribute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttType<'a> {
+    /// `CDATA`
+    CData,
+    /// `ID`
+    Id,
+    /// `IDREF`
+    IdRef,
+    /// `IDREFS`
+    IdRefs,
+    /// `ENTITY`
+    Entity,
+    /// `ENTITIES`
+    Entities,
+    /// `NMTOKEN`
+    NmToken,
+    /// `NMTOKENS`
+    NmTokens,
+    /// `NOTATION (name1 | name2)`
+    Notation(Vec<&'a [u8]>),
+    /// `(value1 | value2)`
+    Enumeration(Vec<&'a [u8]>),
+}
+
+/// Default value of an attribute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DefaultDecl<'a> {
+    /// `#REQUIRED`
+    Required,
+    /// `#IMPLIED`
+    Implied,
+    /// `#FIXED "value"`. Contains the raw (escaped) value
+    Fixed(&'a [u8]),
+    /// `"value"`. Contains the raw (escaped) value
+    Value(&'a [u8]),
+}
+
+/// Entity declaration: `<!ENTITY name definition>` or
+/// `<!ENTITY % name definition>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntityDecl<'a> {
+    /// Name of the entity
+    pub name: &'a [u8],
+    /// `true` for parameter entities, declared with `%`
+    pub parameter: bool,
+    /// Definition of the entity
+    pub def: EntityDef<'a>,
+}
+
+/// Definition of an entity.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntityDef<'a> {
+    /// Internal entity. Contains the raw literal value, which can contain
+    /// character and entity references
+    Internal(&'a [u8]),
+    /// External entity
+    External {
+        /// Location of the entity
+        id: ExternalId<'a>,
+        /// Name of the notation for unparsed entities (`NDATA name`)
+        ndata: Option<&'a [u8]>,
+    },
+}
+
+/// Notation declaration: `<!NOTATION name id>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotationDecl<'a> {
+    /// Name of the notation
+    pub name: &'a [u8],
+    /// Identifier of the notation
+    pub id: ExternalId<'a>,
+}
+
+type Result<T> = std::result::Result<T, DtdError>;
+
+/// Parses the content of the [`DocType`] event, that is, everything between
+/// `<!DOCTYPE` and the closing `>` with leading whitespaces trimmed.
+///
+/// [`DocType`]: crate::events::Event::DocType
+pub fn parse(content: &[u8]) -> std::result::Result<DocType<'_>, DtdError> {
+    Parser {
+        input: content,
+        pos: 0,
+        lenient: false,
+        depth: 0,
+    }
+    .doctype()
+}
+
+/// Parses the content of the [`DocType`] event like [`parse()`], but skips
+/// declarations that can not be parsed and anything after the internal subset.
+/// Only errors in the name and the external identifier of the DTD are returned.
+///
+/// [`DocType`]: crate::events::Event::DocType
+pub(crate) fn parse_lenient(content: &[u8]) -> std::result::Result<DocType<'_>, DtdError> {
+    Parser {
+        input: content,
+        pos: 0,
+        lenient: true,
+        depth: 0,
+    }
+    .doctype()
+}
+
+/// Checks that the byte can not be a part of a name
+#[inline]
+fn is_delimiter(b: u8) -> bool {
+    is_whitespace(b)
+        || matches!(
+            b,
+            b'(' | b')'
+                | b'|'
+                | b','
+                | b'>'
+                | b'?'
+                | b'*'
+                | b'+'
+                | b';'
+                | b'%'
+                | b'['
+                | b']'
+                | b'"'
+                | b'\''
+        )
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    /// Skip invalid declarations instead of returning errors
+    lenient: bool,
+    /// The number of content model groups opened at the current position
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.input[self.pos..]
+    }
+
+    fn error(&self, expected: &'static str) -> DtdError {
+        if self.pos >= self.input.len() {
+            DtdError::UnexpectedEnd(expected)
+        } else {
+            DtdError::Expected(expected, self.pos)
+        }
+    }
+
+    /// Skips whitespaces and returns `true` if at least one was skipped
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b) if is_whitespace(b)) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    /// Skips required whitespaces
+    fn whitespace(&mut self) -> Result<()> {
+        if self.skip_whitespace() {
+            Ok(())
+        } else {
+            Err(self.error("whitespace"))
+        }
+    }
+
+    /// Consumes `prefix` if the rest of the input starts with it
+    fn eat(&mut self, prefix: &[u8]) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, prefix: &'static str) -> Result<()> {
+        if self.eat(prefix.as_bytes()) {
+            Ok(())
+        } else {
+            Err(self.error(prefix))
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a [u8]> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b) if !is_delimiter(b)) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            Err(self.error("name"))
+        } else {
+            Ok(&self.input[start..self.pos])
+        }
+    }
+
+    /// Reads a quoted literal and returns its content without quotes
+    fn literal(&mut self) -> Result<&'a [u8]> {
+        let quote = match self.peek() {
+            Some(q @ b'"') | Some(q @ b'\'') => q,
+            _ => return Err(self.error("quoted literal")),
+        };
+        let start = self.pos + 1;
+        match memchr::memchr(quote, &self.input[start..]) {
+            Some(len) => {
+                self.pos = start + len + 1;
+                Ok(&self.input[start..start + len])
+            }
+            None => {
+                self.pos = self.input.len();
+                Err(self.error("closing quote"))
+            }
+        }
+    }
+
+    /// Skips optional whitespaces and `>`
+    fn end_of_declaration(&mut self) -> Result<()> {
+        self.skip_whitespace();
+        self.expect(">")
+    }
+
+    fn doctype(mut self) -> Result<DocType<'a>> {
+        self.skip_whitespace();
+        let name = self.name()?;
+        let had_space = self.skip_whitespace();
+        let external_id = match self.peek() {
+            Some(b'S') | Some(b'P') if had_space => Some(self.external_id(false)?),
+            _ => None,
+        };
+        self.skip_whitespace();
+
+        let mut declarations = Vec::new();
+        if self.eat(b"[") {
+            loop {
+                self.skip_whitespace();
+                if self.eat(b"]") {
+                    break;
+                }
+                let start = self.pos;
+                match self.declaration() {
+                    Ok(Some(decl)) => declarations.push(decl),
+                    Ok(None) => {}
+                    Err(e) if !self.lenient => return Err(e),
+                    Err(_) if start < self.input.len() => self.skip_declaration(start),
+                    // The internal subset is not closed
+                    Err(_) => break,
+                }
+            }
+            self.skip_whitespace();
+        }
+        if self.pos < self.input.len() && !self.lenient {
+            return Err(self.error("end of DOCTYPE"));
+        }
+        Ok(DocType {
+            name,
+            external_id,
+            declarations,
+        })
+    }
+
+    /// Parses `SYSTEM "id"` or `PUBLIC "id" "id"`. If `notation` is `true`,
+    /// system identifier after the public identifier is optional
+    fn external_id(&mut self, notation: bool) -> Result<ExternalId<'a>> {
+        if self.eat(b"SYSTEM") {
+            self.whitespace()?;
+            Ok(ExternalId::System(self.literal()?))
+        } else if self.eat(b"PUBLIC") {
+            self.whitespace()?;
+            let public_id = self.literal()?;
+            let start = self.pos;
+            let had_space = self.skip_whitespace();
+            let system_id = match self.peek() {
+                Some(b'"') | Some(b'\'') if had_space => Some(self.literal()?),
+                _ if notation => {
+                    self.pos = start;
+                    None
+                }
+                _ => return Err(self.error("system literal")),
+            };
+            Ok(ExternalId::Public {
+                public_id,
+                system_id,
+            })
+        } else {
+            Err(self.error("SYSTEM or PUBLIC"))
+        }
+    }
+
+    /// Parses a markup declaration or a parameter entity reference. Returns
+    /// `None` for comments and processing instructions
+    fn declaration(&mut self) -> Result<Option<Declaration<'a>>> {
+        if self.eat(b"%") {
+            let name = self.name()?;
+            self.expect(";")?;
+            return Ok(Some(Declaration::PEReference(name)));
+        }
+        if self.eat(b"<!--") {
+            return self.skip_until(b"-->", "-->").map(|_| None);
+        }
+        if self.eat(b"<?") {
+            return self.skip_until(b"?>", "?>").map(|_| None);
+        }
+        let decl = if self.eat(b"<!ELEMENT") {
+            Declaration::Element(self.element_decl()?)
+        } else if self.eat(b"<!ATTLIST") {
+            Declaration::AttList(self.attlist_decl()?)
+        } else if self.eat(b"<!ENTITY") {
+            Declaration::Entity(self.entity_decl()?)
+        } else if self.eat(b"<!NOTATION") {
+            Declaration::Notation(self.notation_decl()?)
+        } else {
+            return Err(self.error("markup declaration"));
+        };
+        Ok(Some(decl))
+    }
+
+    /// Skips an invalid declaration, that starts at `start`, until `>` outside
+    /// of quoted literals
+    fn skip_declaration(&mut self, start: usize) {
+        self.pos = start;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            match b {
+                b'>' => return,
+                b'"' | b'\'' => match memchr::memchr(b, self.rest()) {
+                    Some(len) => self.pos += len + 1,
+                    None => self.pos = self.input.len(),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn skip_until(&mut self, end: &[u8], expected: &'static str) -> Result<()> {
+        match memchr::memmem::find(self.rest(), end) {
+            Some(p) => {
+                self.pos += p + end.len();
+                Ok(())
+            }
+            None => {
+                self.pos = self.input.len();
+                Err(self.error(expected))
+            }
+        }
+    }
+
+    fn element_decl(&mut self) -> Result<ElementDecl<'a>> {
+        self.whitespace()?;
+        let name = self.name()?;
+        self.whitespace()?;
+        let content = if self.eat(b"EMPTY") {
+            ContentSpec::Empty
+        } else if self.eat(b"ANY") {
+            ContentSpec::Any
+        } else {
+            self.expect("(")?;
+            self.skip_whitespace();
+            if self.eat(b"#PCDATA") {
+                self.mixed()?
+            } else {
+                ContentSpec::Children(self.nested_group()?)
+            }
+        };
+        self.end_of_declaration()?;
+        Ok(ElementDecl { name, content })
+    }
+
+    /// Parses the rest of the mixed content model after `(#PCDATA`
+    fn mixed(&mut self) -> Result<ContentSpec<'a>> {
+        let mut names = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat(b")") {
+                break;
+            }
+            self.expect("|")?;
+            self.skip_whitespace();
+            names.push(self.name()?);
+        }
+        if !self.eat(b"*") && !names.is_empty() {
+            return Err(self.error("*"));
+        }
+        Ok(ContentSpec::Mixed(names))
+    }
+
+    /// Parses a choice or a sequence after `(` with a repetition suffix
+    fn group(&mut self) -> Result<ContentParticle<'a>> {
+        let mut particles = vec![self.particle()?];
+        let mut separator = None;
+        loop {
+            self.skip_whitespace();
+            if self.eat(b")") {
+                break;
+            }
+            let sep = match (self.peek(), separator) {
+                (Some(b @ b'|'), None) | (Some(b @ b','), None) => b,
+                (Some(b), Some(s)) if b == s => b,
+                (_, Some(b'|')) => return Err(self.error("| or )")),
+                (_, Some(_)) => return Err(self.error(", or )")),
+                (_, None) => return Err(self.error("|, , or )")),
+            };
+            separator = Some(sep);
+            self.pos += 1;
+            self.skip_whitespace();
+            particles.push(self.particle()?);
+        }
+        let kind = if separator == Some(b'|') {
+            Particle::Choice(particles)
+        } else {
+            Particle::Sequence(particles)
+        };
+        Ok(ContentParticle {
+            kind,
+            repetition: self.repetition(),
+        })
+    }
+
+    /// Parses a group after `(` like [`Self::group()`], if the nesting limit is
+    /// not exceeded
+    fn nested_group(&mut self) -> Result<ContentParticle<'a>> {
+        if self.depth >= MAX_GROUP_DEPTH {
+            // -1 for `(`
+            return Err(DtdError::TooDeep(self.pos - 1));
+        }
+        self.depth += 1;
+        let group = self.group();
+        self.depth -= 1;
+        group
+    }
+
+    fn particle(&mut self) -> Result<ContentParticle<'a>> {
+        if self.eat(b"(") {
+            self.skip_whitespace();
+            self.nested_group()
+        } else {
+            let name = self.name()?;
+            Ok(ContentParticle {
+                kind: Particle::Name(name),
+                repetition: self.repetition(),
+            })
+        }
+    }
+
+    fn repetition(&mut self) -> Repetition {
+        let repetition = match self.peek() {
+            Some(b'?') => Repetition::Optional,
+            Some(b'*') => Repetition::ZeroOrMore,
+            Some(b'+') => Repetition::OneOrMore,
+            _ => return Repetition::Once,
+        };
+        self.pos += 1;
+        repetition
+    }
+
+    fn attlist_decl(&mut self) -> Result<AttListDecl<'a>> {
+        self.whitespace()?;
+        let element = self.name()?;
+        let mut attributes = Vec::new();
+        loop {
+            let had_space = self.skip_whitespace();
+            if self.eat(b">") {
+                break;
+            }
+            if !had_space {
+                return Err(self.error("whitespace"));
+            }
+            let name = self.name()?;
+            self.whitespace()?;
+            let ty = self.att_type()?;
+            self.whitespace()?;
+            let default = self.default_decl()?;
+            attributes.push(AttDef { name, ty, default });
+        }
+        Ok(AttListDecl {
+            element,
+            attributes,
+        })
+    }
+
+    fn att_type(&mut self) -> Result<AttType<'a>> {
+        if self.eat(b"(") {
+            return Ok(AttType::Enumeration(self.enumeration()?));
+        }
+        let start = self.pos;
+        Ok(match self.name()? {
+            b"CDATA" => AttType::CData,
+            b"ID" => AttType::Id,
+            b"IDREF" => AttType::IdRef,
+            b"IDREFS" => AttType::IdRefs,
+            b"ENTITY" => AttType::Entity,
+            b"ENTITIES" => AttType::Entities,
+            b"NMTOKEN" => AttType::NmToken,
+            b"NMTOKENS" => AttType::NmTokens,
+            b"NOTATION" => {
+                self.whitespace()?;
+                self.expect("(")?;
+                AttType::Notation(self.enumeration()?)
+            }
+            _ => return Err(DtdError::Expected("attribute type", start)),
+        })
+    }
+
+    /// Parses `|`-separated names until `)`, after `(`
+    fn enumeration(&mut self) -> Result<Vec<&'a [u8]>> {
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace();
+            values.push(self.name()?);
+            self.skip_whitespace();
+            if self.eat(b")") {
+                return Ok(values);
+            }
+            self.expect("|")?;
+        }
+    }
+
+    fn default_decl(&mut self) -> Result<DefaultDecl<'a>> {
+        if self.eat(b"#REQUIRED") {
+            Ok(DefaultDecl::Required)
+        } else if self.eat(b"#IMPLIED") {
+            Ok(DefaultDecl::Implied)
+        } else if self.eat(b"#FIXED") {
+            self.whitespace()?;
+            Ok(DefaultDecl::Fixed(self.literal()?))
+        } else {
+            Ok(DefaultDecl::Value(self.literal()?))
+        }
+    }
+
+    fn entity_decl(&mut self) -> Result<EntityDecl<'a>> {
+        self.whitespace()?;
+        let parameter = self.eat(b"%");
+        if parameter {
+            self.whitespace()?;
+        }
+        let name = self.name()?;
+        self.whitespace()?;
+        let def = match self.peek() {
+            Some(b'"') | Some(b'\'') => EntityDef::Internal(self.literal()?),
+            _ => {
+                let id = self.external_id(false)?;
+                let start = self.pos;
+                let ndata = if !parameter && self.skip_whitespace() && self.eat(b"NDATA") {
+                    self.whitespace()?;
+                    Some(self.name()?)
+                } else {
+                    self.pos = start;
+                    None
+                };
+                EntityDef::External { id, ndata }
+            }
+        };
+        self.end_of_declaration()?;
+        Ok(EntityDecl {
+            name,
+            parameter,
+            def,
+        })
+    }
+
+    fn notation_decl(&mut self) -> Result<NotationDecl<'a>> {
+        self.whitespace()?;
+        let name = self.name()?;
+        self.whitespace()?;
+        let id = self.external_id(true)?;
+        self.end_of_declaration()?;
+        Ok(NotationDecl { name, id })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn name(name: &[u8], repetition: Repetition) -> ContentParticle {
+        ContentParticle {
+            kind: Particle::Name(name),
+            repetition,
+        }
+    }
+
+    #[test]
+    fn name_only() {
+        assert_eq!(
+            parse(b"html").unwrap(),
+            DocType {
+                name: b"html",
+                external_id: None,
+                declarations: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn external_id() {
+        assert_eq!(
+            parse(b"root SYSTEM 'root.dtd'").unwrap().external_id,
+            Some(ExternalId::System(b"root.dtd"))
+        );
+        assert_eq!(
+            parse(b"html PUBLIC \"-//W3C//DTD XHTML 1.0 Strict//EN\" \"xhtml1.dtd\" []")
+                .unwrap()
+                .external_id,
+            Some(ExternalId::Public {
+                public_id: b"-//W3C//DTD XHTML 1.0 Strict//EN",
+                system_id: Some(b"xhtml1.dtd"),
+            })
+        );
+        assert_eq!(
+            parse(b"html PUBLIC 'id'"),
+            Err(DtdError::UnexpectedEnd("system literal"))
+        );
+    }
+
+    #[test]
+    fn elements() {
+        let doctype = parse(
+            b"root [
+                <!ELEMENT empty EMPTY>
+                <!ELEMENT any ANY >
+                <!ELEMENT text (#PCDATA)>
+                <!ELEMENT mixed (#PCDATA | a | b)*>
+                <!ELEMENT children (head, (p | div)*, foot?)+>
+            ]",
+        )
+        .unwrap();
+        assert_eq!(
+            doctype.declarations,
+            vec![
+                Declaration::Element(ElementDecl {
+                    name: b"empty",
+                    content: ContentSpec::Empty,
+                }),
+                Declaration::Element(ElementDecl {
+                    name: b"any",
+                    content: ContentSpec::Any,
+                }),
+                Declaration::Element(ElementDecl {
+                    name: b"text",
+                    content: ContentSpec::Mixed(vec![]),
+                }),
+                Declaration::Element(ElementDecl {
+                    name: b"mixed",
+                    content: ContentSpec::Mixed(vec![b"a", b"b"]),
+                }),
+                Declaration::Element(ElementDecl {
+                    name: b"children",
+                    content: ContentSpec::Children(ContentParticle {
+                        kind: Particle::Sequence(vec![
+                            name(b"head", Repetition::Once),
+                            ContentParticle {
+                                kind: Particle::Choice(vec![
+                                    name(b"p", Repetition::Once),
+                                    name(b"div", Repetition::Once),
+                                ]),
+                                repetition: Repetition::ZeroOrMore,
+                            },
+                            name(b"foot", Repetition::Optional),
+                        ]),
+                        repetition: Repetition::OneOrMore,
+                    }),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn element_errors() {
+        assert_eq!(
+            parse(b"r [<!ELEMENT a (#PCDATA | b)>]"),
+            Err(DtdError::Expected("*", 28))
+        );
+        assert_eq!(
+            parse(b"r [<!ELEMENT a (b | c, d)>]"),
+            Err(DtdError::Expected("| or )", 21))
+        );
+        assert_eq!(
+            parse(b"r [<!ELEMENT a (b)"),
+            Err(DtdError::UnexpectedEnd(">"))
+        );
+    }
+
+    /// Nesting of groups is limited, so deep nesting does not overflow the stack
+    #[test]
+    fn nested_groups() {
+        let nested = |depth: usize| {
+            format!(
+                "r [<!ELEMENT r {}a{}>]",
+                "(".repeat(depth),
+                ")".repeat(depth)
+            )
+        };
+        assert!(parse(nested(MAX_GROUP_DEPTH).as_bytes()).is_ok());
+        // 15 is the position of the first `(`
+        assert_eq!(
+            parse(nested(MAX_GROUP_DEPTH + 1).as_bytes()),
+            Err(DtdError::TooDeep(15 + MAX_GROUP_DEPTH))
+        );
+        assert_eq!(
+            parse(nested(100_000).as_bytes()),
+            Err(DtdError::TooDeep(15 + MAX_GROUP_DEPTH))
+        );
+        // Declarations, that are too deep, are skipped by the lenient parser
+        let input = nested(100_000);
+        assert_eq!(
+            parse_lenient(input.as_bytes()).unwrap().declarations,
+            vec![]
+        );
+    }
+
+    #[test]
+    fn attlists() {
+        let doctype = parse(
+            b"root [<!ATTLIST img
+                src CDATA #REQUIRED
+                id ID #IMPLIED
+                align (left|right) 'left'
+                type NOTATION (gif | png) #FIXED \"png\"
+            >]",
+        )
+        .unwrap();
+        assert_eq!(
+            doctype.declarations,
+            vec![Declaration::AttList(AttListDecl {
+                element: b"img",
+                attributes: vec![
+                    AttDef {
+                        name: b"src",
+                        ty: AttType::CData,
+                        default: DefaultDecl::Required,
+                    },
+                    AttDef {
+                        name: b"id",
+                        ty: AttType::Id,
+                        default: DefaultDecl::Implied,
+                    },
+                    AttDef {
+                        name: b"align",
+                        ty: AttType::Enumeration(vec![b"left", b"right"]),
+                        default: DefaultDecl::Value(b"left"),
+                    },
+                    AttDef {
+                        name: b"type",
+                        ty: AttType::Notation(vec![b"gif", b"png"]),
+                        default: DefaultDecl::Fixed(b"png"),
+                    },
+                ],
+            })]
+        );
+        assert_eq!(
+            parse(b"r [<!ATTLIST a b STRING #IMPLIED>]"),
+            Err(DtdError::Expected("attribute type", 17))
+        );
+    }
+
+    #[test]
+    fn entities() {
+        let doctype = parse(
+            br#"root [
+                <!ENTITY msg "hello &amp; world">
+                <!ENTITY % param 'value'>
+                <!ENTITY ext SYSTEM "ext.xml">
+                <!ENTITY pic PUBLIC "id" "pic.gif" NDATA gif>
+                <!ENTITY % ext-param SYSTEM "param.ent" >
+                %ext-param;
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            doctype.declarations,
+            vec![
+                Declaration::Entity(EntityDecl {
+                    name: b"msg",
+                    parameter: false,
+                    def: EntityDef::Internal(b"hello &amp; world"),
+                }),
+                Declaration::Entity(EntityDecl {
+                    name: b"param",
+                    parameter: true,
+                    def: EntityDef::Internal(b"value"),
+                }),
+                Declaration::Entity(EntityDecl {
+                    name: b"ext",
+                    parameter: false,
+                    def: EntityDef::External {
+                        id: ExternalId::System(b"ext.xml"),
+                        ndata: None,
+                    },
+                }),
+                Declaration::Entity(EntityDecl {
+                    name: b"pic",
+                    parameter: false,
+                    def: EntityDef::External {
+                        id: ExternalId::Public {
+                            public_id: b"id",
+                            system_id: Some(b"pic.gif"),
+                        },
+                        ndata: Some(b"gif"),
+                    },
+                }),
+                Declaration::Entity(EntityDecl {
+                    name: b"ext-param",
+                    parameter: true,
+                    def: EntityDef::External {
+                        id: ExternalId::System(b"param.ent"),
+                        ndata: None,
+                    },
+                }),
+                Declaration::PEReference(b"ext-param"),
+            ]
+        );
+        assert_eq!(
+            parse(b"r [<!ENTITY a \"value>]"),
+            Err(DtdError::UnexpectedEnd("closing quote"))
+        );
+    }
+
+    #[test]
+    fn notations() {
+        let doctype = parse(
+            b"root [
+                <!NOTATION gif PUBLIC 'image/gif'>
+                <!NOTATION png SYSTEM 'png-viewer'>
+                <!NOTATION jpg PUBLIC 'image/jpeg' 'jpg-viewer'>
+            ]",
+        )
+        .unwrap();
+        assert_eq!(
+            doctype.declarations,
+            vec![
+                Declaration::Notation(NotationDecl {
+                    name: b"gif",
+                    id: ExternalId::Public {
+                        public_id: b"image/gif",
+                        system_id: None,
+                    },
+                }),
+                Declaration::Notation(NotationDecl {
+                    name: b"png",
+                    id: ExternalId::System(b"png-viewer"),
+                }),
+                Declaration::Notation(NotationDecl {
+                    name: b"jpg",
+                    id: ExternalId::Public {
+                        public_id: b"image/jpeg",
+                        system_id: Some(b"jpg-viewer"),
+                    },
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_and_pi() {
+        let doctype =
+            parse(b"root [ <!-- <!ELEMENT a ANY> --> <?pi <!ELEMENT?> <!ELEMENT b ANY> ]").unwrap();
+        assert_eq!(
+            doctype.declarations,
+            vec![Declaration::Element(ElementDecl {
+                name: b"b",
+                content: ContentSpec::Any,
+            })]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse(b""), Err(DtdError::UnexpectedEnd("name")));
+        assert_eq!(
+            parse(b"root ["),
+            Err(DtdError::UnexpectedEnd("markup declaration"))
+        );
+        assert_eq!(
+            parse(b"root [] x"),
+            Err(DtdError::Expected("end of DOCTYPE", 8))
+        );
+        assert_eq!(
+            parse(b"root [<!UNKNOWN>]"),
+            Err(DtdError::Expected("markup declaration", 6))
+        );
+        assert_eq!(
+            parse(b"root [<!-- comment"),
+            Err(DtdError::UnexpectedEnd("-->"))
+        );
+    }
+
+    /// Invalid declarations are skipped
+    #[test]
+    fn lenient() {
+        let doctype =
+            parse_lenient(b"r [<!ENTITY a 'x' junk><!UNKNOWN '>'><!ENTITY b 'y'><!ELEMENT ] x")
+                .unwrap();
+        assert_eq!(
+            doctype.declarations,
+            vec![Declaration::Entity(EntityDecl {
+                name: b"b",
+                parameter: false,
+                def: EntityDef::Internal(b"y"),
+            })]
+        );
+        assert_eq!(parse_lenient(b"r [<!ENTITY").unwrap().declarations, vec![]);
+        assert_eq!(parse_lenient(b""), Err(DtdError::UnexpectedEnd("name")));
+    }
+}
@@ -1,8 +1,11 @@
 //! Error management module
 
+use crate::dtd::DtdError;
 use crate::escape::EscapeError;
 use crate::events::attributes::AttrError;
+use crate::reader::TextPosition;
 use crate::utils::write_byte_string;
+use std::ops::Range;
 use std::str::Utf8Error;
 
 /// The error type used by this crate.
@@ -12,29 +15,117 @@ pub enum Error {
     Io(::std::io::Error),
     /// Utf8 error
     Utf8(Utf8Error),
-    /// Unexpected End of File
-    UnexpectedEof(String),
+    /// Unexpected End of File. The range covers the unclosed markup (or the
+    /// element, if reading until the end of element) from its start up to the
+    /// end of input
+    UnexpectedEof(String, Range<usize>),
     /// End event mismatch
     EndEventMismatch {
         /// Expected end event
         expected: String,
         /// Found end event
         found: String,
+        /// Byte range of the closing tag, including `</` and `>`
+        span: Range<usize>,
     },
-    /// Unexpected token
-    UnexpectedToken(String),
-    /// Unexpected <!>
-    UnexpectedBang(u8),
+    /// Unexpected token. The range covers the token
+    UnexpectedToken(String, Range<usize>),
+    /// Unexpected <!>. The range covers `<!` and the unexpected symbol
+    UnexpectedBang(u8, Range<usize>),
     /// Text not found, expected `Event::Text`
     TextNotFound,
     /// `Event::XmlDecl` must start with *version* attribute
     XmlDeclWithoutVersion(Option<String>),
-    /// Attribute parsing error
+    /// Attribute parsing error. Positions in the error are absolute positions
+    /// in the document if the element was read by the [`Reader`]
+    ///
+    /// [`Reader`]: crate::Reader
     InvalidAttr(AttrError),
     /// Escape error
     EscapeError(EscapeError),
     /// Specified namespace prefix is unknown, cannot resolve namespace for it
     UnknownPrefix(Vec<u8>),
+    /// Error while parsing the content of the DOCTYPE
+    InvalidDtd(DtdError),
+    /// Name of an element, an attribute, a processing instruction target or
+    /// an entity reference does not match the [`Name`] production. The range
+    /// covers the name. Returned only in the [strict mode]
+    ///
+    /// [`Name`]: https://www.w3.org/TR/xml/#NT-Name
+    /// [strict mode]: crate::Reader::strict
+    InvalidName(String, Range<usize>),
+    /// Character or character reference does not match the [`Char`] production.
+    /// Contains the code of the character and its position (or the position of
+    /// `&` of the reference). Returned only in the [strict mode]
+    ///
+    /// [`Char`]: https://www.w3.org/TR/xml/#NT-Char
+    /// [strict mode]: crate::Reader::strict
+    InvalidChar(u32, usize),
+    /// XML declaration is not at the start of the document or document type
+    /// declaration is not before the root element. The range covers the
+    /// declaration. Returned only in the [strict mode]
+    ///
+    /// [strict mode]: crate::Reader::strict
+    MisplacedDeclaration(Range<usize>),
+    /// Element found after the end of the root element. The range covers the
+    /// start tag of the element. Returned only in the [strict mode]
+    ///
+    /// [strict mode]: crate::Reader::strict
+    MultipleRoots(Range<usize>),
+    /// Non-whitespace text or CDATA found outside of the root element. The
+    /// range covers the content. Returned only in the [strict mode]
+    ///
+    /// [strict mode]: crate::Reader::strict
+    ContentOutsideRoot(Range<usize>),
+    /// Document does not contain an element. Contains the empty range at the
+    /// end of the document. Returned only in the [strict mode] and when a tree
+    /// of the document is built
+    ///
+    /// [strict mode]: crate::Reader::strict
+    MissingRoot(Range<usize>),
+    /// Markup between `<` and `>` is longer than [`ReaderLimits::max_markup_len`].
+    /// Contains the limit and the range from `<` up to the position where the
+    /// reading was stopped
+    ///
+    /// [`ReaderLimits::max_markup_len`]: crate::ReaderLimits::max_markup_len
+    MarkupTooLong(usize, Range<usize>),
+    /// Text is longer than [`ReaderLimits::max_text_len`]. Contains the limit
+    /// and the range from the start of the text up to the position where the
+    /// reading was stopped
+    ///
+    /// [`ReaderLimits::max_text_len`]: crate::ReaderLimits::max_text_len
+    TextTooLong(usize, Range<usize>),
+    /// Element is nested deeper than [`ReaderLimits::max_depth`]. Contains the
+    /// limit and the range of the start tag of the element
+    ///
+    /// [`ReaderLimits::max_depth`]: crate::ReaderLimits::max_depth
+    TooDeep(usize, Range<usize>),
+    /// Element has more attributes than [`ReaderLimits::max_attributes`].
+    /// Contains the limit and the range of the start tag of the element
+    ///
+    /// [`ReaderLimits::max_attributes`]: crate::ReaderLimits::max_attributes
+    TooManyAttributes(usize, Range<usize>),
+    /// Name of an element or an attribute is longer than [`ReaderLimits::max_name_len`].
+    /// Contains the limit and the range of the name
+    ///
+    /// [`ReaderLimits::max_name_len`]: crate::ReaderLimits::max_name_len
+    NameTooLong(usize, Range<usize>),
+    /// An error occurred while reading the event, which starts at the specified
+    /// position. Returned only if [position tracking] is enabled.
+    ///
+    /// The `position` is the start of the event, which can be before the place
+    /// in the event where the error was detected. The wrapped error is also
+    /// returned as the [source] of this error.
+    ///
+    /// [source]: std::error::Error::source
+    ///
+    /// [position tracking]: crate::Reader::track_position
+    Positioned {
+        /// The error
+        error: Box<Error>,
+        /// Line and column of the start of the event that caused the error
+        position: TextPosition,
+    },
 }
 
 impl From<::std::io::Error> for Error {
@@ -68,6 +159,13 @@ impl From<AttrError> for Error {
     }
 }
 
+impl From<DtdError> for Error {
+    #[inline]
+    fn from(error: DtdError) -> Self {
+        Error::InvalidDtd(error)
+    }
+}
+
 /// A specialized `Result` type where the error is hard-wired to [`Error`].
 ///
 /// [`Error`]: enum.Error.html
@@ -78,12 +176,12 @@ impl std::fmt::Display for Error {
         match self {
             Error::Io(e) => write!(f, "I/O error: {}", e),
             Error::Utf8(e) => write!(f, "UTF8 error: {}", e),
-            Error::UnexpectedEof(e) => write!(f, "Unexpected EOF during reading {}", e),
-            Error::EndEventMismatch { expected, found } => {
+            Error::UnexpectedEof(e, _) => write!(f, "Unexpected EOF during reading {}", e),
+            Error::EndEventMismatch { expected, found, .. } => {
                 write!(f, "Expecting </{}> found </{}>", expected, found)
             }
-            Error::UnexpectedToken(e) => write!(f, "Unexpected token '{}'", e),
-            Error::UnexpectedBang(b) => write!(
+            Error::UnexpectedToken(e, _) => write!(f, "Unexpected token '{}'", e),
+            Error::UnexpectedBang(b, _) => write!(
                 f,
                 "Only Comment (`--`), CDATA (`[CDATA[`) and DOCTYPE (`DOCTYPE`) nodes can start with a '!', but symbol `{}` found",
                 *b as char
@@ -101,6 +199,32 @@ impl std::fmt::Display for Error {
                 write_byte_string(f, &prefix)?;
                 f.write_str("'")
             }
+            Error::InvalidDtd(e) => write!(f, "error while parsing DOCTYPE: {}", e),
+            Error::InvalidName(name, _) => write!(f, "Invalid name '{}'", name),
+            Error::InvalidChar(ch, _) => write!(f, "Invalid character U+{:04X}", ch),
+            Error::MisplacedDeclaration(_) => write!(
+                f,
+                "XML declaration must be at the start of the document and DOCTYPE before the root element"
+            ),
+            Error::MultipleRoots(_) => write!(f, "Document must contain only one root element"),
+            Error::ContentOutsideRoot(_) => write!(f, "Content is not allowed outside of the root element"),
+            Error::MissingRoot(_) => write!(f, "Document must contain a root element"),
+            Error::MarkupTooLong(limit, _) => {
+                write!(f, "Markup is longer than the limit of {} bytes", limit)
+            }
+            Error::TextTooLong(limit, _) => {
+                write!(f, "Text is longer than the limit of {} bytes", limit)
+            }
+            Error::TooDeep(limit, _) => {
+                write!(f, "Element is nested deeper than the limit of {}", limit)
+            }
+            Error::TooManyAttributes(limit, _) => {
+                write!(f, "Element has more than {} attributes", limit)
+            }
+            Error::NameTooLong(limit, _) => {
+                write!(f, "Name is longer than the limit of {} bytes", limit)
+            }
+            Error::Positioned { error, position } => write!(f, "{} at {}", error, position),
         }
     }
 }
@@ -112,6 +236,8 @@ impl std::error::Error for Error {
             Error::Utf8(e) => Some(e),
             Error::InvalidAttr(e) => Some(e),
             Error::EscapeError(e) => Some(e),
+            Error::InvalidDtd(e) => Some(e),
+            Error::Positioned { error, .. } => Some(&**error),
             _ => None,
         }
     }
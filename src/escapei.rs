@@ -2,14 +2,14 @@
 
 use memchr;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Range;
 
 #[cfg(test)]
 use pretty_assertions::assert_eq;
 
 /// Error for XML escape/unescqpe.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum EscapeError {
     /// Entity with Null character
     EntityWithNull(::std::ops::Range<usize>),
@@ -30,6 +30,11 @@ pub enum EscapeError {
     InvalidDecimal(char),
     /// Not a valid unicode codepoint
     InvalidCodepoint(u32),
+    /// Entity declared in the DTD references itself, directly or through
+    /// other entities
+    RecursiveEntity(String),
+    /// Expansion of entities exceeds one of the configured [`ExpansionLimits`]
+    ExpansionLimitExceeded(ExpansionLimit),
 }
 
 impl std::fmt::Display for EscapeError {
@@ -57,12 +62,76 @@ impl std::fmt::Display for EscapeError {
             EscapeError::TooLongDecimal => write!(f, "Cannot convert decimal to utf8"),
             EscapeError::InvalidDecimal(e) => write!(f, "'{}' is not a valid decimal character", e),
             EscapeError::InvalidCodepoint(n) => write!(f, "'{}' is not a valid codepoint", n),
+            EscapeError::RecursiveEntity(name) => {
+                write!(f, "Entity '{}' references itself", name)
+            }
+            EscapeError::ExpansionLimitExceeded(limit) => {
+                write!(f, "Entity expansion exceeds {}", limit)
+            }
         }
     }
 }
 
 impl std::error::Error for EscapeError {}
 
+/// A limit of [`ExpansionLimits`] that was exceeded. Contains the configured value
+/// of the limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpansionLimit {
+    /// [`ExpansionLimits::max_size`] was exceeded
+    Size(usize),
+    /// [`ExpansionLimits::max_depth`] was exceeded
+    Depth(usize),
+    /// [`ExpansionLimits::max_ratio`] was exceeded
+    Ratio(usize),
+}
+
+impl std::fmt::Display for ExpansionLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ExpansionLimit::Size(size) => write!(f, "the maximum size of {} bytes", size),
+            ExpansionLimit::Depth(depth) => write!(f, "the maximum depth of {}", depth),
+            ExpansionLimit::Ratio(ratio) => write!(f, "the maximum ratio of {}", ratio),
+        }
+    }
+}
+
+/// Limits of the expansion of custom entities, that protect from documents
+/// that expand to a huge size from a small input, such as the
+/// ["billion laughs"](https://en.wikipedia.org/wiki/Billion_laughs_attack) attack.
+///
+/// Limits are checked by all unescaping functions. Predefined entities and
+/// character references are not counted. Set a limit to `usize::MAX` to disable it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExpansionLimits {
+    /// The maximum total size in bytes of the values of custom entities inserted
+    /// into one unescaped string (`8 MiB` by default)
+    pub max_size: usize,
+    /// The maximum nesting depth of references to other entities in the values of
+    /// entities declared in the DTD. An entity without references has depth 1
+    /// (`16` by default)
+    pub max_depth: usize,
+    /// The maximum ratio between the total size of inserted entity values and
+    /// the size of the escaped input. The ratio is checked only when more than
+    /// 1 KiB was inserted, so short strings can always reference entities
+    /// (`100` by default)
+    pub max_ratio: usize,
+}
+
+impl Default for ExpansionLimits {
+    fn default() -> Self {
+        Self {
+            max_size: 8 * 1024 * 1024,
+            max_depth: 16,
+            max_ratio: 100,
+        }
+    }
+}
+
+/// The size of inserted entity values after which [`ExpansionLimits::max_ratio`]
+/// is checked
+const RATIO_THRESHOLD: usize = 1024;
+
 /// Escapes a `&[u8]` and replaces all xml special characters (<, >, &, ', ") with their
 /// corresponding xml escaped value.
 pub fn escape(raw: &[u8]) -> Cow<[u8]> {
@@ -146,6 +215,152 @@ pub fn unescape_with<'a>(
     do_unescape(raw, Some(custom_entities))
 }
 
+/// Unescape a `&[u8]` and replaces all xml escaped characters ('&...;') into their corresponding
+/// value, using a dictionnary of custom entities and the specified expansion limits
+/// instead of the default ones.
+///
+/// # Pre-condition
+///
+/// The keys and values of `custom_entities`, if any, must be valid UTF-8.
+pub fn unescape_with_limits<'a>(
+    raw: &'a [u8],
+    custom_entities: &HashMap<Vec<u8>, Vec<u8>>,
+    limits: &ExpansionLimits,
+) -> Result<Cow<'a, [u8]>, EscapeError> {
+    do_unescape_with_limits(raw, Some(custom_entities), limits)
+}
+
+/// Expands references to other entities in the values of internal entities
+/// declared in the DTD, so each value can be inserted as is by [`do_unescape`].
+/// Character references and predefined entities in the values are also replaced.
+///
+/// If an entity is declared several times, the first declaration is used.
+pub(crate) fn resolve_entities(
+    declared: &[(&[u8], &[u8])],
+    limits: &ExpansionLimits,
+) -> Result<HashMap<Vec<u8>, Vec<u8>>, EscapeError> {
+    let mut resolver = EntityResolver::new(declared, limits);
+    for &(name, _) in declared {
+        resolver.resolve(name)?;
+    }
+    Ok(resolver.resolved)
+}
+
+/// Expands entities like [`resolve_entities`], but skips entities which values
+/// reference unknown entities, are recursive or are not well-formed, so an
+/// error is returned only if such an entity is referenced. Exceeding of limits
+/// is still an error.
+pub(crate) fn resolve_valid_entities(
+    declared: &[(&[u8], &[u8])],
+    limits: &ExpansionLimits,
+) -> Result<HashMap<Vec<u8>, Vec<u8>>, EscapeError> {
+    let mut resolver = EntityResolver::new(declared, limits);
+    for &(name, _) in declared {
+        if let Err(e @ EscapeError::ExpansionLimitExceeded(_)) = resolver.resolve(name) {
+            return Err(e);
+        }
+    }
+    Ok(resolver.resolved)
+}
+
+/// State of the [`resolve_entities`] function
+struct EntityResolver<'a, 'l> {
+    /// Declared values of entities
+    raw: HashMap<&'a [u8], &'a [u8]>,
+    limits: &'l ExpansionLimits,
+    /// Values of entities with expanded references
+    resolved: HashMap<Vec<u8>, Vec<u8>>,
+    /// Nesting depths of references of the resolved entities
+    depths: HashMap<&'a [u8], usize>,
+    /// Errors of entities which cannot be resolved, so they are not resolved
+    /// again when they are referenced from other entities. Exceeding of limits
+    /// depends on the referencing entities and is not remembered
+    failed: HashMap<&'a [u8], EscapeError>,
+    /// Entities which are currently resolved, used to detect recursion
+    stack: HashSet<&'a [u8]>,
+}
+
+impl<'a, 'l> EntityResolver<'a, 'l> {
+    fn new(declared: &[(&'a [u8], &'a [u8])], limits: &'l ExpansionLimits) -> Self {
+        let mut raw = HashMap::new();
+        for &(name, value) in declared {
+            raw.entry(name).or_insert(value);
+        }
+        Self {
+            raw,
+            limits,
+            resolved: HashMap::new(),
+            depths: HashMap::new(),
+            failed: HashMap::new(),
+            stack: HashSet::new(),
+        }
+    }
+
+    /// Resolves the entity `name` after all entities referenced from its value.
+    /// Returns the nesting depth of references of the entity
+    fn resolve(&mut self, name: &'a [u8]) -> Result<usize, EscapeError> {
+        if let Some(&depth) = self.depths.get(name) {
+            return Ok(depth);
+        }
+        if let Some(e) = self.failed.get(name) {
+            return Err(e.clone());
+        }
+        if self.stack.contains(name) {
+            return Err(EscapeError::RecursiveEntity(
+                String::from_utf8_lossy(name).into_owned(),
+            ));
+        }
+        // Each entity in the stack references the next one, so the depth of
+        // the first one would exceed the limit. Checked before the recursion,
+        // so the limit also bounds the depth of the recursion
+        if self.stack.len() >= self.limits.max_depth {
+            return Err(EscapeError::ExpansionLimitExceeded(ExpansionLimit::Depth(
+                self.limits.max_depth,
+            )));
+        }
+
+        self.stack.insert(name);
+        let result = self.resolve_value(name);
+        self.stack.remove(name);
+        match result {
+            Err(EscapeError::ExpansionLimitExceeded(_)) => {}
+            Err(ref e) => {
+                self.failed.insert(name, e.clone());
+            }
+            Ok(depth) => {
+                self.depths.insert(name, depth);
+            }
+        }
+        result
+    }
+
+    /// Resolves references in the value of the entity `name`, which is in the
+    /// stack, and expands the value
+    fn resolve_value(&mut self, name: &'a [u8]) -> Result<usize, EscapeError> {
+        let value = self.raw[name];
+
+        let mut depth = 1;
+        for start in memchr::memchr_iter(b'&', value) {
+            if let Some(len) = memchr::memchr(b';', &value[start..]) {
+                let reference = &value[start + 1..start + len];
+                if self.raw.contains_key(reference) {
+                    depth = depth.max(self.resolve(reference)? + 1);
+                }
+            }
+        }
+        if depth > self.limits.max_depth {
+            return Err(EscapeError::ExpansionLimitExceeded(ExpansionLimit::Depth(
+                self.limits.max_depth,
+            )));
+        }
+
+        let unescaped =
+            do_unescape_with_limits(value, Some(&self.resolved), self.limits)?.into_owned();
+        self.resolved.insert(name.to_vec(), unescaped);
+        Ok(depth)
+    }
+}
+
 /// Unescape a `&[u8]` and replaces all xml escaped characters ('&...;') into their corresponding
 /// value, using an optional dictionary of custom entities.
 ///
@@ -155,8 +370,25 @@ pub fn unescape_with<'a>(
 pub fn do_unescape<'a>(
     raw: &'a [u8],
     custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
+) -> Result<Cow<'a, [u8]>, EscapeError> {
+    do_unescape_with_limits(raw, custom_entities, &ExpansionLimits::default())
+}
+
+/// Unescape a `&[u8]` and replaces all xml escaped characters ('&...;') into their corresponding
+/// value, using an optional dictionary of custom entities, which expansion is
+/// restricted by `limits`.
+///
+/// # Pre-condition
+///
+/// The keys and values of `custom_entities`, if any, must be valid UTF-8.
+pub fn do_unescape_with_limits<'a>(
+    raw: &'a [u8],
+    custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
+    limits: &ExpansionLimits,
 ) -> Result<Cow<'a, [u8]>, EscapeError> {
     let mut unescaped = None;
+    // Total size of inserted values of custom entities
+    let mut expanded = 0usize;
     let mut last_end = 0;
     let mut iter = memchr::memchr2_iter(b'&', b';', raw);
     while let Some(start) = iter.by_ref().find(|p| raw[*p] == b'&') {
@@ -176,6 +408,17 @@ pub fn do_unescape<'a>(
                 } else if pat.starts_with(b"#") {
                     push_utf8(unescaped, parse_number(&pat[1..], start..end)?);
                 } else if let Some(value) = custom_entities.and_then(|hm| hm.get(pat)) {
+                    expanded = expanded.saturating_add(value.len());
+                    if expanded > limits.max_size {
+                        return Err(EscapeError::ExpansionLimitExceeded(ExpansionLimit::Size(
+                            limits.max_size,
+                        )));
+                    }
+                    if expanded > RATIO_THRESHOLD && expanded / raw.len() > limits.max_ratio {
+                        return Err(EscapeError::ExpansionLimitExceeded(ExpansionLimit::Ratio(
+                            limits.max_ratio,
+                        )));
+                    }
                     unescaped.extend_from_slice(&value);
                 } else {
                     return Err(EscapeError::UnrecognizedSymbol(
@@ -1754,6 +1997,192 @@ fn test_unescape_with() {
     assert!(unescape_with(b"&fop;", &custom_entities).is_err());
 }
 
+#[test]
+fn test_resolve_entities() {
+    let limits = ExpansionLimits::default();
+    let entities = resolve_entities(
+        &[
+            (b"full", b"&greeting;, &name;&#33;"),
+            (b"greeting", b"Hello"),
+            (b"name", b"&lt;World&gt;"),
+            (b"name", b"redeclared"),
+        ],
+        &limits,
+    )
+    .unwrap();
+    assert_eq!(entities.len(), 3);
+    assert_eq!(entities[&b"full".to_vec()], b"Hello, <World>!");
+    assert_eq!(entities[&b"greeting".to_vec()], b"Hello");
+    assert_eq!(entities[&b"name".to_vec()], b"<World>");
+
+    match resolve_entities(&[(b"a", b"&b;"), (b"b", b"&c;"), (b"c", b"&a;")], &limits) {
+        Err(EscapeError::RecursiveEntity(name)) => assert_eq!(name, "a"),
+        x => panic!("Expected `RecursiveEntity`, but got {:?}", x),
+    }
+    match resolve_entities(&[(b"a", b"&undefined;")], &limits) {
+        Err(EscapeError::UnrecognizedSymbol(..)) => {}
+        x => panic!("Expected `UnrecognizedSymbol`, but got {:?}", x),
+    }
+}
+
+#[test]
+fn test_resolve_valid_entities() {
+    let limits = ExpansionLimits::default();
+    let entities = resolve_valid_entities(
+        &[
+            (b"a", b"&b;"),
+            (b"b", b"&c;"),
+            (b"c", b"&a;"),
+            (b"undefined", b"&undefined;"),
+            (b"broken", b"&x"),
+            (b"uses_broken", b"&broken;"),
+            (b"valid", b"&#33;"),
+        ],
+        &limits,
+    )
+    .unwrap();
+    assert_eq!(entities.len(), 1);
+    assert_eq!(entities[&b"valid".to_vec()], b"!");
+
+    let limits = ExpansionLimits {
+        max_depth: 1,
+        ..ExpansionLimits::default()
+    };
+    match resolve_valid_entities(&[(b"a", b"a"), (b"b", b"&a;")], &limits) {
+        Err(EscapeError::ExpansionLimitExceeded(ExpansionLimit::Depth(1))) => {}
+        x => panic!("Expected depth limit error, but got {:?}", x),
+    }
+}
+
+/// Long chains of references neither overflow the stack nor are resolved
+/// several times
+#[test]
+fn test_resolve_long_chains() {
+    let limits = ExpansionLimits::default();
+    let names: Vec<_> = (0..10_000).map(|i| format!("e{}", i)).collect();
+    let values: Vec<_> = (0..10_000)
+        .map(|i| match i {
+            0 => "&undefined;".to_string(),
+            i => format!("&e{};", i - 1),
+        })
+        .collect();
+    let mut declared: Vec<_> = names
+        .iter()
+        .zip(&values)
+        .map(|(name, value)| (name.as_bytes(), value.as_bytes()))
+        .collect();
+
+    // Each entity references a previously resolved one, so the chain is
+    // resolved without the recursion
+    match resolve_entities(&declared, &limits) {
+        Err(EscapeError::UnrecognizedSymbol(..)) => {}
+        x => panic!("Expected `UnrecognizedSymbol`, but got {:?}", x),
+    }
+    assert_eq!(resolve_valid_entities(&declared, &limits).unwrap().len(), 0);
+
+    // The first entity references the whole chain
+    declared.reverse();
+    match resolve_entities(&declared, &limits) {
+        Err(EscapeError::ExpansionLimitExceeded(ExpansionLimit::Depth(16))) => {}
+        x => panic!("Expected depth limit error, but got {:?}", x),
+    }
+    match resolve_valid_entities(&declared, &limits) {
+        Err(EscapeError::ExpansionLimitExceeded(ExpansionLimit::Depth(16))) => {}
+        x => panic!("Expected depth limit error, but got {:?}", x),
+    }
+}
+
+#[test]
+fn test_expansion_limits() {
+    macro_rules! assert_limit {
+        ($result:expr, $limit:pat) => {
+            match $result {
+                Err(EscapeError::ExpansionLimitExceeded($limit)) => {}
+                x => panic!("Expected `{}`, but got {:?}", stringify!($limit), x),
+            }
+        };
+    }
+
+    // "Billion laughs": each entity references the previous one 10 times
+    let names: Vec<_> = (0..10).map(|i| format!("lol{}", i)).collect();
+    let values: Vec<_> = (0..10)
+        .map(|i| match i {
+            0 => "lol".to_string(),
+            _ => format!("&lol{};", i - 1).repeat(10),
+        })
+        .collect();
+    let declared: Vec<_> = names
+        .iter()
+        .zip(&values)
+        .map(|(n, v)| (n.as_bytes(), v.as_bytes()))
+        .collect();
+    let limits = ExpansionLimits::default();
+    assert_limit!(
+        resolve_entities(&declared, &limits),
+        ExpansionLimit::Ratio(100)
+    );
+    let limits = ExpansionLimits {
+        max_ratio: usize::MAX,
+        ..ExpansionLimits::default()
+    };
+    assert_limit!(
+        resolve_entities(&declared, &limits),
+        ExpansionLimit::Size(8388608)
+    );
+
+    // Depth does not depend on the order of declarations
+    let declared: &[(&[u8], &[u8])] = &[(b"a", b"a"), (b"b", b"&a;"), (b"c", b"&b;")];
+    let limits = ExpansionLimits {
+        max_depth: 2,
+        ..ExpansionLimits::default()
+    };
+    assert_limit!(
+        resolve_entities(declared, &limits),
+        ExpansionLimit::Depth(2)
+    );
+    let limits = ExpansionLimits {
+        max_depth: 3,
+        ..ExpansionLimits::default()
+    };
+    assert_eq!(resolve_entities(declared, &limits).unwrap().len(), 3);
+
+    let entities = vec![(b"big".to_vec(), vec![b'x'; 2000])]
+        .into_iter()
+        .collect();
+    let limits = ExpansionLimits {
+        max_size: 5000,
+        max_depth: 1,
+        max_ratio: 50,
+    };
+    // 2000 bytes from 5 bytes of input
+    assert_limit!(
+        unescape_with_limits(b"&big;", &entities, &limits),
+        ExpansionLimit::Ratio(50)
+    );
+    // 6000 bytes from 205 bytes of input
+    let raw = format!("{}{}", "x".repeat(190), "&big;".repeat(3));
+    assert_limit!(
+        unescape_with_limits(raw.as_bytes(), &entities, &limits),
+        ExpansionLimit::Size(5000)
+    );
+    // 4000 bytes from 200 bytes of input
+    let raw = format!("{}{}", "x".repeat(190), "&big;".repeat(2));
+    assert_eq!(
+        unescape_with_limits(raw.as_bytes(), &entities, &limits)
+            .unwrap()
+            .len(),
+        4190
+    );
+    // Ratio is not checked for short values
+    let entities = vec![(b"small".to_vec(), vec![b'x'; 1000])]
+        .into_iter()
+        .collect();
+    assert_eq!(
+        &*unescape_with(b"&small;", &entities).unwrap(),
+        &[b'x'; 1000][..]
+    );
+}
+
 #[test]
 fn test_escape() {
     assert_eq!(&*escape(b"test"), b"test");
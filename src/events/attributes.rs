@@ -3,13 +3,13 @@
 //! Provides an iterator over attributes key/value pairs
 
 use crate::errors::{Error, Result as XmlResult};
-use crate::escape::{do_unescape, escape};
+use crate::escape::{do_unescape, do_unescape_with_limits, escape};
 use crate::name::QName;
-use crate::reader::{is_whitespace, Reader};
+use crate::reader::{scan, Reader};
 use crate::utils::{write_byte_string, write_cow_string, Bytes};
 use std::fmt::{self, Debug, Display, Formatter};
 use std::iter::FusedIterator;
-use std::{borrow::Cow, collections::HashMap, io::BufRead, ops::Range};
+use std::{borrow::Cow, collections::HashMap, ops::Range};
 
 /// A struct representing a key/value XML attribute.
 ///
@@ -39,7 +39,13 @@ impl<'a> Attribute<'a> {
     ///
     /// This will allocate if the value contains any escape sequences.
     ///
-    /// See also [`unescaped_value_with_custom_entities()`](#method.unescaped_value_with_custom_entities)
+    /// Entities declared in the DTD are not known to the attribute, use
+    /// [`unescape_and_decode_value()`] to expand them or pass [`Reader::entities()`]
+    /// to [`unescaped_value_with_custom_entities()`].
+    ///
+    /// [`unescape_and_decode_value()`]: #method.unescape_and_decode_value
+    /// [`Reader::entities()`]: crate::Reader::entities
+    /// [`unescaped_value_with_custom_entities()`]: #method.unescaped_value_with_custom_entities
     pub fn unescaped_value(&self) -> XmlResult<Cow<[u8]>> {
         self.make_unescaped_value(None)
     }
@@ -81,7 +87,7 @@ impl<'a> Attribute<'a> {
     ///
     /// [`unescaped_value()`]: #method.unescaped_value
     /// [`Reader::decode()`]: ../../reader/struct.Reader.html#method.decode
-    pub fn unescape_and_decode_value<B: BufRead>(&self, reader: &Reader<B>) -> XmlResult<String> {
+    pub fn unescape_and_decode_value<B>(&self, reader: &Reader<B>) -> XmlResult<String> {
         self.do_unescape_and_decode_value(reader, None)
     }
 
@@ -99,7 +105,7 @@ impl<'a> Attribute<'a> {
     /// # Pre-condition
     ///
     /// The keys and values of `custom_entities`, if any, must be valid UTF-8.
-    pub fn unescape_and_decode_value_with_custom_entities<B: BufRead>(
+    pub fn unescape_and_decode_value_with_custom_entities<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: &HashMap<Vec<u8>, Vec<u8>>,
@@ -109,26 +115,34 @@ impl<'a> Attribute<'a> {
 
     /// The keys and values of `custom_entities`, if any, must be valid UTF-8.
     #[cfg(feature = "encoding")]
-    fn do_unescape_and_decode_value<B: BufRead>(
+    fn do_unescape_and_decode_value<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
     ) -> XmlResult<String> {
         let decoded = reader.decode(&*self.value);
-        let unescaped =
-            do_unescape(decoded.as_bytes(), custom_entities).map_err(Error::EscapeError)?;
+        let unescaped = do_unescape_with_limits(
+            decoded.as_bytes(),
+            Some(&reader.entities_with(custom_entities)),
+            &reader.expansion_limits,
+        )
+        .map_err(Error::EscapeError)?;
         String::from_utf8(unescaped.into_owned()).map_err(|e| Error::Utf8(e.utf8_error()))
     }
 
     #[cfg(not(feature = "encoding"))]
-    fn do_unescape_and_decode_value<B: BufRead>(
+    fn do_unescape_and_decode_value<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
     ) -> XmlResult<String> {
         let decoded = reader.decode(&*self.value)?;
-        let unescaped =
-            do_unescape(decoded.as_bytes(), custom_entities).map_err(Error::EscapeError)?;
+        let unescaped = do_unescape_with_limits(
+            decoded.as_bytes(),
+            Some(&reader.entities_with(custom_entities)),
+            &reader.expansion_limits,
+        )
+        .map_err(Error::EscapeError)?;
         String::from_utf8(unescaped.into_owned()).map_err(|e| Error::Utf8(e.utf8_error()))
     }
 
@@ -140,10 +154,7 @@ impl<'a> Attribute<'a> {
     /// 1. BytesText::unescaped()
     /// 2. Reader::decode(...)
     #[cfg(feature = "encoding")]
-    pub fn unescape_and_decode_without_bom<B: BufRead>(
-        &self,
-        reader: &mut Reader<B>,
-    ) -> XmlResult<String> {
+    pub fn unescape_and_decode_without_bom<B>(&self, reader: &mut Reader<B>) -> XmlResult<String> {
         self.do_unescape_and_decode_without_bom(reader, None)
     }
 
@@ -155,10 +166,7 @@ impl<'a> Attribute<'a> {
     /// 1. BytesText::unescaped()
     /// 2. Reader::decode(...)
     #[cfg(not(feature = "encoding"))]
-    pub fn unescape_and_decode_without_bom<B: BufRead>(
-        &self,
-        reader: &Reader<B>,
-    ) -> XmlResult<String> {
+    pub fn unescape_and_decode_without_bom<B>(&self, reader: &Reader<B>) -> XmlResult<String> {
         self.do_unescape_and_decode_without_bom(reader, None)
     }
 
@@ -174,7 +182,7 @@ impl<'a> Attribute<'a> {
     ///
     /// The keys and values of `custom_entities`, if any, must be valid UTF-8.
     #[cfg(feature = "encoding")]
-    pub fn unescape_and_decode_without_bom_with_custom_entities<B: BufRead>(
+    pub fn unescape_and_decode_without_bom_with_custom_entities<B>(
         &self,
         reader: &mut Reader<B>,
         custom_entities: &HashMap<Vec<u8>, Vec<u8>>,
@@ -194,7 +202,7 @@ impl<'a> Attribute<'a> {
     ///
     /// The keys and values of `custom_entities`, if any, must be valid UTF-8.
     #[cfg(not(feature = "encoding"))]
-    pub fn unescape_and_decode_without_bom_with_custom_entities<B: BufRead>(
+    pub fn unescape_and_decode_without_bom_with_custom_entities<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: &HashMap<Vec<u8>, Vec<u8>>,
@@ -203,26 +211,34 @@ impl<'a> Attribute<'a> {
     }
 
     #[cfg(feature = "encoding")]
-    fn do_unescape_and_decode_without_bom<B: BufRead>(
+    fn do_unescape_and_decode_without_bom<B>(
         &self,
         reader: &mut Reader<B>,
         custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
     ) -> XmlResult<String> {
         let decoded = reader.decode_without_bom(&*self.value);
-        let unescaped =
-            do_unescape(decoded.as_bytes(), custom_entities).map_err(Error::EscapeError)?;
+        let unescaped = do_unescape_with_limits(
+            decoded.as_bytes(),
+            Some(&reader.entities_with(custom_entities)),
+            &reader.expansion_limits,
+        )
+        .map_err(Error::EscapeError)?;
         String::from_utf8(unescaped.into_owned()).map_err(|e| Error::Utf8(e.utf8_error()))
     }
 
     #[cfg(not(feature = "encoding"))]
-    fn do_unescape_and_decode_without_bom<B: BufRead>(
+    fn do_unescape_and_decode_without_bom<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
     ) -> XmlResult<String> {
         let decoded = reader.decode_without_bom(&*self.value)?;
-        let unescaped =
-            do_unescape(decoded.as_bytes(), custom_entities).map_err(Error::EscapeError)?;
+        let unescaped = do_unescape_with_limits(
+            decoded.as_bytes(),
+            Some(&reader.entities_with(custom_entities)),
+            &reader.expansion_limits,
+        )
+        .map_err(Error::EscapeError)?;
         String::from_utf8(unescaped.into_owned()).map_err(|e| Error::Utf8(e.utf8_error()))
     }
 }
@@ -301,6 +317,10 @@ impl<'a> From<Attr<&'a [u8]>> for Attribute<'a> {
 pub struct Attributes<'a> {
     /// slice of `Element` corresponding to attributes
     bytes: &'a [u8],
+    /// Position of `bytes` in the document, added to positions in errors
+    offset: usize,
+    /// Positions of the bytes moved when the reader rewrote the buffer
+    moves: &'a [(usize, usize)],
     /// Iterator state, independent from the actual source of bytes
     state: IterState,
 }
@@ -310,6 +330,8 @@ impl<'a> Attributes<'a> {
     pub fn new(buf: &'a [u8], pos: usize) -> Self {
         Self {
             bytes: buf,
+            offset: 0,
+            moves: &[],
             state: IterState::new(pos, false),
         }
     }
@@ -318,10 +340,46 @@ impl<'a> Attributes<'a> {
     pub fn html(buf: &'a [u8], pos: usize) -> Self {
         Self {
             bytes: buf,
+            offset: 0,
+            moves: &[],
             state: IterState::new(pos, true),
         }
     }
 
+    /// Sets the position of the buffer in the document, so errors will report
+    /// document-absolute positions
+    #[inline]
+    pub(crate) fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the positions of bytes of the buffer, which was rewritten by the reader
+    #[inline]
+    pub(crate) fn with_moves(mut self, moves: &'a Moves) -> Self {
+        self.moves = &moves.0;
+        self
+    }
+
+    /// Returns the position in the document of the byte at `pos` in the buffer
+    fn document_position(&self, pos: usize) -> usize {
+        source_position(self.moves, pos) + self.offset
+    }
+
+    /// Returns the range in the document of the bytes in `range` of the buffer.
+    /// The range ends at the byte which follows it in the buffer
+    fn document_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.document_position(range.start);
+        let end = if range.is_empty() {
+            start
+        } else if range.end < self.bytes.len() {
+            self.document_position(range.end)
+        } else {
+            self.document_position(range.end - 1) + 1
+        };
+        start..end
+    }
+
     /// Changes whether attributes should be checked for uniqueness.
     ///
     /// The XML specification requires attribute keys in the same element to be unique. This check
@@ -332,6 +390,44 @@ impl<'a> Attributes<'a> {
         self.state.check_duplicates = val;
         self
     }
+
+    /// Converts this iterator into an iterator that yields each attribute
+    /// together with the byte ranges of its key and value.
+    ///
+    /// Ranges are positions in the document for attributes of a [`BytesStart`]
+    /// read by the [`Reader`] and positions in the buffer otherwise. If the reader
+    /// [normalized] the tag or removed malformed attributes from it in the [recovery]
+    /// mode, ranges point to the original bytes in the document.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::Reader;
+    ///
+    /// let xml = r#"<tag key="value" flag='yes'/>"#;
+    /// let mut reader = Reader::from_str(xml);
+    /// reader.trim_text(true);
+    /// let event = reader.read_event_unbuffered().unwrap();
+    /// let spans: Vec<_> = match event {
+    ///     Event::Empty(e) => e.attributes().with_spans().map(|a| a.unwrap().1).collect(),
+    ///     e => panic!("Unexpected event {:?}", e),
+    /// };
+    /// assert_eq!(&xml[spans[0].key.clone()], "key");
+    /// assert_eq!(&xml[spans[0].value.clone()], "value");
+    /// assert_eq!(&xml[spans[1].key.clone()], "flag");
+    /// assert_eq!(&xml[spans[1].value.clone()], "yes");
+    /// ```
+    ///
+    /// [`BytesStart`]: crate::events::BytesStart
+    /// [`Reader`]: crate::Reader
+    /// [normalized]: crate::Reader::normalize
+    /// [recovery]: crate::Reader::recover
+    #[inline]
+    pub fn with_spans(self) -> SpannedAttributes<'a> {
+        SpannedAttributes { inner: self }
+    }
 }
 
 impl<'a> Iterator for Attributes<'a> {
@@ -342,20 +438,124 @@ impl<'a> Iterator for Attributes<'a> {
         match self.state.next(self.bytes) {
             None => None,
             Some(Ok(a)) => Some(Ok(a.map(|range| &self.bytes[range]).into())),
-            Some(Err(e)) => Some(Err(e)),
+            Some(Err(e)) => Some(Err(e.locate(|pos| self.document_position(pos)))),
         }
     }
 }
 
 impl<'a> FusedIterator for Attributes<'a> {}
 
+/// Byte ranges of a key and a value of an attribute, yielded by [`SpannedAttributes`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AttrSpan {
+    /// Position of the attribute key
+    pub key: Range<usize>,
+    /// Position of the attribute value without quotes. For an attribute without
+    /// value this is an empty range right after the key
+    pub value: Range<usize>,
+}
+
+impl AttrSpan {
+    fn new(attr: Attr<Range<usize>>, attributes: &Attributes) -> Self {
+        let (key, value) = match attr {
+            // Quotes are never rewritten by the reader, so the value is what
+            // is between them in the document, even if it was normalized
+            Attr::DoubleQ(key, value) | Attr::SingleQ(key, value) => {
+                let start = attributes.document_position(value.start - 1) + 1;
+                (key, start..attributes.document_position(value.end))
+            }
+            Attr::Unquoted(key, value) => (key, attributes.document_range(value)),
+            Attr::Empty(key) => {
+                let end = attributes.document_range(key.clone()).end;
+                (key, end..end)
+            }
+        };
+        Self {
+            key: attributes.document_range(key),
+            value,
+        }
+    }
+}
+
+/// Iterator over XML attributes and their positions, created by
+/// [`Attributes::with_spans()`].
+///
+/// Yields `Result<(Attribute, AttrSpan)>`. Errors are the same as in [`Attributes`].
+#[derive(Clone, Debug)]
+pub struct SpannedAttributes<'a> {
+    inner: Attributes<'a>,
+}
+
+impl<'a> Iterator for SpannedAttributes<'a> {
+    type Item = Result<(Attribute<'a>, AttrSpan), AttrError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let inner = &mut self.inner;
+        match inner.state.next(inner.bytes) {
+            None => None,
+            Some(Ok(a)) => {
+                let attribute = a.clone().map(|range| &inner.bytes[range]).into();
+                Some(Ok((attribute, AttrSpan::new(a, inner))))
+            }
+            Some(Err(e)) => Some(Err(e.locate(|pos| inner.document_position(pos)))),
+        }
+    }
+}
+
+impl<'a> FusedIterator for SpannedAttributes<'a> {}
+
+/// Positions of bytes of a start tag content, rewritten by the reader, in the
+/// original content. Consists of pairs of a position in the rewritten content
+/// and the original position of the byte at it. Bytes up to the next pair follow
+/// that byte in the original content, bytes before the first pair are not moved.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Moves(Vec<(usize, usize)>);
+
+impl Moves {
+    /// Records that the byte at `pos` of the rewritten content was at `source`
+    /// in the original content. Bytes should be recorded in the order of their
+    /// positions, recording a byte forgets the bytes recorded after it.
+    pub fn record(&mut self, pos: usize, source: usize) {
+        while let Some(&(last, _)) = self.0.last() {
+            if last < pos {
+                break;
+            }
+            self.0.pop();
+        }
+        if source_position(&self.0, pos) != source {
+            self.0.push((pos, source));
+        }
+    }
+
+    /// Returns the original position of the byte at `pos`
+    pub fn source(&self, pos: usize) -> usize {
+        source_position(&self.0, pos)
+    }
+}
+
+fn source_position(moves: &[(usize, usize)], pos: usize) -> usize {
+    match moves.binary_search_by_key(&pos, |(pos, _)| *pos) {
+        Ok(i) => moves[i].1,
+        Err(0) => pos,
+        Err(i) => moves[i - 1].1 + (pos - moves[i - 1].0),
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Errors that can be raised during parsing attributes.
 ///
 /// Recovery position in examples shows the position from which parsing of the
 /// next attribute will be attempted.
-#[derive(Debug, PartialEq)]
+///
+/// Positions are relative to the start of the owning tag, as in examples, when
+/// attributes are parsed from a manually created [`BytesStart`]. For elements
+/// read by the [`Reader`] positions are absolute positions in the document.
+///
+/// [`BytesStart`]: crate::events::BytesStart
+/// [`Reader`]: crate::Reader
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum AttrError {
     /// Attribute key was not followed by `=`, position relative to the start of
     /// the owning tag is provided.
@@ -440,6 +640,24 @@ pub enum AttrError {
     Duplicated(usize, usize),
 }
 
+impl AttrError {
+    /// Moves all positions in the error by `offset`
+    pub(crate) fn shift(self, offset: usize) -> Self {
+        self.locate(|pos| pos + offset)
+    }
+
+    /// Replaces all positions in the error with positions returned by `position`
+    fn locate(self, position: impl Fn(usize) -> usize) -> Self {
+        match self {
+            Self::ExpectedEq(pos) => Self::ExpectedEq(position(pos)),
+            Self::ExpectedValue(pos) => Self::ExpectedValue(position(pos)),
+            Self::UnquotedValue(pos) => Self::UnquotedValue(position(pos)),
+            Self::ExpectedQuote(pos, quote) => Self::ExpectedQuote(position(pos), quote),
+            Self::Duplicated(pos1, pos2) => Self::Duplicated(position(pos1), position(pos2)),
+        }
+    }
+}
+
 impl Display for AttrError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
@@ -594,6 +812,15 @@ impl<T> From<Attr<T>> for (T, Option<T>) {
 
 type AttrResult = Result<Attr<Range<usize>>, AttrError>;
 
+/// Returns the position of the first byte at or after `from` found by `find`
+#[inline]
+fn find_from<F>(slice: &[u8], from: usize, find: F) -> Option<usize>
+where
+    F: FnOnce(&[u8]) -> Option<usize>,
+{
+    find(&slice[from..]).map(|i| from + i)
+}
+
 #[derive(Clone, Copy, Debug)]
 enum State {
     /// Iteration finished, iterator will return `None` to all [`IterState::next`]
@@ -652,60 +879,41 @@ impl IterState {
     /// Skip all characters up to first space symbol or end-of-input
     #[inline]
     fn skip_value(&self, slice: &[u8], offset: usize) -> Option<usize> {
-        let mut iter = (offset..).zip(slice[offset..].iter());
-
-        match iter.find(|(_, &b)| is_whitespace(b)) {
-            // Input: `    key  =  value `
-            //                     |    ^
-            //                offset    e
-            Some((e, _)) => Some(e),
-            // Input: `    key  =  value`
-            //                     |    ^
-            //                offset    e = len()
-            None => None,
-        }
+        // Input: `    key  =  value `
+        //                     |    ^
+        //                offset    e
+        // Input: `    key  =  value`
+        //                     |    ^
+        //                offset    e = len()
+        find_from(slice, offset, scan::find_whitespace)
     }
 
     /// Skip all characters up to first space symbol or end-of-input
     #[inline]
     fn skip_eq_value(&self, slice: &[u8], offset: usize) -> Option<usize> {
-        let mut iter = (offset..).zip(slice[offset..].iter());
-
         // Skip all up to the quote and get the quote type
-        let quote = match iter.find(|(_, &b)| !is_whitespace(b)) {
+        let quote = match find_from(slice, offset, scan::skip_whitespace) {
             // Input: `    key  =  "`
             //                  |  ^
             //             offset
-            Some((_, b'"')) => b'"',
-            // Input: `    key  =  '`
-            //                  |  ^
-            //             offset
-            Some((_, b'\'')) => b'\'',
+            Some(s) if slice[s] == b'"' || slice[s] == b'\'' => s,
 
             // Input: `    key  =  x`
             //                  |  ^
             //             offset
-            Some((offset, _)) => return self.skip_value(slice, offset),
+            Some(offset) => return self.skip_value(slice, offset),
             // Input: `    key  =  `
             //                  |  ^
             //             offset
             None => return None,
         };
 
-        match iter.find(|(_, &b)| b == quote) {
-            // Input: `    key  =  "   "`
-            //                         ^
-            Some((e, b'"')) => Some(e),
-            // Input: `    key  =  '   '`
-            //                         ^
-            Some((e, _)) => Some(e),
-
-            // Input: `    key  =  "   `
-            // Input: `    key  =  '   `
-            //                         ^
-            // Closing quote not found
-            None => None,
-        }
+        // Input: `    key  =  "   "`
+        //                         ^
+        // Input: `    key  =  "   `
+        //                         ^
+        // Closing quote not found
+        memchr::memchr(slice[quote], &slice[quote + 1..]).map(|e| quote + 1 + e)
     }
 
     #[inline]
@@ -756,16 +964,13 @@ impl IterState {
     }
 
     pub fn next(&mut self, slice: &[u8]) -> Option<AttrResult> {
-        let mut iter = match self.recover(slice) {
-            Some(offset) => (offset..).zip(slice[offset..].iter()),
-            None => return None,
-        };
+        let offset = self.recover(slice)?;
 
         // Index where next key started
-        let start_key = match iter.find(|(_, &b)| !is_whitespace(b)) {
+        let start_key = match find_from(slice, offset, scan::skip_whitespace) {
             // Input: `    key`
             //             ^
-            Some((s, _)) => s,
+            Some(s) => s,
             // Input: `    `
             //             ^
             None => {
@@ -775,24 +980,24 @@ impl IterState {
             }
         };
         // Span of a key
-        let (key, offset) = match iter.find(|(_, &b)| b == b'=' || is_whitespace(b)) {
+        let (key, offset) = match find_from(slice, start_key + 1, scan::find_name_end) {
             // Input: `    key=`
             //             |  ^
             //             s  e
-            Some((e, b'=')) => (start_key..e, e),
+            Some(e) if slice[e] == b'=' => (start_key..e, e),
 
             // Input: `    key `
             //                ^
-            Some((e, _)) => match iter.find(|(_, &b)| !is_whitespace(b)) {
+            Some(e) => match find_from(slice, e + 1, scan::skip_whitespace) {
                 // Input: `    key  =`
                 //             |  | ^
                 //     start_key  e
-                Some((offset, b'=')) => (start_key..e, offset),
+                Some(offset) if slice[offset] == b'=' => (start_key..e, offset),
                 // Input: `    key  x`
                 //             |  | ^
                 //     start_key  e
                 // If HTML-like attributes is allowed, this is the result, otherwise error
-                Some((offset, _)) => {
+                Some(offset) => {
                     // In any case, recovering is not required
                     self.state = State::Next(offset);
                     return self.key_only(slice, start_key..e, offset);
@@ -831,26 +1036,26 @@ impl IterState {
         ////////////////////////////////////////////////////////////////////////
 
         // Gets the position of quote and quote type
-        let (start_value, quote) = match iter.find(|(_, &b)| !is_whitespace(b)) {
+        let (start_value, quote) = match find_from(slice, offset + 1, scan::skip_whitespace) {
             // Input: `    key  =  "`
             //                     ^
-            Some((s, b'"')) => (s + 1, b'"'),
+            Some(s) if slice[s] == b'"' => (s + 1, b'"'),
             // Input: `    key  =  '`
             //                     ^
-            Some((s, b'\'')) => (s + 1, b'\''),
+            Some(s) if slice[s] == b'\'' => (s + 1, b'\''),
 
             // Input: `    key  =  x`
             //                     ^
             // If HTML-like attributes is allowed, this is the start of the value
-            Some((s, _)) if self.html => {
+            Some(s) if self.html => {
                 // We do not check validity of attribute value characters as required
                 // according to https://html.spec.whatwg.org/#unquoted. It can be done
                 // during validation phase
-                let end = match iter.find(|(_, &b)| is_whitespace(b)) {
+                let end = match find_from(slice, s + 1, scan::find_whitespace) {
                     // Input: `    key  =  value `
                     //                     |    ^
                     //                     s    e
-                    Some((e, _)) => e,
+                    Some(e) => e,
                     // Input: `    key  =  value`
                     //                     |    ^
                     //                     s    e = len()
@@ -861,7 +1066,7 @@ impl IterState {
             }
             // Input: `    key  =  x`
             //                     ^
-            Some((s, _)) => {
+            Some(s) => {
                 self.state = State::SkipValue(s);
                 return Some(Err(AttrError::UnquotedValue(s)));
             }
@@ -875,13 +1080,13 @@ impl IterState {
             }
         };
 
-        match iter.find(|(_, &b)| b == quote) {
+        match memchr::memchr(quote, &slice[start_value..]) {
             // Input: `    key  =  "   "`
             //                         ^
-            Some((e, b'"')) => self.double_q(key, start_value..e),
+            Some(e) if quote == b'"' => self.double_q(key, start_value..start_value + e),
             // Input: `    key  =  '   '`
             //                         ^
-            Some((e, _)) => self.single_q(key, start_value..e),
+            Some(e) => self.single_q(key, start_value..start_value + e),
 
             // Input: `    key  =  "   `
             // Input: `    key  =  '   `
@@ -890,7 +1095,7 @@ impl IterState {
             None => {
                 // Because we reach end-of-input, stop iteration on next call
                 self.state = State::Done;
-                return Some(Err(AttrError::ExpectedQuote(slice.len(), quote)));
+                Some(Err(AttrError::ExpectedQuote(slice.len(), quote)))
             }
         }
     }
@@ -1580,6 +1785,65 @@ mod xml {
         assert_eq!(iter.next(), None);
         assert_eq!(iter.next(), None);
     }
+
+    /// Spans of keys and values are reported relative to the buffer
+    #[test]
+    fn spans() {
+        let mut iter = Attributes::new(br#"tag key='value' regular="attribute""#, 3).with_spans();
+
+        assert_eq!(
+            iter.next(),
+            Some(Ok((
+                Attribute {
+                    key: QName(b"key"),
+                    value: Cow::Borrowed(b"value"),
+                },
+                AttrSpan {
+                    key: 4..7,
+                    value: 9..14,
+                },
+            )))
+        );
+        assert_eq!(
+            iter.next(),
+            Some(Ok((
+                Attribute {
+                    key: QName(b"regular"),
+                    value: Cow::Borrowed(b"attribute"),
+                },
+                AttrSpan {
+                    key: 16..23,
+                    value: 25..34,
+                },
+            )))
+        );
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    /// Spans and error positions are shifted by the offset of the buffer
+    #[test]
+    fn spans_with_offset() {
+        let mut iter = Attributes::new(br#"tag key='value' key="again""#, 3)
+            .with_offset(10)
+            .with_spans();
+
+        assert_eq!(
+            iter.next(),
+            Some(Ok((
+                Attribute {
+                    key: QName(b"key"),
+                    value: Cow::Borrowed(b"value"),
+                },
+                AttrSpan {
+                    key: 14..17,
+                    value: 19..24,
+                },
+            )))
+        );
+        assert_eq!(iter.next(), Some(Err(AttrError::Duplicated(26, 14))));
+        assert_eq!(iter.next(), None);
+    }
 }
 
 /// Checks, how parsing of HTML-style attributes works. Each attribute can be
@@ -2353,4 +2617,38 @@ mod html {
         assert_eq!(iter.next(), None);
         assert_eq!(iter.next(), None);
     }
+
+    /// Attributes without value have an empty value span after the key
+    #[test]
+    fn spans() {
+        let mut iter = Attributes::html(br#"tag key=value flag"#, 3).with_spans();
+
+        assert_eq!(
+            iter.next(),
+            Some(Ok((
+                Attribute {
+                    key: QName(b"key"),
+                    value: Cow::Borrowed(b"value"),
+                },
+                AttrSpan {
+                    key: 4..7,
+                    value: 8..13,
+                },
+            )))
+        );
+        assert_eq!(
+            iter.next(),
+            Some(Ok((
+                Attribute {
+                    key: QName(b"flag"),
+                    value: Cow::Borrowed(&[]),
+                },
+                AttrSpan {
+                    key: 14..18,
+                    value: 18..18,
+                },
+            )))
+        );
+        assert_eq!(iter.next(), None);
+    }
 }
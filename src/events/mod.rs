@@ -38,13 +38,14 @@ pub mod attributes;
 
 #[cfg(feature = "encoding_rs")]
 use encoding_rs::Encoding;
-use std::{borrow::Cow, collections::HashMap, io::BufRead, ops::Deref, str::from_utf8};
+use std::{borrow::Cow, collections::HashMap, ops::Deref, str::from_utf8};
 
-use crate::escape::{do_unescape, escape, partial_escape};
+use crate::dtd::DocType;
+use crate::escape::{do_unescape, do_unescape_with_limits, escape, partial_escape};
 use crate::name::{LocalName, QName};
 use crate::utils::write_cow_string;
 use crate::{errors::Error, errors::Result, reader::Reader};
-use attributes::{Attribute, Attributes};
+use attributes::{Attribute, Attributes, Moves};
 
 #[cfg(feature = "serialize")]
 use crate::escape::EscapeError;
@@ -60,12 +61,17 @@ use crate::escape::EscapeError;
 /// [`local_name`]: #method.local_name
 /// [`unescaped`]: #method.unescaped
 /// [`attributes`]: #method.attributes
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq)]
 pub struct BytesStart<'a> {
     /// content of the element, before any utf8 conversion
     buf: Cow<'a, [u8]>,
     /// end of the element name, the name starts at that the start of `buf`
     name_len: usize,
+    /// position of the start of `buf` in the document, if the element was read
+    /// by the `Reader`, otherwise 0. Used to report positions in [`AttrError`]s
+    offset: usize,
+    /// positions of bytes in the document, if the reader rewrote `buf`
+    moves: Moves,
 }
 
 impl<'a> BytesStart<'a> {
@@ -79,6 +85,8 @@ impl<'a> BytesStart<'a> {
         BytesStart {
             buf: Cow::Borrowed(content),
             name_len,
+            offset: 0,
+            moves: Moves::default(),
         }
     }
 
@@ -100,6 +108,8 @@ impl<'a> BytesStart<'a> {
         BytesStart {
             buf: Cow::Owned(content.into()),
             name_len,
+            offset: 0,
+            moves: Moves::default(),
         }
     }
 
@@ -112,17 +122,52 @@ impl<'a> BytesStart<'a> {
         BytesStart {
             name_len: content.len(),
             buf: Cow::Owned(content),
+            offset: 0,
+            moves: Moves::default(),
         }
     }
 
+    /// Sets the position of the element content in the document
+    #[inline]
+    pub(crate) fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the positions of bytes in the document, if the content was rewritten
+    #[inline]
+    pub(crate) fn with_moves(mut self, moves: Moves) -> Self {
+        self.moves = moves;
+        self
+    }
+
+    /// Returns the positions of bytes in the document, if the content was rewritten
+    #[inline]
+    pub(crate) fn moves(&self) -> &Moves {
+        &self.moves
+    }
+
+    /// Replaces the content of the element with a normalized one, which has
+    /// the same name
+    #[inline]
+    pub(crate) fn with_content(self, content: Vec<u8>, moves: Moves) -> BytesStart<'static> {
+        Self::owned(content, self.name_len)
+            .with_offset(self.offset)
+            .with_moves(moves)
+    }
+
     /// Converts the event into an owned event.
     pub fn into_owned(self) -> BytesStart<'static> {
         Self::owned(self.buf.into_owned(), self.name_len)
+            .with_offset(self.offset)
+            .with_moves(self.moves)
     }
 
     /// Converts the event into an owned event without taking ownership of Event
     pub fn to_owned(&self) -> BytesStart<'static> {
         Self::owned(self.buf.to_owned(), self.name_len)
+            .with_offset(self.offset)
+            .with_moves(self.moves.clone())
     }
 
     /// Converts the event into a borrowed event. Most useful when paired with [`to_end`].
@@ -151,6 +196,8 @@ impl<'a> BytesStart<'a> {
     /// [`to_end`]: #method.to_end
     pub fn to_borrowed(&self) -> BytesStart {
         BytesStart::borrowed(&self.buf, self.name_len)
+            .with_offset(self.offset)
+            .with_moves(self.moves.clone())
     }
 
     /// Creates new paired close tag
@@ -176,9 +223,12 @@ impl<'a> BytesStart<'a> {
     /// Gets the unescaped tag name.
     ///
     /// XML escape sequences like "`&lt;`" will be replaced by their unescaped characters like
-    /// "`<`".
+    /// "`<`". Entities declared in the DTD are not known to the event, use
+    /// [`unescape_and_decode()`] to expand them.
     ///
     /// See also [`unescaped_with_custom_entities()`](#method.unescaped_with_custom_entities)
+    ///
+    /// [`unescape_and_decode()`]: #method.unescape_and_decode
     #[inline]
     pub fn unescaped(&self) -> Result<Cow<[u8]>> {
         self.make_unescaped(None)
@@ -222,7 +272,7 @@ impl<'a> BytesStart<'a> {
     /// [`unescaped()`]: #method.unescaped
     /// [`Reader::decode()`]: ../reader/struct.Reader.html#method.decode
     #[inline]
-    pub fn unescape_and_decode<B: BufRead>(&self, reader: &Reader<B>) -> Result<String> {
+    pub fn unescape_and_decode<B>(&self, reader: &Reader<B>) -> Result<String> {
         self.do_unescape_and_decode_with_custom_entities(reader, None)
     }
 
@@ -241,7 +291,7 @@ impl<'a> BytesStart<'a> {
     ///
     /// The keys and values of `custom_entities`, if any, must be valid UTF-8.
     #[inline]
-    pub fn unescape_and_decode_with_custom_entities<B: BufRead>(
+    pub fn unescape_and_decode_with_custom_entities<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: &HashMap<Vec<u8>, Vec<u8>>,
@@ -251,27 +301,35 @@ impl<'a> BytesStart<'a> {
 
     #[cfg(feature = "encoding")]
     #[inline]
-    fn do_unescape_and_decode_with_custom_entities<B: BufRead>(
+    fn do_unescape_and_decode_with_custom_entities<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
     ) -> Result<String> {
         let decoded = reader.decode(&*self);
-        let unescaped =
-            do_unescape(decoded.as_bytes(), custom_entities).map_err(Error::EscapeError)?;
+        let unescaped = do_unescape_with_limits(
+            decoded.as_bytes(),
+            Some(&reader.entities_with(custom_entities)),
+            &reader.expansion_limits,
+        )
+        .map_err(Error::EscapeError)?;
         String::from_utf8(unescaped.into_owned()).map_err(|e| Error::Utf8(e.utf8_error()))
     }
 
     #[cfg(not(feature = "encoding"))]
     #[inline]
-    fn do_unescape_and_decode_with_custom_entities<B: BufRead>(
+    fn do_unescape_and_decode_with_custom_entities<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
     ) -> Result<String> {
         let decoded = reader.decode(&*self)?;
-        let unescaped =
-            do_unescape(decoded.as_bytes(), custom_entities).map_err(Error::EscapeError)?;
+        let unescaped = do_unescape_with_limits(
+            decoded.as_bytes(),
+            Some(&reader.entities_with(custom_entities)),
+            &reader.expansion_limits,
+        )
+        .map_err(Error::EscapeError)?;
         String::from_utf8(unescaped.into_owned()).map_err(|e| Error::Utf8(e.utf8_error()))
     }
 
@@ -339,11 +397,15 @@ impl<'a> BytesStart<'a> {
     /// Returns an iterator over the attributes of this tag.
     pub fn attributes(&self) -> Attributes {
         Attributes::new(&self.buf, self.name_len)
+            .with_offset(self.offset)
+            .with_moves(&self.moves)
     }
 
     /// Returns an iterator over the HTML-like attributes of this tag (no mandatory quotes or `=`).
     pub fn html_attributes(&self) -> Attributes {
         Attributes::html(self, self.name_len)
+            .with_offset(self.offset)
+            .with_moves(&self.moves)
     }
 
     /// Gets the undecoded raw string with the attributes of this tag as a `&[u8]`,
@@ -368,6 +430,14 @@ impl<'a> BytesStart<'a> {
     }
 }
 
+/// Elements are equal if they have the same content, regardless of their
+/// positions in documents
+impl<'a> PartialEq for BytesStart<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.buf == other.buf && self.name_len == other.name_len
+    }
+}
+
 impl<'a> std::fmt::Debug for BytesStart<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "BytesStart {{ buf: ")?;
@@ -723,17 +793,30 @@ impl<'a> BytesText<'a> {
         self.content
     }
 
+    /// Parses the content of the [`DocType`] event into typed declarations
+    /// of the internal subset of the DTD. See the [`dtd`] module for details.
+    ///
+    /// [`DocType`]: Event::DocType
+    /// [`dtd`]: crate::dtd
+    pub fn parse_doctype(&self) -> Result<DocType<'_>> {
+        Ok(crate::dtd::parse(&self.content)?)
+    }
+
     /// Returns unescaped version of the text content, that can be written
     /// as CDATA in XML
     #[cfg(feature = "serialize")]
-    pub(crate) fn unescape(self) -> std::result::Result<BytesCData<'a>, EscapeError> {
+    pub(crate) fn unescape(
+        self,
+        entities: &HashMap<Vec<u8>, Vec<u8>>,
+    ) -> std::result::Result<BytesCData<'a>, EscapeError> {
         //TODO: need to think about better API instead of dozens similar functions
         // Maybe use builder pattern. After that expose function as public API
-        //FIXME: need to take into account entities defined in the document
-        Ok(BytesCData::new(match do_unescape(&self.content, None)? {
-            Cow::Borrowed(_) => self.content,
-            Cow::Owned(unescaped) => Cow::Owned(unescaped),
-        }))
+        Ok(BytesCData::new(
+            match do_unescape(&self.content, Some(entities))? {
+                Cow::Borrowed(_) => self.content,
+                Cow::Owned(unescaped) => Cow::Owned(unescaped),
+            },
+        ))
     }
 
     /// gets escaped content
@@ -741,7 +824,13 @@ impl<'a> BytesText<'a> {
     /// Searches for '&' into content and try to escape the coded character if possible
     /// returns Malformed error with index within element if '&' is not followed by ';'
     ///
-    /// See also [`unescaped_with_custom_entities()`](#method.unescaped_with_custom_entities)
+    /// Entities declared in the DTD are not known to the event, use
+    /// [`unescape_and_decode()`] to expand them or pass [`Reader::entities()`]
+    /// to [`unescaped_with_custom_entities()`].
+    ///
+    /// [`unescape_and_decode()`]: #method.unescape_and_decode
+    /// [`Reader::entities()`]: crate::Reader::entities
+    /// [`unescaped_with_custom_entities()`]: #method.unescaped_with_custom_entities
     pub fn unescaped(&self) -> Result<Cow<[u8]>> {
         self.make_unescaped(None)
     }
@@ -779,10 +868,7 @@ impl<'a> BytesText<'a> {
     /// 1. BytesText::unescaped()
     /// 2. Reader::decode(...)
     #[cfg(feature = "encoding")]
-    pub fn unescape_and_decode_without_bom<B: BufRead>(
-        &self,
-        reader: &mut Reader<B>,
-    ) -> Result<String> {
+    pub fn unescape_and_decode_without_bom<B>(&self, reader: &mut Reader<B>) -> Result<String> {
         self.do_unescape_and_decode_without_bom(reader, None)
     }
 
@@ -794,10 +880,7 @@ impl<'a> BytesText<'a> {
     /// 1. BytesText::unescaped()
     /// 2. Reader::decode(...)
     #[cfg(not(feature = "encoding"))]
-    pub fn unescape_and_decode_without_bom<B: BufRead>(
-        &self,
-        reader: &Reader<B>,
-    ) -> Result<String> {
+    pub fn unescape_and_decode_without_bom<B>(&self, reader: &Reader<B>) -> Result<String> {
         self.do_unescape_and_decode_without_bom(reader, None)
     }
 
@@ -813,7 +896,7 @@ impl<'a> BytesText<'a> {
     ///
     /// The keys and values of `custom_entities`, if any, must be valid UTF-8.
     #[cfg(feature = "encoding")]
-    pub fn unescape_and_decode_without_bom_with_custom_entities<B: BufRead>(
+    pub fn unescape_and_decode_without_bom_with_custom_entities<B>(
         &self,
         reader: &mut Reader<B>,
         custom_entities: &HashMap<Vec<u8>, Vec<u8>>,
@@ -833,7 +916,7 @@ impl<'a> BytesText<'a> {
     ///
     /// The keys and values of `custom_entities`, if any, must be valid UTF-8.
     #[cfg(not(feature = "encoding"))]
-    pub fn unescape_and_decode_without_bom_with_custom_entities<B: BufRead>(
+    pub fn unescape_and_decode_without_bom_with_custom_entities<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: &HashMap<Vec<u8>, Vec<u8>>,
@@ -842,26 +925,34 @@ impl<'a> BytesText<'a> {
     }
 
     #[cfg(feature = "encoding")]
-    fn do_unescape_and_decode_without_bom<B: BufRead>(
+    fn do_unescape_and_decode_without_bom<B>(
         &self,
         reader: &mut Reader<B>,
         custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
     ) -> Result<String> {
         let decoded = reader.decode_without_bom(&*self);
-        let unescaped =
-            do_unescape(decoded.as_bytes(), custom_entities).map_err(Error::EscapeError)?;
+        let unescaped = do_unescape_with_limits(
+            decoded.as_bytes(),
+            Some(&reader.entities_with(custom_entities)),
+            &reader.expansion_limits,
+        )
+        .map_err(Error::EscapeError)?;
         String::from_utf8(unescaped.into_owned()).map_err(|e| Error::Utf8(e.utf8_error()))
     }
 
     #[cfg(not(feature = "encoding"))]
-    fn do_unescape_and_decode_without_bom<B: BufRead>(
+    fn do_unescape_and_decode_without_bom<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
     ) -> Result<String> {
         let decoded = reader.decode_without_bom(&*self)?;
-        let unescaped =
-            do_unescape(decoded.as_bytes(), custom_entities).map_err(Error::EscapeError)?;
+        let unescaped = do_unescape_with_limits(
+            decoded.as_bytes(),
+            Some(&reader.entities_with(custom_entities)),
+            &reader.expansion_limits,
+        )
+        .map_err(Error::EscapeError)?;
         String::from_utf8(unescaped.into_owned()).map_err(|e| Error::Utf8(e.utf8_error()))
     }
 
@@ -871,7 +962,7 @@ impl<'a> BytesText<'a> {
     /// it might be wiser to manually use
     /// 1. BytesText::unescaped()
     /// 2. Reader::decode(...)
-    pub fn unescape_and_decode<B: BufRead>(&self, reader: &Reader<B>) -> Result<String> {
+    pub fn unescape_and_decode<B>(&self, reader: &Reader<B>) -> Result<String> {
         self.do_unescape_and_decode_with_custom_entities(reader, None)
     }
 
@@ -885,7 +976,7 @@ impl<'a> BytesText<'a> {
     /// # Pre-condition
     ///
     /// The keys and values of `custom_entities`, if any, must be valid UTF-8.
-    pub fn unescape_and_decode_with_custom_entities<B: BufRead>(
+    pub fn unescape_and_decode_with_custom_entities<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: &HashMap<Vec<u8>, Vec<u8>>,
@@ -894,26 +985,34 @@ impl<'a> BytesText<'a> {
     }
 
     #[cfg(feature = "encoding")]
-    fn do_unescape_and_decode_with_custom_entities<B: BufRead>(
+    fn do_unescape_and_decode_with_custom_entities<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
     ) -> Result<String> {
         let decoded = reader.decode(&*self);
-        let unescaped =
-            do_unescape(decoded.as_bytes(), custom_entities).map_err(Error::EscapeError)?;
+        let unescaped = do_unescape_with_limits(
+            decoded.as_bytes(),
+            Some(&reader.entities_with(custom_entities)),
+            &reader.expansion_limits,
+        )
+        .map_err(Error::EscapeError)?;
         String::from_utf8(unescaped.into_owned()).map_err(|e| Error::Utf8(e.utf8_error()))
     }
 
     #[cfg(not(feature = "encoding"))]
-    fn do_unescape_and_decode_with_custom_entities<B: BufRead>(
+    fn do_unescape_and_decode_with_custom_entities<B>(
         &self,
         reader: &Reader<B>,
         custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
     ) -> Result<String> {
         let decoded = reader.decode(&*self)?;
-        let unescaped =
-            do_unescape(decoded.as_bytes(), custom_entities).map_err(Error::EscapeError)?;
+        let unescaped = do_unescape_with_limits(
+            decoded.as_bytes(),
+            Some(&reader.entities_with(custom_entities)),
+            &reader.expansion_limits,
+        )
+        .map_err(Error::EscapeError)?;
         String::from_utf8(unescaped.into_owned()).map_err(|e| Error::Utf8(e.utf8_error()))
     }
 
@@ -133,14 +133,31 @@
 #![deny(missing_docs)]
 #![recursion_limit = "1024"]
 
+/// Asserts that the result is an error that matches the pattern
+#[cfg(test)]
+macro_rules! assert_err {
+    ($result:expr, $pattern:pat $(if $guard:expr)?) => {
+        match $result {
+            Err($pattern) $(if $guard)? => {}
+            x => panic!("Expected `{}`, but got {:?}", stringify!($pattern), x),
+        }
+    };
+}
+
 #[cfg(feature = "serialize")]
 pub mod de;
+#[cfg(feature = "dom")]
+pub mod dom;
+pub mod dtd;
 mod errors;
 mod escapei;
 pub mod escape {
     //! Manage xml character escapes
-    pub(crate) use crate::escapei::{do_unescape, EscapeError};
-    pub use crate::escapei::{escape, partial_escape, unescape, unescape_with};
+    pub(crate) use crate::escapei::{do_unescape, do_unescape_with_limits};
+    pub use crate::escapei::{
+        escape, partial_escape, unescape, unescape_with, unescape_with_limits, EscapeError,
+        ExpansionLimit, ExpansionLimits,
+    };
 }
 pub mod events;
 pub mod name;
@@ -151,10 +168,14 @@ pub mod se;
 #[doc(hidden)]
 pub mod utils;
 mod writer;
+pub mod xpath;
 
 // reexports
 #[cfg(feature = "serialize")]
 pub use crate::errors::serialize::DeError;
 pub use crate::errors::{Error, Result};
-pub use crate::reader::Reader;
+pub use crate::reader::{
+    Diagnostic, ElementPath, NsReader, OpenedNames, PushBuffer, Reader, ReaderLimits, ReaderState,
+    RecordChunk, ResolvedAttributes, Subtree, Subtrees, TextPosition, UnbufferedSubtrees,
+};
 pub use crate::writer::{ElementWriter, Writer};
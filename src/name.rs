@@ -10,6 +10,7 @@ use crate::utils::write_byte_string;
 use memchr::memchr;
 use std::convert::TryFrom;
 use std::fmt::{self, Debug, Formatter};
+use std::iter::FusedIterator;
 
 /// A [qualified name] of an element or an attribute, including an optional
 /// namespace [prefix](Prefix) and a [local name](LocalName).
@@ -333,15 +334,15 @@ impl<'ns> TryFrom<ResolveResult<'ns>> for Option<Namespace<'ns>> {
 ///
 /// [namespace prefix]: https://www.w3.org/TR/xml-names11/#dt-prefix
 /// [namespace name]: https://www.w3.org/TR/xml-names11/#dt-NSName
-#[derive(Debug, Clone)]
-struct NamespaceEntry {
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct NamespaceEntry {
     /// Index of the namespace in the buffer
-    start: usize,
+    pub(crate) start: usize,
     /// Length of the prefix
     /// * if greater than zero, then binds this namespace to the slice
     ///   `[start..start + prefix_len]` in the buffer.
     /// * else defines the current default namespace.
-    prefix_len: usize,
+    pub(crate) prefix_len: usize,
     /// The length of a namespace name (the URI) of this namespace declaration.
     /// Name started just after prefix and extend for `value_len` bytes.
     ///
@@ -351,11 +352,11 @@ struct NamespaceEntry {
     /// behaviour of leaving unqualified element names unqualified.
     ///
     /// [specifies]: https://www.w3.org/TR/xml-names11/#scoping
-    value_len: usize,
+    pub(crate) value_len: usize,
     /// Level of nesting at which this namespace was declared. The declaring element is included,
     /// i.e., a declaration on the document root has `level = 1`.
     /// This is used to pop the namespace when the element gets closed.
-    level: i32,
+    pub(crate) level: i32,
 }
 
 impl NamespaceEntry {
@@ -387,14 +388,44 @@ impl NamespaceEntry {
 
 /// A namespace management buffer.
 ///
-/// Holds all internal logic to push/pop namespaces with their levels.
-#[derive(Debug, Default, Clone)]
-pub(crate) struct NamespaceResolver {
+/// Holds all internal logic to push/pop namespaces with their levels. Prefixes
+/// and namespace names of the bindings are stored in a buffer provided by the
+/// caller, so one resolver can be driven by any source of events.
+///
+/// # Examples
+///
+/// ```
+/// # use pretty_assertions::assert_eq;
+/// use quick_xml::events::BytesStart;
+/// use quick_xml::name::{LocalName, Namespace, NamespaceResolver, QName, ResolveResult::*};
+///
+/// let mut resolver = NamespaceResolver::default();
+/// let mut buffer = Vec::new();
+///
+/// // <root xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:ns="urn:ns">
+/// let root = BytesStart::borrowed(
+///     br#"root xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:ns="urn:ns""#,
+///     4,
+/// );
+/// resolver.push(&root, &mut buffer);
+///
+/// // QName in the content, for example, in the `xsi:type="ns:Foo"` attribute
+/// assert_eq!(
+///     resolver.resolve(QName(b"ns:Foo"), &buffer, true),
+///     (Bound(Namespace(b"urn:ns")), LocalName::from(QName(b"Foo"))),
+/// );
+///
+/// // </root>
+/// resolver.pop(&mut buffer);
+/// assert_eq!(resolver.find(QName(b"ns:Foo"), &buffer), Unknown(b"ns".to_vec()));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NamespaceResolver {
     /// A stack of namespace bindings to prefixes that currently in scope
-    bindings: Vec<NamespaceEntry>,
+    pub(crate) bindings: Vec<NamespaceEntry>,
     /// The number of open tags at the moment. We need to keep track of this to know which namespace
     /// declarations to remove when we encounter an `End` event.
-    nesting_level: i32,
+    pub(crate) nesting_level: i32,
 }
 
 impl NamespaceResolver {
@@ -507,6 +538,49 @@ impl NamespaceResolver {
         self.resolve_prefix(element_name.prefix(), buffer, true)
     }
 
+    /// Returns an iterator over all [namespace bindings] in scope, ordered from
+    /// the outermost to the innermost declaration.
+    ///
+    /// Bindings that are overridden by other declarations of the same prefix
+    /// are not returned, as well as the bindings that were removed from scope
+    /// using `xmlns[:prefix]=""`.
+    ///
+    /// # Lifetimes
+    ///
+    /// - `'a`: lifetime of the resolver and of the namespaces buffer, where
+    ///   all found namespaces are stored
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::BytesStart;
+    /// use quick_xml::name::{Namespace, NamespaceResolver, PrefixDeclaration};
+    ///
+    /// let mut resolver = NamespaceResolver::default();
+    /// let mut buffer = Vec::new();
+    ///
+    /// resolver.push(&BytesStart::borrowed(b"a xmlns='a' xmlns:p='p1' xmlns:q='q'", 1), &mut buffer);
+    /// resolver.push(&BytesStart::borrowed(b"b xmlns='' xmlns:p='p2'", 1), &mut buffer);
+    ///
+    /// assert_eq!(
+    ///     resolver.bindings(&buffer).collect::<Vec<_>>(),
+    ///     vec![
+    ///         (PrefixDeclaration::Named(b"q"), Namespace(b"q")),
+    ///         (PrefixDeclaration::Named(b"p"), Namespace(b"p2")),
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [namespace bindings]: https://www.w3.org/TR/xml-names11/#dt-NSDecl
+    #[inline]
+    pub fn bindings<'a>(&'a self, buffer: &'a [u8]) -> NamespaceBindingsIter<'a> {
+        NamespaceBindingsIter {
+            bindings: &self.bindings,
+            buffer,
+        }
+    }
+
     fn resolve_prefix<'ns>(
         &self,
         prefix: Option<Prefix>,
@@ -546,6 +620,46 @@ impl NamespaceResolver {
     }
 }
 
+/// Iterator over [namespace bindings] in scope, created by [`NamespaceResolver::bindings()`].
+///
+/// [namespace bindings]: https://www.w3.org/TR/xml-names11/#dt-NSDecl
+#[derive(Debug, Clone)]
+pub struct NamespaceBindingsIter<'a> {
+    /// Bindings that were not returned yet
+    bindings: &'a [NamespaceEntry],
+    /// Buffer with prefixes and namespace names of the bindings
+    buffer: &'a [u8],
+}
+
+impl<'a> Iterator for NamespaceBindingsIter<'a> {
+    type Item = (PrefixDeclaration<'a>, Namespace<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((entry, rest)) = self.bindings.split_first() {
+            self.bindings = rest;
+            let prefix = entry.prefix(self.buffer);
+            // Skip bindings overridden by the inner declarations
+            if rest.iter().any(|n| n.prefix(self.buffer) == prefix) {
+                continue;
+            }
+            if let ResolveResult::Bound(namespace) = entry.namespace(self.buffer) {
+                let prefix = match prefix {
+                    Some(p) => PrefixDeclaration::Named(p.into_inner()),
+                    None => PrefixDeclaration::Default,
+                };
+                return Some((prefix, namespace));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.bindings.len()))
+    }
+}
+
+impl<'a> FusedIterator for NamespaceBindingsIter<'a> {}
+
 #[cfg(test)]
 mod namespaces {
     use super::*;
@@ -779,6 +893,79 @@ mod namespaces {
         }
     }
 
+    /// Tests of the view of bindings in scope
+    mod bindings {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn bindings<'a>(
+            resolver: &'a NamespaceResolver,
+            buffer: &'a [u8],
+        ) -> Vec<(PrefixDeclaration<'a>, Namespace<'a>)> {
+            resolver.bindings(buffer).collect()
+        }
+
+        #[test]
+        fn empty() {
+            let resolver = NamespaceResolver::default();
+            assert_eq!(bindings(&resolver, b""), vec![]);
+        }
+
+        /// Inner declarations hide outer ones, and popping them restores outer
+        #[test]
+        fn shadowing() {
+            let mut resolver = NamespaceResolver::default();
+            let mut buffer = Vec::new();
+
+            resolver.push(
+                &BytesStart::borrowed(b" xmlns='a' xmlns:p='p1' xmlns:q='q'", 0),
+                &mut buffer,
+            );
+            resolver.push(&BytesStart::borrowed(b" xmlns:p='p2'", 0), &mut buffer);
+            assert_eq!(
+                bindings(&resolver, &buffer),
+                vec![
+                    (PrefixDeclaration::Default, Namespace(b"a")),
+                    (PrefixDeclaration::Named(b"q"), Namespace(b"q")),
+                    (PrefixDeclaration::Named(b"p"), Namespace(b"p2")),
+                ]
+            );
+
+            resolver.pop(&mut buffer);
+            assert_eq!(
+                bindings(&resolver, &buffer),
+                vec![
+                    (PrefixDeclaration::Default, Namespace(b"a")),
+                    (PrefixDeclaration::Named(b"p"), Namespace(b"p1")),
+                    (PrefixDeclaration::Named(b"q"), Namespace(b"q")),
+                ]
+            );
+        }
+
+        /// Declarations with an empty namespace remove bindings from scope
+        #[test]
+        fn reset() {
+            let mut resolver = NamespaceResolver::default();
+            let mut buffer = Vec::new();
+
+            resolver.push(
+                &BytesStart::borrowed(b" xmlns='a' xmlns:p='p'", 0),
+                &mut buffer,
+            );
+            resolver.push(
+                &BytesStart::borrowed(b" xmlns='' xmlns:p=''", 0),
+                &mut buffer,
+            );
+            assert_eq!(bindings(&resolver, &buffer), vec![]);
+
+            resolver.push(&BytesStart::borrowed(b" xmlns='b'", 0), &mut buffer);
+            assert_eq!(
+                bindings(&resolver, &buffer),
+                vec![(PrefixDeclaration::Default, Namespace(b"b"))]
+            );
+        }
+    }
+
     #[test]
     fn undeclared_prefix() {
         let name = QName(b"unknown:prefix");
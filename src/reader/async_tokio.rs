@@ -0,0 +1,199 @@
+//! This is an implementation of [`Reader`] for reading from a [`AsyncBufRead`]
+//! as underlying byte stream. This reader fully implements async/await so reading
+//! can use non-blocking I/O.
+
+use tokio::io::{self, AsyncBufRead, AsyncBufReadExt};
+
+use crate::errors::{Error, Result};
+use crate::events::{BytesText, Event};
+use crate::reader::{
+    escaped_text, html, limit_chunk, scan, BangType, PositionTracker, ReadElementState, Reader,
+    TagState,
+};
+
+/// A struct for read XML asynchronously from an [`AsyncBufRead`].
+///
+/// Having own struct allows us to implement anything without risk of name conflicts
+/// and does not suffer from the impossibility of having `async` in traits.
+struct TokioAdapter<'a, R>(&'a mut R);
+
+impl<'a, R: AsyncBufRead + Unpin> TokioAdapter<'a, R> {
+    impl_buffered_source!('b, 0, async, await);
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+impl<R: AsyncBufRead + Unpin> Reader<R> {
+    /// An asynchronous version of [`read_event()`]. Reads the next event into
+    /// given buffer.
+    ///
+    /// > This function should be defined as
+    /// > ```ignore
+    /// > pub async fn read_event_async<'b>(
+    /// >     &mut self,
+    /// >     buf: &'b mut Vec<u8>
+    /// > ) -> Result<Event<'b>>;
+    /// > ```
+    /// > The explicit lifetimes are required to express that the returned event
+    /// > borrows from `buf`.
+    ///
+    /// This is the main entry point for reading XML `Event`s when using an async reader.
+    ///
+    /// See the documentation of [`read_event()`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::Reader;
+    /// use quick_xml::events::Event;
+    ///
+    /// // This explicitly uses `from_reader("...".as_bytes())` to use a buffered
+    /// // reader instead of relying on the zero-copy optimizations for reading
+    /// // from byte slices, which provides the sync interface anyway.
+    /// let mut reader = Reader::from_reader(r#"
+    ///     <tag1 att1 = "test">
+    ///        <tag2><!--Test comment-->Test</tag2>
+    ///        <tag2>Test 2</tag2>
+    ///     </tag1>
+    /// "#.as_bytes());
+    /// reader.trim_text(true);
+    ///
+    /// let mut count = 0;
+    /// let mut buf = Vec::new();
+    /// let mut txt = Vec::new();
+    /// loop {
+    ///     match reader.read_event_async(&mut buf).await {
+    ///         Ok(Event::Start(_)) => count += 1,
+    ///         Ok(Event::Text(e)) => txt.push(e.unescape_and_decode(&reader).unwrap()),
+    ///         Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
+    ///         Ok(Event::Eof) => break,
+    ///         _ => (),
+    ///     }
+    ///     buf.clear();
+    /// }
+    /// assert_eq!(count, 3);
+    /// assert_eq!(txt, vec!["Test".to_string(), "Test 2".to_string()]);
+    /// # }) // tokio_test::block_on
+    /// ```
+    ///
+    /// [`read_event()`]: Reader::read_event
+    pub async fn read_event_async<'b>(&mut self, buf: &'b mut Vec<u8>) -> Result<Event<'b>> {
+        let event = self.read_event_uncoalesced_async(buf).await;
+        coalesce_text!(self, event, self.peek_text_async().await, |buf| self
+            .read_event_uncoalesced_async(&mut buf)
+            .await)
+    }
+
+    /// Reads the next event without merging of consecutive text events
+    async fn read_event_uncoalesced_async<'b>(
+        &mut self,
+        buf: &'b mut Vec<u8>,
+    ) -> Result<Event<'b>> {
+        read_event_impl!(
+            self,
+            buf,
+            read_until_open_async,
+            read_until_close_async,
+            await
+        )
+    }
+
+    /// Checks whether the next event can be a part of coalesced text
+    async fn peek_text_async(&mut self) -> Result<bool> {
+        peek_text!(self, TokioAdapter(&mut self.reader), await)
+    }
+
+    /// An asynchronous version of [`read_to_end()`]. Reads asynchronously until
+    /// end element is found using provided buffer as intermediate storage for
+    /// events content.
+    ///
+    /// Manages nested cases where parent and child elements have the same name.
+    ///
+    /// See the documentation of [`read_to_end()`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::Reader;
+    /// use quick_xml::events::{BytesStart, Event};
+    ///
+    /// let mut reader = Reader::from_reader(r#"
+    ///     <outer>
+    ///         <inner>
+    ///             <inner></inner>
+    ///             <inner/>
+    ///             <outer></outer>
+    ///             <outer/>
+    ///         </inner>
+    ///     </outer>
+    /// "#.as_bytes());
+    /// reader.trim_text(true);
+    /// let mut buf = Vec::new();
+    ///
+    /// let start = BytesStart::borrowed_name(b"outer");
+    /// let end   = start.to_end().into_owned();
+    ///
+    /// // First, we read a start event...
+    /// assert_eq!(reader.read_event_async(&mut buf).await.unwrap(), Event::Start(start));
+    ///
+    /// // ...then, we could skip all events to the corresponding end event.
+    /// // This call will correctly handle nested <outer> elements.
+    /// // Note, however, that this method does not handle namespaces.
+    /// reader.read_to_end_async(end.name(), &mut buf).await.unwrap();
+    ///
+    /// // At the end we should get an Eof event, because we ate the whole XML
+    /// assert_eq!(reader.read_event_async(&mut buf).await.unwrap(), Event::Eof);
+    /// # }) // tokio_test::block_on
+    /// ```
+    ///
+    /// [`read_to_end()`]: Reader::read_to_end
+    pub async fn read_to_end_async<K: AsRef<[u8]>>(
+        &mut self,
+        end: K,
+        buf: &mut Vec<u8>,
+    ) -> Result<()> {
+        let mut depth = 0;
+        let end = end.as_ref();
+        let start = self.markup_start;
+        loop {
+            match self.read_event_async(buf).await {
+                Ok(Event::End(ref e)) if e.name().as_ref() == end => {
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                    depth -= 1;
+                }
+                Ok(Event::Start(ref e)) if e.name().as_ref() == end => depth += 1,
+                Err(e) => return Err(e),
+                Ok(Event::Eof) => {
+                    return Err(Error::UnexpectedEof(
+                        format!("</{:?}>", std::str::from_utf8(end)),
+                        start..self.buf_position,
+                    ));
+                }
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+
+    /// Read until '<' is found and return either a `Text` event or a buffer
+    /// back to the caller, if reading should be continued by
+    /// [`Self::read_until_close_async`].
+    async fn read_until_open_async<'b>(
+        &mut self,
+        buf: &'b mut Vec<u8>,
+    ) -> Result<std::result::Result<Event<'b>, &'b mut Vec<u8>>> {
+        read_until_open!(self, buf, TokioAdapter(&mut self.reader), await)
+    }
+
+    /// Private function to read until `>` is found. This function expects that
+    /// it was called just after encounter a `<` symbol.
+    async fn read_until_close_async<'b>(&mut self, buf: &'b mut Vec<u8>) -> Result<Event<'b>> {
+        read_until_close!(self, buf, TokioAdapter(&mut self.reader), await)
+    }
+}
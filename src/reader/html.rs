@@ -0,0 +1,262 @@
+//! Helpers for the HTML mode of the [`Reader`], in which the reader follows
+//! the parsing rules of HTML for void elements, raw text elements and
+//! end tags that can be omitted.
+//!
+//! All element names are compared case-insensitively.
+//!
+//! [`Reader`]: super::Reader
+
+use std::ops::Range;
+
+use crate::reader::is_whitespace;
+
+/// A start tag that implicitly closed opened elements. It is returned after
+/// `End` events of those elements.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PendingStart {
+    /// Content of the tag between `<` and `>` without the `/` of self-closed tags
+    pub content: Vec<u8>,
+    /// Length of the element name in the content
+    pub name_len: usize,
+    /// `true` if the tag is self-closed
+    pub empty: bool,
+    /// Byte range of the tag in the document
+    pub span: Range<usize>,
+}
+
+/// [Void elements] that never have content and end tags
+///
+/// [Void elements]: https://html.spec.whatwg.org/multipage/syntax.html#void-elements
+const VOID: &[&[u8]] = &[
+    b"area", b"base", b"br", b"col", b"embed", b"hr", b"img", b"input", b"link", b"meta", b"param",
+    b"source", b"track", b"wbr",
+];
+
+/// Elements which content is read as a text until their end tag
+const RAW_TEXT: &[&[u8]] = &[b"script", b"style"];
+
+/// Elements which start tags close an opened `p` element
+const CLOSE_P: &[&[u8]] = &[
+    b"address",
+    b"article",
+    b"aside",
+    b"blockquote",
+    b"dd",
+    b"details",
+    b"div",
+    b"dl",
+    b"dt",
+    b"fieldset",
+    b"figcaption",
+    b"figure",
+    b"footer",
+    b"form",
+    b"h1",
+    b"h2",
+    b"h3",
+    b"h4",
+    b"h5",
+    b"h6",
+    b"header",
+    b"hr",
+    b"li",
+    b"main",
+    b"menu",
+    b"nav",
+    b"ol",
+    b"p",
+    b"pre",
+    b"section",
+    b"table",
+    b"ul",
+];
+
+/// Elements that a `p` element can not be implicitly closed through
+const P_SCOPE: &[&[u8]] = &[b"button", b"table", b"td", b"th", b"html"];
+/// Elements that a `li` element can not be implicitly closed through
+const LI_SCOPE: &[&[u8]] = &[b"ul", b"ol", b"menu", b"table"];
+/// Elements that a cell can not be implicitly closed through
+const CELL_SCOPE: &[&[u8]] = &[b"tr", b"table"];
+/// Elements that a row can not be implicitly closed through
+const ROW_SCOPE: &[&[u8]] = &[b"table", b"thead", b"tbody", b"tfoot"];
+
+/// Returns `true` if the element name is one of the names in the list
+fn is_one_of(name: &[u8], list: &[&[u8]]) -> bool {
+    list.iter().any(|n| name.eq_ignore_ascii_case(n))
+}
+
+/// Returns `true` if the element never has content, like `<br>`
+pub(crate) fn is_void(name: &[u8]) -> bool {
+    is_one_of(name, VOID)
+}
+
+/// Returns `true` if the content of the element is a raw text, like in `<script>`
+pub(crate) fn is_raw_text(name: &[u8]) -> bool {
+    is_one_of(name, RAW_TEXT)
+}
+
+/// Iterates over names of opened elements from the innermost one, together
+/// with the number of elements opened before each of them
+fn opened<'a>(
+    buffer: &'a [u8],
+    starts: &'a [usize],
+) -> impl Iterator<Item = (usize, &'a [u8])> + 'a {
+    (0..starts.len()).rev().map(move |i| {
+        let end = starts.get(i + 1).copied().unwrap_or(buffer.len());
+        (i, &buffer[starts[i]..end])
+    })
+}
+
+/// Finds the innermost opened element from `targets`, which is not hidden
+/// by an element from `scope`
+fn find_in_scope(
+    buffer: &[u8],
+    starts: &[usize],
+    targets: &[&[u8]],
+    scope: &[&[u8]],
+) -> Option<usize> {
+    for (depth, name) in opened(buffer, starts) {
+        if is_one_of(name, targets) {
+            return Some(depth);
+        }
+        if is_one_of(name, scope) {
+            return None;
+        }
+    }
+    None
+}
+
+/// Returns the number of opened elements that are left opened before the start
+/// tag with the specified name, if the start tag implicitly closes any elements.
+///
+/// `buffer` and `starts` are the stack of opened elements of the reader.
+pub(crate) fn implied_end(name: &[u8], buffer: &[u8], starts: &[usize]) -> Option<usize> {
+    let (targets, scope): (&[&[u8]], _) = if name.eq_ignore_ascii_case(b"li") {
+        (&[b"li"], LI_SCOPE)
+    } else if is_one_of(name, &[b"dt", b"dd"]) {
+        (&[b"dt", b"dd"], LI_SCOPE)
+    } else if is_one_of(name, &[b"td", b"th"]) {
+        (&[b"td", b"th"], CELL_SCOPE)
+    } else if name.eq_ignore_ascii_case(b"tr") {
+        (&[b"tr"], ROW_SCOPE)
+    } else {
+        (&[], &[])
+    };
+    let sibling = find_in_scope(buffer, starts, targets, scope);
+    let p = if is_one_of(name, CLOSE_P) {
+        find_in_scope(buffer, starts, &[b"p"], P_SCOPE)
+    } else {
+        None
+    };
+    // Close the outermost of elements, so all elements inside it are closed too
+    match (sibling, p) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Returns `true` if `tag`, which starts with `<` and ends with `>`, is an end
+/// tag of the element with the specified name
+fn is_end_tag(tag: &[u8], name: &[u8]) -> bool {
+    // `</` + name + `>`
+    tag.len() >= name.len() + 3
+        && tag[1] == b'/'
+        && tag[2..2 + name.len()].eq_ignore_ascii_case(name)
+        && tag[2 + name.len()..tag.len() - 1]
+            .iter()
+            .all(|b| is_whitespace(*b))
+}
+
+/// Returns the position after the first end tag of the element with the specified
+/// name in the raw text
+pub(crate) fn find_end_tag(bytes: &[u8], name: &[u8]) -> Option<usize> {
+    memchr::memchr_iter(b'<', bytes).find_map(|start| {
+        let end = start + memchr::memchr(b'>', &bytes[start..])?;
+        if is_end_tag(&bytes[start..=end], name) {
+            Some(end + 1)
+        } else {
+            None
+        }
+    })
+}
+
+/// Returns the position of the end tag of the element with the specified name,
+/// if the raw text ends with it
+pub(crate) fn end_tag_start(bytes: &[u8], name: &[u8]) -> Option<usize> {
+    if bytes.last() != Some(&b'>') {
+        return None;
+    }
+    let start = memchr::memrchr(b'<', bytes)?;
+    if is_end_tag(&bytes[start..], name) {
+        Some(start)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Builds the stack of opened elements from names
+    fn stack(names: &[&str]) -> (Vec<u8>, Vec<usize>) {
+        let mut buffer = Vec::new();
+        let mut starts = Vec::new();
+        for name in names {
+            starts.push(buffer.len());
+            buffer.extend_from_slice(name.as_bytes());
+        }
+        (buffer, starts)
+    }
+
+    fn implied(name: &str, names: &[&str]) -> Option<usize> {
+        let (buffer, starts) = stack(names);
+        implied_end(name.as_bytes(), &buffer, &starts)
+    }
+
+    #[test]
+    fn list_items() {
+        assert_eq!(implied("li", &["ul", "li"]), Some(1));
+        assert_eq!(implied("LI", &["ul", "li", "b"]), Some(1));
+        // Nested lists
+        assert_eq!(implied("li", &["ul", "li", "ol"]), None);
+        assert_eq!(implied("li", &["ul", "li", "ol", "Li"]), Some(3));
+        assert_eq!(implied("dd", &["dl", "dt"]), Some(1));
+    }
+
+    #[test]
+    fn cells() {
+        assert_eq!(implied("td", &["table", "tr", "td"]), Some(2));
+        assert_eq!(implied("th", &["table", "tr", "td", "p"]), Some(2));
+        assert_eq!(implied("td", &["table", "tr"]), None);
+        assert_eq!(implied("tr", &["table", "tr", "td"]), Some(1));
+        // Nested table
+        assert_eq!(implied("td", &["table", "tr", "td", "table", "tr"]), None);
+    }
+
+    #[test]
+    fn paragraphs() {
+        assert_eq!(implied("p", &["body", "p"]), Some(1));
+        assert_eq!(implied("div", &["body", "p", "b"]), Some(1));
+        assert_eq!(implied("span", &["body", "p"]), None);
+        assert_eq!(implied("p", &["body", "p", "button"]), None);
+        // Both a list item and a paragraph
+        assert_eq!(implied("li", &["ul", "li", "p"]), Some(1));
+        assert_eq!(implied("li", &["body", "p"]), Some(1));
+    }
+
+    #[test]
+    fn end_tags() {
+        assert_eq!(
+            find_end_tag(b"a < b</scrip></SCRIPT >x", b"script"),
+            Some(23)
+        );
+        assert_eq!(find_end_tag(b"a</scripts>", b"script"), None);
+        assert_eq!(find_end_tag(b"</script", b"script"), None);
+
+        assert_eq!(end_tag_start(b"a</b></Style\t>", b"style"), Some(5));
+        assert_eq!(end_tag_start(b"a</b>", b"style"), None);
+        assert_eq!(end_tag_start(b"</style", b"style"), None);
+    }
+}
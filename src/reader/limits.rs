@@ -0,0 +1,147 @@
+//! Limits of the resources used by the [`Reader`] to read a document.
+//!
+//! [`Reader`]: super::Reader
+
+use std::ops::Range;
+
+use crate::errors::{Error, Result};
+use crate::events::attributes::{Attr, IterState};
+
+/// Limits of the resources used by the [`Reader`] to read a document, that allow
+/// to safely read documents from untrusted sources. Without limits a document
+/// with a never closed markup or a very deep nesting of elements makes the
+/// reader grow its buffers without bound.
+///
+/// Each exceeded limit is reported as its own [`Error`] variant. All limits are
+/// disabled (equal to `usize::MAX`) by default.
+///
+/// [`Reader`]: crate::Reader
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReaderLimits {
+    /// The maximum length in bytes of a markup between `<` and `>`: start and end
+    /// tags, comments, CDATA sections, processing instructions and DOCTYPE.
+    /// Reported as [`Error::MarkupTooLong`]
+    pub max_markup_len: usize,
+    /// The maximum length in bytes of a text between markups, before unescaping.
+    /// Reported as [`Error::TextTooLong`]
+    pub max_text_len: usize,
+    /// The maximum number of nested elements. Reported as [`Error::TooDeep`]
+    pub max_depth: usize,
+    /// The maximum number of attributes of an element, including namespace
+    /// declarations. Reported as [`Error::TooManyAttributes`]
+    pub max_attributes: usize,
+    /// The maximum length in bytes of names of elements and attributes.
+    /// Reported as [`Error::NameTooLong`]
+    pub max_name_len: usize,
+}
+
+impl Default for ReaderLimits {
+    fn default() -> Self {
+        Self {
+            max_markup_len: usize::MAX,
+            max_text_len: usize::MAX,
+            max_depth: usize::MAX,
+            max_attributes: usize::MAX,
+            max_name_len: usize::MAX,
+        }
+    }
+}
+
+impl ReaderLimits {
+    /// Checks the length of the element name and the number and names of
+    /// attributes of a start tag.
+    ///
+    /// # Parameters
+    /// - `content`: the content of the tag between `<` and `>` (or `/>`)
+    /// - `name_len`: the length of the element name
+    /// - `offset`: position of the content in the document
+    /// - `span`: position of the whole tag in the document
+    pub(crate) fn check_start(
+        &self,
+        content: &[u8],
+        name_len: usize,
+        offset: usize,
+        span: Range<usize>,
+    ) -> Result<()> {
+        self.check_name(name_len, offset)?;
+        if self.max_attributes == usize::MAX && self.max_name_len == usize::MAX {
+            return Ok(());
+        }
+
+        let mut state = IterState::new(name_len, false);
+        let mut count = 0;
+        // Malformed attributes are counted too, errors in them are reported
+        // when attributes are read
+        while let Some(attr) = state.next(content) {
+            count += 1;
+            if count > self.max_attributes {
+                return Err(Error::TooManyAttributes(self.max_attributes, span));
+            }
+            if let Ok(Attr::DoubleQ(key, _))
+            | Ok(Attr::SingleQ(key, _))
+            | Ok(Attr::Unquoted(key, _))
+            | Ok(Attr::Empty(key)) = attr
+            {
+                self.check_name(key.len(), offset + key.start)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the length of a name which starts at `offset`
+    pub(crate) fn check_name(&self, name_len: usize, offset: usize) -> Result<()> {
+        if name_len > self.max_name_len {
+            return Err(Error::NameTooLong(
+                self.max_name_len,
+                offset..offset + name_len,
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unlimited() {
+        let limits = ReaderLimits::default();
+        assert!(limits.check_start(b"tag a='1' b=2 c", 3, 1, 0..17).is_ok());
+    }
+
+    #[test]
+    fn attributes() {
+        let limits = ReaderLimits {
+            max_attributes: 2,
+            ..ReaderLimits::default()
+        };
+        assert!(limits.check_start(b"tag a='1' b='2'", 3, 1, 0..17).is_ok());
+        assert_err!(
+            limits.check_start(b"tag a='1' b='2' c='3'", 3, 1, 0..23),
+            Error::TooManyAttributes(2, span) if span == (0..23)
+        );
+        // Malformed attributes are counted too
+        assert_err!(
+            limits.check_start(b"tag a='1' b c='3'", 3, 1, 0..19),
+            Error::TooManyAttributes(2, _)
+        );
+    }
+
+    #[test]
+    fn names() {
+        let limits = ReaderLimits {
+            max_name_len: 3,
+            ..ReaderLimits::default()
+        };
+        assert!(limits.check_start(b"tag abc='1'", 3, 1, 0..13).is_ok());
+        assert_err!(
+            limits.check_start(b"tags a='1'", 4, 1, 0..12),
+            Error::NameTooLong(3, span) if span == (1..5)
+        );
+        assert_err!(
+            limits.check_start(b"tag a='1' abcd='2'", 3, 1, 0..20),
+            Error::NameTooLong(3, span) if span == (11..15)
+        );
+    }
+}
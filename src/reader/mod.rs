@@ -0,0 +1,5870 @@
+//! A module to handle `Reader`
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufReader};
+use std::ops::Range;
+use std::{fs::File, path::Path, str::from_utf8};
+
+#[cfg(feature = "encoding")]
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+
+use crate::dtd::{AttType, Declaration, EntityDef};
+use crate::errors::{Error, Result};
+use crate::escapei::{escape, resolve_entities, resolve_valid_entities, ExpansionLimits};
+use crate::events::{BytesCData, BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use crate::name::{LocalName, NamespaceBindingsIter, NamespaceResolver, QName, ResolveResult};
+
+use memchr;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The state machine of the reader, shared between synchronous and asynchronous
+/// readers. Reads the next event from `$reader` and updates `tag_state`.
+///
+/// # Parameters
+/// - `$self`: the `Reader` instance
+/// - `$buf`: the buffer that will be passed to the source
+/// - `$read_until_open`, `$read_until_close`: names of methods that should be
+///   called in the `Closed` and `Opened` states, respectively
+/// - `$await`: the `await` keyword, if the methods are `async`
+macro_rules! read_event_impl {
+    (
+        $self:ident, $buf:ident,
+        $read_until_open:ident,
+        $read_until_close:ident
+        $(, $await:ident)?
+    ) => {{
+        // An event read ahead of coalesced text
+        if let Some((event, span, position)) = $self.lookahead.take() {
+            $self.event_span = span;
+            $self.tracker.event = position;
+            return Ok(event);
+        }
+        let event = match $self.tag_state {
+            TagState::Opened => $self.$read_until_close($buf) $(.$await)?,
+            TagState::Closed | TagState::RawText => match $self.$read_until_open($buf) $(.$await)? {
+                Ok(Ok(ev)) => Ok(ev),
+                Ok(Err(b)) => $self.$read_until_close(b) $(.$await)?,
+                Err(e) => Err(e),
+            },
+            TagState::Empty => {
+                $self.event_span = $self.buf_position..$self.buf_position;
+                $self.close_expanded_empty()
+            }
+            TagState::AutoClose(depth) => {
+                $self.event_span = $self.buf_position..$self.buf_position;
+                $self.close_unclosed(depth)
+            }
+            TagState::PendingStart => $self.read_pending_start(),
+            TagState::Exit => {
+                $self.event_span = $self.buf_position..$self.buf_position;
+                return Ok(Event::Eof);
+            }
+        };
+        let event = match event {
+            Ok(event) if $self.normalize => Ok($self.normalize_event(event)),
+            event => event,
+        };
+        match event {
+            Err(e) => {
+                $self.tag_state = TagState::Exit;
+                Err($self.tracker.locate(e))
+            }
+            Ok(Event::Eof) => {
+                $self.tag_state = TagState::Exit;
+                Ok(Event::Eof)
+            }
+            Ok(Event::Start(_)) => {
+                $self.depth += 1;
+                event
+            }
+            Ok(Event::End(_)) => {
+                $self.depth = $self.depth.saturating_sub(1);
+                event
+            }
+            _ => event,
+        }
+    }};
+}
+
+/// Reads until `<` is found and returns a `Text` event. If text should be
+/// trimmed and `<` is found just after the trimmed whitespaces, returns
+/// `Ok(Err(buf))`, which means that the caller should continue reading the
+/// markup using [`read_until_close!`].
+///
+/// In the `RawText` state reads the content of the last opened element until
+/// its end tag instead.
+macro_rules! read_until_open {
+    (
+        $self:ident, $buf:ident,
+        $reader:expr
+        $(, $await:ident)?
+    ) => {{
+        if let TagState::RawText = $self.tag_state {
+            // The end tag is read together with the content, and the `End`
+            // event is generated from the stack of opened elements
+            $self.tag_state = TagState::Empty;
+            $self.tracker.start_event();
+            let start = $self.buf_position;
+            let limit = $self.limits.max_text_len;
+            let name = &$self.opened_buffer[$self.opened_starts.last().copied().unwrap_or(0)..];
+            return match $reader
+                .read_raw_text(name, $buf, limit, &mut $self.buf_position)
+                $(.$await)?
+            {
+                Ok(Some(bytes)) => {
+                    let text = &bytes[..html::end_tag_start(bytes, name).unwrap_or(bytes.len())];
+                    if text.len() > limit {
+                        return Err(Error::TextTooLong(limit, start..$self.buf_position));
+                    }
+                    $self.tracker.advance(bytes);
+                    $self.event_span = start..start + text.len();
+                    $self.read_raw_text_event(text).map(Ok)
+                }
+                Ok(None) => $self.close_expanded_empty().map(Ok),
+                Err(e) => Err(e),
+            };
+        }
+        $self.tag_state = TagState::Opened;
+
+        if $self.trim_text_start {
+            $reader.skip_whitespace(&mut $self.buf_position, &mut $self.tracker) $(.$await)? ?;
+            if $reader.skip_one(b'<', &mut $self.buf_position) $(.$await)? ? {
+                return Ok(Err($buf));
+            }
+        }
+
+        $self.tracker.start_event();
+        let start = $self.buf_position;
+        let limit = $self.limits.max_text_len;
+        match $reader
+            .read_bytes_until(b'<', $buf, limit, &mut $self.buf_position)
+            $(.$await)?
+        {
+            Ok(Some(bytes)) if bytes.len() > limit => {
+                Err(Error::TextTooLong(limit, start..$self.buf_position))
+            }
+            Ok(Some(bytes)) => {
+                $self.event_span = start..start + bytes.len();
+                $self.tracker.advance(bytes);
+                $self.read_text_event(bytes, start).map(Ok)
+            }
+            Ok(None) => {
+                $self.event_span = start..start;
+                $self.read_eof().map(Ok)
+            }
+            Err(e) => Err(e),
+        }
+    }};
+}
+
+/// Reads until `>` is found. Expects that it was called just after encounter
+/// a `<` symbol.
+macro_rules! read_until_close {
+    (
+        $self:ident, $buf:ident,
+        $reader:expr
+        $(, $await:ident)?
+    ) => {{
+        $self.tag_state = TagState::Closed;
+        $self.tracker.start_event();
+        // `<` is already consumed
+        $self.markup_start = $self.buf_position.saturating_sub(1);
+        let limit = $self.limits.max_markup_len;
+
+        let event = match $reader.peek_one() $(.$await)? {
+            // `<!` - comment, CDATA or DOCTYPE declaration
+            Ok(Some(b'!')) => match $reader
+                .read_bang_element($buf, limit, &mut $self.buf_position)
+                $(.$await)?
+            {
+                Ok(None) => Ok(Event::Eof),
+                Ok(Some((_, bytes))) if bytes.len() > limit => Err($self.markup_too_long()),
+                Ok(Some((bang_type, bytes))) => {
+                $self.tracker.advance_markup(bytes);
+                // `]]` at the end of CDATA is not a part of its content
+                if let BangType::CData = bang_type {
+                    $self.tracker.advance(b"]]");
+                }
+                $self.read_bang(bang_type, bytes)
+            }
+                Err(e) => Err(e),
+            },
+            // `</` - closing tag
+            Ok(Some(b'/')) => match $reader
+                .read_bytes_until(b'>', $buf, limit, &mut $self.buf_position)
+                $(.$await)?
+            {
+                Ok(None) => Ok(Event::Eof),
+                Ok(Some(bytes)) if bytes.len() > limit => Err($self.markup_too_long()),
+                Ok(Some(bytes)) => {
+                $self.tracker.advance_markup(bytes);
+                $self.read_end(bytes)
+            }
+                Err(e) => Err(e),
+            },
+            // `<?` - processing instruction
+            Ok(Some(b'?')) => match $reader
+                .read_bytes_until(b'>', $buf, limit, &mut $self.buf_position)
+                $(.$await)?
+            {
+                Ok(None) => Ok(Event::Eof),
+                Ok(Some(bytes)) if bytes.len() > limit => Err($self.markup_too_long()),
+                Ok(Some(bytes)) => {
+                $self.tracker.advance_markup(bytes);
+                $self.read_question_mark(bytes)
+            }
+                Err(e) => Err(e),
+            },
+            // `<...` - opening or self-closed tag
+            Ok(Some(_)) => match $reader
+                .read_element($buf, limit, &mut $self.buf_position)
+                $(.$await)?
+            {
+                Ok(None) => Ok(Event::Eof),
+                Ok(Some(bytes)) if bytes.len() > limit => Err($self.markup_too_long()),
+                Ok(Some(bytes)) => {
+                $self.tracker.advance_markup(bytes);
+                $self.read_start(bytes)
+            }
+                Err(e) => Err(e),
+            },
+            // The input ends after a text
+            Ok(None) => {
+                $self.markup_start = $self.buf_position;
+                $self.read_eof()
+            }
+            Err(e) => Err(e),
+        };
+        $self.event_span = match $self.tag_state {
+            // The end tag closes several elements and its span is given to the
+            // last `End` event, see `Reader::close_unclosed()`
+            TagState::AutoClose(_) => $self.buf_position..$self.buf_position,
+            _ => $self.markup_start..$self.buf_position,
+        };
+        event
+    }};
+}
+
+/// The source of a reader over a [`BufRead`] type, which records the consumed
+/// input while the `recorded` field of the reader is set.
+#[cfg(not(feature = "encoding"))]
+macro_rules! buffered_source {
+    ($self:ident) => {
+        Recorder::new(&mut $self.reader, $self.recorded.as_mut())
+    };
+}
+
+/// The same as above, but reads through the transcoder of UTF-16 and UTF-32
+/// documents
+#[cfg(feature = "encoding")]
+macro_rules! buffered_source {
+    ($self:ident) => {
+        Recorder::new(
+            Utf8Source::new(&mut $self.reader, &mut $self.transcoder),
+            $self.recorded.as_mut(),
+        )
+    };
+}
+
+/// Prepares reading of the next part of coalesced text and returns `true` if
+/// the next event can be a part of it: a text, a CDATA section, a comment or a
+/// processing instruction. Start and end tags are never read ahead, so the
+/// stack of opened elements is not changed before the text is returned.
+macro_rules! peek_text {
+    (
+        $self:ident,
+        $reader:expr
+        $(, $await:ident)?
+    ) => {{
+        if let TagState::Closed = $self.tag_state {
+            if $self.trim_text_start {
+                $reader.skip_whitespace(&mut $self.buf_position, &mut $self.tracker) $(.$await)? ?;
+            }
+            if $reader.skip_one(b'<', &mut $self.buf_position) $(.$await)? ? {
+                $self.tag_state = TagState::Opened;
+            }
+        }
+        Ok(match $self.tag_state {
+            // A text or the end of input
+            TagState::Closed => $reader.peek_one() $(.$await)? ?.is_some(),
+            TagState::Opened => match $reader.peek_one() $(.$await)? ? {
+                Some(b'!') => true,
+                Some(b'?') => $self.skip_comments_in_text,
+                _ => false,
+            },
+            _ => false,
+        })
+    }};
+}
+
+/// Reads past end tags skipped in the recovery or HTML mode, then merges the
+/// first `Text` or `CData` event with the following `Text` and `CData` events
+/// into one `Text` event, if [`Reader::coalesce_text()`] is enabled. Comments
+/// and processing instructions inside the text are skipped if
+/// [`Reader::skip_comments_in_text()`] is enabled, other events that were read
+/// ahead are stored and returned by the next call.
+///
+/// # Parameters
+/// - `$self`: the `Reader` instance
+/// - `$event`: the result of reading the first event
+/// - `$peek`: an expression that checks whether the next event can be a part
+///   of the text, see [`peek_text!`]
+/// - `$buf`, `$read`: an expression that reads the next event into the buffer `$buf`
+macro_rules! coalesce_text {
+    (
+        $self:ident, $event:expr, $peek:expr,
+        |$buf:ident| $read:expr
+    ) => {{
+        let mut first = $event;
+        if $self.skip_event {
+            let mut $buf = std::mem::take(&mut $self.coalesce_buf);
+            while $self.skip_event {
+                $self.skip_event = false;
+                $buf.clear();
+                first = $read.map(Event::into_owned);
+            }
+            $self.coalesce_buf = $buf;
+        }
+        let first = first?;
+        if !($self.coalesce_text && matches!(first, Event::Text(_) | Event::CData(_))) {
+            return Ok(first);
+        }
+        let start = $self.event_span.start;
+        let mut end = $self.event_span.end;
+        let position = $self.tracker.event;
+        let mut text: Option<Vec<u8>> = None;
+        let mut $buf = std::mem::take(&mut $self.coalesce_buf);
+        while $peek? {
+            $buf.clear();
+            match $read? {
+                event @ Event::Text(_) | event @ Event::CData(_) => {
+                    let text = text.get_or_insert_with(|| escaped_text(&first).into_owned());
+                    text.extend_from_slice(&escaped_text(&event));
+                    end = $self.event_span.end;
+                }
+                Event::Comment(_) | Event::PI(_) if $self.skip_comments_in_text => {}
+                event => {
+                    let span = $self.event_span.clone();
+                    $self.lookahead = Some((event.into_owned(), span, $self.tracker.event));
+                    break;
+                }
+            }
+        }
+        $self.coalesce_buf = $buf;
+        match text {
+            Some(text) => {
+                $self.event_span = start..end;
+                $self.tracker.event = position;
+                Ok(Event::Text(BytesText::from_escaped(text)))
+            }
+            None => Ok(first),
+        }
+    }};
+}
+
+/// Generates methods of the [`XmlSource`] trait for sources, that copy data
+/// into the user-provided buffer. Used to implement both synchronous source
+/// for any [`BufRead`] type and asynchronous source for tokio's `AsyncBufRead`.
+///
+/// When the underlying reader returns an I/O error, the data of the unfinished
+/// event is removed from the buffer, so reading can be retried after the error.
+///
+/// # Parameters
+/// - `$lf`: lifetime of the buffer, if methods should be generic over it
+/// - `$reader`: a name of the field of `self` with the underlying reader, if
+///   `self` is an adapter
+/// - `$async`, `$await`: keywords that should be inserted into methods
+macro_rules! impl_buffered_source {
+    ($($lf:lifetime, $reader:tt, $async:ident, $await:ident)?) => {
+        #[inline]
+        $($async)? fn read_bytes_until $(<$lf>)? (
+            &mut self,
+            byte: u8,
+            buf: &'b mut Vec<u8>,
+            limit: usize,
+            position: &mut usize,
+        ) -> Result<Option<&'b [u8]>> {
+            let mut read = 0;
+            let mut done = false;
+            let start = buf.len();
+            while !done && read <= limit {
+                let used = {
+                    let available = match self $(.$reader)? .fill_buf() $(.$await)? {
+                        Ok(n) if n.is_empty() => break,
+                        Ok(n) => limit_chunk(n, limit, read),
+                        Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(e) => {
+                            buf.truncate(start);
+                            *position += read;
+                            return Err(Error::Io(e));
+                        }
+                    };
+
+                    match memchr::memchr(byte, available) {
+                        Some(i) => {
+                            buf.extend_from_slice(&available[..i]);
+                            done = true;
+                            i + 1
+                        }
+                        None => {
+                            buf.extend_from_slice(available);
+                            available.len()
+                        }
+                    }
+                };
+                self $(.$reader)? .consume(used);
+                read += used;
+            }
+            *position += read;
+
+            if read == 0 {
+                Ok(None)
+            } else {
+                Ok(Some(&buf[start..]))
+            }
+        }
+
+        $($async)? fn read_bang_element $(<$lf>)? (
+            &mut self,
+            buf: &'b mut Vec<u8>,
+            limit: usize,
+            position: &mut usize,
+        ) -> Result<Option<(BangType, &'b [u8])>> {
+            // Peeked one bang ('!') before being called, so it's guaranteed to
+            // start with it.
+            let start = buf.len();
+            let mut read = 1;
+            buf.push(b'!');
+            self $(.$reader)? .consume(1);
+
+            let bang_type = match self.peek_one() $(.$await)? {
+                Ok(byte) => BangType::new(byte, *position)?,
+                Err(e) => {
+                    buf.truncate(start);
+                    return Err(e);
+                }
+            };
+
+            loop {
+                if read > limit {
+                    *position += read;
+                    break;
+                }
+                match self $(.$reader)? .fill_buf() $(.$await)? {
+                    // Note: Do not update position, so the error points to
+                    // somewhere sane rather than at the EOF
+                    Ok(n) if n.is_empty() => {
+                        return Err(bang_type.to_err(position.saturating_sub(1)..*position + read))
+                    }
+                    Ok(available) => {
+                        let available = limit_chunk(available, limit, read);
+                        if let Some((consumed, used)) = bang_type.parse(available, read) {
+                            buf.extend_from_slice(consumed);
+
+                            self $(.$reader)? .consume(used);
+                            read += used;
+
+                            *position += read;
+                            break;
+                        } else {
+                            buf.extend_from_slice(available);
+
+                            let used = available.len();
+                            self $(.$reader)? .consume(used);
+                            read += used;
+                        }
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        buf.truncate(start);
+                        *position += read;
+                        return Err(Error::Io(e));
+                    }
+                }
+            }
+
+            if read == 0 {
+                Ok(None)
+            } else {
+                Ok(Some((bang_type, &buf[start..])))
+            }
+        }
+
+        #[inline]
+        $($async)? fn read_element $(<$lf>)? (
+            &mut self,
+            buf: &'b mut Vec<u8>,
+            limit: usize,
+            position: &mut usize,
+        ) -> Result<Option<&'b [u8]>> {
+            let mut state = ReadElementState::Elem;
+            let mut read = 0;
+
+            let start = buf.len();
+            loop {
+                if read > limit {
+                    *position += read;
+                    break;
+                }
+                match self $(.$reader)? .fill_buf() $(.$await)? {
+                    Ok(n) if n.is_empty() => break,
+                    Ok(available) => {
+                        let available = limit_chunk(available, limit, read);
+                        if let Some((consumed, used)) = state.change(available) {
+                            buf.extend_from_slice(consumed);
+
+                            self $(.$reader)? .consume(used);
+                            read += used;
+
+                            *position += read;
+                            break;
+                        } else {
+                            buf.extend_from_slice(available);
+
+                            let used = available.len();
+                            self $(.$reader)? .consume(used);
+                            read += used;
+                        }
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        buf.truncate(start);
+                        *position += read;
+                        return Err(Error::Io(e));
+                    }
+                };
+            }
+
+            if read == 0 {
+                Ok(None)
+            } else {
+                Ok(Some(&buf[start..]))
+            }
+        }
+
+        $($async)? fn read_raw_text $(<$lf>)? (
+            &mut self,
+            name: &[u8],
+            buf: &'b mut Vec<u8>,
+            limit: usize,
+            position: &mut usize,
+        ) -> Result<Option<&'b [u8]>> {
+            let mut read = 0;
+            let start = buf.len();
+            while read <= limit {
+                let used = {
+                    let available = match self $(.$reader)? .fill_buf() $(.$await)? {
+                        Ok(n) if n.is_empty() => break,
+                        Ok(n) => limit_chunk(n, limit, read),
+                        Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(e) => {
+                            buf.truncate(start);
+                            *position += read;
+                            return Err(Error::Io(e));
+                        }
+                    };
+
+                    // Read until each `>` and check if it ends the end tag
+                    match memchr::memchr(b'>', available) {
+                        Some(i) => {
+                            buf.extend_from_slice(&available[..=i]);
+                            i + 1
+                        }
+                        None => {
+                            buf.extend_from_slice(available);
+                            available.len()
+                        }
+                    }
+                };
+                self $(.$reader)? .consume(used);
+                read += used;
+                if html::end_tag_start(&buf[start..], name).is_some() {
+                    break;
+                }
+            }
+            *position += read;
+
+            if read == 0 {
+                Ok(None)
+            } else {
+                Ok(Some(&buf[start..]))
+            }
+        }
+
+        /// Consume and discard all the whitespace until the next non-whitespace
+        /// character or EOF.
+        $($async)? fn skip_whitespace(
+            &mut self,
+            position: &mut usize,
+            tracker: &mut PositionTracker,
+        ) -> Result<()> {
+            loop {
+                break match self $(.$reader)? .fill_buf() $(.$await)? {
+                    Ok(n) => {
+                        let count = scan::skip_whitespace(n).unwrap_or(n.len());
+                        if count > 0 {
+                            tracker.advance(&n[..count]);
+                            self $(.$reader)? .consume(count);
+                            *position += count;
+                            continue;
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => Err(Error::Io(e)),
+                };
+            }
+        }
+
+        /// Consume and discard one character if it matches the given byte. Return
+        /// true if it matched.
+        $($async)? fn skip_one(&mut self, byte: u8, position: &mut usize) -> Result<bool> {
+            match self.peek_one() $(.$await)? ? {
+                Some(b) if b == byte => {
+                    *position += 1;
+                    self $(.$reader)? .consume(1);
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+
+        /// Return one character without consuming it, so that future `read_*` calls
+        /// will still include it. On EOF, return None.
+        $($async)? fn peek_one(&mut self) -> Result<Option<u8>> {
+            loop {
+                break match self $(.$reader)? .fill_buf() $(.$await)? {
+                    Ok(n) if n.is_empty() => Ok(None),
+                    Ok(n) => Ok(Some(n[0])),
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => Err(Error::Io(e)),
+                };
+            }
+        }
+    };
+}
+
+// Macros should be defined before the modules that using them
+#[cfg(feature = "async-tokio")]
+mod async_tokio;
+mod html;
+mod limits;
+mod normalize;
+mod ns_reader;
+mod parallel;
+mod path;
+mod push;
+mod recovery;
+pub(crate) mod scan;
+mod state;
+mod strict;
+mod subtree;
+#[cfg(feature = "encoding")]
+mod transcode;
+
+pub use limits::ReaderLimits;
+pub use ns_reader::{NsReader, ResolvedAttributes};
+pub use parallel::RecordChunk;
+pub use path::{ElementPath, OpenedNames};
+pub use push::PushBuffer;
+pub use recovery::Diagnostic;
+pub use state::ReaderState;
+pub use subtree::{Subtree, Subtrees, UnbufferedSubtrees};
+
+use html::PendingStart;
+use subtree::Recorder;
+#[cfg(feature = "encoding")]
+use transcode::{Transcoder, Utf8Source};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, PartialEq)]
+enum TagState {
+    Opened,
+    Closed,
+    Empty,
+    /// End events for unclosed elements are generated in the recovery mode
+    /// until only the specified number of elements is left opened
+    AutoClose(usize),
+    /// The content of a `<script>` or `<style>` element should be read as a
+    /// text in the HTML mode
+    RawText,
+    /// A start tag that implicitly closed opened elements in the HTML mode
+    /// should be returned
+    PendingStart,
+    /// Either Eof or Errored
+    Exit,
+}
+
+/// A low level encoding-agnostic XML event reader.
+///
+/// Consumes a `BufRead` and streams XML `Event`s.
+///
+/// # Examples
+///
+/// ```
+/// use quick_xml::Reader;
+/// use quick_xml::events::Event;
+///
+/// let xml = r#"<tag1 att1 = "test">
+///                 <tag2><!--Test comment-->Test</tag2>
+///                 <tag2>Test 2</tag2>
+///             </tag1>"#;
+/// let mut reader = Reader::from_str(xml);
+/// reader.trim_text(true);
+/// let mut count = 0;
+/// let mut txt = Vec::new();
+/// let mut buf = Vec::new();
+/// loop {
+///     match reader.read_event(&mut buf) {
+///         Ok(Event::Start(ref e)) => {
+///             match e.name().as_ref() {
+///                 b"tag1" => println!("attributes values: {:?}",
+///                                     e.attributes().map(|a| a.unwrap().value)
+///                                     .collect::<Vec<_>>()),
+///                 b"tag2" => count += 1,
+///                 _ => (),
+///             }
+///         },
+///         Ok(Event::Text(e)) => txt.push(e.unescape_and_decode(&reader).unwrap()),
+///         Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
+///         Ok(Event::Eof) => break,
+///         _ => (),
+///     }
+///     buf.clear();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Reader<R> {
+    /// reader
+    pub(crate) reader: R,
+    /// current buffer position, useful for debugging errors
+    buf_position: usize,
+    /// position of the `<` of the last read markup, used to report errors
+    markup_start: usize,
+    /// current state Open/Close
+    tag_state: TagState,
+    /// expand empty element into an opening and closing element
+    expand_empty_elements: bool,
+    /// trims leading whitespace in Text events, skip the element if text is empty
+    trim_text_start: bool,
+    /// trims trailing whitespace in Text events.
+    trim_text_end: bool,
+    /// trims trailing whitespaces from markup names in closing tags `</a >`
+    trim_markup_names_in_closing_tags: bool,
+    /// check if End nodes match last Start node
+    check_end_names: bool,
+    /// check if comments contains `--` (false per default)
+    check_comments: bool,
+    /// tracks line and column of the events (disabled per default)
+    tracker: PositionTracker,
+    /// fix problems of malformed documents instead of returning errors (false per default)
+    recover: bool,
+    /// problems fixed in the recovery mode
+    diagnostics: Vec<Diagnostic>,
+    /// follow the HTML parsing rules (false per default)
+    html: bool,
+    /// a start tag that implicitly closed opened elements in the HTML mode
+    pending_start: Option<PendingStart>,
+    /// check XML 1.0 well-formedness constraints (false per default)
+    strict: bool,
+    /// whether the root element was read, used in the strict mode
+    root_seen: bool,
+    /// whether the DOCTYPE was read, used in the strict mode
+    doctype_seen: bool,
+    /// length of the byte order mark at the start of the document, used in the strict mode
+    bom_len: usize,
+    /// remember internal entities declared in the DTD (true per default)
+    expand_entities: bool,
+    /// internal entities declared in the DTD, with references to other entities expanded
+    entities: HashMap<Vec<u8>, Vec<u8>>,
+    /// normalize line endings and attribute values (false per default)
+    normalize: bool,
+    /// names of attributes which type declared in the DTD is not `CDATA`, by element names
+    tokenized_attributes: HashMap<Vec<u8>, Vec<Vec<u8>>>,
+    /// merge consecutive `Text` and `CData` events (false per default)
+    coalesce_text: bool,
+    /// skip comments and processing instructions inside coalesced text (false per default)
+    skip_comments_in_text: bool,
+    /// an event read after coalesced text together with its span and position,
+    /// returned by the next call
+    lookahead: Option<(Event<'static>, Range<usize>, TextPosition)>,
+    /// a buffer for events read after the first part of coalesced text
+    coalesce_buf: Vec<u8>,
+    /// the last read event is a placeholder for a skipped end tag and should
+    /// not be returned
+    skip_event: bool,
+    /// the input consumed by a reader over a [`BufRead`] type, if it is
+    /// recorded by [`Reader::subtrees()`] or [`Reader::read_inner_xml()`]
+    recorded: Option<Vec<u8>>,
+    /// limits of the expansion of entities
+    pub(crate) expansion_limits: ExpansionLimits,
+    /// limits of the resources used to read the document
+    limits: ReaderLimits,
+    /// number of currently opened elements
+    depth: usize,
+    /// byte range of the last read event in the input
+    event_span: Range<usize>,
+    /// the input is a part of the document, so elements can be left opened at
+    /// the end of the input (used by chunks of records)
+    partial: bool,
+    /// All currently Started elements which didn't have a matching
+    /// End element yet.
+    ///
+    /// For an XML
+    ///
+    /// ```xml
+    /// <root><one/><inner attr="value">|<tag></inner></root>
+    /// ```
+    /// when cursor at the `|` position buffer contains:
+    ///
+    /// ```text
+    /// rootinner
+    /// ^   ^
+    /// ```
+    ///
+    /// The `^` symbols shows which positions stored in the [`Self::opened_starts`]
+    /// (0 and 4 in that case).
+    opened_buffer: Vec<u8>,
+    /// Opened name start indexes into [`Self::opened_buffer`]. See documentation
+    /// for that field for details
+    opened_starts: Vec<usize>,
+
+    /// A buffer to manage namespaces
+    ns_resolver: NamespaceResolver,
+    /// For `Empty` events keep the 'scope' of the namespace on the stack artificially. That way, the
+    /// consumer has a chance to use `resolve` in the context of the empty element. We perform the
+    /// pop as the first operation in the next `next()` call.
+    pending_pop: bool,
+
+    #[cfg(feature = "encoding")]
+    /// the encoding specified in the xml, defaults to utf8
+    encoding: &'static Encoding,
+    #[cfg(feature = "encoding")]
+    /// check if quick-rs could find out the encoding
+    is_encoding_set: bool,
+    #[cfg(feature = "encoding")]
+    /// state of the transcoding of UTF-16 and UTF-32 documents into UTF-8
+    transcoder: Transcoder,
+}
+
+impl<R> Reader<R> {
+    /// Creates a `Reader` that reads from a reader implementing `BufRead`.
+    pub fn from_reader(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            opened_buffer: Vec::new(),
+            opened_starts: Vec::new(),
+            tag_state: TagState::Closed,
+            expand_empty_elements: false,
+            trim_text_start: false,
+            trim_text_end: false,
+            trim_markup_names_in_closing_tags: true,
+            check_end_names: true,
+            buf_position: 0,
+            markup_start: 0,
+            check_comments: false,
+            tracker: PositionTracker::default(),
+            recover: false,
+            diagnostics: Vec::new(),
+            html: false,
+            pending_start: None,
+            strict: false,
+            root_seen: false,
+            doctype_seen: false,
+            bom_len: 0,
+            expand_entities: true,
+            entities: HashMap::new(),
+            normalize: false,
+            tokenized_attributes: HashMap::new(),
+            coalesce_text: false,
+            skip_comments_in_text: false,
+            lookahead: None,
+            coalesce_buf: Vec::new(),
+            skip_event: false,
+            recorded: None,
+            expansion_limits: ExpansionLimits::default(),
+            limits: ReaderLimits::default(),
+            depth: 0,
+            event_span: 0..0,
+            partial: false,
+
+            ns_resolver: NamespaceResolver::default(),
+            pending_pop: false,
+
+            #[cfg(feature = "encoding")]
+            encoding: ::encoding_rs::UTF_8,
+            #[cfg(feature = "encoding")]
+            is_encoding_set: false,
+            #[cfg(feature = "encoding")]
+            transcoder: Transcoder::new(),
+        }
+    }
+
+    /// Changes whether empty elements should be split into an `Open` and a `Close` event.
+    ///
+    /// When set to `true`, all [`Empty`] events produced by a self-closing tag like `<tag/>` are
+    /// expanded into a [`Start`] event followed by an [`End`] event. When set to `false` (the
+    /// default), those tags are represented by an [`Empty`] event instead.
+    ///
+    /// The tag name for the [`End`] event is moved out of the stack of opened
+    /// elements, which is maintained regardless of [`Self::check_end_names()`].
+    ///
+    /// (`false` by default)
+    ///
+    /// [`Empty`]: events/enum.Event.html#variant.Empty
+    /// [`Start`]: events/enum.Event.html#variant.Start
+    /// [`End`]: events/enum.Event.html#variant.End
+    pub fn expand_empty_elements(&mut self, val: bool) -> &mut Reader<R> {
+        self.expand_empty_elements = val;
+        self
+    }
+
+    /// Changes whether whitespace before and after character data should be removed.
+    ///
+    /// When set to `true`, all [`Text`] events are trimmed. If they are empty, no event will be
+    /// pushed.
+    ///
+    /// (`false` by default)
+    ///
+    /// [`Text`]: events/enum.Event.html#variant.Text
+    pub fn trim_text(&mut self, val: bool) -> &mut Reader<R> {
+        self.trim_text_start = val;
+        self.trim_text_end = val;
+        self
+    }
+
+    /// Changes whether whitespace after character data should be removed.
+    ///
+    /// When set to `true`, trailing whitespace is trimmed in [`Text`] events.
+    ///
+    /// (`false` by default)
+    ///
+    /// [`Text`]: events/enum.Event.html#variant.Text
+    pub fn trim_text_end(&mut self, val: bool) -> &mut Reader<R> {
+        self.trim_text_end = val;
+        self
+    }
+
+    /// Changes whether trailing whitespaces after the markup name are trimmed in closing tags
+    /// `</a >`.
+    ///
+    /// If true the emitted [`End`] event is stripped of trailing whitespace after the markup name.
+    ///
+    /// Note that if set to `false` and `check_end_names` is true the comparison of markup names is
+    /// going to fail erronously if a closing tag contains trailing whitespaces.
+    ///
+    /// (`true` by default)
+    ///
+    /// [`End`]: events/enum.Event.html#variant.End
+    pub fn trim_markup_names_in_closing_tags(&mut self, val: bool) -> &mut Reader<R> {
+        self.trim_markup_names_in_closing_tags = val;
+        self
+    }
+
+    /// Changes whether mismatched closing tag names should be detected.
+    ///
+    /// When set to `false`, it won't check if a closing tag matches the corresponding opening tag.
+    /// For example, `<mytag></different_tag>` will be permitted.
+    ///
+    /// Note that the emitted [`End`] event will not be modified if this is disabled, ie. it will
+    /// contain the data of the mismatched end tag.
+    ///
+    /// The names of opened elements are stored regardless of this option, so
+    /// [`Self::path()`] is available even if it is disabled. In that case each
+    /// end tag closes the innermost opened element.
+    ///
+    /// (`true` by default)
+    ///
+    /// [`End`]: events/enum.Event.html#variant.End
+    pub fn check_end_names(&mut self, val: bool) -> &mut Reader<R> {
+        self.check_end_names = val;
+        self
+    }
+
+    /// Changes whether comments should be validated.
+    ///
+    /// When set to `true`, every [`Comment`] event will be checked for not containing `--`, which
+    /// is not allowed in XML comments. Most of the time we don't want comments at all so we don't
+    /// really care about comment correctness, thus the default value is `false` to improve
+    /// performance.
+    ///
+    /// (`false` by default)
+    ///
+    /// [`Comment`]: events/enum.Event.html#variant.Comment
+    pub fn check_comments(&mut self, val: bool) -> &mut Reader<R> {
+        self.check_comments = val;
+        self
+    }
+
+    /// Changes whether the line and column of the events should be tracked.
+    ///
+    /// When set to `true`, the position of the start of the last read event is
+    /// available from [`Self::event_position()`] and all errors returned when
+    /// reading events are wrapped into [`Error::Positioned`] with the position
+    /// of the start of the event that caused the error.
+    ///
+    /// Columns are counted in UTF-8 characters, not in bytes. `\n`, `\r` and
+    /// `\r\n` are recognized as line ends.
+    ///
+    /// (`false` by default)
+    pub fn track_position(&mut self, val: bool) -> &mut Reader<R> {
+        self.tracker.enabled = val;
+        self
+    }
+
+    /// Changes whether the reader should recover from common problems of
+    /// malformed documents instead of returning an error.
+    ///
+    /// When set to `true`:
+    /// - `&` in the text or in an attribute value which does not start
+    ///   a character or an entity reference is treated as a text and is
+    ///   escaped as `&amp;`;
+    /// - if [`Self::check_end_names()`] is set, an end tag that matches one of
+    ///   the outer opened elements closes all elements opened after it, and an
+    ///   end tag that does not match any opened element is skipped;
+    /// - malformed and duplicated attributes are removed from the [`Start`] and
+    ///   [`Empty`] events.
+    ///
+    /// Each fixed problem is recorded as a [`Diagnostic`] which can be
+    /// inspected with [`Self::diagnostics()`].
+    ///
+    /// (`false` by default)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quick_xml::events::{BytesEnd, BytesText, Event};
+    /// use quick_xml::{Diagnostic, Reader};
+    ///
+    /// let mut reader = Reader::from_str("<root><p>Tom & Jerry</root>");
+    /// reader.trim_text(true).recover(true);
+    /// let mut buf = Vec::new();
+    ///
+    /// reader.read_event(&mut buf).unwrap();
+    /// reader.read_event(&mut buf).unwrap();
+    /// assert_eq!(
+    ///     reader.read_event(&mut buf).unwrap(),
+    ///     Event::Text(BytesText::from_escaped_str("Tom &amp; Jerry"))
+    /// );
+    /// assert_eq!(reader.read_event(&mut buf).unwrap(), Event::End(BytesEnd::borrowed(b"p")));
+    /// assert_eq!(reader.read_event(&mut buf).unwrap(), Event::End(BytesEnd::borrowed(b"root")));
+    /// assert_eq!(reader.read_event(&mut buf).unwrap(), Event::Eof);
+    ///
+    /// assert_eq!(reader.diagnostics(), &[
+    ///     Diagnostic::BareAmpersand(13),
+    ///     Diagnostic::MismatchedEnd {
+    ///         expected: "p".to_string(),
+    ///         found: "root".to_string(),
+    ///         span: 20..27,
+    ///     },
+    /// ]);
+    /// ```
+    ///
+    /// [`Start`]: events/enum.Event.html#variant.Start
+    /// [`Empty`]: events/enum.Event.html#variant.Empty
+    pub fn recover(&mut self, val: bool) -> &mut Reader<R> {
+        self.recover = val;
+        self
+    }
+
+    /// Gets the problems of the document that were fixed in the [recovery mode]
+    /// so far, in order of their appearance.
+    ///
+    /// [recovery mode]: Self::recover
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Takes the problems of the document that were fixed in the [recovery mode]
+    /// so far, leaving the list empty.
+    ///
+    /// [recovery mode]: Self::recover
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Changes whether the reader should check that the document is well-formed
+    /// according to the [XML 1.0] specification.
+    ///
+    /// When set to `true`, the following constraints are checked in addition
+    /// to the usual checks of the reader and violations are returned as errors:
+    /// - names of elements, attributes, processing instruction targets and
+    ///   entity references match the [`Name`] production
+    ///   ([`Error::InvalidName`]);
+    /// - characters and character references match the [`Char`] production
+    ///   ([`Error::InvalidChar`]);
+    /// - entity and character references are well-formed, text does not contain
+    ///   `]]>` and attribute values do not contain `<`
+    ///   ([`Error::UnexpectedToken`]);
+    /// - attributes are well-formed and unique ([`Error::InvalidAttr`]);
+    /// - the XML declaration is at the very start of the document and the
+    ///   DOCTYPE is before the root element ([`Error::MisplacedDeclaration`]);
+    /// - the document contains exactly one root element
+    ///   ([`Error::MissingRoot`], [`Error::MultipleRoots`]) and no text or
+    ///   CDATA outside of it ([`Error::ContentOutsideRoot`]);
+    /// - all elements are closed ([`Error::UnexpectedEof`]).
+    ///
+    /// The strict mode also implies [`Self::check_end_names()`] and
+    /// [`Self::check_comments()`].
+    ///
+    /// (`false` by default)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quick_xml::{Error, Reader};
+    ///
+    /// let mut reader = Reader::from_str("<root/><root/>");
+    /// reader.trim_text(true).strict(true);
+    /// let mut buf = Vec::new();
+    ///
+    /// reader.read_event(&mut buf).unwrap();
+    /// match reader.read_event(&mut buf) {
+    ///     Err(Error::MultipleRoots(span)) => assert_eq!(span, 7..14),
+    ///     x => panic!("Expected `Error::MultipleRoots`, but got {:?}", x),
+    /// }
+    /// ```
+    ///
+    /// [XML 1.0]: https://www.w3.org/TR/xml/
+    /// [`Name`]: https://www.w3.org/TR/xml/#NT-Name
+    /// [`Char`]: https://www.w3.org/TR/xml/#NT-Char
+    pub fn strict(&mut self, val: bool) -> &mut Reader<R> {
+        self.strict = val;
+        self
+    }
+
+    /// Changes whether the document should be read by the parsing rules of HTML
+    /// instead of XML, so ordinary HTML pages can be read with the same events.
+    ///
+    /// When set to `true`:
+    /// - [void elements], like `<br>`, `<img>` and `<meta>`, are reported as
+    ///   [`Empty`] events, and their end tags are skipped;
+    /// - the content of `<script>` and `<style>` elements is returned as a [`Text`]
+    ///   event as is, until the end tag of the element. `<` and `&` in the
+    ///   content do not start markup and are escaped in the event;
+    /// - start tags close opened elements which end tags can be omitted:
+    ///   `<li>` closes a previous `<li>` of the same list, `<td>` and `<th>`
+    ///   close a previous cell of the same row, `<tr>` closes a previous row,
+    ///   `<dt>` and `<dd>` close each other, and block elements, like `<div>`
+    ///   or `<p>`, close an opened `<p>`. [`End`] events for the closed elements
+    ///   are returned before the [`Start`] event;
+    /// - end tags close all elements opened inside the element, and end tags
+    ///   that do not match any opened element are skipped;
+    /// - elements left opened at the end of the document are closed;
+    /// - element names are matched case-insensitively. [`End`] events have
+    ///   the names from the [`Start`] events.
+    ///
+    /// Attributes of HTML elements can be read with [`BytesStart::html_attributes()`],
+    /// the [recovery mode](Self::recover) does not remove unquoted values and
+    /// attributes without values. Checks of the [strict mode](Self::strict) are
+    /// not relaxed by this mode.
+    ///
+    /// (`false` by default)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str("<UL><li>One<br><li>Two</ul>");
+    /// reader.trim_text(true).html(true);
+    /// let mut events = Vec::new();
+    /// loop {
+    ///     match reader.read_event_unbuffered().unwrap() {
+    ///         Event::Eof => break,
+    ///         e => events.push(e),
+    ///     }
+    /// }
+    /// assert_eq!(
+    ///     events,
+    ///     vec![
+    ///         Event::Start(BytesStart::borrowed_name(b"UL")),
+    ///         Event::Start(BytesStart::borrowed_name(b"li")),
+    ///         Event::Text(BytesText::from_escaped_str("One")),
+    ///         Event::Empty(BytesStart::borrowed_name(b"br")),
+    ///         Event::End(BytesEnd::borrowed(b"li")),
+    ///         Event::Start(BytesStart::borrowed_name(b"li")),
+    ///         Event::Text(BytesText::from_escaped_str("Two")),
+    ///         Event::End(BytesEnd::borrowed(b"li")),
+    ///         Event::End(BytesEnd::borrowed(b"UL")),
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [void elements]: https://html.spec.whatwg.org/multipage/syntax.html#void-elements
+    /// [`Start`]: events/enum.Event.html#variant.Start
+    /// [`Empty`]: events/enum.Event.html#variant.Empty
+    /// [`End`]: events/enum.Event.html#variant.End
+    /// [`Text`]: events/enum.Event.html#variant.Text
+    /// [`BytesStart::html_attributes()`]: crate::events::BytesStart::html_attributes
+    pub fn html(&mut self, val: bool) -> &mut Reader<R> {
+        self.html = val;
+        self
+    }
+
+    /// Changes whether internal entities declared in the DTD should be remembered
+    /// and used to unescape text and attribute values.
+    ///
+    /// When set to `true`, the content of the [`DocType`] event is parsed and
+    /// internal general entities from it are stored in the reader, available
+    /// from [`Self::entities()`]. References to other entities in the values of
+    /// entities are expanded recursively. The entities are used by the methods
+    /// that unescape text and attribute values with the reader, such as
+    /// [`BytesText::unescape_and_decode()`] and
+    /// [`Attribute::unescape_and_decode_value()`], and by the serde deserializer.
+    /// Custom entities passed to the `*_with_custom_entities` methods override
+    /// the declared entities with the same names. Methods that unescape without
+    /// the reader, such as [`BytesText::unescaped()`], do not know the declared
+    /// entities.
+    ///
+    /// In the [strict mode] errors in the DTD are reported as [`Error::InvalidDtd`],
+    /// and recursive or undefined entities as [`Error::EscapeError`] when the
+    /// [`DocType`] event is read. Otherwise invalid declarations and entities are
+    /// skipped, and unescaping fails only if such an entity is referenced.
+    /// Exceeding of the [expansion limits] is always an error.
+    ///
+    /// (`true` by default)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quick_xml::events::Event;
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str(r#"<!DOCTYPE root [
+    ///     <!ENTITY name "World">
+    ///     <!ENTITY greeting "Hello, &name;!">
+    /// ]>
+    /// <root attr="&greeting;">&greeting;</root>"#);
+    /// reader.trim_text(true);
+    /// let mut buf = Vec::new();
+    ///
+    /// assert!(matches!(reader.read_event(&mut buf).unwrap(), Event::DocType(_)));
+    /// match reader.read_event(&mut buf).unwrap() {
+    ///     Event::Start(e) => {
+    ///         let attr = e.attributes().next().unwrap().unwrap();
+    ///         assert_eq!(attr.unescape_and_decode_value(&reader).unwrap(), "Hello, World!");
+    ///     }
+    ///     e => panic!("Unexpected event {:?}", e),
+    /// }
+    /// match reader.read_event(&mut buf).unwrap() {
+    ///     Event::Text(e) => assert_eq!(e.unescape_and_decode(&reader).unwrap(), "Hello, World!"),
+    ///     e => panic!("Unexpected event {:?}", e),
+    /// }
+    /// ```
+    ///
+    /// [`DocType`]: events/enum.Event.html#variant.DocType
+    /// [`BytesText::unescape_and_decode()`]: crate::events::BytesText::unescape_and_decode
+    /// [`Attribute::unescape_and_decode_value()`]: crate::events::attributes::Attribute::unescape_and_decode_value
+    /// [`BytesText::unescaped()`]: crate::events::BytesText::unescaped
+    /// [strict mode]: Self::strict
+    /// [expansion limits]: Self::expansion_limits
+    pub fn expand_entities(&mut self, val: bool) -> &mut Reader<R> {
+        self.expand_entities = val;
+        self
+    }
+
+    /// Gets the internal entities declared in the DTD of the document, with
+    /// references to other entities expanded. Empty until the [`DocType`] event
+    /// is read or if [`Self::expand_entities()`] is disabled.
+    ///
+    /// [`DocType`]: events/enum.Event.html#variant.DocType
+    pub fn entities(&self) -> &HashMap<Vec<u8>, Vec<u8>> {
+        &self.entities
+    }
+
+    /// Returns the entities declared in the DTD together with the custom entities,
+    /// which override the declared entities with the same names
+    pub(crate) fn entities_with<'e>(
+        &'e self,
+        custom_entities: Option<&'e HashMap<Vec<u8>, Vec<u8>>>,
+    ) -> Cow<'e, HashMap<Vec<u8>, Vec<u8>>> {
+        match custom_entities {
+            None => Cow::Borrowed(&self.entities),
+            Some(custom) if self.entities.is_empty() => Cow::Borrowed(custom),
+            Some(custom) => {
+                let mut entities = self.entities.clone();
+                entities.extend(custom.iter().map(|(k, v)| (k.clone(), v.clone())));
+                Cow::Owned(entities)
+            }
+        }
+    }
+
+    /// Changes the limits of the expansion of entities, used when entities
+    /// declared in the DTD are [expanded] and by the methods that unescape
+    /// text and attribute values with the reader. Methods that unescape without
+    /// the reader, such as [`BytesText::unescaped()`], always use the default limits.
+    ///
+    /// Exceeding a limit is reported as [`EscapeError::ExpansionLimitExceeded`].
+    ///
+    /// ([`ExpansionLimits::default()`] by default)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quick_xml::escape::{EscapeError, ExpansionLimit, ExpansionLimits};
+    /// use quick_xml::{Error, Reader};
+    ///
+    /// let mut reader = Reader::from_str(r#"<!DOCTYPE root [
+    ///     <!ENTITY a "a">
+    ///     <!ENTITY b "&a;&a;">
+    ///     <!ENTITY c "&b;&b;">
+    /// ]>
+    /// <root>&c;</root>"#);
+    /// reader.trim_text(true);
+    /// reader.expansion_limits(ExpansionLimits {
+    ///     max_depth: 2,
+    ///     ..ExpansionLimits::default()
+    /// });
+    ///
+    /// match reader.read_event_unbuffered() {
+    ///     Err(Error::EscapeError(EscapeError::ExpansionLimitExceeded(ExpansionLimit::Depth(2)))) => {}
+    ///     x => panic!("Expected depth limit error, but got {:?}", x),
+    /// }
+    /// ```
+    ///
+    /// [expanded]: Self::expand_entities
+    /// [`BytesText::unescaped()`]: crate::events::BytesText::unescaped
+    /// [`EscapeError::ExpansionLimitExceeded`]: crate::escape::EscapeError::ExpansionLimitExceeded
+    /// [`ExpansionLimits::default()`]: crate::escape::ExpansionLimits
+    pub fn expansion_limits(&mut self, limits: ExpansionLimits) -> &mut Reader<R> {
+        self.expansion_limits = limits;
+        self
+    }
+
+    /// Changes whether the content of events should be normalized as the XML
+    /// specification requires, so it matches the content reported by other
+    /// XML processors:
+    ///
+    /// - [line endings] `\r\n` and `\r` are replaced by `\n` in text, `CDATA`
+    ///   sections, comments, processing instructions, the DTD and start tags;
+    /// - [attribute values] of start tags have each whitespace character replaced
+    ///   by a space. Values of attributes which type declared in the DTD is not
+    ///   `CDATA` also have leading and trailing spaces removed and sequences of
+    ///   spaces replaced by a single space.
+    ///
+    /// Events are normalized before unescaping, so characters written as
+    /// character references, like `&#13;` or `&#10;`, are kept as is, and
+    /// [`BytesText::unescaped()`] and [`Attribute::unescaped_value()`] return
+    /// normalized values. Positions of attributes in [errors] of normalized
+    /// start tags are not adjusted for removed characters.
+    ///
+    /// (`false` by default)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str(
+    ///     "<!DOCTYPE root [<!ATTLIST root id ID #IMPLIED>]>\r\n\
+    ///      <root id=' a\r\n' title='one\r\ntwo&#10;'>one\r\ntwo&#13;</root>",
+    /// );
+    /// reader.trim_text(true).normalize(true);
+    ///
+    /// assert!(matches!(reader.read_event_unbuffered().unwrap(), Event::DocType(_)));
+    /// match reader.read_event_unbuffered().unwrap() {
+    ///     Event::Start(e) => {
+    ///         let id = e.try_get_attribute("id").unwrap().unwrap();
+    ///         assert_eq!(id.unescaped_value().unwrap(), "a".as_bytes());
+    ///         let title = e.try_get_attribute("title").unwrap().unwrap();
+    ///         assert_eq!(title.unescaped_value().unwrap(), "one two\n".as_bytes());
+    ///     }
+    ///     e => panic!("Unexpected event {:?}", e),
+    /// }
+    /// match reader.read_event_unbuffered().unwrap() {
+    ///     Event::Text(e) => assert_eq!(e.unescaped().unwrap(), "one\ntwo\r".as_bytes()),
+    ///     e => panic!("Unexpected event {:?}", e),
+    /// }
+    /// ```
+    ///
+    /// [line endings]: https://www.w3.org/TR/xml/#sec-line-ends
+    /// [attribute values]: https://www.w3.org/TR/xml/#AVNormalize
+    /// [`BytesText::unescaped()`]: crate::events::BytesText::unescaped
+    /// [`Attribute::unescaped_value()`]: crate::events::attributes::Attribute::unescaped_value
+    /// [errors]: crate::events::attributes::AttrError
+    pub fn normalize(&mut self, val: bool) -> &mut Reader<R> {
+        self.normalize = val;
+        self
+    }
+
+    /// Changes whether consecutive `Text` and `CData` events are merged into one
+    /// `Text` event, so a text, that is split by CDATA sections, like
+    /// `foo<![CDATA[<bar>]]>baz`, is read as a single event.
+    ///
+    /// The content of CDATA sections is escaped in the merged event, so
+    /// [`BytesText::unescaped()`] returns the whole text. A single `Text` or `CData`
+    /// event is returned unchanged. Start and end tags are never read ahead,
+    /// so the reader is in the same state after the merged text as after its
+    /// last part.
+    ///
+    /// See also [`Self::skip_comments_in_text()`].
+    ///
+    /// (`false` by default)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::{BytesEnd, BytesStart, Event};
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str("<a>foo<![CDATA[<bar>]]>baz<!-- c --></a>");
+    /// reader.trim_text(true).coalesce_text(true);
+    ///
+    /// assert_eq!(reader.read_event_unbuffered().unwrap(), Event::Start(BytesStart::borrowed_name(b"a")));
+    /// match reader.read_event_unbuffered().unwrap() {
+    ///     Event::Text(e) => assert_eq!(e.unescaped().unwrap(), "foo<bar>baz".as_bytes()),
+    ///     e => panic!("Unexpected event {:?}", e),
+    /// }
+    /// assert!(matches!(reader.read_event_unbuffered().unwrap(), Event::Comment(_)));
+    /// assert_eq!(reader.read_event_unbuffered().unwrap(), Event::End(BytesEnd::borrowed(b"a")));
+    /// ```
+    ///
+    /// [`BytesText::unescaped()`]: crate::events::BytesText::unescaped
+    pub fn coalesce_text(&mut self, val: bool) -> &mut Reader<R> {
+        self.coalesce_text = val;
+        self
+    }
+
+    /// Changes whether comments and processing instructions inside a text are
+    /// skipped when [`Self::coalesce_text()`] is enabled, so `a<!-- c -->b` is
+    /// read as a single `Text` event. Comments and processing instructions,
+    /// that are not preceded by a text, are still returned.
+    ///
+    /// (`false` by default)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::{BytesText, Event};
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str("<a>a<!-- c -->b<?pi?>c</a>");
+    /// reader
+    ///     .trim_text(true)
+    ///     .coalesce_text(true)
+    ///     .skip_comments_in_text(true);
+    ///
+    /// reader.read_event_unbuffered().unwrap(); // <a>
+    /// assert_eq!(
+    ///     reader.read_event_unbuffered().unwrap(),
+    ///     Event::Text(BytesText::from_escaped_str("abc"))
+    /// );
+    /// ```
+    pub fn skip_comments_in_text(&mut self, val: bool) -> &mut Reader<R> {
+        self.skip_comments_in_text = val;
+        self
+    }
+
+    /// Changes the limits of the resources used to read the document: the
+    /// length of markup, text and names, the nesting depth of elements and
+    /// the number of attributes. Limits protect from untrusted documents
+    /// that make the reader grow its buffers without bound.
+    ///
+    /// Markup and text are never read into the buffer beyond the limit, so
+    /// the memory used by the reader is bounded even if a `>` or `<` never
+    /// appears in the input.
+    ///
+    /// ([`ReaderLimits::default()`], that does not limit anything, by default)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quick_xml::{Error, Reader, ReaderLimits};
+    ///
+    /// let mut reader = Reader::from_str("<root><a><b><c/></b></a></root>");
+    /// reader.trim_text(true).limits(ReaderLimits {
+    ///     max_depth: 2,
+    ///     ..ReaderLimits::default()
+    /// });
+    /// let mut buf = Vec::new();
+    ///
+    /// reader.read_event(&mut buf).unwrap(); // <root>
+    /// reader.read_event(&mut buf).unwrap(); // <a>
+    /// match reader.read_event(&mut buf) {
+    ///     Err(Error::TooDeep(2, span)) => assert_eq!(span, 9..12),
+    ///     x => panic!("Expected `TooDeep`, but got {:?}", x),
+    /// }
+    /// ```
+    ///
+    /// [`ReaderLimits::default()`]: ReaderLimits
+    pub fn limits(&mut self, limits: ReaderLimits) -> &mut Reader<R> {
+        self.limits = limits;
+        self
+    }
+
+    /// Gets the line and column of the start of the last read event.
+    ///
+    /// For markup events this is the position of the opening `<`, for [`Text`]
+    /// events the position of the first character (after trimmed whitespaces,
+    /// if text is trimmed). Returns `None` if position tracking is not
+    /// [enabled](Self::track_position).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quick_xml::events::Event;
+    /// use quick_xml::{Reader, TextPosition};
+    ///
+    /// let mut reader = Reader::from_str("<root>\n  <child/>\n</root>");
+    /// reader.trim_text(true).track_position(true);
+    /// let mut buf = Vec::new();
+    ///
+    /// reader.read_event(&mut buf).unwrap();
+    /// match reader.read_event(&mut buf).unwrap() {
+    ///     Event::Empty(e) => assert_eq!(e.name().as_ref(), b"child"),
+    ///     e => panic!("Unexpected event {:?}", e),
+    /// }
+    /// assert_eq!(reader.event_position(), Some(TextPosition { line: 2, column: 3 }));
+    /// ```
+    ///
+    /// [`Text`]: events/enum.Event.html#variant.Text
+    pub fn event_position(&self) -> Option<TextPosition> {
+        if self.tracker.enabled {
+            Some(self.tracker.event)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the byte range of the last read event in the input: from `<` to `>`
+    /// inclusive for markup and the raw content for [`Text`] events. Leading
+    /// whitespaces skipped when [`trim_text`] is set are not part of any event,
+    /// but trailing whitespaces, removed by [`trim_text_end`], are included.
+    ///
+    /// [`End`] events of [expanded empty elements] and elements closed implicitly
+    /// in the [recovery mode], and the [`Eof`] event are not present in the input
+    /// and have an empty range at the position where they are generated. When
+    /// an end tag closes several elements, its range is given to the `End` event
+    /// of the element with the same name, which is returned last.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quick_xml::Reader;
+    ///
+    /// let xml = "<root>\n  <child attr='value'/>\n</root>";
+    /// let mut reader = Reader::from_str(xml);
+    /// reader.trim_text(true);
+    ///
+    /// reader.read_event_unbuffered().unwrap();
+    /// reader.read_event_unbuffered().unwrap();
+    /// assert_eq!(&xml[reader.event_span()], "<child attr='value'/>");
+    /// ```
+    ///
+    /// [`Text`]: events/enum.Event.html#variant.Text
+    /// [`End`]: events/enum.Event.html#variant.End
+    /// [`Eof`]: events/enum.Event.html#variant.Eof
+    /// [expanded empty elements]: Self::expand_empty_elements
+    /// [recovery mode]: Self::recover
+    /// [`trim_text`]: Self::trim_text
+    /// [`trim_text_end`]: Self::trim_text_end
+    pub fn event_span(&self) -> Range<usize> {
+        self.event_span.clone()
+    }
+
+    /// Gets the current byte position in the input data.
+    ///
+    /// Useful when debugging errors.
+    pub fn buffer_position(&self) -> usize {
+        // when internal state is Opened, we have actually read until '<',
+        // which we don't want to show
+        if let TagState::Opened = self.tag_state {
+            self.buf_position - 1
+        } else {
+            self.buf_position
+        }
+    }
+
+    /// Returns the number of currently opened elements.
+    ///
+    /// The element is opened after its [`Start`] event and closed after its
+    /// [`End`] event, so elements of [`Empty`] events are never opened, unless
+    /// [`Self::expand_empty_elements()`] is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str("<root><a><b/></a></root>");
+    /// reader.trim_text(true);
+    ///
+    /// assert_eq!(reader.depth(), 0);
+    /// reader.read_event_unbuffered().unwrap(); // <root>
+    /// reader.read_event_unbuffered().unwrap(); // <a>
+    /// assert_eq!(reader.depth(), 2);
+    /// reader.read_event_unbuffered().unwrap(); // <b/>
+    /// assert_eq!(reader.depth(), 2);
+    /// reader.read_event_unbuffered().unwrap(); // </a>
+    /// assert_eq!(reader.depth(), 1);
+    /// ```
+    ///
+    /// [`Start`]: events/enum.Event.html#variant.Start
+    /// [`End`]: events/enum.Event.html#variant.End
+    /// [`Empty`]: events/enum.Event.html#variant.Empty
+    pub fn depth(&self) -> usize {
+        self.opened_starts.len()
+    }
+
+    /// Returns an iterator over the names of currently opened elements, from
+    /// the root to the innermost one. See [`Self::depth()`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::name::QName;
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str("<root><p:a><b>");
+    /// reader.trim_text(true);
+    /// for _ in 0..3 {
+    ///     reader.read_event_unbuffered().unwrap();
+    /// }
+    ///
+    /// assert_eq!(
+    ///     reader.opened_names().collect::<Vec<_>>(),
+    ///     vec![QName(b"root"), QName(b"p:a"), QName(b"b")]
+    /// );
+    /// assert_eq!(reader.opened_names().next_back(), Some(QName(b"b")));
+    /// ```
+    pub fn opened_names(&self) -> OpenedNames<'_> {
+        OpenedNames::new(&self.opened_buffer, &self.opened_starts)
+    }
+
+    /// Returns the path to the innermost opened element, which is displayed
+    /// like `/root/a/b`. See [`Self::depth()`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quick_xml::events::Event;
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str("<root><a>text</a></root>");
+    /// reader.trim_text(true);
+    ///
+    /// assert_eq!(reader.path().to_string(), "/");
+    /// loop {
+    ///     match reader.read_event_unbuffered().unwrap() {
+    ///         Event::Text(_) => break,
+    ///         _ => {}
+    ///     }
+    /// }
+    /// assert_eq!(reader.path().to_string(), "/root/a");
+    /// ```
+    pub fn path(&self) -> ElementPath<'_> {
+        ElementPath(self.opened_names())
+    }
+
+    /// private function to read until '<' is found
+    /// return a `Text` event
+    fn read_until_open<'i, B>(&mut self, buf: B) -> Result<std::result::Result<Event<'i>, B>>
+    where
+        R: XmlSource<'i, B>,
+    {
+        read_until_open!(self, buf, self.reader)
+    }
+
+    /// Private function to read until `>` is found. This function expects that
+    /// it was called just after encounter a `<` symbol.
+    fn read_until_close<'i, B>(&mut self, buf: B) -> Result<Event<'i>>
+    where
+        R: XmlSource<'i, B>,
+    {
+        read_until_close!(self, buf, self.reader)
+    }
+
+    /// Trims trailing whitespaces from the text, if [`Self::trim_text_end`] is
+    /// set, and returns a `Text` event. `start` is the position of the text in
+    /// the document
+    fn read_text_event<'b>(&mut self, bytes: &'b [u8], start: usize) -> Result<Event<'b>> {
+        if self.strict {
+            self.check_text(bytes, start)?;
+        }
+        let bytes = if self.trim_text_end {
+            // Skip the ending '<
+            let len = bytes
+                .iter()
+                .rposition(|&b| !is_whitespace(b))
+                .map_or_else(|| bytes.len(), |p| p + 1);
+            &bytes[..len]
+        } else {
+            bytes
+        };
+        if self.recover {
+            if let Some(escaped) =
+                recovery::escape_bare_ampersands(bytes, start, &mut self.diagnostics)
+            {
+                return Ok(Event::Text(BytesText::from_escaped(escaped)));
+            }
+        }
+        Ok(Event::Text(BytesText::from_escaped(bytes)))
+    }
+
+    /// reads `BytesElement` starting with a `/`,
+    /// if `self.check_end_names`, checks that element matches last opened element
+    /// return `End` event
+    fn read_end<'a, 'b>(&'a mut self, buf: &'b [u8]) -> Result<Event<'b>> {
+        // XML standard permits whitespaces after the markup name in closing tags.
+        // Let's strip them from the buffer before comparing tag names.
+        let name = if self.trim_markup_names_in_closing_tags {
+            if let Some(pos_end_name) = buf[1..].iter().rposition(|&b| !b.is_ascii_whitespace()) {
+                let (name, _) = buf[1..].split_at(pos_end_name + 1);
+                name
+            } else {
+                &buf[1..]
+            }
+        } else {
+            &buf[1..]
+        };
+        // +2 for `</`
+        self.limits.check_name(name.len(), self.markup_start + 2)?;
+        if self.html {
+            return Ok(self.close_html(name, self.markup_start..self.buf_position));
+        }
+        if self.check_end_names || self.strict {
+            let span = self.markup_start..self.buf_position;
+            let mismatch_err = |expected: &[u8], found: &[u8], buf_position: &mut usize| {
+                *buf_position -= buf.len();
+                Err(Error::EndEventMismatch {
+                    expected: from_utf8(expected).unwrap_or("").to_owned(),
+                    found: from_utf8(found).unwrap_or("").to_owned(),
+                    span: span.clone(),
+                })
+            };
+            match self.opened_starts.pop() {
+                Some(start) => {
+                    let expected = &self.opened_buffer[start..];
+                    if name != expected {
+                        if self.recover {
+                            self.opened_starts.push(start);
+                            return Ok(self.recover_end(name, span));
+                        }
+                        mismatch_err(expected, name, &mut self.buf_position)
+                    } else {
+                        self.opened_buffer.truncate(start);
+                        Ok(Event::End(BytesEnd::borrowed(name)))
+                    }
+                }
+                None if self.recover => Ok(self.recover_end(name, span)),
+                None => mismatch_err(b"", &buf[1..], &mut self.buf_position),
+            }
+        } else {
+            // Names are not compared, so the innermost element is closed
+            if let Some(start) = self.opened_starts.pop() {
+                self.opened_buffer.truncate(start);
+            }
+            Ok(Event::End(BytesEnd::borrowed(name)))
+        }
+    }
+
+    /// Checks the well-formedness of a text in the strict mode. A byte order
+    /// mark is allowed at the start of the document
+    fn check_text(&mut self, bytes: &[u8], start: usize) -> Result<()> {
+        let (bytes, start) = if start == 0 && bytes.starts_with(b"\xEF\xBB\xBF") {
+            self.bom_len = 3;
+            (&bytes[3..], 3)
+        } else {
+            (bytes, start)
+        };
+        strict::check_text(bytes, start, !self.opened_starts.is_empty(), self.is_utf8())
+    }
+
+    /// Returns an `Eof` event. In the strict mode checks that the document
+    /// contains a root element and that all elements are closed. In the HTML
+    /// mode closes all opened elements first
+    fn read_eof(&mut self) -> Result<Event<'static>> {
+        if self.html && !self.partial && !self.opened_starts.is_empty() {
+            return self.close_unclosed(0);
+        }
+        if self.strict && !self.partial {
+            if !self.opened_starts.is_empty() {
+                let end = self.buf_position;
+                return Err(Error::UnexpectedEof("Element".to_string(), end..end));
+            }
+            if !self.root_seen {
+                let end = self.buf_position;
+                return Err(Error::MissingRoot(end..end));
+            }
+        }
+        Ok(Event::Eof)
+    }
+
+    /// Handles an end tag that does not match the last opened element in the
+    /// recovery mode. If one of the outer elements has the same name, closes
+    /// all elements opened after it, otherwise skips the end tag
+    fn recover_end(&mut self, name: &[u8], span: Range<usize>) -> Event<'static> {
+        let buffer = &self.opened_buffer;
+        let starts = &self.opened_starts;
+        let found = (0..starts.len()).rposition(|i| {
+            let end = starts.get(i + 1).copied().unwrap_or(buffer.len());
+            &buffer[starts[i]..end] == name
+        });
+        let diagnostic = match found {
+            Some(_) => Diagnostic::MismatchedEnd {
+                expected: from_utf8(&buffer[*starts.last().unwrap()..])
+                    .unwrap_or("")
+                    .to_owned(),
+                found: from_utf8(name).unwrap_or("").to_owned(),
+                span,
+            },
+            None => Diagnostic::UnmatchedEnd {
+                found: from_utf8(name).unwrap_or("").to_owned(),
+                span,
+            },
+        };
+        self.diagnostics.push(diagnostic);
+        match found {
+            Some(depth) => self.close_unclosed(depth).unwrap(),
+            None => self.skip_end(),
+        }
+    }
+
+    /// Marks the end tag as skipped and returns a placeholder event, that is
+    /// never returned to the user
+    fn skip_end(&mut self) -> Event<'static> {
+        self.skip_event = true;
+        Event::Text(BytesText::from_escaped(Vec::new()))
+    }
+
+    /// Returns an `End` event for the last opened element and continues
+    /// generating them until `depth` elements are left opened. The last `End`
+    /// event gets the span of the markup that closed the elements, which is
+    /// empty unless it is an end tag
+    fn close_unclosed(&mut self, depth: usize) -> Result<Event<'static>> {
+        let event = self.close_expanded_empty()?;
+        if self.opened_starts.len() > depth {
+            self.tag_state = TagState::AutoClose(depth);
+        } else {
+            self.event_span = self.markup_start..self.buf_position;
+            if self.pending_start.is_some() {
+                self.tag_state = TagState::PendingStart;
+            }
+        }
+        Ok(event)
+    }
+
+    /// Handles an end tag in the HTML mode. Closes the innermost opened element
+    /// with the same name, compared case-insensitively, and all elements opened
+    /// after it, otherwise skips the end tag
+    fn close_html(&mut self, name: &[u8], span: Range<usize>) -> Event<'static> {
+        let buffer = &self.opened_buffer;
+        let starts = &self.opened_starts;
+        let found = (0..starts.len()).rposition(|i| {
+            let end = starts.get(i + 1).copied().unwrap_or(buffer.len());
+            buffer[starts[i]..end].eq_ignore_ascii_case(name)
+        });
+        match found {
+            Some(depth) => self.close_unclosed(depth).unwrap(),
+            None => {
+                // End tags of void elements are expected in HTML
+                if self.recover && !html::is_void(name) {
+                    self.diagnostics.push(Diagnostic::UnmatchedEnd {
+                        found: from_utf8(name).unwrap_or("").to_owned(),
+                        span,
+                    });
+                }
+                self.skip_end()
+            }
+        }
+    }
+
+    /// reads `BytesElement` starting with a `!`,
+    /// return `Comment`, `CData` or `DocType` event
+    fn read_bang<'a, 'b>(&'a mut self, bang_type: BangType, buf: &'b [u8]) -> Result<Event<'b>> {
+        let uncased_starts_with = |string: &[u8], prefix: &[u8]| {
+            string.len() >= prefix.len() && string[..prefix.len()].eq_ignore_ascii_case(prefix)
+        };
+
+        let len = buf.len();
+        match bang_type {
+            BangType::Comment if buf.starts_with(b"!--") => {
+                if self.strict {
+                    // +1 for `<`, +3 for `!--`
+                    strict::check_chars(&buf[3..len - 2], self.markup_start + 4, self.is_utf8())?;
+                }
+                if self.check_comments || self.strict {
+                    // search if '--' not in comments
+                    if let Some(p) =
+                        memchr::memchr_iter(b'-', &buf[3..len - 2]).find(|p| buf[3 + p + 1] == b'-')
+                    {
+                        // +1 for `<`, +3 for `!--`
+                        let start = self.markup_start + 4 + p;
+                        self.buf_position += len - p;
+                        return Err(Error::UnexpectedToken("--".to_string(), start..start + 2));
+                    }
+                }
+                Ok(Event::Comment(BytesText::from_escaped(&buf[3..len - 2])))
+            }
+            BangType::CData if uncased_starts_with(buf, b"![CDATA[") => {
+                if self.strict {
+                    if self.opened_starts.is_empty() {
+                        return Err(Error::ContentOutsideRoot(
+                            self.markup_start..self.buf_position,
+                        ));
+                    }
+                    // +1 for `<`, +8 for `![CDATA[`
+                    strict::check_chars(&buf[8..], self.markup_start + 9, self.is_utf8())?;
+                }
+                Ok(Event::CData(BytesCData::new(&buf[8..])))
+            }
+            BangType::DocType if uncased_starts_with(buf, b"!DOCTYPE") => {
+                let start = buf[8..]
+                    .iter()
+                    .position(|b| !is_whitespace(*b))
+                    .unwrap_or_else(|| len - 8);
+                debug_assert!(start < len - 8, "DocType must have a name");
+                if self.strict {
+                    if self.root_seen || self.doctype_seen {
+                        return Err(Error::MisplacedDeclaration(
+                            self.markup_start..self.buf_position,
+                        ));
+                    }
+                    self.doctype_seen = true;
+                }
+                let content = &buf[8 + start..];
+                if self.expand_entities || self.normalize {
+                    self.read_dtd(content)?;
+                }
+                Ok(Event::DocType(BytesText::from_escaped(content)))
+            }
+            _ => Err(bang_type.to_err(self.markup_start..self.buf_position)),
+        }
+    }
+
+    /// reads `BytesElement` starting with a `?`,
+    /// return `Decl` or `PI` event
+    fn read_question_mark<'a, 'b>(&'a mut self, buf: &'b [u8]) -> Result<Event<'b>> {
+        let len = buf.len();
+        if len > 2 && buf[len - 1] == b'?' {
+            if len > 5 && &buf[1..4] == b"xml" && is_whitespace(buf[4]) {
+                if self.strict && self.markup_start != self.bom_len {
+                    return Err(Error::MisplacedDeclaration(
+                        self.markup_start..self.buf_position,
+                    ));
+                }
+                // +1 for `<`, +1 for `?`
+                let event = BytesDecl::from_start(
+                    BytesStart::borrowed(&buf[1..len - 1], 3).with_offset(self.markup_start + 2),
+                );
+
+                // Try getting encoding from the declaration event. Transcoded
+                // documents are already in UTF-8, whatever the declaration says
+                #[cfg(feature = "encoding")]
+                if let Some(enc) = event
+                    .encoder()
+                    .filter(|_| !self.transcoder.is_transcoding())
+                {
+                    self.encoding = enc;
+                    self.is_encoding_set = true;
+                }
+
+                Ok(Event::Decl(event))
+            } else {
+                if self.strict {
+                    // +1 for `<`, +1 for `?`
+                    self.check_pi(&buf[1..len - 1], self.markup_start + 2)?;
+                }
+                Ok(Event::PI(BytesText::from_escaped(&buf[1..len - 1])))
+            }
+        } else {
+            let span = self.markup_start..self.buf_position;
+            self.buf_position -= len;
+            Err(Error::UnexpectedEof("XmlDecl".to_string(), span))
+        }
+    }
+
+    /// Parses the DTD and remembers internal general entities declared in it
+    /// and, in the normalization mode, names of attributes that are not `CDATA`.
+    /// Invalid declarations and entities are errors only in the strict mode,
+    /// otherwise they are skipped
+    fn read_dtd(&mut self, doctype: &[u8]) -> Result<()> {
+        let normalized;
+        let doctype = match normalize::line_endings(doctype) {
+            Some(n) if self.normalize => {
+                normalized = n;
+                &normalized
+            }
+            _ => doctype,
+        };
+        let doctype = if self.strict {
+            crate::dtd::parse(doctype)?
+        } else {
+            match crate::dtd::parse_lenient(doctype) {
+                Ok(doctype) => doctype,
+                Err(_) => return Ok(()),
+            }
+        };
+        if self.expand_entities {
+            let declared: Vec<_> = doctype
+                .declarations
+                .iter()
+                .filter_map(|decl| match decl {
+                    Declaration::Entity(entity) if !entity.parameter => match entity.def {
+                        EntityDef::Internal(value) => Some((entity.name, value)),
+                        EntityDef::External { .. } => None,
+                    },
+                    _ => None,
+                })
+                .collect();
+            self.entities = if self.strict {
+                resolve_entities(&declared, &self.expansion_limits)?
+            } else {
+                resolve_valid_entities(&declared, &self.expansion_limits)?
+            };
+        }
+        if self.normalize {
+            // The first declaration of an attribute is binding
+            let mut declared = HashSet::new();
+            for decl in &doctype.declarations {
+                if let Declaration::AttList(list) = decl {
+                    for attr in &list.attributes {
+                        if declared.insert((list.element, attr.name)) && attr.ty != AttType::CData {
+                            self.tokenized_attributes
+                                .entry(list.element.to_vec())
+                                .or_default()
+                                .push(attr.name.to_vec());
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the target and the content of a processing instruction in the strict mode
+    fn check_pi(&self, content: &[u8], offset: usize) -> Result<()> {
+        let target_len = content
+            .iter()
+            .position(|&b| is_whitespace(b))
+            .unwrap_or(content.len());
+        let target = &content[..target_len];
+        // Targets matching `[Xx][Mm][Ll]` are reserved
+        if target.eq_ignore_ascii_case(b"xml") {
+            return Err(Error::InvalidName(
+                String::from_utf8_lossy(target).into_owned(),
+                offset..offset + target_len,
+            ));
+        }
+        strict::check_name(target, offset)?;
+        strict::check_chars(content, offset, self.is_utf8())
+    }
+
+    /// Checks the name and the attributes of a start tag in the strict mode
+    /// and that it is not after the end of the root element
+    fn check_start(&mut self, content: &[u8], name_len: usize, offset: usize) -> Result<()> {
+        if self.root_seen && self.opened_starts.is_empty() {
+            return Err(Error::MultipleRoots(self.markup_start..self.buf_position));
+        }
+        self.root_seen = true;
+        strict::check_name(&content[..name_len], offset)?;
+        strict::check_attributes(content, name_len, offset, self.is_utf8())
+    }
+
+    /// Returns `true` if the content of events is in UTF-8, so all characters
+    /// can be checked in the strict mode. Without the `encoding` feature the
+    /// document is always expected to be in UTF-8
+    fn is_utf8(&self) -> bool {
+        #[cfg(feature = "encoding")]
+        {
+            self.encoding == encoding_rs::UTF_8 || self.transcoder.is_transcoding()
+        }
+        #[cfg(not(feature = "encoding"))]
+        true
+    }
+
+    #[inline]
+    fn close_expanded_empty(&mut self) -> Result<Event<'static>> {
+        self.tag_state = TagState::Closed;
+        let name = self
+            .opened_buffer
+            .split_off(self.opened_starts.pop().unwrap());
+        Ok(Event::End(BytesEnd::owned(name)))
+    }
+
+    /// reads `BytesElement` starting with any character except `/`, `!` or ``?`
+    /// return `Start` or `Empty` event
+    fn read_start<'a, 'b>(&'a mut self, buf: &'b [u8]) -> Result<Event<'b>> {
+        // TODO: do this directly when reading bufreader ...
+        let len = buf.len();
+        let name_end = scan::find_whitespace(buf).unwrap_or(len);
+        // +1 for `<`
+        let offset = self.markup_start + 1;
+        let span = self.markup_start..self.buf_position;
+        if self.depth >= self.limits.max_depth {
+            return Err(Error::TooDeep(self.limits.max_depth, span));
+        }
+        let (content, name_end, empty) = if let Some(&b'/') = buf.last() {
+            let end = if name_end < len { name_end } else { len - 1 };
+            (&buf[..len - 1], end, true)
+        } else {
+            (buf, name_end, false)
+        };
+        self.limits
+            .check_start(content, name_end, offset, span.clone())?;
+        if self.strict {
+            self.check_start(content, name_end, offset)?;
+        }
+        if self.html {
+            let name = &content[..name_end];
+            if let Some(depth) = html::implied_end(name, &self.opened_buffer, &self.opened_starts) {
+                self.pending_start = Some(PendingStart {
+                    content: content.to_vec(),
+                    name_len: name_end,
+                    empty,
+                    span,
+                });
+                // `End` events are synthesized, so their spans are empty
+                self.markup_start = self.buf_position;
+                return self.close_unclosed(depth);
+            }
+        }
+        Ok(self.open_element(content, name_end, offset, empty))
+    }
+
+    /// Returns a `Start` or `Empty` event for a start tag and pushes the element
+    /// to the stack of opened elements, if its `End` event is expected
+    fn open_element<'b>(
+        &mut self,
+        content: &'b [u8],
+        name_len: usize,
+        offset: usize,
+        empty: bool,
+    ) -> Event<'b> {
+        let name = &content[..name_len];
+        if empty || (self.html && html::is_void(name)) {
+            if self.expand_empty_elements {
+                self.tag_state = TagState::Empty;
+                self.opened_starts.push(self.opened_buffer.len());
+                self.opened_buffer.extend(name);
+                Event::Start(self.start_event(content, name_len, offset))
+            } else {
+                Event::Empty(self.start_event(content, name_len, offset))
+            }
+        } else {
+            self.opened_starts.push(self.opened_buffer.len());
+            self.opened_buffer.extend(name);
+            if self.html && html::is_raw_text(name) {
+                self.tag_state = TagState::RawText;
+            }
+            Event::Start(self.start_event(content, name_len, offset))
+        }
+    }
+
+    /// Returns a start tag that implicitly closed opened elements in the HTML mode
+    fn read_pending_start(&mut self) -> Result<Event<'static>> {
+        self.tag_state = TagState::Closed;
+        let start = self.pending_start.take().unwrap();
+        self.markup_start = start.span.start;
+        self.event_span = start.span;
+        // +1 for `<`
+        let offset = self.markup_start + 1;
+        Ok(self
+            .open_element(&start.content, start.name_len, offset, start.empty)
+            .into_owned())
+    }
+
+    /// Normalizes line endings in the content of the event and attribute values
+    /// of start tags
+    fn normalize_event<'b>(&self, event: Event<'b>) -> Event<'b> {
+        let text = |e: BytesText<'b>| match normalize::line_endings(&e) {
+            Some(normalized) => BytesText::from_escaped(normalized),
+            None => e,
+        };
+        match event {
+            Event::Start(e) => Event::Start(self.normalize_start(e)),
+            Event::Empty(e) => Event::Empty(self.normalize_start(e)),
+            Event::Text(e) => Event::Text(text(e)),
+            Event::Comment(e) => Event::Comment(text(e)),
+            Event::PI(e) => Event::PI(text(e)),
+            Event::DocType(e) => Event::DocType(text(e)),
+            Event::CData(e) => match normalize::line_endings(&e) {
+                Some(normalized) => Event::CData(BytesCData::new(normalized)),
+                None => Event::CData(e),
+            },
+            event => event,
+        }
+    }
+
+    /// Normalizes line endings and attribute values of a start tag
+    fn normalize_start<'b>(&self, start: BytesStart<'b>) -> BytesStart<'b> {
+        let name = start.name();
+        let tokenized = match self.tokenized_attributes.get(name.as_ref()) {
+            Some(names) => names.as_slice(),
+            None => &[],
+        };
+        match normalize::start(&start, name.as_ref().len(), tokenized, start.moves()) {
+            Some((normalized, moves)) => start.with_content(normalized, moves),
+            None => start,
+        }
+    }
+
+    /// Returns a `Text` event with the content of a `<script>` or `<style>`
+    /// element in the HTML mode, or an `End` event of the element if the
+    /// content is empty
+    fn read_raw_text_event<'b>(&mut self, text: &'b [u8]) -> Result<Event<'b>> {
+        let skip = if self.trim_text_start {
+            text.iter().all(|b| is_whitespace(*b))
+        } else {
+            text.is_empty()
+        };
+        if skip {
+            return self.close_expanded_empty();
+        }
+        Ok(Event::Text(BytesText::from_plain(text)))
+    }
+
+    /// Creates an error for a markup that exceeds [`ReaderLimits::max_markup_len`]
+    fn markup_too_long(&self) -> Error {
+        Error::MarkupTooLong(
+            self.limits.max_markup_len,
+            self.markup_start..self.buf_position,
+        )
+    }
+
+    /// Creates a `BytesStart`, repairing attributes in the recovery mode
+    fn start_event<'b>(
+        &mut self,
+        content: &'b [u8],
+        name_len: usize,
+        offset: usize,
+    ) -> BytesStart<'b> {
+        if self.recover && name_len < content.len() {
+            if let Some((fixed, moves)) = recovery::repair_attributes(
+                content,
+                name_len,
+                offset,
+                self.html,
+                &mut self.diagnostics,
+            ) {
+                return BytesStart::owned(fixed, name_len)
+                    .with_offset(offset)
+                    .with_moves(moves);
+            }
+        }
+        BytesStart::borrowed(content, name_len).with_offset(offset)
+    }
+
+    /// Read text into the given buffer, and return an event that borrows from
+    /// either that buffer or from the input itself, based on the type of the
+    /// reader.
+    fn read_event_buffered<'i, B>(&mut self, buf: B) -> Result<Event<'i>>
+    where
+        R: XmlSource<'i, B>,
+    {
+        read_event_impl!(self, buf, read_until_open, read_until_close)
+    }
+
+    /// Checks whether the next event can be a part of coalesced text, see [`peek_text!`]
+    fn peek_text<'i, B>(&mut self) -> Result<bool>
+    where
+        R: XmlSource<'i, B>,
+    {
+        peek_text!(self, self.reader)
+    }
+
+    /// Resolves a potentially qualified **event name** into (namespace name, local name).
+    ///
+    /// *Qualified* attribute names have the form `prefix:local-name` where the`prefix` is defined
+    /// on any containing XML element via `xmlns:prefix="the:namespace:uri"`. The namespace prefix
+    /// can be defined on the same element as the attribute in question.
+    ///
+    /// *Unqualified* event inherits the current *default namespace*.
+    ///
+    /// # Lifetimes
+    ///
+    /// - `'n`: lifetime of an element name
+    /// - `'ns`: lifetime of a namespaces buffer, where all found namespaces are stored
+    #[inline]
+    pub fn event_namespace<'n, 'ns>(
+        &self,
+        name: QName<'n>,
+        namespace_buffer: &'ns [u8],
+    ) -> (ResolveResult<'ns>, LocalName<'n>) {
+        self.ns_resolver.resolve(name, namespace_buffer, true)
+    }
+
+    /// Resolves a potentially qualified **attribute name** into (namespace name, local name).
+    ///
+    /// *Qualified* attribute names have the form `prefix:local-name` where the`prefix` is defined
+    /// on any containing XML element via `xmlns:prefix="the:namespace:uri"`. The namespace prefix
+    /// can be defined on the same element as the attribute in question.
+    ///
+    /// *Unqualified* attribute names do *not* inherit the current *default namespace*.
+    ///
+    /// # Lifetimes
+    ///
+    /// - `'n`: lifetime of an attribute
+    /// - `'ns`: lifetime of a namespaces buffer, where all found namespaces are stored
+    #[inline]
+    pub fn attribute_namespace<'n, 'ns>(
+        &self,
+        name: QName<'n>,
+        namespace_buffer: &'ns [u8],
+    ) -> (ResolveResult<'ns>, LocalName<'n>) {
+        self.ns_resolver.resolve(name, namespace_buffer, false)
+    }
+
+    /// Returns an iterator over all namespace bindings in scope of the last event
+    /// read by [`Self::read_namespaced_event()`]. Bindings declared on an `Empty`
+    /// element are in scope of that element.
+    ///
+    /// See [`NamespaceResolver::bindings()`] for details.
+    ///
+    /// # Lifetimes
+    ///
+    /// - `'ns`: lifetime of the reader and of the namespaces buffer, where all
+    ///   found namespaces are stored
+    #[inline]
+    pub fn namespaces<'ns>(&'ns self, namespace_buffer: &'ns [u8]) -> NamespaceBindingsIter<'ns> {
+        self.ns_resolver.bindings(namespace_buffer)
+    }
+
+    /// Ends the namespace scope of the last `Empty` or `End` event returned by
+    /// [`Self::read_namespaced_event()`], if any
+    fn pop_namespaces(&mut self, namespace_buffer: &mut Vec<u8>) {
+        if self.pending_pop {
+            self.ns_resolver.pop(namespace_buffer);
+        }
+        self.pending_pop = false;
+    }
+
+    /// Updates namespace scopes with the read event and resolves the namespace
+    /// of its element name
+    fn resolve_event<'i, 'ns>(
+        &mut self,
+        event: Result<Event<'i>>,
+        namespace_buffer: &'ns mut Vec<u8>,
+    ) -> Result<(ResolveResult<'ns>, Event<'i>)> {
+        match event {
+            Ok(Event::Eof) => Ok((ResolveResult::Unbound, Event::Eof)),
+            Ok(Event::Start(e)) => {
+                self.ns_resolver.push(&e, namespace_buffer);
+                Ok((
+                    self.ns_resolver.find(e.name(), namespace_buffer),
+                    Event::Start(e),
+                ))
+            }
+            Ok(Event::Empty(e)) => {
+                // For empty elements we need to 'artificially' keep the namespace scope on the
+                // stack until the next `next()` call occurs.
+                // Otherwise the caller has no chance to use `resolve` in the context of the
+                // namespace declarations that are 'in scope' for the empty element alone.
+                // Ex: <img rdf:nodeID="abc" xmlns:rdf="urn:the-rdf-uri" />
+                self.ns_resolver.push(&e, namespace_buffer);
+                // notify next `read_namespaced_event()` invocation that it needs to pop this
+                // namespace scope
+                self.pending_pop = true;
+                Ok((
+                    self.ns_resolver.find(e.name(), namespace_buffer),
+                    Event::Empty(e),
+                ))
+            }
+            Ok(Event::End(e)) => {
+                // notify next `read_namespaced_event()` invocation that it needs to pop this
+                // namespace scope
+                self.pending_pop = true;
+                Ok((
+                    self.ns_resolver.find(e.name(), namespace_buffer),
+                    Event::End(e),
+                ))
+            }
+            Ok(e) => Ok((ResolveResult::Unbound, e)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the `Reader`s encoding.
+    ///
+    /// The used encoding may change after parsing the XML declaration.
+    ///
+    /// This encoding will be used by [`decode`].
+    ///
+    /// [`decode`]: #method.decode
+    #[cfg(feature = "encoding")]
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    /// Decodes a slice using the encoding specified in the XML declaration.
+    ///
+    /// Decode `bytes` with BOM sniffing and with malformed sequences replaced with the
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// If no encoding is specified, defaults to UTF-8.
+    #[inline]
+    #[cfg(feature = "encoding")]
+    pub fn decode<'b, 'c>(&'b self, bytes: &'c [u8]) -> Cow<'c, str> {
+        self.encoding.decode(bytes).0
+    }
+
+    /// Decodes a UTF8 slice without BOM (Byte order mark) regardless of XML declaration.
+    ///
+    /// Decode `bytes` without BOM and with malformed sequences replaced with the
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Note
+    ///
+    /// If you instead want to use XML declared encoding, use the `encoding` feature
+    #[inline]
+    #[cfg(not(feature = "encoding"))]
+    pub fn decode_without_bom<'c>(&self, bytes: &'c [u8]) -> Result<&'c str> {
+        if bytes.starts_with(b"\xEF\xBB\xBF") {
+            from_utf8(&bytes[3..]).map_err(Error::Utf8)
+        } else {
+            from_utf8(bytes).map_err(Error::Utf8)
+        }
+    }
+
+    /// Decodes a slice using without BOM (Byte order mark) the encoding specified in the XML declaration.
+    ///
+    /// Decode `bytes` without BOM and with malformed sequences replaced with the
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// If no encoding is specified, defaults to UTF-8.
+    #[inline]
+    #[cfg(feature = "encoding")]
+    pub fn decode_without_bom<'b, 'c>(&'b mut self, mut bytes: &'c [u8]) -> Cow<'c, str> {
+        if self.is_encoding_set {
+            return self.encoding.decode_with_bom_removal(bytes).0;
+        }
+        if bytes.starts_with(b"\xEF\xBB\xBF") {
+            self.is_encoding_set = true;
+            bytes = &bytes[3..];
+        } else if bytes.starts_with(b"\xFF\xFE") {
+            self.is_encoding_set = true;
+            self.encoding = UTF_16LE;
+            bytes = &bytes[2..];
+        } else if bytes.starts_with(b"\xFE\xFF") {
+            self.is_encoding_set = true;
+            self.encoding = UTF_16BE;
+            bytes = &bytes[3..];
+        };
+        self.encoding.decode_without_bom_handling(bytes).0
+    }
+
+    /// Decodes a UTF8 slice regardless of XML declaration.
+    ///
+    /// Decode `bytes` with BOM sniffing and with malformed sequences replaced with the
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Note
+    ///
+    /// If you instead want to use XML declared encoding, use the `encoding` feature
+    #[inline]
+    #[cfg(not(feature = "encoding"))]
+    pub fn decode<'c>(&self, bytes: &'c [u8]) -> Result<&'c str> {
+        from_utf8(bytes).map_err(Error::Utf8)
+    }
+
+    /// Get utf8 decoder
+    #[cfg(feature = "encoding")]
+    pub fn decoder(&self) -> Decoder {
+        Decoder {
+            encoding: self.encoding,
+        }
+    }
+
+    /// Get utf8 decoder
+    #[cfg(not(feature = "encoding"))]
+    pub fn decoder(&self) -> Decoder {
+        Decoder
+    }
+
+    /// Consumes `Reader` returning the underlying reader
+    ///
+    /// Can be used to compute line and column of a parsing error position
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use std::{str, io::Cursor};
+    /// use quick_xml::Reader;
+    /// use quick_xml::events::Event;
+    ///
+    /// let xml = r#"<tag1 att1 = "test">
+    ///                 <tag2><!--Test comment-->Test</tag2>
+    ///                 <tag3>Test 2</tag3>
+    ///             </tag1>"#;
+    /// let mut reader = Reader::from_reader(Cursor::new(xml.as_bytes()));
+    /// let mut buf = Vec::new();
+    ///
+    /// fn into_line_and_column(reader: Reader<Cursor<&[u8]>>) -> (usize, usize) {
+    ///     let end_pos = reader.buffer_position();
+    ///     let mut cursor = reader.into_inner();
+    ///     let s = String::from_utf8(cursor.into_inner()[0..end_pos].to_owned())
+    ///         .expect("can't make a string");
+    ///     let mut line = 1;
+    ///     let mut column = 0;
+    ///     for c in s.chars() {
+    ///         if c == '\n' {
+    ///             line += 1;
+    ///             column = 0;
+    ///         } else {
+    ///             column += 1;
+    ///         }
+    ///     }
+    ///     (line, column)
+    /// }
+    ///
+    /// loop {
+    ///     match reader.read_event(&mut buf) {
+    ///         Ok(Event::Start(ref e)) => match e.name().as_ref() {
+    ///             b"tag1" | b"tag2" => (),
+    ///             tag => {
+    ///                 assert_eq!(b"tag3", tag);
+    ///                 assert_eq!((3, 22), into_line_and_column(reader));
+    ///                 break;
+    ///             }
+    ///         },
+    ///         Ok(Event::Eof) => unreachable!(),
+    ///         _ => (),
+    ///     }
+    ///     buf.clear();
+    /// }
+    /// ```
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Reads the next `Event`.
+    ///
+    /// This is the main entry point for reading XML `Event`s.
+    ///
+    /// `Event`s borrow `buf` and can be converted to own their data if needed (uses `Cow`
+    /// internally).
+    ///
+    /// Having the possibility to control the internal buffers gives you some additional benefits
+    /// such as:
+    ///
+    /// - Reduce the number of allocations by reusing the same buffer. For constrained systems,
+    ///   you can call `buf.clear()` once you are done with processing the event (typically at the
+    ///   end of your loop).
+    /// - Reserve the buffer length if you know the file size (using `Vec::with_capacity`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quick_xml::Reader;
+    /// use quick_xml::events::Event;
+    ///
+    /// let xml = r#"<tag1 att1 = "test">
+    ///                 <tag2><!--Test comment-->Test</tag2>
+    ///                 <tag2>Test 2</tag2>
+    ///             </tag1>"#;
+    /// let mut reader = Reader::from_str(xml);
+    /// reader.trim_text(true);
+    /// let mut count = 0;
+    /// let mut buf = Vec::new();
+    /// let mut txt = Vec::new();
+    /// loop {
+    ///     match reader.read_event(&mut buf) {
+    ///         Ok(Event::Start(ref e)) => count += 1,
+    ///         Ok(Event::Text(e)) => txt.push(e.unescape_and_decode(&reader).expect("Error!")),
+    ///         Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
+    ///         Ok(Event::Eof) => break,
+    ///         _ => (),
+    ///     }
+    ///     buf.clear();
+    /// }
+    /// println!("Found {} start events", count);
+    /// println!("Text events: {:?}", txt);
+    /// ```
+    #[inline]
+    pub fn read_event<'a, 'b>(&'a mut self, buf: &'b mut Vec<u8>) -> Result<Event<'b>> {
+        let event = self.read_event_uncoalesced(buf);
+        coalesce_text!(self, event, self.peek_text_buffered(), |buf| self
+            .read_event_uncoalesced(&mut buf))
+    }
+
+    /// Reads the next event without merging of consecutive text events
+    #[inline]
+    fn read_event_uncoalesced<'b>(&mut self, buf: &'b mut Vec<u8>) -> Result<Event<'b>> {
+        read_event_impl!(
+            self,
+            buf,
+            read_until_open_recorded,
+            read_until_close_recorded
+        )
+    }
+
+    /// Checks whether the next event can be a part of coalesced text, see [`peek_text!`]
+    fn peek_text_buffered(&mut self) -> Result<bool> {
+        peek_text!(self, buffered_source!(self))
+    }
+
+    /// The same as [`Self::read_until_open`], but reads from the source, that
+    /// records the consumed input, see [`buffered_source!`]
+    fn read_until_open_recorded<'b>(
+        &mut self,
+        buf: &'b mut Vec<u8>,
+    ) -> Result<std::result::Result<Event<'b>, &'b mut Vec<u8>>> {
+        read_until_open!(self, buf, buffered_source!(self))
+    }
+
+    /// The same as [`Self::read_until_close`], but reads from the source, that
+    /// records the consumed input, see [`buffered_source!`]
+    fn read_until_close_recorded<'b>(&mut self, buf: &'b mut Vec<u8>) -> Result<Event<'b>> {
+        read_until_close!(self, buf, buffered_source!(self))
+    }
+
+    /// Reads the next event and resolves its namespace (if applicable).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::str::from_utf8;
+    /// use quick_xml::Reader;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::name::ResolveResult::*;
+    ///
+    /// let xml = r#"<x:tag1 xmlns:x="www.xxxx" xmlns:y="www.yyyy" att1 = "test">
+    ///                 <y:tag2><!--Test comment-->Test</y:tag2>
+    ///                 <y:tag2>Test 2</y:tag2>
+    ///             </x:tag1>"#;
+    /// let mut reader = Reader::from_str(xml);
+    /// reader.trim_text(true);
+    /// let mut count = 0;
+    /// let mut buf = Vec::new();
+    /// let mut ns_buf = Vec::new();
+    /// let mut txt = Vec::new();
+    /// loop {
+    ///     match reader.read_namespaced_event(&mut buf, &mut ns_buf) {
+    ///         Ok((Bound(ns), Event::Start(e))) => {
+    ///             count += 1;
+    ///             match (ns.as_ref(), e.local_name().as_ref()) {
+    ///                 (b"www.xxxx", b"tag1") => (),
+    ///                 (b"www.yyyy", b"tag2") => (),
+    ///                 (ns, n) => panic!("Namespace and local name mismatch"),
+    ///             }
+    ///             println!("Resolved namespace: {:?}", ns);
+    ///         }
+    ///         Ok((Unbound, Event::Start(_))) => {
+    ///             panic!("Element not in any namespace")
+    ///         },
+    ///         Ok((Unknown(p), Event::Start(_))) => {
+    ///             panic!("Undeclared namespace prefix {:?}", String::from_utf8(p))
+    ///         }
+    ///         Ok((_, Event::Text(e))) => {
+    ///             txt.push(e.unescape_and_decode(&reader).expect("Error!"))
+    ///         },
+    ///         Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
+    ///         Ok((_, Event::Eof)) => break,
+    ///         _ => (),
+    ///     }
+    ///     buf.clear();
+    /// }
+    /// println!("Found {} start events", count);
+    /// println!("Text events: {:?}", txt);
+    /// ```
+    pub fn read_namespaced_event<'b, 'ns>(
+        &mut self,
+        buf: &'b mut Vec<u8>,
+        namespace_buffer: &'ns mut Vec<u8>,
+    ) -> Result<(ResolveResult<'ns>, Event<'b>)> {
+        self.pop_namespaces(namespace_buffer);
+        let event = self.read_event(buf);
+        self.resolve_event(event, namespace_buffer)
+    }
+
+    /// Reads until end element is found
+    ///
+    /// Manages nested cases where parent and child elements have the same name
+    pub fn read_to_end<K: AsRef<[u8]>>(&mut self, end: K, buf: &mut Vec<u8>) -> Result<()> {
+        let mut depth = 0;
+        let end = end.as_ref();
+        let start = self.markup_start;
+        loop {
+            match self.read_event(buf) {
+                Ok(Event::End(ref e)) if e.name().as_ref() == end => {
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                    depth -= 1;
+                }
+                Ok(Event::Start(ref e)) if e.name().as_ref() == end => depth += 1,
+                Err(e) => return Err(e),
+                Ok(Event::Eof) => {
+                    return Err(Error::UnexpectedEof(
+                        format!("</{:?}>", from_utf8(end)),
+                        start..self.buf_position,
+                    ));
+                }
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+
+    /// Reads optional text between start and end tags.
+    ///
+    /// If the next event is a [`Text`] event, returns the decoded and unescaped content as a
+    /// `String`. If the next event is an [`End`] event, returns the empty string. In all other
+    /// cases, returns an error.
+    ///
+    /// Any text will be decoded using the XML encoding specified in the XML declaration (or UTF-8
+    /// if none is specified).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::Reader;
+    /// use quick_xml::events::Event;
+    ///
+    /// let mut xml = Reader::from_reader(b"
+    ///     <a>&lt;b&gt;</a>
+    ///     <a></a>
+    /// " as &[u8]);
+    /// xml.trim_text(true);
+    ///
+    /// let expected = ["<b>", ""];
+    /// for &content in expected.iter() {
+    ///     match xml.read_event(&mut Vec::new()) {
+    ///         Ok(Event::Start(ref e)) => {
+    ///             assert_eq!(&xml.read_text(e.name(), &mut Vec::new()).unwrap(), content);
+    ///         },
+    ///         e => panic!("Expecting Start event, found {:?}", e),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`Text`]: events/enum.Event.html#variant.Text
+    /// [`End`]: events/enum.Event.html#variant.End
+    pub fn read_text<K: AsRef<[u8]>>(&mut self, end: K, buf: &mut Vec<u8>) -> Result<String> {
+        let s = match self.read_event(buf) {
+            Ok(Event::Text(e)) => e.unescape_and_decode(self),
+            Ok(Event::End(ref e)) if e.name().as_ref() == end.as_ref() => return Ok("".to_string()),
+            Err(e) => return Err(e),
+            Ok(Event::Eof) => {
+                return Err(Error::UnexpectedEof(
+                    "Text".to_string(),
+                    self.buf_position..self.buf_position,
+                ))
+            }
+            _ => return Err(Error::TextNotFound),
+        };
+        self.read_to_end(end, buf)?;
+        s
+    }
+}
+
+impl Reader<BufReader<File>> {
+    /// Creates an XML reader from a file path.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>> {
+        let file = File::open(path).map_err(Error::Io)?;
+        let reader = BufReader::new(file);
+        Ok(Reader::from_reader(reader))
+    }
+}
+
+impl<'a> Reader<&'a [u8]> {
+    /// Creates an XML reader from a string slice.
+    pub fn from_str(s: &'a str) -> Reader<&'a [u8]> {
+        Reader::from_reader(s.as_bytes())
+    }
+
+    /// Creates an XML reader from a slice of bytes.
+    pub fn from_bytes(s: &'a [u8]) -> Reader<&'a [u8]> {
+        Reader::from_reader(s)
+    }
+
+    /// Read an event that borrows from the input rather than a buffer.
+    #[inline]
+    pub fn read_event_unbuffered(&mut self) -> Result<Event<'a>> {
+        let event = self.read_event_buffered(());
+        coalesce_text!(self, event, self.peek_text::<()>(), |buf| self
+            .read_event_buffered(()))
+    }
+
+    /// Reads an event that borrows from the input together with the byte range
+    /// of the event in the input. See [`Self::event_span()`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::Reader;
+    ///
+    /// let xml = "<tag>text</tag>";
+    /// let mut reader = Reader::from_str(xml);
+    /// let mut spans = Vec::new();
+    /// loop {
+    ///     match reader.read_event_with_span().unwrap() {
+    ///         (Event::Eof, _) => break,
+    ///         (_, span) => spans.push(&xml[span]),
+    ///     }
+    /// }
+    /// // The empty text before the root element
+    /// assert_eq!(spans, vec!["", "<tag>", "text", "</tag>"]);
+    /// ```
+    #[inline]
+    pub fn read_event_with_span(&mut self) -> Result<(Event<'a>, Range<usize>)> {
+        let event = self.read_event_unbuffered()?;
+        Ok((event, self.event_span()))
+    }
+
+    /// Reads until end element is found
+    ///
+    /// Manages nested cases where parent and child elements have the same name
+    pub fn read_to_end_unbuffered<K: AsRef<[u8]>>(&mut self, end: K) -> Result<()> {
+        let mut depth = 0;
+        let end = end.as_ref();
+        let start = self.markup_start;
+        loop {
+            match self.read_event_unbuffered() {
+                Ok(Event::End(ref e)) if e.name().as_ref() == end => {
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                    depth -= 1;
+                }
+                Ok(Event::Start(ref e)) if e.name().as_ref() == end => depth += 1,
+                Err(e) => return Err(e),
+                Ok(Event::Eof) => {
+                    return Err(Error::UnexpectedEof(
+                        format!("</{:?}>", from_utf8(end)),
+                        start..self.buf_position,
+                    ));
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Represents an input for a reader that can return borrowed data.
+///
+/// There are two implementors of this trait: generic one that read data from
+/// `Self`, copies some part of it into a provided buffer of type `B` and then
+/// returns data that borrow from that buffer.
+///
+/// The other implementor is for `&[u8]` and instead of copying data returns
+/// borrowed data from `Self` instead. This implementation allows zero-copy
+/// deserialization.
+///
+/// # Parameters
+/// - `'r`: lifetime of a buffer from which events will borrow
+/// - `B`: a type of a buffer that can be used to store data read from `Self` and
+///   from which events can borrow
+trait XmlSource<'r, B> {
+    /// Read input until `byte` is found or end of input is reached.
+    ///
+    /// Returns a slice of data read up to `byte`, which does not include into result.
+    /// If input (`Self`) is exhausted, returns `None`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut position = 0;
+    /// let mut input = b"abc*def".as_ref();
+    /// //                    ^= 4
+    ///
+    /// assert_eq!(
+    ///     input.read_bytes_until(b'*', (), usize::MAX, &mut position).unwrap(),
+    ///     Some(b"abc".as_ref())
+    /// );
+    /// assert_eq!(position, 4); // position after the symbol matched
+    /// ```
+    ///
+    /// # Parameters
+    /// - `byte`: Byte for search
+    /// - `buf`: Buffer that could be filled from an input (`Self`) and
+    ///   from which [events] could borrow their data
+    /// - `limit`: Maximum length of the result. If `byte` is not found in the
+    ///   first `limit + 1` bytes, reading is stopped and these bytes are returned
+    /// - `position`: Will be increased by amount of bytes consumed
+    ///
+    /// [events]: crate::events::Event
+    fn read_bytes_until(
+        &mut self,
+        byte: u8,
+        buf: B,
+        limit: usize,
+        position: &mut usize,
+    ) -> Result<Option<&'r [u8]>>;
+
+    /// Read input until comment, CDATA or processing instruction is finished.
+    ///
+    /// This method expect that `<` already was read.
+    ///
+    /// Returns a slice of data read up to end of comment, CDATA or processing
+    /// instruction (`>`), which does not include into result.
+    ///
+    /// If input (`Self`) is exhausted and nothing was read, returns `None`.
+    ///
+    /// # Parameters
+    /// - `buf`: Buffer that could be filled from an input (`Self`) and
+    ///   from which [events] could borrow their data
+    /// - `limit`: Maximum length of the result. If the element is not finished
+    ///   in the first `limit + 1` bytes, reading is stopped and these bytes
+    ///   are returned
+    /// - `position`: Will be increased by amount of bytes consumed
+    ///
+    /// [events]: crate::events::Event
+    fn read_bang_element(
+        &mut self,
+        buf: B,
+        limit: usize,
+        position: &mut usize,
+    ) -> Result<Option<(BangType, &'r [u8])>>;
+
+    /// Read input until XML element is closed by approaching a `>` symbol.
+    /// Returns `Some(buffer)` that contains a data between `<` and `>` or
+    /// `None` if end-of-input was reached and nothing was read.
+    ///
+    /// Derived from `read_until`, but modified to handle XML attributes
+    /// using a minimal state machine.
+    ///
+    /// Attribute values are [defined] as follows:
+    /// ```plain
+    /// AttValue := '"' (([^<&"]) | Reference)* '"'
+    ///           | "'" (([^<&']) | Reference)* "'"
+    /// ```
+    /// (`Reference` is something like `&quot;`, but we don't care about
+    /// escaped characters at this level)
+    ///
+    /// # Parameters
+    /// - `buf`: Buffer that could be filled from an input (`Self`) and
+    ///   from which [events] could borrow their data
+    /// - `limit`: Maximum length of the result. If the element is not closed
+    ///   in the first `limit + 1` bytes, reading is stopped and these bytes
+    ///   are returned
+    /// - `position`: Will be increased by amount of bytes consumed
+    ///
+    /// [defined]: https://www.w3.org/TR/xml11/#NT-AttValue
+    /// [events]: crate::events::Event
+    fn read_element(
+        &mut self,
+        buf: B,
+        limit: usize,
+        position: &mut usize,
+    ) -> Result<Option<&'r [u8]>>;
+
+    /// Read input until the end tag of the element with the specified name
+    /// (compared case-insensitively) is read. Used to read the content of
+    /// `<script>` and `<style>` elements in the HTML mode.
+    ///
+    /// Returns a slice of data read up to and including the end tag, or up to
+    /// the end of input if the end tag is not found. If input (`Self`) is
+    /// exhausted and nothing was read, returns `None`.
+    ///
+    /// # Parameters
+    /// - `name`: Name of the element
+    /// - `buf`: Buffer that could be filled from an input (`Self`) and
+    ///   from which [events] could borrow their data
+    /// - `limit`: Maximum length of the result. If the end tag is not found
+    ///   in the first `limit + 1` bytes, reading is stopped and these bytes
+    ///   are returned
+    /// - `position`: Will be increased by amount of bytes consumed
+    ///
+    /// [events]: crate::events::Event
+    fn read_raw_text(
+        &mut self,
+        name: &[u8],
+        buf: B,
+        limit: usize,
+        position: &mut usize,
+    ) -> Result<Option<&'r [u8]>>;
+
+    /// Consume and discard all the whitespace until the next non-whitespace
+    /// character or EOF. Because skipped whitespaces are not returned, they are
+    /// reported to the `tracker`.
+    fn skip_whitespace(
+        &mut self,
+        position: &mut usize,
+        tracker: &mut PositionTracker,
+    ) -> Result<()>;
+
+    fn skip_one(&mut self, byte: u8, position: &mut usize) -> Result<bool>;
+
+    fn peek_one(&mut self) -> Result<Option<u8>>;
+}
+
+/// Implementation of `XmlSource` for any `BufRead` reader using a user-given
+/// `Vec<u8>` as buffer that will be borrowed by events.
+impl<'b, R: BufRead> XmlSource<'b, &'b mut Vec<u8>> for R {
+    impl_buffered_source!();
+}
+
+/// Implementation of `XmlSource` for `&[u8]` reader using a `Self` as buffer
+/// that will be borrowed by events. This implementation provides a zero-copy deserialization
+impl<'a> XmlSource<'a, ()> for &'a [u8] {
+    fn read_bytes_until(
+        &mut self,
+        byte: u8,
+        _buf: (),
+        limit: usize,
+        position: &mut usize,
+    ) -> Result<Option<&'a [u8]>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let available = limit_chunk(self, limit, 0);
+        Ok(Some(if let Some(i) = memchr::memchr(byte, available) {
+            *position += i + 1;
+            let bytes = &self[..i];
+            *self = &self[i + 1..];
+            bytes
+        } else {
+            *position += available.len();
+            *self = &self[available.len()..];
+            available
+        }))
+    }
+
+    fn read_bang_element(
+        &mut self,
+        _buf: (),
+        limit: usize,
+        position: &mut usize,
+    ) -> Result<Option<(BangType, &'a [u8])>> {
+        // Peeked one bang ('!') before being called, so it's guaranteed to
+        // start with it.
+        debug_assert_eq!(self[0], b'!');
+
+        let bang_type = BangType::new(self[1..].first().copied(), *position)?;
+
+        let available = limit_chunk(self, limit, 0);
+        if let Some((bytes, i)) = bang_type.parse(available, 0) {
+            *position += i;
+            *self = &self[i..];
+            return Ok(Some((bang_type, bytes)));
+        }
+        if available.len() < self.len() {
+            *position += available.len();
+            *self = &self[available.len()..];
+            return Ok(Some((bang_type, available)));
+        }
+
+        // Note: Do not update position, so the error points to
+        // somewhere sane rather than at the EOF
+        Err(bang_type.to_err(position.saturating_sub(1)..*position + self.len()))
+    }
+
+    fn read_element(
+        &mut self,
+        _buf: (),
+        limit: usize,
+        position: &mut usize,
+    ) -> Result<Option<&'a [u8]>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let mut state = ReadElementState::Elem;
+
+        let available = limit_chunk(self, limit, 0);
+        if let Some((bytes, i)) = state.change(available) {
+            *position += i;
+            *self = &self[i..];
+            return Ok(Some(bytes));
+        }
+        if available.len() < self.len() {
+            *position += available.len();
+            *self = &self[available.len()..];
+            return Ok(Some(available));
+        }
+
+        // Note: Do not update position, so the error points to a sane place
+        // rather than at the EOF.
+        Err(Error::UnexpectedEof(
+            "Element".to_string(),
+            position.saturating_sub(1)..*position + self.len(),
+        ))
+
+        // FIXME: Figure out why the other one works without UnexpectedEof
+    }
+
+    fn read_raw_text(
+        &mut self,
+        name: &[u8],
+        _buf: (),
+        limit: usize,
+        position: &mut usize,
+    ) -> Result<Option<&'a [u8]>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let available = limit_chunk(self, limit, 0);
+        let len = html::find_end_tag(available, name).unwrap_or(available.len());
+        *position += len;
+        let bytes = &self[..len];
+        *self = &self[len..];
+        Ok(Some(bytes))
+    }
+
+    fn skip_whitespace(
+        &mut self,
+        position: &mut usize,
+        tracker: &mut PositionTracker,
+    ) -> Result<()> {
+        let whitespaces = scan::skip_whitespace(self).unwrap_or(self.len());
+        tracker.advance(&self[..whitespaces]);
+        *position += whitespaces;
+        *self = &self[whitespaces..];
+        Ok(())
+    }
+
+    fn skip_one(&mut self, byte: u8, position: &mut usize) -> Result<bool> {
+        if self.first() == Some(&byte) {
+            *self = &self[1..];
+            *position += 1;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn peek_one(&mut self) -> Result<Option<u8>> {
+        Ok(self.first().copied())
+    }
+}
+
+/// Returns the part of `chunk` that can be read without exceeding `limit + 1`
+/// bytes, if `read` bytes were already read. Reading of one byte more than
+/// the limit allows the caller to detect that the limit is exceeded.
+#[inline]
+fn limit_chunk(chunk: &[u8], limit: usize, read: usize) -> &[u8] {
+    let remaining = limit.saturating_add(1).saturating_sub(read);
+    &chunk[..chunk.len().min(remaining)]
+}
+
+/// Returns the escaped content of a `Text` or `CData` event, used to coalesce text
+fn escaped_text<'e>(event: &'e Event) -> Cow<'e, [u8]> {
+    match event {
+        Event::CData(e) => escape(e),
+        event => Cow::Borrowed(event),
+    }
+}
+
+/// Possible elements started with `<!`
+#[derive(Debug, PartialEq)]
+enum BangType {
+    /// <![CDATA[...]]>
+    CData,
+    /// <!--...-->
+    Comment,
+    /// <!DOCTYPE...>
+    DocType,
+}
+impl BangType {
+    /// Determines the type of markup by the byte after `!`. `position` is the
+    /// position of `!` and is used to report errors
+    #[inline(always)]
+    fn new(byte: Option<u8>, position: usize) -> Result<Self> {
+        let start = position.saturating_sub(1);
+        Ok(match byte {
+            Some(b'[') => Self::CData,
+            Some(b'-') => Self::Comment,
+            Some(b'D') | Some(b'd') => Self::DocType,
+            Some(b) => return Err(Error::UnexpectedBang(b, start..position + 2)),
+            None => {
+                return Err(Error::UnexpectedEof(
+                    "Bang".to_string(),
+                    start..position + 1,
+                ))
+            }
+        })
+    }
+
+    /// If element is finished, returns its content up to `>` symbol and
+    /// an index of this symbol, otherwise returns `None`
+    #[inline(always)]
+    fn parse<'b>(&self, chunk: &'b [u8], offset: usize) -> Option<(&'b [u8], usize)> {
+        for i in memchr::memchr_iter(b'>', chunk) {
+            match self {
+                // Need to read at least 6 symbols (`!---->`) for properly finished comment
+                // <!----> - XML comment
+                //  012345 - i
+                Self::Comment => {
+                    if offset + i > 4 && chunk[..i].ends_with(b"--") {
+                        // We cannot strip last `--` from the buffer because we need it in case of
+                        // check_comments enabled option. XML standard requires that comment
+                        // will not end with `--->` sequence because this is a special case of
+                        // `--` in the comment (https://www.w3.org/TR/xml11/#sec-comments)
+                        return Some((&chunk[..i], i + 1)); // +1 for `>`
+                    }
+                }
+                Self::CData => {
+                    if chunk[..i].ends_with(b"]]") {
+                        return Some((&chunk[..i - 2], i + 1)); // +1 for `>`
+                    }
+                }
+                Self::DocType => {
+                    let content = &chunk[..i];
+                    let balance = memchr::memchr2_iter(b'<', b'>', content)
+                        .map(|p| if content[p] == b'<' { 1i32 } else { -1 })
+                        .sum::<i32>();
+                    if balance == 0 {
+                        return Some((content, i + 1)); // +1 for `>`
+                    }
+                }
+            }
+        }
+        None
+    }
+    #[inline]
+    fn to_err(self, span: Range<usize>) -> Error {
+        let bang_str = match self {
+            Self::CData => "CData",
+            Self::Comment => "Comment",
+            Self::DocType => "DOCTYPE",
+        };
+        Error::UnexpectedEof(bang_str.to_string(), span)
+    }
+}
+
+/// State machine for the [`XmlSource::read_element`]
+#[derive(Clone, Copy)]
+enum ReadElementState {
+    /// The initial state (inside element, but outside of attribute value)
+    Elem,
+    /// Inside a single-quoted attribute value
+    SingleQ,
+    /// Inside a double-quoted attribute value
+    DoubleQ,
+}
+impl ReadElementState {
+    /// Changes state by analyzing part of input.
+    /// Returns a tuple with part of chunk up to element closing symbol `>`
+    /// and a position after that symbol or `None` if such symbol was not found
+    #[inline(always)]
+    fn change<'b>(&mut self, chunk: &'b [u8]) -> Option<(&'b [u8], usize)> {
+        let mut i = 0;
+        while i < chunk.len() {
+            let rest = &chunk[i..];
+            // Inside of an attribute value only the closing quote changes
+            // the state, so the whole value is skipped at once
+            let found = match *self {
+                Self::Elem => memchr::memchr3(b'>', b'\'', b'"', rest),
+                Self::SingleQ => memchr::memchr(b'\'', rest),
+                Self::DoubleQ => memchr::memchr(b'"', rest),
+            };
+            i += found?;
+            *self = match (*self, chunk[i]) {
+                // only allowed to match `>` while we are in state `Elem`
+                (Self::Elem, b'>') => return Some((&chunk[..i], i + 1)),
+                (Self::Elem, b'\'') => Self::SingleQ,
+                (Self::Elem, b'"') => Self::DoubleQ,
+
+                // the only end_byte that gets us out if the same character
+                _ => Self::Elem,
+            };
+            i += 1;
+        }
+        None
+    }
+}
+
+/// A line and a column in the document, both starting from 1.
+///
+/// Columns are counted in UTF-8 characters, not in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextPosition {
+    /// Line number, starting from 1
+    pub line: usize,
+    /// Column number, starting from 1
+    pub column: usize,
+}
+
+impl std::fmt::Display for TextPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Counts lines and columns of all bytes consumed by the reader.
+#[derive(Clone, Debug)]
+pub(crate) struct PositionTracker {
+    /// If `false`, the tracker does nothing
+    enabled: bool,
+    /// Position of the next unread character
+    current: TextPosition,
+    /// Position of the start of the last event
+    event: TextPosition,
+    /// `true` if the last seen byte was `\r`, so the following `\n` does not
+    /// start another line
+    after_cr: bool,
+}
+
+impl Default for PositionTracker {
+    fn default() -> Self {
+        let start = TextPosition { line: 1, column: 1 };
+        PositionTracker {
+            enabled: false,
+            current: start,
+            event: start,
+            after_cr: false,
+        }
+    }
+}
+
+impl PositionTracker {
+    /// Remembers the current position as the start of the event
+    #[inline]
+    fn start_event(&mut self) {
+        self.event = self.current;
+    }
+
+    /// Moves the current position over the consumed bytes
+    #[inline]
+    fn advance(&mut self, bytes: &[u8]) {
+        if !self.enabled {
+            return;
+        }
+        for &b in bytes {
+            match b {
+                b'\n' if self.after_cr => {}
+                b'\n' | b'\r' => {
+                    self.current.line += 1;
+                    self.current.column = 1;
+                }
+                // Count only the first byte of each UTF-8 character
+                _ if b & 0b1100_0000 != 0b1000_0000 => self.current.column += 1,
+                _ => {}
+            }
+            self.after_cr = b == b'\r';
+        }
+    }
+
+    /// Moves the current position over the markup between `<` and `>` (exclusive)
+    #[inline]
+    fn advance_markup(&mut self, bytes: &[u8]) {
+        self.advance(b"<");
+        self.advance(bytes);
+        self.advance(b">");
+    }
+
+    /// Adds the position of the last event to the error, if tracking is enabled
+    fn locate(&self, error: Error) -> Error {
+        if self.enabled {
+            Error::Positioned {
+                error: Box::new(error),
+                position: self.event,
+            }
+        } else {
+            error
+        }
+    }
+}
+
+/// A function to check whether the byte is a whitespace (blank, new line, carriage return or tab)
+#[inline]
+pub(crate) fn is_whitespace(b: u8) -> bool {
+    match b {
+        b' ' | b'\r' | b'\n' | b'\t' => true,
+        _ => false,
+    }
+}
+
+/// Utf8 Decoder
+#[cfg(not(feature = "encoding"))]
+#[derive(Clone, Copy, Debug)]
+pub struct Decoder;
+
+/// Utf8 Decoder
+#[cfg(feature = "encoding")]
+#[derive(Clone, Copy, Debug)]
+pub struct Decoder {
+    encoding: &'static Encoding,
+}
+
+impl Decoder {
+    #[cfg(not(feature = "encoding"))]
+    pub fn decode<'c>(&self, bytes: &'c [u8]) -> Result<&'c str> {
+        from_utf8(bytes).map_err(Error::Utf8)
+    }
+
+    #[cfg(not(feature = "encoding"))]
+    pub fn decode_owned<'c>(&self, bytes: Vec<u8>) -> Result<String> {
+        String::from_utf8(bytes).map_err(|e| Error::Utf8(e.utf8_error()))
+    }
+
+    #[cfg(feature = "encoding")]
+    pub fn decode<'c>(&self, bytes: &'c [u8]) -> Cow<'c, str> {
+        self.encoding.decode(bytes).0
+    }
+}
+
+/// This implementation is required for tests of other parts of the library
+#[cfg(test)]
+#[cfg(feature = "serialize")]
+impl Decoder {
+    #[cfg(not(feature = "encoding"))]
+    pub(crate) fn utf8() -> Self {
+        Decoder
+    }
+
+    #[cfg(feature = "encoding")]
+    pub(crate) fn utf8() -> Self {
+        Decoder {
+            encoding: encoding_rs::UTF_8,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    macro_rules! check {
+        ($buf:expr) => {
+            mod read_bytes_until {
+                use crate::reader::XmlSource;
+                // Use Bytes for printing bytes as strings for ASCII range
+                use crate::utils::Bytes;
+                use pretty_assertions::assert_eq;
+
+                /// Checks that search in the empty buffer returns `None`
+                #[test]
+                fn empty() {
+                    let buf = $buf;
+                    let mut position = 0;
+                    let mut input = b"".as_ref();
+                    //                ^= 0
+
+                    assert_eq!(
+                        input
+                            .read_bytes_until(b'*', buf, usize::MAX, &mut position)
+                            .unwrap()
+                            .map(Bytes),
+                        None
+                    );
+                    assert_eq!(position, 0);
+                }
+
+                /// Checks that search in the buffer non-existent value returns entire buffer
+                /// as a result and set `position` to `len()`
+                #[test]
+                fn non_existent() {
+                    let buf = $buf;
+                    let mut position = 0;
+                    let mut input = b"abcdef".as_ref();
+                    //                      ^= 6
+
+                    assert_eq!(
+                        input
+                            .read_bytes_until(b'*', buf, usize::MAX, &mut position)
+                            .unwrap()
+                            .map(Bytes),
+                        Some(Bytes(b"abcdef"))
+                    );
+                    assert_eq!(position, 6);
+                }
+
+                /// Checks that search in the buffer an element that is located in the front of
+                /// buffer returns empty slice as a result and set `position` to one symbol
+                /// after match (`1`)
+                #[test]
+                fn at_the_start() {
+                    let buf = $buf;
+                    let mut position = 0;
+                    let mut input = b"*abcdef".as_ref();
+                    //                 ^= 1
+
+                    assert_eq!(
+                        input
+                            .read_bytes_until(b'*', buf, usize::MAX, &mut position)
+                            .unwrap()
+                            .map(Bytes),
+                        Some(Bytes(b""))
+                    );
+                    assert_eq!(position, 1); // position after the symbol matched
+                }
+
+                /// Checks that search in the buffer an element that is located in the middle of
+                /// buffer returns slice before that symbol as a result and set `position` to one
+                /// symbol after match
+                #[test]
+                fn inside() {
+                    let buf = $buf;
+                    let mut position = 0;
+                    let mut input = b"abc*def".as_ref();
+                    //                    ^= 4
+
+                    assert_eq!(
+                        input
+                            .read_bytes_until(b'*', buf, usize::MAX, &mut position)
+                            .unwrap()
+                            .map(Bytes),
+                        Some(Bytes(b"abc"))
+                    );
+                    assert_eq!(position, 4); // position after the symbol matched
+                }
+
+                /// Checks that search in the buffer an element that is located in the end of
+                /// buffer returns slice before that symbol as a result and set `position` to one
+                /// symbol after match (`len()`)
+                #[test]
+                fn in_the_end() {
+                    let buf = $buf;
+                    let mut position = 0;
+                    let mut input = b"abcdef*".as_ref();
+                    //                       ^= 7
+
+                    assert_eq!(
+                        input
+                            .read_bytes_until(b'*', buf, usize::MAX, &mut position)
+                            .unwrap()
+                            .map(Bytes),
+                        Some(Bytes(b"abcdef"))
+                    );
+                    assert_eq!(position, 7); // position after the symbol matched
+                }
+            }
+
+            mod read_bang_element {
+                /// Checks that reading CDATA content works correctly
+                mod cdata {
+                    use crate::errors::Error;
+                    use crate::reader::{BangType, XmlSource};
+                    use crate::utils::Bytes;
+                    use pretty_assertions::assert_eq;
+
+                    /// Checks that if input begins like CDATA element, but CDATA start sequence
+                    /// is not finished, parsing ends with an error
+                    #[test]
+                    #[ignore = "start CDATA sequence fully checked outside of `read_bang_element`"]
+                    fn not_properly_start() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"![]]>other content".as_ref();
+                        //                ^= 0
+
+                        match input.read_bang_element(buf, usize::MAX, &mut position) {
+                            Err(Error::UnexpectedEof(s, _)) if s == "CData" => {}
+                            x => assert!(
+                                false,
+                                r#"Expected `UnexpectedEof("CData")`, but result is: {:?}"#,
+                                x
+                            ),
+                        }
+                        assert_eq!(position, 0);
+                    }
+
+                    /// Checks that if CDATA startup sequence was matched, but an end sequence
+                    /// is not found, parsing ends with an error
+                    #[test]
+                    fn not_closed() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"![CDATA[other content".as_ref();
+                        //                ^= 0
+
+                        match input.read_bang_element(buf, usize::MAX, &mut position) {
+                            Err(Error::UnexpectedEof(s, _)) if s == "CData" => {}
+                            x => assert!(
+                                false,
+                                r#"Expected `UnexpectedEof("CData")`, but result is: {:?}"#,
+                                x
+                            ),
+                        }
+                        assert_eq!(position, 0);
+                    }
+
+                    /// Checks that CDATA element without content inside parsed successfully
+                    #[test]
+                    fn empty() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"![CDATA[]]>other content".as_ref();
+                        //                           ^= 11
+
+                        assert_eq!(
+                            input
+                                .read_bang_element(buf, usize::MAX, &mut position)
+                                .unwrap()
+                                .map(|(ty, data)| (ty, Bytes(data))),
+                            Some((BangType::CData, Bytes(b"![CDATA[")))
+                        );
+                        assert_eq!(position, 11);
+                    }
+
+                    /// Checks that CDATA element with content parsed successfully.
+                    /// Additionally checks that sequences inside CDATA that may look like
+                    /// a CDATA end sequence do not interrupt CDATA parsing
+                    #[test]
+                    fn with_content() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"![CDATA[cdata]] ]>content]]>other content]]>".as_ref();
+                        //                                            ^= 28
+
+                        assert_eq!(
+                            input
+                                .read_bang_element(buf, usize::MAX, &mut position)
+                                .unwrap()
+                                .map(|(ty, data)| (ty, Bytes(data))),
+                            Some((BangType::CData, Bytes(b"![CDATA[cdata]] ]>content")))
+                        );
+                        assert_eq!(position, 28);
+                    }
+                }
+
+                /// Checks that reading XML comments works correctly. According to the [specification],
+                /// comment data can contain any sequence except `--`:
+                ///
+                /// ```peg
+                /// comment = '<--' (!'--' char)* '-->';
+                /// char = [#x1-#x2C]
+                ///      / [#x2E-#xD7FF]
+                ///      / [#xE000-#xFFFD]
+                ///      / [#x10000-#x10FFFF]
+                /// ```
+                ///
+                /// The presence of this limitation, however, is simply a poorly designed specification
+                /// (maybe for purpose of building of LL(1) XML parser) and quick-xml does not check for
+                /// presence of these sequences by default. This tests allow such content.
+                ///
+                /// [specification]: https://www.w3.org/TR/xml11/#dt-comment
+                mod comment {
+                    use crate::errors::Error;
+                    use crate::reader::{BangType, XmlSource};
+                    use crate::utils::Bytes;
+                    use pretty_assertions::assert_eq;
+
+                    #[test]
+                    #[ignore = "start comment sequence fully checked outside of `read_bang_element`"]
+                    fn not_properly_start() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"!- -->other content".as_ref();
+                        //                ^= 0
+
+                        match input.read_bang_element(buf, usize::MAX, &mut position) {
+                            Err(Error::UnexpectedEof(s, _)) if s == "Comment" => {}
+                            x => assert!(
+                                false,
+                                r#"Expected `UnexpectedEof("Comment")`, but result is: {:?}"#,
+                                x
+                            ),
+                        }
+                        assert_eq!(position, 0);
+                    }
+
+                    #[test]
+                    fn not_properly_end() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"!->other content".as_ref();
+                        //                ^= 0
+
+                        match input.read_bang_element(buf, usize::MAX, &mut position) {
+                            Err(Error::UnexpectedEof(s, _)) if s == "Comment" => {}
+                            x => assert!(
+                                false,
+                                r#"Expected `UnexpectedEof("Comment")`, but result is: {:?}"#,
+                                x
+                            ),
+                        }
+                        assert_eq!(position, 0);
+                    }
+
+                    #[test]
+                    fn not_closed1() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"!--other content".as_ref();
+                        //                ^= 0
+
+                        match input.read_bang_element(buf, usize::MAX, &mut position) {
+                            Err(Error::UnexpectedEof(s, _)) if s == "Comment" => {}
+                            x => assert!(
+                                false,
+                                r#"Expected `UnexpectedEof("Comment")`, but result is: {:?}"#,
+                                x
+                            ),
+                        }
+                        assert_eq!(position, 0);
+                    }
+
+                    #[test]
+                    fn not_closed2() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"!-->other content".as_ref();
+                        //                ^= 0
+
+                        match input.read_bang_element(buf, usize::MAX, &mut position) {
+                            Err(Error::UnexpectedEof(s, _)) if s == "Comment" => {}
+                            x => assert!(
+                                false,
+                                r#"Expected `UnexpectedEof("Comment")`, but result is: {:?}"#,
+                                x
+                            ),
+                        }
+                        assert_eq!(position, 0);
+                    }
+
+                    #[test]
+                    fn not_closed3() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"!--->other content".as_ref();
+                        //                ^= 0
+
+                        match input.read_bang_element(buf, usize::MAX, &mut position) {
+                            Err(Error::UnexpectedEof(s, _)) if s == "Comment" => {}
+                            x => assert!(
+                                false,
+                                r#"Expected `UnexpectedEof("Comment")`, but result is: {:?}"#,
+                                x
+                            ),
+                        }
+                        assert_eq!(position, 0);
+                    }
+
+                    #[test]
+                    fn empty() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"!---->other content".as_ref();
+                        //                      ^= 6
+
+                        assert_eq!(
+                            input
+                                .read_bang_element(buf, usize::MAX, &mut position)
+                                .unwrap()
+                                .map(|(ty, data)| (ty, Bytes(data))),
+                            Some((BangType::Comment, Bytes(b"!----")))
+                        );
+                        assert_eq!(position, 6);
+                    }
+
+                    #[test]
+                    fn with_content() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"!--->comment<--->other content".as_ref();
+                        //                                 ^= 17
+
+                        assert_eq!(
+                            input
+                                .read_bang_element(buf, usize::MAX, &mut position)
+                                .unwrap()
+                                .map(|(ty, data)| (ty, Bytes(data))),
+                            Some((BangType::Comment, Bytes(b"!--->comment<---")))
+                        );
+                        assert_eq!(position, 17);
+                    }
+                }
+
+                /// Checks that reading DOCTYPE definition works correctly
+                mod doctype {
+                    mod uppercase {
+                        use crate::errors::Error;
+                        use crate::reader::{BangType, XmlSource};
+                        use crate::utils::Bytes;
+                        use pretty_assertions::assert_eq;
+
+                        #[test]
+                        fn not_properly_start() {
+                            let buf = $buf;
+                            let mut position = 0;
+                            let mut input = b"!D other content".as_ref();
+                            //                ^= 0
+
+                            match input.read_bang_element(buf, usize::MAX, &mut position) {
+                                Err(Error::UnexpectedEof(s, _)) if s == "DOCTYPE" => {}
+                                x => assert!(
+                                    false,
+                                    r#"Expected `UnexpectedEof("DOCTYPE")`, but result is: {:?}"#,
+                                    x
+                                ),
+                            }
+                            assert_eq!(position, 0);
+                        }
+
+                        #[test]
+                        fn without_space() {
+                            let buf = $buf;
+                            let mut position = 0;
+                            let mut input = b"!DOCTYPEother content".as_ref();
+                            //                ^= 0
+
+                            match input.read_bang_element(buf, usize::MAX, &mut position) {
+                                Err(Error::UnexpectedEof(s, _)) if s == "DOCTYPE" => {}
+                                x => assert!(
+                                    false,
+                                    r#"Expected `UnexpectedEof("DOCTYPE")`, but result is: {:?}"#,
+                                    x
+                                ),
+                            }
+                            assert_eq!(position, 0);
+                        }
+
+                        #[test]
+                        fn empty() {
+                            let buf = $buf;
+                            let mut position = 0;
+                            let mut input = b"!DOCTYPE>other content".as_ref();
+                            //                         ^= 9
+
+                            assert_eq!(
+                                input
+                                    .read_bang_element(buf, usize::MAX, &mut position)
+                                    .unwrap()
+                                    .map(|(ty, data)| (ty, Bytes(data))),
+                                Some((BangType::DocType, Bytes(b"!DOCTYPE")))
+                            );
+                            assert_eq!(position, 9);
+                        }
+
+                        #[test]
+                        fn not_closed() {
+                            let buf = $buf;
+                            let mut position = 0;
+                            let mut input = b"!DOCTYPE other content".as_ref();
+                            //                ^= 0
+
+                            match input.read_bang_element(buf, usize::MAX, &mut position) {
+                                Err(Error::UnexpectedEof(s, _)) if s == "DOCTYPE" => {}
+                                x => assert!(
+                                    false,
+                                    r#"Expected `UnexpectedEof("DOCTYPE")`, but result is: {:?}"#,
+                                    x
+                                ),
+                            }
+                            assert_eq!(position, 0);
+                        }
+                    }
+
+                    mod lowercase {
+                        use crate::errors::Error;
+                        use crate::reader::{BangType, XmlSource};
+                        use crate::utils::Bytes;
+                        use pretty_assertions::assert_eq;
+
+                        #[test]
+                        fn not_properly_start() {
+                            let buf = $buf;
+                            let mut position = 0;
+                            let mut input = b"!d other content".as_ref();
+                            //                ^= 0
+
+                            match input.read_bang_element(buf, usize::MAX, &mut position) {
+                                Err(Error::UnexpectedEof(s, _)) if s == "DOCTYPE" => {}
+                                x => assert!(
+                                    false,
+                                    r#"Expected `UnexpectedEof("DOCTYPE")`, but result is: {:?}"#,
+                                    x
+                                ),
+                            }
+                            assert_eq!(position, 0);
+                        }
+
+                        #[test]
+                        fn without_space() {
+                            let buf = $buf;
+                            let mut position = 0;
+                            let mut input = b"!doctypeother content".as_ref();
+                            //                ^= 0
+
+                            match input.read_bang_element(buf, usize::MAX, &mut position) {
+                                Err(Error::UnexpectedEof(s, _)) if s == "DOCTYPE" => {}
+                                x => assert!(
+                                    false,
+                                    r#"Expected `UnexpectedEof("DOCTYPE")`, but result is: {:?}"#,
+                                    x
+                                ),
+                            }
+                            assert_eq!(position, 0);
+                        }
+
+                        #[test]
+                        fn empty() {
+                            let buf = $buf;
+                            let mut position = 0;
+                            let mut input = b"!doctype>other content".as_ref();
+                            //                         ^= 9
+
+                            assert_eq!(
+                                input
+                                    .read_bang_element(buf, usize::MAX, &mut position)
+                                    .unwrap()
+                                    .map(|(ty, data)| (ty, Bytes(data))),
+                                Some((BangType::DocType, Bytes(b"!doctype")))
+                            );
+                            assert_eq!(position, 9);
+                        }
+
+                        #[test]
+                        fn not_closed() {
+                            let buf = $buf;
+                            let mut position = 0;
+                            let mut input = b"!doctype other content".as_ref();
+                            //                ^= 0
+
+                            match input.read_bang_element(buf, usize::MAX, &mut position) {
+                                Err(Error::UnexpectedEof(s, _)) if s == "DOCTYPE" => {}
+                                x => assert!(
+                                    false,
+                                    r#"Expected `UnexpectedEof("DOCTYPE")`, but result is: {:?}"#,
+                                    x
+                                ),
+                            }
+                            assert_eq!(position, 0);
+                        }
+                    }
+                }
+            }
+
+            mod read_element {
+                use crate::reader::XmlSource;
+                use crate::utils::Bytes;
+                use pretty_assertions::assert_eq;
+
+                /// Checks that nothing was read from empty buffer
+                #[test]
+                fn empty() {
+                    let buf = $buf;
+                    let mut position = 0;
+                    let mut input = b"".as_ref();
+                    //                ^= 0
+
+                    assert_eq!(input.read_element(buf, usize::MAX, &mut position).unwrap().map(Bytes), None);
+                    assert_eq!(position, 0);
+                }
+
+                mod open {
+                    use crate::reader::XmlSource;
+                    use crate::utils::Bytes;
+                    use pretty_assertions::assert_eq;
+
+                    #[test]
+                    fn empty_tag() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b">".as_ref();
+                        //                 ^= 1
+
+                        assert_eq!(
+                            input.read_element(buf, usize::MAX, &mut position).unwrap().map(Bytes),
+                            Some(Bytes(b""))
+                        );
+                        assert_eq!(position, 1);
+                    }
+
+                    #[test]
+                    fn normal() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"tag>".as_ref();
+                        //                    ^= 4
+
+                        assert_eq!(
+                            input.read_element(buf, usize::MAX, &mut position).unwrap().map(Bytes),
+                            Some(Bytes(b"tag"))
+                        );
+                        assert_eq!(position, 4);
+                    }
+
+                    #[test]
+                    fn empty_ns_empty_tag() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b":>".as_ref();
+                        //                  ^= 2
+
+                        assert_eq!(
+                            input.read_element(buf, usize::MAX, &mut position).unwrap().map(Bytes),
+                            Some(Bytes(b":"))
+                        );
+                        assert_eq!(position, 2);
+                    }
+
+                    #[test]
+                    fn empty_ns() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b":tag>".as_ref();
+                        //                     ^= 5
+
+                        assert_eq!(
+                            input.read_element(buf, usize::MAX, &mut position).unwrap().map(Bytes),
+                            Some(Bytes(b":tag"))
+                        );
+                        assert_eq!(position, 5);
+                    }
+
+                    #[test]
+                    fn with_attributes() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = br#"tag  attr-1=">"  attr2  =  '>'  3attr>"#.as_ref();
+                        //                                                        ^= 38
+
+                        assert_eq!(
+                            input.read_element(buf, usize::MAX, &mut position).unwrap().map(Bytes),
+                            Some(Bytes(br#"tag  attr-1=">"  attr2  =  '>'  3attr"#))
+                        );
+                        assert_eq!(position, 38);
+                    }
+                }
+
+                mod self_closed {
+                    use crate::reader::XmlSource;
+                    use crate::utils::Bytes;
+                    use pretty_assertions::assert_eq;
+
+                    #[test]
+                    fn empty_tag() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"/>".as_ref();
+                        //                  ^= 2
+
+                        assert_eq!(
+                            input.read_element(buf, usize::MAX, &mut position).unwrap().map(Bytes),
+                            Some(Bytes(b"/"))
+                        );
+                        assert_eq!(position, 2);
+                    }
+
+                    #[test]
+                    fn normal() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b"tag/>".as_ref();
+                        //                     ^= 5
+
+                        assert_eq!(
+                            input.read_element(buf, usize::MAX, &mut position).unwrap().map(Bytes),
+                            Some(Bytes(b"tag/"))
+                        );
+                        assert_eq!(position, 5);
+                    }
+
+                    #[test]
+                    fn empty_ns_empty_tag() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b":/>".as_ref();
+                        //                   ^= 3
+
+                        assert_eq!(
+                            input.read_element(buf, usize::MAX, &mut position).unwrap().map(Bytes),
+                            Some(Bytes(b":/"))
+                        );
+                        assert_eq!(position, 3);
+                    }
+
+                    #[test]
+                    fn empty_ns() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = b":tag/>".as_ref();
+                        //                      ^= 6
+
+                        assert_eq!(
+                            input.read_element(buf, usize::MAX, &mut position).unwrap().map(Bytes),
+                            Some(Bytes(b":tag/"))
+                        );
+                        assert_eq!(position, 6);
+                    }
+
+                    #[test]
+                    fn with_attributes() {
+                        let buf = $buf;
+                        let mut position = 0;
+                        let mut input = br#"tag  attr-1="/>"  attr2  =  '/>'  3attr/>"#.as_ref();
+                        //                                                           ^= 41
+
+                        assert_eq!(
+                            input.read_element(buf, usize::MAX, &mut position).unwrap().map(Bytes),
+                            Some(Bytes(br#"tag  attr-1="/>"  attr2  =  '/>'  3attr/"#))
+                        );
+                        assert_eq!(position, 41);
+                    }
+                }
+            }
+
+            mod issue_344 {
+                use crate::errors::Error;
+
+                #[test]
+                fn cdata() {
+                    let doc = "![]]>";
+                    let mut reader = crate::Reader::from_str(doc);
+
+                    match reader.read_until_close($buf) {
+                        Err(Error::UnexpectedEof(s, _)) if s == "CData" => {}
+                        x => assert!(
+                            false,
+                            r#"Expected `UnexpectedEof("CData")`, but result is: {:?}"#,
+                            x
+                        ),
+                    }
+                }
+
+                #[test]
+                fn comment() {
+                    let doc = "!- -->";
+                    let mut reader = crate::Reader::from_str(doc);
+
+                    match reader.read_until_close($buf) {
+                        Err(Error::UnexpectedEof(s, _)) if s == "Comment" => {}
+                        x => assert!(
+                            false,
+                            r#"Expected `UnexpectedEof("Comment")`, but result is: {:?}"#,
+                            x
+                        ),
+                    }
+                }
+
+                #[test]
+                fn doctype_uppercase() {
+                    let doc = "!D>";
+                    let mut reader = crate::Reader::from_str(doc);
+
+                    match reader.read_until_close($buf) {
+                        Err(Error::UnexpectedEof(s, _)) if s == "DOCTYPE" => {}
+                        x => assert!(
+                            false,
+                            r#"Expected `UnexpectedEof("DOCTYPE")`, but result is: {:?}"#,
+                            x
+                        ),
+                    }
+                }
+
+                #[test]
+                fn doctype_lowercase() {
+                    let doc = "!d>";
+                    let mut reader = crate::Reader::from_str(doc);
+
+                    match reader.read_until_close($buf) {
+                        Err(Error::UnexpectedEof(s, _)) if s == "DOCTYPE" => {}
+                        x => assert!(
+                            false,
+                            r#"Expected `UnexpectedEof("DOCTYPE")`, but result is: {:?}"#,
+                            x
+                        ),
+                    }
+                }
+            }
+        };
+    }
+
+    /// Tests for reader that generates events that borrow from the provided buffer
+    mod buffered {
+        check!(&mut Vec::new());
+    }
+
+    /// Tests for reader that generates events that borrow from the input
+    mod borrowed {
+        check!(());
+    }
+
+    /// Checks byte ranges, reported in errors
+    mod span {
+        use crate::errors::Error;
+        use crate::events::attributes::AttrError;
+        use crate::events::Event;
+        use crate::reader::Reader;
+        use pretty_assertions::assert_eq;
+
+        /// Reads events until an error with both buffered and borrowing readers
+        /// and checks that they report the same error
+        fn error(xml: &str) -> Error {
+            let mut reader = Reader::from_str(xml);
+            reader.trim_text(true).check_comments(true);
+            let borrowed = loop {
+                match reader.read_event_unbuffered() {
+                    Ok(Event::Eof) => panic!("Expected error, but EOF reached"),
+                    Ok(_) => {}
+                    Err(e) => break e,
+                }
+            };
+
+            let mut reader = Reader::from_reader(xml.as_bytes());
+            reader.trim_text(true).check_comments(true);
+            let mut buf = Vec::new();
+            let buffered = loop {
+                match reader.read_event(&mut buf) {
+                    Ok(Event::Eof) => panic!("Expected error, but EOF reached"),
+                    Ok(_) => {}
+                    Err(e) => break e,
+                }
+            };
+            assert_eq!(format!("{:?}", borrowed), format!("{:?}", buffered));
+            borrowed
+        }
+
+        #[test]
+        fn end_event_mismatch() {
+            match error("<root>\n  <a></b></root>") {
+                Error::EndEventMismatch { span, .. } => assert_eq!(span, 12..16),
+                e => panic!("Expected `EndEventMismatch`, but got {:?}", e),
+            }
+        }
+
+        #[test]
+        fn unexpected_token() {
+            match error("<root><!-- a -- b --></root>") {
+                Error::UnexpectedToken(token, span) => {
+                    assert_eq!(token, "--");
+                    assert_eq!(span, 13..15);
+                }
+                e => panic!("Expected `UnexpectedToken`, but got {:?}", e),
+            }
+        }
+
+        #[test]
+        fn unexpected_bang() {
+            match error("<root><!x></root>") {
+                Error::UnexpectedBang(b, span) => {
+                    assert_eq!(b, b'x');
+                    assert_eq!(span, 6..9);
+                }
+                e => panic!("Expected `UnexpectedBang`, but got {:?}", e),
+            }
+        }
+
+        #[test]
+        fn unexpected_eof() {
+            match error("<root><!-- comment") {
+                Error::UnexpectedEof(what, span) => {
+                    assert_eq!(what, "Comment");
+                    assert_eq!(span, 6..18);
+                }
+                e => panic!("Expected `UnexpectedEof`, but got {:?}", e),
+            }
+            match error("<root><?xml >") {
+                Error::UnexpectedEof(what, span) => {
+                    assert_eq!(what, "XmlDecl");
+                    assert_eq!(span, 6..13);
+                }
+                e => panic!("Expected `UnexpectedEof`, but got {:?}", e),
+            }
+        }
+
+        #[test]
+        fn read_to_end() {
+            let mut reader = Reader::from_str("<root>\n<a><b></b>");
+            reader.trim_text(true);
+            reader.read_event_unbuffered().unwrap();
+            reader.read_event_unbuffered().unwrap();
+            match reader.read_to_end_unbuffered("a") {
+                Err(Error::UnexpectedEof(_, span)) => assert_eq!(span, 7..17),
+                e => panic!("Expected `UnexpectedEof`, but got {:?}", e),
+            }
+        }
+
+        /// Positions in attribute errors are absolute
+        #[test]
+        fn attributes() {
+            let mut reader = Reader::from_str("<root>\n<a key/>");
+            reader.trim_text(true);
+            reader.read_event_unbuffered().unwrap();
+            match reader.read_event_unbuffered().unwrap() {
+                Event::Empty(e) => {
+                    assert_eq!(e.attributes().next(), Some(Err(AttrError::ExpectedEq(13))));
+                    assert_eq!(
+                        e.into_owned().attributes().next(),
+                        Some(Err(AttrError::ExpectedEq(13)))
+                    );
+                }
+                e => panic!("Expected `Empty`, but got {:?}", e),
+            }
+        }
+
+        /// Reads all events with both buffered and borrowing readers and returns
+        /// slices of the document covered by them
+        fn events(xml: &str, configure: fn(&mut Reader<&[u8]>)) -> Vec<&str> {
+            let mut reader = Reader::from_str(xml);
+            configure(&mut reader);
+            let mut borrowed = Vec::new();
+            loop {
+                match reader.read_event_with_span().unwrap() {
+                    (Event::Eof, span) => {
+                        assert_eq!(span, xml.len()..xml.len());
+                        break;
+                    }
+                    (_, span) => borrowed.push(&xml[span]),
+                }
+            }
+
+            let mut reader = Reader::from_reader(xml.as_bytes());
+            configure(&mut reader);
+            let mut buf = Vec::new();
+            let mut buffered = Vec::new();
+            loop {
+                match reader.read_event(&mut buf).unwrap() {
+                    Event::Eof => break,
+                    _ => buffered.push(&xml[reader.event_span()]),
+                }
+                buf.clear();
+            }
+            assert_eq!(borrowed, buffered);
+            borrowed
+        }
+
+        /// Spans of all kinds of events
+        #[test]
+        fn event_spans() {
+            let xml = "<?xml version='1.0'?><!DOCTYPE root>\n<root a='>'> text &amp; \
+                       <![CDATA[<]]><!--c--><?pi?><empty/></root>";
+            assert_eq!(
+                events(xml, |_| {}),
+                vec![
+                    "",
+                    "<?xml version='1.0'?>",
+                    "",
+                    "<!DOCTYPE root>",
+                    "\n",
+                    "<root a='>'>",
+                    " text &amp; ",
+                    "<![CDATA[<]]>",
+                    "",
+                    "<!--c-->",
+                    "",
+                    "<?pi?>",
+                    "",
+                    "<empty/>",
+                    "",
+                    "</root>",
+                ]
+            );
+        }
+
+        /// Spans of text do not include skipped leading whitespaces
+        #[test]
+        fn trimmed_text() {
+            assert_eq!(
+                events("<root>\n  text\n</root>", |r| {
+                    r.trim_text(true);
+                }),
+                vec!["<root>", "text\n", "</root>"]
+            );
+        }
+
+        /// Generated `End` events have an empty span
+        #[test]
+        fn expanded_empty() {
+            let xml = "<root><empty/></root>";
+            let mut reader = Reader::from_str(xml);
+            reader.expand_empty_elements(true).trim_text(true);
+            let mut spans = Vec::new();
+            loop {
+                match reader.read_event_with_span().unwrap() {
+                    (Event::Eof, _) => break,
+                    (_, span) => spans.push(span),
+                }
+            }
+            assert_eq!(spans, vec![0..6, 6..14, 14..14, 14..21]);
+        }
+
+        /// Spans of attributes are absolute
+        #[test]
+        fn attribute_spans() {
+            let xml = "<root>\n<a key='value'/>";
+            let mut reader = Reader::from_str(xml);
+            reader.trim_text(true);
+            reader.read_event_unbuffered().unwrap();
+            match reader.read_event_unbuffered().unwrap() {
+                Event::Empty(e) => {
+                    let (_, span) = e.attributes().with_spans().next().unwrap().unwrap();
+                    assert_eq!(&xml[span.key], "key");
+                    assert_eq!(&xml[span.value], "value");
+                }
+                e => panic!("Expected `Empty`, but got {:?}", e),
+            }
+        }
+
+        /// Spans of attributes point to the document when the tag is normalized
+        #[test]
+        fn normalized_attribute_spans() {
+            let xml = "<!DOCTYPE a [<!ATTLIST a id ID #IMPLIED>]>\
+                       <a\r\nb='1'\r\nid=' x\r\n\ty '\r\nc='2\r\n'/>";
+            let mut reader = Reader::from_str(xml);
+            reader.trim_text(true).normalize(true);
+            reader.read_event_unbuffered().unwrap();
+            match reader.read_event_unbuffered().unwrap() {
+                Event::Empty(e) => {
+                    let spans: Vec<_> = e
+                        .attributes()
+                        .with_spans()
+                        .map(|a| {
+                            let span = a.unwrap().1;
+                            (&xml[span.key], &xml[span.value])
+                        })
+                        .collect();
+                    assert_eq!(
+                        spans,
+                        vec![("b", "1"), ("id", " x\r\n\ty "), ("c", "2\r\n"),]
+                    );
+                }
+                e => panic!("Expected `Empty`, but got {:?}", e),
+            }
+        }
+    }
+
+    mod position {
+        use crate::errors::Error;
+        use crate::events::Event;
+        use crate::reader::{Reader, TextPosition};
+        use pretty_assertions::assert_eq;
+
+        /// Reads all events and returns their kind and position
+        fn positions(xml: &str, trim: bool) -> Vec<(&'static str, usize, usize)> {
+            let mut reader = Reader::from_str(xml);
+            reader.trim_text(trim).track_position(true);
+            let mut buffered = Reader::from_reader(xml.as_bytes());
+            buffered.trim_text(trim).track_position(true);
+
+            let mut result = Vec::new();
+            let mut buf = Vec::new();
+            loop {
+                let event = reader.read_event_unbuffered().unwrap();
+                let kind = match event {
+                    Event::Start(_) => "Start",
+                    Event::End(_) => "End",
+                    Event::Empty(_) => "Empty",
+                    Event::Text(_) => "Text",
+                    Event::Comment(_) => "Comment",
+                    Event::CData(_) => "CData",
+                    Event::Decl(_) => "Decl",
+                    Event::PI(_) => "PI",
+                    Event::DocType(_) => "DocType",
+                    Event::Eof => "Eof",
+                };
+                let TextPosition { line, column } = reader.event_position().unwrap();
+                result.push((kind, line, column));
+
+                assert_eq!(
+                    std::mem::discriminant(&buffered.read_event(&mut buf).unwrap()),
+                    std::mem::discriminant(&event)
+                );
+                assert_eq!(buffered.event_position(), reader.event_position());
+
+                if let Event::Eof = event {
+                    break;
+                }
+            }
+            result
+        }
+
+        #[test]
+        fn disabled() {
+            let mut reader = Reader::from_str("<root>");
+            assert_eq!(reader.event_position(), None);
+            reader.read_event_unbuffered().unwrap();
+            assert_eq!(reader.event_position(), None);
+        }
+
+        #[test]
+        fn events() {
+            assert_eq!(
+                positions(
+                    "<?xml version='1.0'?>\n<root>\n  <!-- comment -->\n  <a x='1\n2'/>text</root>",
+                    true
+                ),
+                vec![
+                    ("Decl", 1, 1),
+                    ("Start", 2, 1),
+                    ("Comment", 3, 3),
+                    ("Empty", 4, 3),
+                    ("Text", 5, 5),
+                    ("End", 5, 9),
+                    ("Eof", 5, 16),
+                ]
+            );
+        }
+
+        #[test]
+        fn cdata() {
+            assert_eq!(
+                positions("<a><![CDATA[x]]><b/></a>", true),
+                vec![
+                    ("Start", 1, 1),
+                    ("CData", 1, 4),
+                    ("Empty", 1, 17),
+                    ("End", 1, 21),
+                    ("Eof", 1, 25)
+                ]
+            );
+        }
+
+        #[test]
+        fn untrimmed_text() {
+            assert_eq!(
+                positions("<a>\n  text\n</a>", false),
+                vec![
+                    ("Text", 1, 1),
+                    ("Start", 1, 1),
+                    ("Text", 1, 4),
+                    ("End", 3, 1),
+                    ("Eof", 3, 5)
+                ]
+            );
+        }
+
+        #[test]
+        fn line_ends() {
+            assert_eq!(
+                positions("<a>\r\n<b/>\r<c/>\n\r<d/></a>", true),
+                vec![
+                    ("Start", 1, 1),
+                    ("Empty", 2, 1),
+                    ("Empty", 3, 1),
+                    ("Empty", 5, 1),
+                    ("End", 5, 5),
+                    ("Eof", 5, 9),
+                ]
+            );
+        }
+
+        /// Columns are counted in characters, not in bytes
+        #[test]
+        fn multibyte() {
+            assert_eq!(
+                positions("<корень>текст<b/>😀<c/></корень>", false),
+                vec![
+                    ("Text", 1, 1),
+                    ("Start", 1, 1),
+                    ("Text", 1, 9),
+                    ("Empty", 1, 14),
+                    ("Text", 1, 18),
+                    ("Empty", 1, 19),
+                    ("Text", 1, 23),
+                    ("End", 1, 23),
+                    ("Eof", 1, 32),
+                ]
+            );
+        }
+
+        #[test]
+        fn error() {
+            let mut reader = Reader::from_str("<root>\n  <a></b>\n</root>");
+            reader.trim_text(true).track_position(true);
+
+            assert!(matches!(
+                reader.read_event_unbuffered(),
+                Ok(Event::Start(_))
+            ));
+            assert!(matches!(
+                reader.read_event_unbuffered(),
+                Ok(Event::Start(_))
+            ));
+            let error = reader.read_event_unbuffered().unwrap_err();
+            // The wrapped error is the source
+            let source = std::error::Error::source(&error)
+                .and_then(|e| e.downcast_ref::<Error>())
+                .map(|e| e.to_string());
+            match error {
+                Error::Positioned { error, position } => {
+                    assert_eq!(source, Some(error.to_string()));
+                    assert!(matches!(*error, Error::EndEventMismatch { .. }));
+                    assert_eq!(position, TextPosition { line: 2, column: 6 });
+                }
+                x => panic!("Expected `Error::Positioned`, but got {:?}", x),
+            }
+        }
+    }
+
+    mod limits {
+        use crate::errors::{Error, Result};
+        use crate::reader::{Reader, ReaderLimits};
+        use pretty_assertions::assert_eq;
+        use std::io::{BufReader, Read};
+
+        /// Reads all events with both buffered (with a small buffer, to check
+        /// reading by chunks) and borrowed readers and returns the error, which
+        /// should be the same for both readers, as a result
+        fn error(xml: &str, limits: ReaderLimits) -> Result<()> {
+            let mut reader = Reader::from_str(xml);
+            reader.limits(limits);
+            let mut buffered = Reader::from_reader(BufReader::with_capacity(3, xml.as_bytes()));
+            buffered.limits(limits);
+
+            let mut buf = Vec::new();
+            loop {
+                let event = reader.read_event_unbuffered().map(|e| e.into_owned());
+                let buffered = buffered.read_event(&mut buf).map(|e| e.into_owned());
+                assert_eq!(format!("{:?}", buffered), format!("{:?}", event));
+                buf.clear();
+                match event {
+                    Ok(crate::events::Event::Eof) => panic!("Expected error"),
+                    Ok(_) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        #[test]
+        fn markup() {
+            let limits = ReaderLimits {
+                max_markup_len: 5,
+                ..ReaderLimits::default()
+            };
+            // All markups exactly at the limit
+            let xml = "<abcd ><?pi ?><!---->t</abcd>";
+            let mut reader = Reader::from_str(xml);
+            reader.limits(limits);
+            while reader.read_event_unbuffered().unwrap() != crate::events::Event::Eof {}
+
+            assert_err!(error("<a b='1'>", limits), Error::MarkupTooLong(5, span) if span == (0..7));
+            assert_err!(error("<a></a    >", limits), Error::MarkupTooLong(5, span) if span == (3..10));
+            assert_err!(error("<?pi  ?>", limits), Error::MarkupTooLong(5, span) if span == (0..7));
+            assert_err!(error("<!-- -->", limits), Error::MarkupTooLong(5, span) if span == (0..7));
+            assert_err!(error("<![CDATA[]]>", limits), Error::MarkupTooLong(5, _));
+            assert_err!(error("<!DOCTYPE x>", limits), Error::MarkupTooLong(5, _));
+        }
+
+        #[test]
+        fn text() {
+            let limits = ReaderLimits {
+                max_text_len: 5,
+                ..ReaderLimits::default()
+            };
+            let mut reader = Reader::from_str("<a>12345</a>12345");
+            reader.limits(limits);
+            while reader.read_event_unbuffered().unwrap() != crate::events::Event::Eof {}
+
+            assert_err!(error("<a>123456</a>", limits), Error::TextTooLong(5, span) if span == (3..9));
+            assert_err!(error("123456", limits), Error::TextTooLong(5, span) if span == (0..6));
+        }
+
+        #[test]
+        fn depth() {
+            let limits = ReaderLimits {
+                max_depth: 2,
+                ..ReaderLimits::default()
+            };
+            let mut reader = Reader::from_str("<a><b/><b></b></a><a><b/></a>");
+            reader.limits(limits);
+            while reader.read_event_unbuffered().unwrap() != crate::events::Event::Eof {}
+
+            assert_err!(error("<a><b><c>", limits), Error::TooDeep(2, span) if span == (6..9));
+            assert_err!(error("<a><b><c/>", limits), Error::TooDeep(2, span) if span == (6..10));
+        }
+
+        #[test]
+        fn depth_expanded_empty() {
+            let mut reader = Reader::from_str("<a><b/><b/></a>");
+            reader.expand_empty_elements(true).limits(ReaderLimits {
+                max_depth: 2,
+                ..ReaderLimits::default()
+            });
+            while reader.read_event_unbuffered().unwrap() != crate::events::Event::Eof {}
+        }
+
+        #[test]
+        fn attributes() {
+            let limits = ReaderLimits {
+                max_attributes: 1,
+                ..ReaderLimits::default()
+            };
+            assert_err!(error("<a x='' y=''>", limits), Error::TooManyAttributes(1, span) if span == (0..13));
+            assert_err!(error("<a><b x='' y=''/>", limits), Error::TooManyAttributes(1, span) if span == (3..17));
+        }
+
+        #[test]
+        fn names() {
+            let limits = ReaderLimits {
+                max_name_len: 2,
+                ..ReaderLimits::default()
+            };
+            assert_err!(error("<abc>", limits), Error::NameTooLong(2, span) if span == (1..4));
+            assert_err!(error("<ab abc=''/>", limits), Error::NameTooLong(2, span) if span == (4..7));
+            assert_err!(error("<ab></abc>", limits), Error::NameTooLong(2, span) if span == (6..9));
+        }
+
+        /// The buffer should not grow beyond the limit for the endless input
+        #[test]
+        fn bounded_buffer() {
+            let limits = ReaderLimits {
+                max_markup_len: 100,
+                max_text_len: 100,
+                ..ReaderLimits::default()
+            };
+
+            let input = b"<a".chain(std::io::repeat(b'a'));
+            let mut reader = Reader::from_reader(BufReader::new(input));
+            reader.trim_text(true).limits(limits);
+            let mut buf = Vec::new();
+            assert_err!(reader.read_event(&mut buf), Error::MarkupTooLong(100, _));
+            assert!(buf.len() <= 101);
+
+            let input = std::io::repeat(b'a');
+            let mut reader = Reader::from_reader(BufReader::new(input));
+            reader.limits(limits);
+            let mut buf = Vec::new();
+            assert_err!(reader.read_event(&mut buf), Error::TextTooLong(100, _));
+            assert!(buf.len() <= 101);
+        }
+    }
+
+    mod recover {
+        use crate::errors::Error;
+        use crate::events::attributes::AttrError;
+        use crate::events::{BytesEnd, BytesStart, BytesText, Event};
+        use crate::reader::{Diagnostic, Reader};
+        use pretty_assertions::assert_eq;
+
+        /// Reads all events in the recovery mode with both buffered and
+        /// borrowed readers and checks that they produce the same results
+        fn recover(xml: &str) -> (Vec<Event<'static>>, Vec<Diagnostic>) {
+            let mut reader = Reader::from_str(xml);
+            reader.trim_text(true).recover(true);
+            let mut buffered = Reader::from_reader(xml.as_bytes());
+            buffered.trim_text(true).recover(true);
+
+            let mut events = Vec::new();
+            let mut buf = Vec::new();
+            loop {
+                let event = reader.read_event_unbuffered().unwrap().into_owned();
+                assert_eq!(buffered.read_event(&mut buf).unwrap(), event);
+                buf.clear();
+                if let Event::Eof = event {
+                    break;
+                }
+                events.push(event);
+            }
+            assert_eq!(buffered.diagnostics(), reader.diagnostics());
+            (events, reader.take_diagnostics())
+        }
+
+        #[test]
+        fn bare_ampersand() {
+            let (events, diagnostics) = recover("<a>a & b &amp; &c d&#32;&#x;</a>");
+            assert_eq!(
+                events,
+                vec![
+                    Event::Start(BytesStart::borrowed_name(b"a")),
+                    Event::Text(BytesText::from_escaped_str(
+                        "a &amp; b &amp; &amp;c d&#32;&amp;#x;"
+                    )),
+                    Event::End(BytesEnd::borrowed(b"a")),
+                ]
+            );
+            assert_eq!(
+                diagnostics,
+                vec![
+                    Diagnostic::BareAmpersand(5),
+                    Diagnostic::BareAmpersand(15),
+                    Diagnostic::BareAmpersand(24),
+                ]
+            );
+        }
+
+        #[test]
+        fn mismatched_end() {
+            let (events, diagnostics) = recover("<a><b><c>text</a><d></d>");
+            assert_eq!(
+                events,
+                vec![
+                    Event::Start(BytesStart::borrowed_name(b"a")),
+                    Event::Start(BytesStart::borrowed_name(b"b")),
+                    Event::Start(BytesStart::borrowed_name(b"c")),
+                    Event::Text(BytesText::from_escaped_str("text")),
+                    Event::End(BytesEnd::borrowed(b"c")),
+                    Event::End(BytesEnd::borrowed(b"b")),
+                    Event::End(BytesEnd::borrowed(b"a")),
+                    Event::Start(BytesStart::borrowed_name(b"d")),
+                    Event::End(BytesEnd::borrowed(b"d")),
+                ]
+            );
+            assert_eq!(
+                diagnostics,
+                vec![Diagnostic::MismatchedEnd {
+                    expected: "c".to_string(),
+                    found: "a".to_string(),
+                    span: 13..17,
+                }]
+            );
+        }
+
+        #[test]
+        fn unmatched_end() {
+            let (events, diagnostics) = recover("<a></b></a></c>");
+            assert_eq!(
+                events,
+                vec![
+                    Event::Start(BytesStart::borrowed_name(b"a")),
+                    Event::End(BytesEnd::borrowed(b"a")),
+                ]
+            );
+            assert_eq!(
+                diagnostics,
+                vec![
+                    Diagnostic::UnmatchedEnd {
+                        found: "b".to_string(),
+                        span: 3..7,
+                    },
+                    Diagnostic::UnmatchedEnd {
+                        found: "c".to_string(),
+                        span: 11..15,
+                    },
+                ]
+            );
+        }
+
+        /// The span of the end tag is given to the `End` event of the element
+        /// with the same name, implied `End` events have empty spans
+        #[test]
+        fn mismatched_end_spans() {
+            let mut reader = Reader::from_str("<a><b><c></a>");
+            reader.recover(true);
+            let mut spans = Vec::new();
+            loop {
+                match reader.read_event_with_span().unwrap() {
+                    (Event::Eof, _) => break,
+                    (Event::End(e), span) => spans.push((e.name().as_ref().to_vec(), span)),
+                    _ => {}
+                }
+            }
+            assert_eq!(
+                spans,
+                vec![
+                    (b"c".to_vec(), 13..13),
+                    (b"b".to_vec(), 13..13),
+                    (b"a".to_vec(), 9..13),
+                ]
+            );
+        }
+
+        /// Skipped end tags do not produce empty `Text` events
+        #[test]
+        fn unmatched_end_nested() {
+            let (events, diagnostics) = recover("<a><b></c></a>");
+            assert_eq!(
+                events,
+                vec![
+                    Event::Start(BytesStart::borrowed_name(b"a")),
+                    Event::Start(BytesStart::borrowed_name(b"b")),
+                    Event::End(BytesEnd::borrowed(b"b")),
+                    Event::End(BytesEnd::borrowed(b"a")),
+                ]
+            );
+            assert_eq!(diagnostics.len(), 2);
+
+            let (events, _) = recover("<a>x</c>y</a>");
+            assert_eq!(
+                events,
+                vec![
+                    Event::Start(BytesStart::borrowed_name(b"a")),
+                    Event::Text(BytesText::from_escaped_str("x")),
+                    Event::Text(BytesText::from_escaped_str("y")),
+                    Event::End(BytesEnd::borrowed(b"a")),
+                ]
+            );
+        }
+
+        #[test]
+        fn invalid_attributes() {
+            let (events, diagnostics) = recover("<a x='1' y=2 x='3'/><b z='4'>");
+            assert_eq!(
+                events,
+                vec![
+                    Event::Empty(BytesStart::borrowed(b"a x='1'", 1)),
+                    Event::Start(BytesStart::borrowed(b"b z='4'", 1)),
+                ]
+            );
+            assert_eq!(
+                diagnostics,
+                vec![
+                    Diagnostic::InvalidAttr(AttrError::UnquotedValue(11)),
+                    Diagnostic::InvalidAttr(AttrError::Duplicated(13, 3)),
+                ]
+            );
+        }
+
+        #[test]
+        fn attribute_ampersands() {
+            let xml = "<a href='x?a=1&b=2' title='&amp;'/>";
+            let mut reader = Reader::from_str(xml);
+            reader.trim_text(true).recover(true);
+            match reader.read_event_unbuffered().unwrap() {
+                Event::Empty(e) => {
+                    let mut attributes = e.attributes().with_spans();
+                    let (href, span) = attributes.next().unwrap().unwrap();
+                    assert_eq!(&*href.unescaped_value().unwrap(), b"x?a=1&b=2");
+                    assert_eq!(&xml[span.value], "x?a=1&b=2");
+                    let (title, span) = attributes.next().unwrap().unwrap();
+                    assert_eq!(&*title.unescaped_value().unwrap(), b"&");
+                    assert_eq!(&xml[span.key], "title");
+                }
+                e => panic!("Expected `Empty`, but got {:?}", e),
+            }
+            assert_eq!(reader.diagnostics(), &[Diagnostic::BareAmpersand(14)]);
+        }
+
+        /// Spans of attributes point to the document when invalid attributes are removed
+        #[test]
+        fn attribute_spans() {
+            let xml = "<a x b='1'\r\nc = \"2\r\n\"/>";
+            let mut reader = Reader::from_str(xml);
+            reader.trim_text(true).recover(true).normalize(true);
+            match reader.read_event_unbuffered().unwrap() {
+                Event::Empty(e) => {
+                    let spans: Vec<_> = e
+                        .attributes()
+                        .with_spans()
+                        .map(|a| {
+                            let span = a.unwrap().1;
+                            (&xml[span.key], &xml[span.value])
+                        })
+                        .collect();
+                    assert_eq!(spans, vec![("b", "1"), ("c", "2\r\n")]);
+                }
+                e => panic!("Expected `Empty`, but got {:?}", e),
+            }
+        }
+
+        /// Without the recovery mode errors are returned as usual
+        #[test]
+        fn disabled() {
+            let mut reader = Reader::from_str("<a></b>");
+            reader.trim_text(true);
+            reader.read_event_unbuffered().unwrap();
+            match reader.read_event_unbuffered() {
+                Err(Error::EndEventMismatch { .. }) => {}
+                x => panic!("Expected `Error::EndEventMismatch`, but got {:?}", x),
+            }
+            assert_eq!(reader.diagnostics(), &[]);
+        }
+    }
+
+    mod html {
+        use crate::events::attributes::AttrError;
+        use crate::events::{BytesEnd, BytesStart, BytesText, Event};
+        use crate::reader::{Diagnostic, Reader};
+        use pretty_assertions::assert_eq;
+        use std::io::BufReader;
+
+        /// Reads all events in the HTML mode with both buffered and borrowed
+        /// readers and checks that they produce the same results. The buffered
+        /// reader reads the input in small chunks
+        fn html(xml: &str, expand_empty_elements: bool) -> Vec<Event<'static>> {
+            let mut reader = Reader::from_str(xml);
+            reader
+                .trim_text(true)
+                .expand_empty_elements(expand_empty_elements)
+                .html(true);
+            let mut buffered = Reader::from_reader(BufReader::with_capacity(3, xml.as_bytes()));
+            buffered
+                .trim_text(true)
+                .expand_empty_elements(expand_empty_elements)
+                .html(true);
+
+            let mut events = Vec::new();
+            let mut buf = Vec::new();
+            loop {
+                let event = reader.read_event_unbuffered().unwrap().into_owned();
+                assert_eq!(buffered.read_event(&mut buf).unwrap(), event);
+                buf.clear();
+                if let Event::Eof = event {
+                    break;
+                }
+                events.push(event);
+            }
+            events
+        }
+
+        fn start(name: &str) -> Event<'static> {
+            Event::Start(BytesStart::owned_name(name))
+        }
+
+        fn end(name: &str) -> Event<'static> {
+            Event::End(BytesEnd::owned(name.as_bytes().to_vec()))
+        }
+
+        fn text(text: &str) -> Event<'static> {
+            Event::Text(BytesText::from_plain_str(text).into_owned())
+        }
+
+        #[test]
+        fn void_elements() {
+            assert_eq!(
+                html("<p>a<br>b<IMG src=x></br><wbr/></p>", false),
+                vec![
+                    start("p"),
+                    text("a"),
+                    Event::Empty(BytesStart::owned_name("br")),
+                    text("b"),
+                    Event::Empty(BytesStart::owned("IMG src=x", 3)),
+                    Event::Empty(BytesStart::owned_name("wbr")),
+                    end("p"),
+                ]
+            );
+        }
+
+        /// HTML attributes are not removed in the recovery mode
+        #[test]
+        fn recovered_attributes() {
+            let mut reader = Reader::from_str("<input disabled value=x a='1' a>");
+            reader.trim_text(true).html(true).recover(true);
+            assert_eq!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::Empty(BytesStart::borrowed(b"input disabled value=x a='1'", 5))
+            );
+            assert_eq!(
+                reader.diagnostics(),
+                &[Diagnostic::InvalidAttr(AttrError::Duplicated(30, 24))]
+            );
+        }
+
+        #[test]
+        fn void_elements_expanded() {
+            assert_eq!(
+                html("<p><br><meta charset=utf-8></p>", true),
+                vec![
+                    start("p"),
+                    start("br"),
+                    end("br"),
+                    Event::Start(BytesStart::owned("meta charset=utf-8", 4)),
+                    end("meta"),
+                    end("p"),
+                ]
+            );
+        }
+
+        #[test]
+        fn raw_text() {
+            assert_eq!(
+                html(
+                    "<script>if (a<b && c) { x = '</div>'; }</SCRIPT ><style>\n</style><script></script><p>",
+                    false
+                ),
+                vec![
+                    start("script"),
+                    text("if (a<b && c) { x = '</div>'; }"),
+                    end("script"),
+                    start("style"),
+                    end("style"),
+                    start("script"),
+                    end("script"),
+                    start("p"),
+                    end("p"),
+                ]
+            );
+        }
+
+        /// Content of a raw text element without the end tag lasts until the end of input
+        #[test]
+        fn raw_text_unclosed() {
+            assert_eq!(
+                html("<style>a > b { }</styl", false),
+                vec![start("style"), text("a > b { }</styl"), end("style")]
+            );
+        }
+
+        #[test]
+        fn implied_end_tags() {
+            assert_eq!(
+                html("<ul><li>One<li><b>Two<li><p>Three<div>Four</ul>", false),
+                vec![
+                    start("ul"),
+                    start("li"),
+                    text("One"),
+                    end("li"),
+                    start("li"),
+                    start("b"),
+                    text("Two"),
+                    end("b"),
+                    end("li"),
+                    start("li"),
+                    start("p"),
+                    text("Three"),
+                    end("p"),
+                    start("div"),
+                    text("Four"),
+                    end("div"),
+                    end("li"),
+                    end("ul"),
+                ]
+            );
+        }
+
+        #[test]
+        fn tables() {
+            assert_eq!(
+                html("<table><tr><td>1<td>2<tr><th>3</table>", false),
+                vec![
+                    start("table"),
+                    start("tr"),
+                    start("td"),
+                    text("1"),
+                    end("td"),
+                    start("td"),
+                    text("2"),
+                    end("td"),
+                    end("tr"),
+                    start("tr"),
+                    start("th"),
+                    text("3"),
+                    end("th"),
+                    end("tr"),
+                    end("table"),
+                ]
+            );
+        }
+
+        /// End events have names of the start tags
+        #[test]
+        fn case_insensitive() {
+            assert_eq!(
+                html("<DIV><P>text</p></Div>", false),
+                vec![start("DIV"), start("P"), text("text"), end("P"), end("DIV")]
+            );
+        }
+
+        #[test]
+        fn unclosed_at_eof() {
+            assert_eq!(
+                html("<html><body><p>text", false),
+                vec![
+                    start("html"),
+                    start("body"),
+                    start("p"),
+                    text("text"),
+                    end("p"),
+                    end("body"),
+                    end("html"),
+                ]
+            );
+        }
+
+        fn spans(xml: &str) -> Vec<std::ops::Range<usize>> {
+            let mut reader = Reader::from_str(xml);
+            reader.html(true);
+            let mut spans = Vec::new();
+            loop {
+                match reader.read_event_with_span().unwrap() {
+                    (Event::Eof, _) => break,
+                    (_, span) => spans.push(span),
+                }
+            }
+            spans
+        }
+
+        /// Spans of synthesized `End` events are empty, and spans of start tags
+        /// that implicitly closed elements are the tags
+        #[test]
+        fn implied_end_spans() {
+            assert_eq!(
+                spans("<li>a<li>b"),
+                vec![0..0, 0..4, 4..5, 9..9, 5..9, 9..10, 10..10]
+            );
+        }
+
+        /// The span of an end tag, that closes several elements, is given to
+        /// the `End` event of the element with the same name
+        #[test]
+        fn end_tag_spans() {
+            let xml = "<ul><li>one<li>two</ul>";
+            assert_eq!(
+                spans(xml),
+                vec![
+                    0..0,
+                    0..4,
+                    4..4,
+                    4..8,
+                    8..11,
+                    15..15,
+                    11..15,
+                    15..18,
+                    23..23,
+                    18..23
+                ]
+            );
+            assert_eq!(&xml[18..23], "</ul>");
+        }
+    }
+
+    mod normalize {
+        use crate::events::{BytesCData, BytesStart, BytesText, Event};
+        use crate::reader::Reader;
+        use pretty_assertions::assert_eq;
+
+        /// Reads all events with normalization with both buffered and borrowed
+        /// readers and checks that they produce the same results
+        fn normalize(xml: &str) -> Vec<Event<'static>> {
+            let mut reader = Reader::from_str(xml);
+            reader.trim_text(true).normalize(true);
+            let mut buffered = Reader::from_reader(xml.as_bytes());
+            buffered.trim_text(true).normalize(true);
+
+            let mut events = Vec::new();
+            let mut buf = Vec::new();
+            loop {
+                let event = reader.read_event_unbuffered().unwrap().into_owned();
+                assert_eq!(buffered.read_event(&mut buf).unwrap(), event);
+                buf.clear();
+                if let Event::Eof = event {
+                    break;
+                }
+                events.push(event);
+            }
+            events
+        }
+
+        #[test]
+        fn line_endings() {
+            assert_eq!(
+                normalize("a\r\nb\rc<![CDATA[\r\n]]><!--\r--><?pi \r\n?>&#13;"),
+                vec![
+                    Event::Text(BytesText::from_escaped_str("a\nb\nc")),
+                    Event::CData(BytesCData::from_str("\n")),
+                    Event::Comment(BytesText::from_escaped_str("\n")),
+                    Event::PI(BytesText::from_escaped_str("pi \n")),
+                    Event::Text(BytesText::from_escaped_str("&#13;")),
+                ]
+            );
+        }
+
+        #[test]
+        fn attribute_values() {
+            assert_eq!(
+                normalize("<a\r\nb='\tx\r\ny'\rc=\"&#10;\n\"/>"),
+                vec![Event::Empty(BytesStart::owned(
+                    "a\nb=' x y'\nc=\"&#10; \"",
+                    1
+                ))]
+            );
+        }
+
+        /// Values of attributes declared with a type other than `CDATA` are collapsed
+        #[test]
+        fn declared_types() {
+            let xml = "<!DOCTYPE a [\
+                <!ATTLIST a id ID #IMPLIED cdata CDATA #IMPLIED>\
+                <!ATTLIST a cdata NMTOKENS #IMPLIED refs IDREFS #IMPLIED>\
+            ]>\
+            <a id=' x ' cdata=' x ' refs=' x  y\n'><b id=' x '/></a>";
+            let mut reader = Reader::from_str(xml);
+            reader
+                .trim_text(true)
+                .normalize(true)
+                .expand_entities(false);
+
+            assert!(matches!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::DocType(_)
+            ));
+            assert_eq!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::Start(BytesStart::borrowed(b"a id='x' cdata=' x ' refs='x y'", 1))
+            );
+            // Declarations of other elements are not used
+            assert_eq!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::Empty(BytesStart::borrowed(b"b id=' x '", 1))
+            );
+        }
+
+        /// Nothing is changed without normalization
+        #[test]
+        fn disabled() {
+            let mut reader = Reader::from_str("x\r\n<a b='\r\n'/>");
+            assert_eq!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::Text(BytesText::from_escaped_str("x\r\n"))
+            );
+            assert_eq!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::Empty(BytesStart::borrowed(b"a b='\r\n'", 1))
+            );
+        }
+    }
+
+    mod coalesce {
+        use crate::events::{BytesCData, BytesEnd, BytesStart, BytesText, Event};
+        use crate::reader::Reader;
+        use pretty_assertions::assert_eq;
+
+        /// Reads all events with coalescing with both buffered and borrowed
+        /// readers and checks that they produce the same results
+        fn coalesce(xml: &str, skip_comments: bool) -> Vec<Event<'static>> {
+            let mut reader = Reader::from_str(xml);
+            reader
+                .trim_text(true)
+                .coalesce_text(true)
+                .skip_comments_in_text(skip_comments);
+            let mut buffered = Reader::from_reader(xml.as_bytes());
+            buffered
+                .trim_text(true)
+                .coalesce_text(true)
+                .skip_comments_in_text(skip_comments);
+
+            let mut events = Vec::new();
+            let mut buf = Vec::new();
+            loop {
+                let event = reader.read_event_unbuffered().unwrap().into_owned();
+                assert_eq!(buffered.read_event(&mut buf).unwrap(), event);
+                buf.clear();
+                if let Event::Eof = event {
+                    break;
+                }
+                events.push(event);
+            }
+            events
+        }
+
+        #[test]
+        fn cdata() {
+            let events = coalesce("<a>x &lt;<![CDATA[ <y> ]]><![CDATA[&]]>z</a>", false);
+            assert_eq!(
+                events,
+                vec![
+                    Event::Start(BytesStart::borrowed_name(b"a")),
+                    Event::Text(BytesText::from_escaped_str("x &lt; &lt;y&gt; &amp;z")),
+                    Event::End(BytesEnd::borrowed(b"a")),
+                ]
+            );
+            match &events[1] {
+                Event::Text(e) => assert_eq!(e.unescaped().unwrap(), "x < <y> &z".as_bytes()),
+                e => panic!("Unexpected event {:?}", e),
+            }
+        }
+
+        /// Single events are returned unchanged
+        #[test]
+        fn single() {
+            assert_eq!(
+                coalesce("<a><![CDATA[&]]></a>text", false),
+                vec![
+                    Event::Start(BytesStart::borrowed_name(b"a")),
+                    Event::CData(BytesCData::from_str("&")),
+                    Event::End(BytesEnd::borrowed(b"a")),
+                    Event::Text(BytesText::from_escaped_str("text")),
+                ]
+            );
+        }
+
+        #[test]
+        fn comments() {
+            let xml = "<a>x<!--c-->y<?pi?><![CDATA[z]]></a>";
+            assert_eq!(
+                coalesce(xml, false),
+                vec![
+                    Event::Start(BytesStart::borrowed_name(b"a")),
+                    Event::Text(BytesText::from_escaped_str("x")),
+                    Event::Comment(BytesText::from_escaped_str("c")),
+                    Event::Text(BytesText::from_escaped_str("y")),
+                    Event::PI(BytesText::from_escaped_str("pi")),
+                    Event::CData(BytesCData::from_str("z")),
+                    Event::End(BytesEnd::borrowed(b"a")),
+                ]
+            );
+            assert_eq!(
+                coalesce(xml, true),
+                vec![
+                    Event::Start(BytesStart::borrowed_name(b"a")),
+                    Event::Text(BytesText::from_escaped_str("xyz")),
+                    Event::End(BytesEnd::borrowed(b"a")),
+                ]
+            );
+            // Skipped comments after the text are dropped
+            assert_eq!(
+                coalesce("<a>x<!--c--></a>", true),
+                vec![
+                    Event::Start(BytesStart::borrowed_name(b"a")),
+                    Event::Text(BytesText::from_escaped_str("x")),
+                    Event::End(BytesEnd::borrowed(b"a")),
+                ]
+            );
+        }
+
+        #[test]
+        fn spans() {
+            let mut reader = Reader::from_str("<a>x<![CDATA[y]]><!--c--></a>");
+            reader.trim_text(true).coalesce_text(true);
+
+            assert_eq!(reader.read_event_with_span().unwrap().1, 0..3);
+            assert_eq!(
+                reader.read_event_with_span().unwrap(),
+                (Event::Text(BytesText::from_escaped_str("xy")), 3..17)
+            );
+            assert_eq!(
+                reader.read_event_with_span().unwrap(),
+                (Event::Comment(BytesText::from_escaped_str("c")), 17..25)
+            );
+            assert_eq!(reader.read_event_with_span().unwrap().1, 25..29);
+        }
+
+        /// An event read after the text is kept in the state
+        #[test]
+        fn state() {
+            let xml = "<a>x<!--c-->y</a>";
+            let mut reader = Reader::from_str(xml);
+            reader.trim_text(true).coalesce_text(true);
+
+            reader.read_event_unbuffered().unwrap();
+            assert_eq!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::Text(BytesText::from_escaped_str("x"))
+            );
+            let state = reader.state(&[]);
+
+            let mut reader = Reader::from_str(&xml[state.input_position()..]);
+            reader.trim_text(true).coalesce_text(true);
+            reader.resume(state, &mut Vec::new());
+            assert_eq!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::Comment(BytesText::from_escaped_str("c"))
+            );
+            assert_eq!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::Text(BytesText::from_escaped_str("y"))
+            );
+            assert_eq!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::End(BytesEnd::borrowed(b"a"))
+            );
+        }
+    }
+
+    mod path {
+        use crate::events::Event;
+        use crate::reader::Reader;
+        use pretty_assertions::assert_eq;
+
+        /// Reads all events and returns the path after each of them
+        fn paths(reader: &mut Reader<&[u8]>) -> Vec<String> {
+            let mut paths = Vec::new();
+            loop {
+                let event = reader.read_event_unbuffered().unwrap();
+                assert_eq!(reader.depth(), reader.opened_names().count());
+                if let Event::Eof = event {
+                    break;
+                }
+                paths.push(reader.path().to_string());
+            }
+            paths
+        }
+
+        #[test]
+        fn simple() {
+            let mut reader = Reader::from_str("<root><a><b/></a><c></c></root>");
+            reader.trim_text(true);
+            assert_eq!(
+                paths(&mut reader),
+                vec!["/root", "/root/a", "/root/a", "/root", "/root/c", "/root", "/"]
+            );
+        }
+
+        #[test]
+        fn expand_empty_elements() {
+            let mut reader = Reader::from_str("<root><a><b/></a></root>");
+            reader.trim_text(true).expand_empty_elements(true);
+            assert_eq!(
+                paths(&mut reader),
+                vec!["/root", "/root/a", "/root/a/b", "/root/a", "/root", "/"]
+            );
+        }
+
+        /// Each end tag closes the innermost element, even if names do not match
+        #[test]
+        fn unchecked_end_names() {
+            let mut reader = Reader::from_str("<root><a></b><c/></root>");
+            reader.trim_text(true).check_end_names(false);
+            assert_eq!(
+                paths(&mut reader),
+                vec!["/root", "/root/a", "/root", "/root", "/"]
+            );
+        }
+
+        /// End tags without opened elements are ignored
+        #[test]
+        fn unmatched_end() {
+            let mut reader = Reader::from_str("</a><root/>");
+            reader.trim_text(true).check_end_names(false);
+            assert_eq!(paths(&mut reader), vec!["/", "/"]);
+        }
+
+        #[test]
+        fn html() {
+            let mut reader = Reader::from_str("<ul><li>a<li>b</ul>");
+            reader.trim_text(true).html(true);
+            assert_eq!(
+                paths(&mut reader),
+                vec!["/ul", "/ul/li", "/ul/li", "/ul", "/ul/li", "/ul/li", "/ul", "/"]
+            );
+        }
+    }
+
+    mod strict {
+        use crate::errors::{Error, Result};
+        use crate::events::attributes::AttrError;
+        use crate::reader::Reader;
+        use pretty_assertions::assert_eq;
+
+        /// Reads all events in the strict mode with both buffered and borrowed
+        /// readers and returns the result of the borrowed reader
+        fn read(xml: &str) -> Result<usize> {
+            let mut reader = Reader::from_str(xml);
+            reader.strict(true);
+            let mut buffered = Reader::from_reader(xml.as_bytes());
+            buffered.strict(true);
+
+            let mut count = 0;
+            let mut buf = Vec::new();
+            loop {
+                let event = reader.read_event_unbuffered();
+                let buffered_event = buffered.read_event(&mut buf);
+                assert_eq!(
+                    buffered_event.as_ref().map_err(|e| e.to_string()),
+                    event.as_ref().map_err(|e| e.to_string())
+                );
+                match event? {
+                    crate::events::Event::Eof => break,
+                    _ => count += 1,
+                }
+                buf.clear();
+            }
+            Ok(count)
+        }
+
+        #[test]
+        fn well_formed() {
+            let xml = "\u{FEFF}<?xml version='1.0'?>\n\
+                <!DOCTYPE root>\n\
+                <!-- comment -->\n\
+                <?pi content?>\n\
+                <root attr='&amp;&#x20;'>\n\
+                    <имя>&lt;text&#60;</имя>\n\
+                    <![CDATA[<>]]>\n\
+                    <empty/>\n\
+                </root>\n\
+                <!-- comment -->\n";
+            assert!(read(xml).is_ok());
+        }
+
+        #[test]
+        fn declaration() {
+            assert_err!(read(" <?xml version='1.0'?><root/>"), Error::MisplacedDeclaration(span) if span == (1..22));
+            assert_err!(
+                read("<!-- --><?xml version='1.0'?><root/>"),
+                Error::MisplacedDeclaration(..)
+            );
+            assert_err!(
+                read("<?xml version='1.0'?><?xml version='1.0'?><root/>"),
+                Error::MisplacedDeclaration(..)
+            );
+            assert_err!(read("<?xml?><root/>"), Error::InvalidName(name, _) if name == "xml");
+            assert_err!(read("<?XmL content?><root/>"), Error::InvalidName(..));
+        }
+
+        #[test]
+        fn doctype() {
+            assert_err!(read("<root/><!DOCTYPE root>"), Error::MisplacedDeclaration(span) if span == (7..22));
+            assert_err!(
+                read("<!DOCTYPE root><!DOCTYPE root><root/>"),
+                Error::MisplacedDeclaration(..)
+            );
+        }
+
+        #[test]
+        fn root() {
+            assert_err!(read(""), Error::MissingRoot(span) if span == (0..0));
+            assert_err!(read("<!-- comment -->"), Error::MissingRoot(span) if span == (16..16));
+            assert_err!(read("<root/><root/>"), Error::MultipleRoots(span) if span == (7..14));
+            assert_err!(read("<root></root><root>"), Error::MultipleRoots(..));
+            assert_err!(read("<root>"), Error::UnexpectedEof(..));
+        }
+
+        #[test]
+        fn outside_root() {
+            assert_err!(read("text<root/>"), Error::ContentOutsideRoot(span) if span == (0..4));
+            assert_err!(read("<root/> text "), Error::ContentOutsideRoot(span) if span == (8..12));
+            assert_err!(read("<root/><![CDATA[]]>"), Error::ContentOutsideRoot(span) if span == (7..19));
+        }
+
+        #[test]
+        fn names() {
+            assert_err!(read("<1root/>"), Error::InvalidName(name, span) if name == "1root" && span == (1..6));
+            assert_err!(read("<root 1a='1'/>"), Error::InvalidName(name, span) if name == "1a" && span == (6..8));
+            assert_err!(read("<root>&1a;</root>"), Error::InvalidName(..));
+            assert_err!(read("<?1pi?><root/>"), Error::InvalidName(..));
+        }
+
+        #[test]
+        fn chars() {
+            assert_err!(read("<root>&#1;</root>"), Error::InvalidChar(1, 6));
+            assert_err!(read("<root>\u{1}</root>"), Error::InvalidChar(1, 6));
+            assert_err!(read("<root a='&#0;'/>"), Error::InvalidChar(0, 9));
+            assert_err!(read("<root><!--\u{2}--></root>"), Error::InvalidChar(2, 10));
+            assert_err!(
+                read("<root><![CDATA[\u{FFFF}]]></root>"),
+                Error::InvalidChar(0xFFFF, 15)
+            );
+
+            // Surrogates and byte sequences that are not UTF-8
+            let read_bytes = |xml: &[u8]| {
+                let mut reader = Reader::from_reader(xml);
+                reader.strict(true);
+                loop {
+                    match reader.read_event(&mut Vec::new()) {
+                        Ok(crate::events::Event::Eof) => return Ok(()),
+                        Ok(_) => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+            };
+            assert_err!(
+                read_bytes(b"<root>\xED\xA0\x80</root>"),
+                Error::InvalidChar(0xD800, 6)
+            );
+            assert_err!(
+                read_bytes(b"<root a='\xFF'/>"),
+                Error::UnexpectedToken(t, span) if t == "\\xFF" && span == (9..10)
+            );
+        }
+
+        #[test]
+        fn tokens() {
+            assert_err!(read("<root>a & b</root>"), Error::UnexpectedToken(t, _) if t == "&");
+            assert_err!(read("<root>]]></root>"), Error::UnexpectedToken(t, _) if t == "]]>");
+            assert_err!(read("<root a='<'/>"), Error::UnexpectedToken(t, _) if t == "<");
+            assert_err!(read("<root><!-- -- --></root>"), Error::UnexpectedToken(t, _) if t == "--");
+        }
+
+        #[test]
+        fn attributes() {
+            assert_err!(
+                read("<root a='1' a='2'/>"),
+                Error::InvalidAttr(AttrError::Duplicated(12, 6))
+            );
+            assert_err!(
+                read("<root a=1/>"),
+                Error::InvalidAttr(AttrError::UnquotedValue(8))
+            );
+        }
+
+        /// End names are checked even if `check_end_names` is disabled
+        #[test]
+        fn end_names() {
+            let mut reader = Reader::from_str("<root></other>");
+            reader.strict(true).check_end_names(false).trim_text(true);
+            reader.read_event_unbuffered().unwrap();
+            match reader.read_event_unbuffered() {
+                Err(Error::EndEventMismatch { .. }) => {}
+                x => panic!("Expected `Error::EndEventMismatch`, but got {:?}", x),
+            }
+        }
+    }
+
+    mod entities {
+        use crate::errors::Error;
+        use crate::escape::EscapeError;
+        use crate::events::Event;
+        use crate::reader::Reader;
+        use pretty_assertions::assert_eq;
+
+        const XML: &str = r#"<!DOCTYPE root [
+            <!ENTITY % param "parameter">
+            <!ENTITY ext SYSTEM "ext.xml">
+            <!ENTITY name "World">
+            <!ENTITY greeting "Hello, &name;&#33;">
+        ]><root>&greeting;</root>"#;
+
+        /// Reads events until the text and returns its unescaped content
+        fn text(reader: &mut Reader<&[u8]>) -> Result<String, Error> {
+            loop {
+                match reader.read_event_unbuffered()? {
+                    Event::Text(e) if !e.is_empty() => return e.unescape_and_decode(reader),
+                    Event::Eof => panic!("Text not found"),
+                    _ => {}
+                }
+            }
+        }
+
+        #[test]
+        fn expanded() {
+            let mut reader = Reader::from_str(XML);
+            assert_eq!(text(&mut reader).unwrap(), "Hello, World!");
+
+            let mut entities: Vec<_> = reader.entities().iter().collect();
+            entities.sort();
+            assert_eq!(
+                entities,
+                vec![
+                    (&b"greeting".to_vec(), &b"Hello, World!".to_vec()),
+                    (&b"name".to_vec(), &b"World".to_vec()),
+                ]
+            );
+        }
+
+        /// Custom entities override the declared entities with the same names
+        #[test]
+        fn custom() {
+            let xml = r#"<!DOCTYPE root [
+                <!ENTITY name "World">
+                <!ENTITY greeting "Hello">
+            ]><root a="&greeting;, &name;!">&greeting;, &name;!</root>"#;
+            let custom = vec![(b"name".to_vec(), b"Custom".to_vec())]
+                .into_iter()
+                .collect();
+            let mut reader = Reader::from_str(xml);
+            reader.trim_text(true);
+            loop {
+                match reader.read_event_unbuffered().unwrap() {
+                    Event::Start(e) => {
+                        let a = e.try_get_attribute("a").unwrap().unwrap();
+                        assert_eq!(
+                            a.unescape_and_decode_value_with_custom_entities(&reader, &custom)
+                                .unwrap(),
+                            "Hello, Custom!"
+                        );
+                    }
+                    Event::Text(e) => {
+                        assert_eq!(
+                            e.unescape_and_decode_with_custom_entities(&reader, &custom)
+                                .unwrap(),
+                            "Hello, Custom!"
+                        );
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        #[test]
+        fn disabled() {
+            let mut reader = Reader::from_str(XML);
+            reader.expand_entities(false);
+            match text(&mut reader) {
+                Err(Error::EscapeError(EscapeError::UnrecognizedSymbol(..))) => {}
+                x => panic!("Expected `UnrecognizedSymbol`, but got {:?}", x),
+            }
+            assert!(reader.entities().is_empty());
+        }
+
+        #[test]
+        fn recursive() {
+            let xml = r#"<!DOCTYPE root [<!ENTITY a "&b;"><!ENTITY b "&a;">]><root>&a;</root>"#;
+            let mut reader = Reader::from_str(xml);
+            reader.trim_text(true).strict(true);
+            match reader.read_event_unbuffered() {
+                Err(Error::EscapeError(EscapeError::RecursiveEntity(name))) => {
+                    assert_eq!(name, "a")
+                }
+                x => panic!("Expected `RecursiveEntity`, but got {:?}", x),
+            }
+
+            // Outside of the strict mode the entities are skipped
+            let mut reader = Reader::from_str(xml);
+            reader.trim_text(true);
+            assert!(matches!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::DocType(_)
+            ));
+            assert!(reader.entities().is_empty());
+            match text(&mut reader) {
+                Err(Error::EscapeError(EscapeError::UnrecognizedSymbol(..))) => {}
+                x => panic!("Expected `UnrecognizedSymbol`, but got {:?}", x),
+            }
+        }
+
+        /// Invalid entities and declarations are skipped outside of the strict
+        /// mode, and are errors only if they are referenced
+        #[test]
+        fn invalid() {
+            let mut reader = Reader::from_str("<!DOCTYPE r [<!ENTITY a '&b;'>]><r/>");
+            reader.trim_text(true);
+            assert!(matches!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::DocType(_)
+            ));
+            assert!(matches!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::Empty(_)
+            ));
+
+            let mut reader = Reader::from_str(r#"<!DOCTYPE r [<!ENTITY a "x" junk>]><r/>"#);
+            reader.trim_text(true);
+            assert!(matches!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::DocType(_)
+            ));
+            assert!(matches!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::Empty(_)
+            ));
+
+            let xml = r#"<!DOCTYPE r [<!ENTITY a "x" junk><!ENTITY b "y">]><r>&b;</r>"#;
+            let mut reader = Reader::from_str(xml);
+            assert_eq!(text(&mut reader).unwrap(), "y");
+
+            let mut reader = Reader::from_str(r#"<!DOCTYPE r [<!ENTITY a "x" junk>]><r/>"#);
+            reader.trim_text(true).strict(true);
+            match reader.read_event_unbuffered() {
+                Err(Error::InvalidDtd(_)) => {}
+                x => panic!("Expected `InvalidDtd`, but got {:?}", x),
+            }
+        }
+
+        /// Long chains of entity references are stopped at the depth limit
+        #[test]
+        fn long_chain() {
+            let mut xml = "<!DOCTYPE r [".to_string();
+            for i in (1..1000).rev() {
+                xml.push_str(&format!("<!ENTITY e{} '&e{};'>", i, i - 1));
+            }
+            xml.push_str("<!ENTITY e0 'x'>]><r/>");
+            let mut reader = Reader::from_str(&xml);
+            reader.trim_text(true);
+            match reader.read_event_unbuffered() {
+                Err(Error::EscapeError(EscapeError::ExpansionLimitExceeded(_))) => {}
+                x => panic!("Expected `ExpansionLimitExceeded`, but got {:?}", x),
+            }
+        }
+
+        /// Deeply nested content models do not overflow the stack
+        #[test]
+        fn nested_groups() {
+            let xml = format!(
+                "<!DOCTYPE r [<!ELEMENT r {}a{}>]><r/>",
+                "(".repeat(100_000),
+                ")".repeat(100_000)
+            );
+            let mut reader = Reader::from_str(&xml);
+            reader.trim_text(true);
+            assert!(matches!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::DocType(_)
+            ));
+            assert!(matches!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::Empty(_)
+            ));
+
+            let mut reader = Reader::from_str(&xml);
+            reader.trim_text(true).strict(true);
+            match reader.read_event_unbuffered() {
+                Err(Error::InvalidDtd(crate::dtd::DtdError::TooDeep(_))) => {}
+                x => panic!("Expected `InvalidDtd(TooDeep(_))`, but got {:?}", x),
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Normalization of line endings and attribute values, performed by the [`Reader`]
+//! when the [normalization] is enabled.
+//!
+//! The XML specification requires [line endings] `\r\n` and `\r` to be replaced
+//! by `\n` in the whole document and [attribute values] to be normalized: each
+//! whitespace character is replaced by a space, and for attributes which type
+//! declared in the DTD is not `CDATA`, leading and trailing spaces are removed
+//! and sequences of spaces are replaced by a single space.
+//!
+//! The content of events is normalized before unescaping, so characters
+//! written as character references, like `&#13;` or `&#10;`, are left intact,
+//! as the specification requires.
+//!
+//! [`Reader`]: super::Reader
+//! [normalization]: super::Reader::normalize
+//! [line endings]: https://www.w3.org/TR/xml/#sec-line-ends
+//! [attribute values]: https://www.w3.org/TR/xml/#AVNormalize
+
+use crate::events::attributes::Moves;
+use crate::reader::is_whitespace;
+
+/// Returns the content with `\r\n` and `\r` replaced by `\n`, or `None` if
+/// the content does not contain `\r`
+pub(crate) fn line_endings(bytes: &[u8]) -> Option<Vec<u8>> {
+    let first = memchr::memchr(b'\r', bytes)?;
+    let mut normalized = Vec::with_capacity(bytes.len());
+    normalized.extend_from_slice(&bytes[..first]);
+    let mut rest = &bytes[first..];
+    while let Some(cr) = memchr::memchr(b'\r', rest) {
+        normalized.extend_from_slice(&rest[..cr]);
+        normalized.push(b'\n');
+        rest = &rest[cr + 1..];
+        if rest.first() == Some(&b'\n') {
+            rest = &rest[1..];
+        }
+    }
+    normalized.extend_from_slice(rest);
+    Some(normalized)
+}
+
+/// Removes leading and trailing whitespaces
+fn trim(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| !is_whitespace(*b));
+    let end = bytes.iter().rposition(|b| !is_whitespace(*b));
+    match (start, end) {
+        (Some(start), Some(end)) => &bytes[start..=end],
+        _ => &bytes[..0],
+    }
+}
+
+/// Returns the name of the attribute, which value starts after `prefix`,
+/// if `prefix` ends with `name =`
+fn attribute_name(prefix: &[u8]) -> &[u8] {
+    let prefix = trim(prefix);
+    trim(prefix.strip_suffix(b"=").unwrap_or(prefix))
+}
+
+/// Normalizes the content of a start tag: line endings outside of attribute
+/// values and whitespaces in quoted attribute values. Values of attributes
+/// from `tokenized` are also collapsed.
+///
+/// `moves` are the positions of bytes of the content in the document. Returns
+/// `None` if the content does not change, otherwise the normalized content and
+/// positions of its bytes in the document.
+pub(crate) fn start(
+    content: &[u8],
+    name_len: usize,
+    tokenized: &[Vec<u8>],
+    moves: &Moves,
+) -> Option<(Vec<u8>, Moves)> {
+    let changes_whitespace = content[name_len..]
+        .iter()
+        .any(|b| matches!(b, b'\t' | b'\r' | b'\n'));
+    if !changes_whitespace && tokenized.is_empty() {
+        return None;
+    }
+
+    let mut normalized = Vec::with_capacity(content.len());
+    normalized.extend_from_slice(&content[..name_len]);
+    let mut normalized_moves = moves.clone();
+    // Start of the bytes after the previous attribute value
+    let mut attribute_start = name_len;
+    // The opening quote, whether the value is collapsed and its start in `normalized`
+    let mut value = None;
+    let mut i = name_len;
+    while i < content.len() {
+        let byte = content[i];
+        // The byte pushed at this iteration, if any, comes from `i`
+        normalized_moves.record(normalized.len(), moves.source(i));
+        match value {
+            None => match byte {
+                b'\r' => {
+                    normalized.push(b'\n');
+                    if content.get(i + 1) == Some(&b'\n') {
+                        i += 1;
+                    }
+                }
+                b'"' | b'\'' => {
+                    let name = attribute_name(&content[attribute_start..i]);
+                    let collapse = tokenized.iter().any(|n| n.as_slice() == name);
+                    normalized.push(byte);
+                    value = Some((byte, collapse, normalized.len()));
+                }
+                _ => normalized.push(byte),
+            },
+            Some((quote, collapse, value_start)) if byte == quote => {
+                if collapse {
+                    while normalized.len() > value_start && normalized.last() == Some(&b' ') {
+                        normalized.pop();
+                    }
+                    normalized_moves.record(normalized.len(), moves.source(i));
+                }
+                normalized.push(byte);
+                attribute_start = i + 1;
+                value = None;
+            }
+            Some((_, collapse, value_start)) if is_whitespace(byte) => {
+                if byte == b'\r' && content.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+                let skip = collapse
+                    && (normalized.len() == value_start || normalized.last() == Some(&b' '));
+                if !skip {
+                    normalized.push(b' ');
+                }
+            }
+            Some(_) => normalized.push(byte),
+        }
+        i += 1;
+    }
+
+    if normalized == content {
+        None
+    } else {
+        Some((normalized, normalized_moves))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn normalize(content: &str, tokenized: &[&str]) -> String {
+        let tokenized: Vec<_> = tokenized.iter().map(|n| n.as_bytes().to_vec()).collect();
+        let name_len = content
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(content.len());
+        match start(content.as_bytes(), name_len, &tokenized, &Moves::default()) {
+            Some((normalized, _)) => String::from_utf8(normalized).unwrap(),
+            None => content.to_string(),
+        }
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(line_endings(b"text"), None);
+        assert_eq!(
+            line_endings(b"\r\na\rb\r\r\nc\n\r").unwrap(),
+            b"\na\nb\n\nc\n\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn cdata_values() {
+        assert_eq!(normalize("tag a='x'", &[]), "tag a='x'");
+        assert_eq!(
+            normalize("tag\r\na=\"\tx\r\n y\r\" b='\n'", &[]),
+            "tag\na=\" x  y \" b=' '"
+        );
+        // Quotes of the other kind do not end the value
+        assert_eq!(normalize("tag a=\"'\n'\"", &[]), "tag a=\"' '\"");
+    }
+
+    #[test]
+    fn tokenized_values() {
+        assert_eq!(
+            normalize("tag id = ' \r\n x\t\ty ' a=' x  y '", &["id"]),
+            "tag id = 'x y' a=' x  y '"
+        );
+        assert_eq!(normalize("tag ids=\"   \"", &["ids"]), "tag ids=\"\"");
+        // Prefixed names are compared as written
+        assert_eq!(normalize("tag p:id=' x '", &["id"]), "tag p:id=' x '");
+        assert_eq!(normalize("tag p:id=' x '", &["p:id"]), "tag p:id='x'");
+    }
+}
@@ -0,0 +1,391 @@
+//! A reader that resolves namespaces of elements and attributes.
+//!
+//! [`NsReader`] owns the buffer with namespace bindings that are in scope, so
+//! the namespace of each element is resolved without any help from the caller.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::iter::FusedIterator;
+use std::ops::Deref;
+use std::path::Path;
+
+use crate::errors::Result;
+use crate::escapei::ExpansionLimits;
+use crate::events::attributes::{AttrError, Attribute, Attributes};
+use crate::events::Event;
+use crate::name::{LocalName, NamespaceBindingsIter, NamespaceResolver, QName, ResolveResult};
+use crate::reader::{Reader, ReaderLimits};
+
+/// Generates methods of [`NsReader`] that change the configuration of the
+/// inner [`Reader`]
+macro_rules! configure {
+    ($($(#[$meta:meta])* $name:ident($ty:ty);)*) => {$(
+        $(#[$meta])*
+        #[inline]
+        pub fn $name(&mut self, val: $ty) -> &mut NsReader<R> {
+            self.reader.$name(val);
+            self
+        }
+    )*};
+}
+
+/// A low level encoding-agnostic XML event reader that resolves namespaces.
+///
+/// Unlike [`Reader::read_namespaced_event()`] the reader owns a buffer with
+/// namespace bindings that are in scope, so only a buffer for events should be
+/// provided. Configuration and the state of the inner [`Reader`] are available
+/// through [`Deref`].
+///
+/// # Examples
+///
+/// ```
+/// # use pretty_assertions::assert_eq;
+/// use quick_xml::events::Event;
+/// use quick_xml::name::{Namespace, ResolveResult::*};
+/// use quick_xml::NsReader;
+///
+/// let xml = r#"<feed xmlns="http://www.w3.org/2005/Atom" xmlns:x="urn:x">
+///                 <entry x:id="1"><title>First</title></entry>
+///              </feed>"#;
+/// let mut reader = NsReader::from_str(xml);
+/// reader.trim_text(true);
+/// let mut buf = Vec::new();
+/// let mut names = Vec::new();
+/// loop {
+///     match reader.read_resolved_event(&mut buf).unwrap() {
+///         (Bound(Namespace(b"http://www.w3.org/2005/Atom")), Event::Start(e)) => {
+///             names.push(e.local_name().as_ref().to_vec());
+///         }
+///         (_, Event::Eof) => break,
+///         _ => (),
+///     }
+///     buf.clear();
+/// }
+/// assert_eq!(names, vec![b"feed".to_vec(), b"entry".to_vec(), b"title".to_vec()]);
+/// ```
+#[derive(Clone)]
+pub struct NsReader<R> {
+    /// The reader of events
+    reader: Reader<R>,
+    /// A buffer with prefixes and namespace names of the bindings that are in scope
+    buffer: Vec<u8>,
+}
+
+impl<R> NsReader<R> {
+    /// Creates a `NsReader` that reads from a reader implementing `BufRead`.
+    pub fn from_reader(reader: R) -> NsReader<R> {
+        NsReader::from(Reader::from_reader(reader))
+    }
+
+    configure! {
+        /// Changes whether empty elements should be split into an `Open` and a `Close` event.
+        /// See [`Reader::expand_empty_elements()`].
+        expand_empty_elements(bool);
+        /// Changes whether whitespace before and after character data should be removed.
+        /// See [`Reader::trim_text()`].
+        trim_text(bool);
+        /// Changes whether whitespace after character data should be removed.
+        /// See [`Reader::trim_text_end()`].
+        trim_text_end(bool);
+        /// Changes whether trailing whitespaces after the markup name are trimmed in closing tags.
+        /// See [`Reader::trim_markup_names_in_closing_tags()`].
+        trim_markup_names_in_closing_tags(bool);
+        /// Changes whether mismatched closing tag names should be detected.
+        /// See [`Reader::check_end_names()`].
+        check_end_names(bool);
+        /// Changes whether comments should be validated.
+        /// See [`Reader::check_comments()`].
+        check_comments(bool);
+        /// Changes whether line and column numbers of events are tracked.
+        /// See [`Reader::track_position()`].
+        track_position(bool);
+        /// Changes whether malformed markup is recovered from.
+        /// See [`Reader::recover()`].
+        recover(bool);
+        /// Changes whether the document is checked for well-formedness.
+        /// See [`Reader::strict()`].
+        strict(bool);
+        /// Changes whether the document is read by the parsing rules of HTML.
+        /// See [`Reader::html()`].
+        html(bool);
+        /// Changes whether entities declared in the DTD are expanded in text.
+        /// See [`Reader::expand_entities()`].
+        expand_entities(bool);
+        /// Changes limits of the expansion of entities.
+        /// See [`Reader::expansion_limits()`].
+        expansion_limits(ExpansionLimits);
+        /// Changes whether line endings and attribute values are normalized.
+        /// See [`Reader::normalize()`].
+        normalize(bool);
+        /// Changes whether consecutive `Text` and `CData` events are merged.
+        /// See [`Reader::coalesce_text()`].
+        coalesce_text(bool);
+        /// Changes whether comments and processing instructions inside merged text are skipped.
+        /// See [`Reader::skip_comments_in_text()`].
+        skip_comments_in_text(bool);
+        /// Changes limits of resources used by the reader.
+        /// See [`Reader::limits()`].
+        limits(ReaderLimits);
+    }
+
+    /// Resolves a potentially qualified **element name** into (namespace name, local name)
+    /// using the namespace bindings in scope of the last read event.
+    ///
+    /// *Unqualified* element names inherit the current *default namespace*.
+    #[inline]
+    pub fn resolve_element<'n>(&self, name: QName<'n>) -> (ResolveResult<'_>, LocalName<'n>) {
+        self.reader.event_namespace(name, &self.buffer)
+    }
+
+    /// Resolves a potentially qualified **attribute name** into (namespace name, local name)
+    /// using the namespace bindings in scope of the last read event.
+    ///
+    /// *Unqualified* attribute names do *not* inherit the current *default namespace*.
+    #[inline]
+    pub fn resolve_attribute<'n>(&self, name: QName<'n>) -> (ResolveResult<'_>, LocalName<'n>) {
+        self.reader.attribute_namespace(name, &self.buffer)
+    }
+
+    /// Returns an iterator over all namespace bindings in scope of the last read
+    /// event. Bindings declared on an `Empty` element are in scope of that element.
+    ///
+    /// The bindings can be used to resolve qualified names in the content,
+    /// for example, in `xsi:type="ns:Type"` attributes, or to write them out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::name::{Namespace, PrefixDeclaration};
+    /// use quick_xml::NsReader;
+    ///
+    /// let mut reader = NsReader::from_str(r#"<a xmlns="urn:a"><b xmlns:x="urn:x"/></a>"#);
+    /// reader.trim_text(true);
+    /// reader.read_event_unbuffered().unwrap();
+    /// reader.read_event_unbuffered().unwrap();
+    /// assert_eq!(
+    ///     reader.namespaces().collect::<Vec<_>>(),
+    ///     vec![
+    ///         (PrefixDeclaration::Default, Namespace(b"urn:a")),
+    ///         (PrefixDeclaration::Named(b"x"), Namespace(b"urn:x")),
+    ///     ]
+    /// );
+    /// ```
+    #[inline]
+    pub fn namespaces(&self) -> NamespaceBindingsIter<'_> {
+        self.reader.namespaces(&self.buffer)
+    }
+
+    /// Returns the resolver of namespaces, which is driven by this reader, and
+    /// the buffer with its bindings.
+    #[inline]
+    pub fn resolver(&self) -> (&NamespaceResolver, &[u8]) {
+        (&self.reader.ns_resolver, &self.buffer)
+    }
+
+    /// Returns an iterator over attributes together with namespaces of their
+    /// names, resolved using the namespace bindings in scope of the last read
+    /// event. The attributes usually are the attributes of that event.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::name::{Namespace, ResolveResult::*};
+    /// use quick_xml::NsReader;
+    ///
+    /// let mut reader = NsReader::from_str(r#"<a xmlns:x="urn:x" x:one="1" two="2"/>"#);
+    /// reader.trim_text(true);
+    /// match reader.read_event_unbuffered().unwrap() {
+    ///     Event::Empty(e) => {
+    ///         let attrs: Vec<_> = reader
+    ///             .resolve_attributes(e.attributes())
+    ///             .map(|a| {
+    ///                 let (ns, a) = a.unwrap();
+    ///                 (ns, a.key.local_name().into_inner(), a.value.into_owned())
+    ///             })
+    ///             .collect();
+    ///         assert_eq!(
+    ///             attrs,
+    ///             vec![
+    ///                 (Unknown(b"xmlns".to_vec()), &b"x"[..], b"urn:x".to_vec()),
+    ///                 (Bound(Namespace(b"urn:x")), &b"one"[..], b"1".to_vec()),
+    ///                 (Unbound, &b"two"[..], b"2".to_vec()),
+    ///             ]
+    ///         );
+    ///     }
+    ///     e => panic!("Expected Empty event, got {:?}", e),
+    /// }
+    /// ```
+    #[inline]
+    pub fn resolve_attributes<'a>(
+        &self,
+        attributes: Attributes<'a>,
+    ) -> ResolvedAttributes<'a, '_, R> {
+        ResolvedAttributes {
+            reader: self,
+            attributes,
+        }
+    }
+
+    /// Consumes `NsReader` returning the underlying reader
+    pub fn into_inner(self) -> Reader<R> {
+        self.reader
+    }
+}
+
+impl<R: BufRead> NsReader<R> {
+    /// Reads the next event and updates the namespace bindings that are in scope.
+    ///
+    /// Namespaces of names in the event can be resolved with [`Self::resolve_element()`]
+    /// and [`Self::resolve_attribute()`] until the next event is read.
+    #[inline]
+    pub fn read_event<'b>(&mut self, buf: &'b mut Vec<u8>) -> Result<Event<'b>> {
+        self.read_resolved_event(buf).map(|(_, event)| event)
+    }
+
+    /// Reads the next event together with the namespace of the element name of
+    /// `Start`, `Empty` and `End` events. For other events the namespace is
+    /// [`ResolveResult::Unbound`].
+    #[inline]
+    pub fn read_resolved_event<'b>(
+        &mut self,
+        buf: &'b mut Vec<u8>,
+    ) -> Result<(ResolveResult<'_>, Event<'b>)> {
+        self.reader.pop_namespaces(&mut self.buffer);
+        let event = self.reader.read_event(buf);
+        self.reader.resolve_event(event, &mut self.buffer)
+    }
+
+    /// Reads until end element is found, skipping all nested elements.
+    ///
+    /// Namespace bindings of the skipped elements are ended, so after that
+    /// the reader is in scope of the parent of the `end` element.
+    pub fn read_to_end<K: AsRef<[u8]>>(&mut self, end: K, buf: &mut Vec<u8>) -> Result<()> {
+        self.reader.read_to_end(end, buf)?;
+        // The End event of the element was read, so its namespace scope ends
+        // before the next event as usual
+        self.reader.pending_pop = true;
+        Ok(())
+    }
+
+    /// Reads until end element is found and returns the raw content of the
+    /// element. See [`Reader::read_inner_xml()`].
+    pub fn read_inner_xml<K: AsRef<[u8]>>(&mut self, end: K, buf: &mut Vec<u8>) -> Result<Vec<u8>> {
+        let inner = self.reader.read_inner_xml(end, buf)?;
+        self.reader.pending_pop = true;
+        Ok(inner)
+    }
+
+    /// Reads optional text between start and end tags.
+    /// See [`Reader::read_text()`].
+    #[inline]
+    pub fn read_text<K: AsRef<[u8]>>(&mut self, end: K, buf: &mut Vec<u8>) -> Result<String> {
+        let text = self.reader.read_text(end, buf)?;
+        self.reader.pending_pop = true;
+        Ok(text)
+    }
+}
+
+impl NsReader<BufReader<File>> {
+    /// Creates an XML reader from a file path.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<NsReader<BufReader<File>>> {
+        Reader::from_file(path).map(NsReader::from)
+    }
+}
+
+impl<'a> NsReader<&'a [u8]> {
+    /// Creates an XML reader from a string slice.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &'a str) -> NsReader<&'a [u8]> {
+        NsReader::from_reader(s.as_bytes())
+    }
+
+    /// Creates an XML reader from a slice of bytes.
+    pub fn from_bytes(s: &'a [u8]) -> NsReader<&'a [u8]> {
+        NsReader::from_reader(s)
+    }
+
+    /// Reads an event that borrows from the input rather than a buffer and
+    /// updates the namespace bindings that are in scope.
+    #[inline]
+    pub fn read_event_unbuffered(&mut self) -> Result<Event<'a>> {
+        self.read_resolved_event_unbuffered()
+            .map(|(_, event)| event)
+    }
+
+    /// Reads an event that borrows from the input rather than a buffer together
+    /// with the namespace of the element name. See [`Self::read_resolved_event()`].
+    #[inline]
+    pub fn read_resolved_event_unbuffered(&mut self) -> Result<(ResolveResult<'_>, Event<'a>)> {
+        self.reader.pop_namespaces(&mut self.buffer);
+        let event = self.reader.read_event_unbuffered();
+        self.reader.resolve_event(event, &mut self.buffer)
+    }
+
+    /// Reads until end element is found, skipping all nested elements.
+    /// See [`Self::read_to_end()`].
+    pub fn read_to_end_unbuffered<K: AsRef<[u8]>>(&mut self, end: K) -> Result<()> {
+        self.reader.read_to_end_unbuffered(end)?;
+        self.reader.pending_pop = true;
+        Ok(())
+    }
+
+    /// Reads until end element is found and returns the raw content of the
+    /// element, borrowed from the input. See [`Reader::read_inner_xml_unbuffered()`].
+    pub fn read_inner_xml_unbuffered<K: AsRef<[u8]>>(&mut self, end: K) -> Result<&'a [u8]> {
+        let inner = self.reader.read_inner_xml_unbuffered(end)?;
+        self.reader.pending_pop = true;
+        Ok(inner)
+    }
+}
+
+/// Wraps a `Reader` that has not read any elements yet.
+///
+/// The namespace bindings of elements that were already read with
+/// [`Reader::read_namespaced_event()`] are stored in the buffer provided by
+/// the caller, so they are unknown to the `NsReader`.
+impl<R> From<Reader<R>> for NsReader<R> {
+    fn from(reader: Reader<R>) -> Self {
+        NsReader {
+            reader,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl<R> Deref for NsReader<R> {
+    type Target = Reader<R>;
+
+    fn deref(&self) -> &Reader<R> {
+        &self.reader
+    }
+}
+
+/// Iterator over attributes together with namespaces of their names.
+///
+/// Created by [`NsReader::resolve_attributes()`].
+pub struct ResolvedAttributes<'a, 'r, R> {
+    /// The reader with the namespace bindings
+    reader: &'r NsReader<R>,
+    /// The resolved attributes
+    attributes: Attributes<'a>,
+}
+
+impl<'a, 'r, R> Iterator for ResolvedAttributes<'a, 'r, R> {
+    type Item = std::result::Result<(ResolveResult<'r>, Attribute<'a>), AttrError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let reader = self.reader;
+        self.attributes.next().map(|attr| {
+            attr.map(|a| {
+                let (ns, _) = reader.resolve_attribute(a.key);
+                (ns, a)
+            })
+        })
+    }
+}
+
+impl<'a, 'r, R> FusedIterator for ResolvedAttributes<'a, 'r, R> {}
@@ -0,0 +1,678 @@
+//! Parallel reading of documents which contain a long sequence of sibling
+//! elements (records), like database dumps.
+
+use std::ops::Range;
+use std::sync::Mutex;
+use std::thread;
+
+use crate::errors::{Error, Result};
+use crate::events::Event;
+use crate::reader::{html, scan, BangType, ReadElementState, Reader, TagState};
+
+/// The number of chunks created for each thread, so threads that have read
+/// their chunks faster can take the remaining ones
+const CHUNKS_PER_THREAD: usize = 4;
+
+/// A part of the content of an element which contains only complete child
+/// elements (records) and the text between them, created by
+/// [`Reader::split_records()`].
+///
+/// The reader of the chunk is seeded with the state of the reader at the start
+/// of the chunk: all opened elements, namespace bindings, entities and the
+/// configuration, so records are read as if the whole document was read. The
+/// reader returns [`Event::Eof`] at the end of the chunk.
+///
+/// [`Reader::split_records()`]: crate::Reader::split_records
+/// [`Event::Eof`]: crate::events::Event::Eof
+#[derive(Clone)]
+pub struct RecordChunk<'a> {
+    reader: Reader<&'a [u8]>,
+    namespace_buffer: Vec<u8>,
+}
+
+impl<'a> RecordChunk<'a> {
+    /// Creates a chunk from the reader positioned at the start of the chunk
+    fn new(mut reader: Reader<&'a [u8]>, end: usize, namespace_buffer: &[u8]) -> Self {
+        let len = end - reader.buf_position;
+        reader.reader = &reader.reader[..len];
+        reader.partial = true;
+        Self {
+            reader,
+            namespace_buffer: namespace_buffer.to_vec(),
+        }
+    }
+
+    /// Returns the position of the chunk in the input
+    pub fn range(&self) -> Range<usize> {
+        self.reader.buf_position..self.reader.buf_position + self.reader.reader.len()
+    }
+
+    /// Returns the reader of the chunk and the buffer with namespace bindings of
+    /// the opened elements, which should be used with [`Reader::read_namespaced_event()`]
+    ///
+    /// [`Reader::read_namespaced_event()`]: crate::Reader::read_namespaced_event
+    pub fn into_reader(self) -> (Reader<&'a [u8]>, Vec<u8>) {
+        (self.reader, self.namespace_buffer)
+    }
+}
+
+/// The result of scanning of one markup in the content of the split element
+enum Step {
+    /// The markup ends before the position
+    Next(usize),
+    /// The markup is a start tag, that implicitly closed the previous child
+    /// of the split element, and ends before the position
+    Sibling(usize),
+    /// The markup closes the split element
+    Close,
+    /// The markup is not finished or is not a valid markup
+    Invalid,
+}
+
+/// Finds boundaries of child elements in the content of the split element
+/// without reading events. Only the ends of markup are searched, like the
+/// reader does, and names of opened elements are tracked to follow the rules
+/// of the HTML and recovery modes.
+struct Scanner<'r, 'a> {
+    reader: &'r Reader<&'a [u8]>,
+    /// Names of opened elements, in the same format as in the reader
+    opened_buffer: Vec<u8>,
+    opened_starts: Vec<usize>,
+    /// The number of opened elements, including the split element
+    depth: usize,
+}
+
+impl<'r, 'a> Scanner<'r, 'a> {
+    fn new(reader: &'r Reader<&'a [u8]>) -> Self {
+        Self {
+            reader,
+            opened_buffer: reader.opened_buffer.clone(),
+            opened_starts: reader.opened_starts.clone(),
+            depth: reader.opened_starts.len(),
+        }
+    }
+
+    /// Returns `true` if only the elements opened before the content are opened
+    fn at_top(&self) -> bool {
+        self.opened_starts.len() == self.depth
+    }
+
+    /// Scans the markup, that starts with `<` at the `start` of the input
+    fn markup(&mut self, input: &[u8], start: usize) -> Step {
+        let rest = &input[start + 1..];
+        match rest.first() {
+            Some(b'!') => {
+                let bang_type = match BangType::new(rest.get(1).copied(), 0) {
+                    Ok(bang_type) => bang_type,
+                    Err(_) => return Step::Invalid,
+                };
+                match bang_type.parse(rest, 0) {
+                    Some((_, len)) => Step::Next(start + 1 + len),
+                    None => Step::Invalid,
+                }
+            }
+            Some(b'?') => match memchr::memchr(b'>', rest) {
+                Some(i) => Step::Next(start + i + 2),
+                None => Step::Invalid,
+            },
+            Some(b'/') => match memchr::memchr(b'>', rest) {
+                Some(i) => self.end_tag(&rest[1..i], start + i + 2),
+                None => Step::Invalid,
+            },
+            Some(_) => match ReadElementState::Elem.change(rest) {
+                Some((content, len)) => self.start_tag(input, content, start + 1 + len),
+                None => Step::Invalid,
+            },
+            None => Step::Invalid,
+        }
+    }
+
+    /// Handles a start tag with the `content` between `<` and `>`, which ends
+    /// before `end`
+    fn start_tag(&mut self, input: &[u8], content: &[u8], end: usize) -> Step {
+        let empty = content.last() == Some(&b'/');
+        let content = if empty {
+            &content[..content.len() - 1]
+        } else {
+            content
+        };
+        let name = &content[..scan::find_whitespace(content).unwrap_or(content.len())];
+        let mut sibling = false;
+        let mut end = end;
+        if self.reader.html {
+            if let Some(depth) = html::implied_end(name, &self.opened_buffer, &self.opened_starts) {
+                if depth < self.depth {
+                    return Step::Close;
+                }
+                self.close(depth);
+                sibling = depth == self.depth;
+            }
+            if html::is_raw_text(name) {
+                // The content lasts until the end of input, if the element is not closed
+                end = match html::find_end_tag(&input[end..], name) {
+                    Some(len) => end + len,
+                    None => input.len(),
+                };
+            } else if !empty && !html::is_void(name) {
+                self.open(name);
+            }
+        } else if !empty {
+            self.open(name);
+        }
+        if sibling {
+            Step::Sibling(end)
+        } else {
+            Step::Next(end)
+        }
+    }
+
+    /// Handles an end tag with the `name` after `</`, which ends before `end`
+    fn end_tag(&mut self, name: &[u8], end: usize) -> Step {
+        let name = if self.reader.trim_markup_names_in_closing_tags {
+            match name.iter().rposition(|b| !b.is_ascii_whitespace()) {
+                Some(last) => &name[..=last],
+                None => name,
+            }
+        } else {
+            name
+        };
+        let buffer = &self.opened_buffer;
+        let starts = &self.opened_starts;
+        let opened =
+            |i: usize| &buffer[starts[i]..starts.get(i + 1).copied().unwrap_or(buffer.len())];
+        let reader = self.reader;
+        let found = if reader.html {
+            (0..starts.len()).rposition(|i| opened(i).eq_ignore_ascii_case(name))
+        } else if reader.recover && (reader.check_end_names || reader.strict) {
+            (0..starts.len()).rposition(|i| opened(i) == name)
+        } else {
+            // Names are not compared or a mismatch is an error of the reader,
+            // in both cases the innermost element is closed
+            starts.len().checked_sub(1)
+        };
+        match found {
+            Some(depth) if depth < self.depth => Step::Close,
+            Some(depth) => {
+                self.close(depth);
+                Step::Next(end)
+            }
+            // Unmatched end tags are skipped
+            None => Step::Next(end),
+        }
+    }
+
+    fn open(&mut self, name: &[u8]) {
+        self.opened_starts.push(self.opened_buffer.len());
+        self.opened_buffer.extend_from_slice(name);
+    }
+
+    /// Closes elements until `depth` elements are left opened
+    fn close(&mut self, depth: usize) {
+        self.opened_buffer.truncate(self.opened_starts[depth]);
+        self.opened_starts.truncate(depth);
+    }
+}
+
+impl<'a> Reader<&'a [u8]> {
+    /// Returns a copy of the reader, which skipped `len` bytes of the input
+    /// without reading events
+    fn skipped(&self, len: usize) -> Self {
+        let mut reader = self.clone();
+        reader.tracker.advance(&self.reader[..len]);
+        reader.reader = &self.reader[len..];
+        reader.buf_position += len;
+        reader
+    }
+
+    /// Reads events until an error, which the reader returns for the invalid
+    /// markup at its position
+    fn markup_error(mut self) -> Error {
+        loop {
+            match self.read_event_unbuffered() {
+                Err(e) => return e,
+                Ok(Event::Eof) => {
+                    let end = self.buf_position;
+                    return Error::UnexpectedEof("Element".to_string(), end..end);
+                }
+                Ok(_) => {}
+            }
+        }
+    }
+
+    /// Splits the content of the element, which [`Start`] event was just read,
+    /// into at most `count` chunks of approximately equal size. Chunks are split
+    /// only between child elements, so each of them contains complete records.
+    ///
+    /// Only the markup of the content is scanned to find the boundaries of child
+    /// elements, events are not created and checked until chunks are read. After
+    /// that this reader is positioned after the end of the element. Unfinished
+    /// markup and errors in the end tag of the element are returned and the
+    /// reader is not changed.
+    ///
+    /// `namespace_buffer` is the buffer passed to [`Self::read_namespaced_event()`],
+    /// which is copied into each chunk. Pass an empty slice if namespaces are not
+    /// resolved.
+    ///
+    /// Chunks can be read on different threads, see [`Self::read_records_parallel()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::Reader;
+    ///
+    /// let xml = "<dump><page>1</page><page>2</page><page>3</page></dump>";
+    /// let mut reader = Reader::from_str(xml);
+    /// reader.trim_text(true);
+    /// reader.read_event_unbuffered().unwrap(); // <dump>
+    ///
+    /// let chunks = reader.split_records(2, &[]).unwrap();
+    /// let ranges: Vec<_> = chunks.iter().map(|c| &xml[c.range()]).collect();
+    /// assert_eq!(ranges, vec!["<page>1</page><page>2</page>", "<page>3</page>"]);
+    ///
+    /// let (mut chunk, _) = chunks[1].clone().into_reader();
+    /// assert!(matches!(chunk.read_event_unbuffered().unwrap(), Event::Start(_)));
+    ///
+    /// // The reader is after the end of the split element
+    /// assert_eq!(reader.read_event_unbuffered().unwrap(), Event::Eof);
+    /// ```
+    ///
+    /// [`Start`]: crate::events::Event::Start
+    pub fn split_records(
+        &mut self,
+        count: usize,
+        namespace_buffer: &[u8],
+    ) -> Result<Vec<RecordChunk<'a>>> {
+        let target = self.reader.len() / count.max(1);
+        let input = self.reader;
+        let mut chunks = Vec::new();
+        // The reader at the start of the current chunk
+        let mut start = self.clone();
+        let mut start_offset = 0;
+        // Content of an element, which is expanded or read as a raw text, has
+        // no markup to scan, it is read with events below
+        let end = if let TagState::Closed = self.tag_state {
+            let mut scanner = Scanner::new(self);
+            let mut offset = 0;
+            loop {
+                let markup = match memchr::memchr(b'<', &input[offset..]) {
+                    Some(i) => offset + i,
+                    // Elements are closed at the end of input in the HTML mode
+                    None if self.html => break input.len(),
+                    None => {
+                        let end = self.buf_position + input.len();
+                        return Err(Error::UnexpectedEof("Element".to_string(), end..end));
+                    }
+                };
+                // A position between children of the split element
+                let boundary = match scanner.markup(input, markup) {
+                    Step::Next(end) => {
+                        offset = end;
+                        Some(end).filter(|_| scanner.at_top())
+                    }
+                    Step::Sibling(end) => {
+                        offset = end;
+                        Some(markup)
+                    }
+                    Step::Close => break markup,
+                    Step::Invalid => {
+                        return Err(start.skipped(markup - start_offset).markup_error())
+                    }
+                };
+                match boundary {
+                    Some(boundary)
+                        if boundary - start_offset >= target && chunks.len() + 1 < count =>
+                    {
+                        let next = start.skipped(boundary - start_offset);
+                        let end = next.buf_position;
+                        chunks.push(RecordChunk::new(start, end, namespace_buffer));
+                        start = next;
+                        start_offset = boundary;
+                    }
+                    _ => {}
+                }
+            }
+        } else {
+            0
+        };
+        let mut rest = start.skipped(end - start_offset);
+        let depth = self.opened_starts.len();
+        while rest.opened_starts.len() >= depth {
+            if let Event::Eof = rest.read_event_unbuffered()? {
+                let end = rest.buf_position;
+                return Err(Error::UnexpectedEof("Element".to_string(), end..end));
+            }
+        }
+        if end > start_offset {
+            let end = self.buf_position + end;
+            chunks.push(RecordChunk::new(start, end, namespace_buffer));
+        }
+        *self = rest;
+        Ok(chunks)
+    }
+
+    /// Reads the content of the element, which [`Start`] event was just read,
+    /// on `threads` threads. The content is [split] into chunks of records and
+    /// each chunk is read by `f` with its own reader and namespace buffer.
+    ///
+    /// Returns the results of `f` in the document order, or the first error in
+    /// the document order. After that this reader is positioned after the end
+    /// of the element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::Reader;
+    ///
+    /// let mut xml = String::from("<dump>");
+    /// for i in 0..1000 {
+    ///     xml.push_str(&format!("<page id='{}'/>", i));
+    /// }
+    /// xml.push_str("</dump>");
+    ///
+    /// let mut reader = Reader::from_str(&xml);
+    /// reader.trim_text(true);
+    /// reader.read_event_unbuffered().unwrap(); // <dump>
+    ///
+    /// // Collect identifiers of pages in each chunk
+    /// let chunks = reader
+    ///     .read_records_parallel(4, &[], |reader, _| {
+    ///         let mut ids = Vec::new();
+    ///         loop {
+    ///             match reader.read_event_unbuffered()? {
+    ///                 Event::Empty(e) => {
+    ///                     let id = e.try_get_attribute("id")?.unwrap();
+    ///                     ids.push(String::from_utf8(id.value.to_vec()).unwrap());
+    ///                 }
+    ///                 Event::Eof => break,
+    ///                 _ => {}
+    ///             }
+    ///         }
+    ///         Ok(ids)
+    ///     })
+    ///     .unwrap();
+    ///
+    /// let ids: Vec<String> = chunks.into_iter().flatten().collect();
+    /// assert_eq!(ids.len(), 1000);
+    /// assert_eq!(ids[999], "999");
+    /// ```
+    ///
+    /// [`Start`]: crate::events::Event::Start
+    /// [split]: Self::split_records
+    pub fn read_records_parallel<T, F>(
+        &mut self,
+        threads: usize,
+        namespace_buffer: &[u8],
+        f: F,
+    ) -> Result<Vec<T>>
+    where
+        T: Send,
+        F: Fn(&mut Reader<&'a [u8]>, &mut Vec<u8>) -> Result<T> + Sync,
+    {
+        let threads = threads.max(1);
+        let chunks = self.split_records(threads * CHUNKS_PER_THREAD, namespace_buffer)?;
+        let count = chunks.len();
+        let queue = Mutex::new(chunks.into_iter().enumerate());
+
+        let mut results: Vec<Option<Result<T>>> = (0..count).map(|_| None).collect();
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.min(count))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+                        loop {
+                            // The lock is released before reading of the chunk
+                            let next = queue.lock().unwrap().next();
+                            let (index, chunk) = match next {
+                                Some(next) => next,
+                                None => break,
+                            };
+                            let (mut reader, mut namespace_buffer) = chunk.into_reader();
+                            results.push((index, f(&mut reader, &mut namespace_buffer)));
+                        }
+                        results
+                    })
+                })
+                .collect();
+            for worker in workers {
+                let worker_results = worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+                for (index, result) in worker_results {
+                    results[index] = Some(result);
+                }
+            }
+        });
+        results.into_iter().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Reads all events of each chunk with namespace resolution and formats them
+    /// together with positions
+    fn read_chunks(chunks: Vec<RecordChunk>) -> Vec<String> {
+        let mut events = Vec::new();
+        for chunk in chunks {
+            let (mut reader, mut ns_buffer) = chunk.into_reader();
+            let mut buf = Vec::new();
+            loop {
+                match reader
+                    .read_namespaced_event(&mut buf, &mut ns_buffer)
+                    .unwrap()
+                {
+                    (_, Event::Eof) => break,
+                    (ns, event) => events.push(format!(
+                        "{:?} {:?} {:?}",
+                        ns,
+                        event,
+                        reader.event_position()
+                    )),
+                }
+                buf.clear();
+            }
+        }
+        events
+    }
+
+    const XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE dump [<!ENTITY e "entity">]>
+<dump xmlns="urn:dump" xmlns:p="urn:p">
+    <p:page id="1">first &e;</p:page>
+    <page id="2"><p:title>second</p:title></page>
+    <!-- comment -->
+    <page id="3"/>
+    <page id="4"><![CDATA[</page>]]></page>
+</dump>
+<!-- after -->
+"#;
+
+    /// Creates a reader positioned after the start of the `dump` element
+    fn open(configure: fn(&mut Reader<&[u8]>)) -> (Reader<&'static [u8]>, Vec<u8>) {
+        let mut reader = Reader::from_str(XML);
+        configure(&mut reader);
+        let mut buf = Vec::new();
+        let mut ns_buffer = Vec::new();
+        loop {
+            match reader
+                .read_namespaced_event(&mut buf, &mut ns_buffer)
+                .unwrap()
+            {
+                (_, Event::Start(_)) => break,
+                _ => buf.clear(),
+            }
+        }
+        (reader, ns_buffer)
+    }
+
+    /// Checks that events of chunks are the same as events read without splitting
+    fn check(configure: fn(&mut Reader<&[u8]>)) {
+        let (reader, ns_buffer) = open(configure);
+        let whole = RecordChunk::new(reader.clone(), XML.find("</dump>").unwrap(), &ns_buffer);
+        let expected = read_chunks(vec![whole]);
+
+        for count in 1..=6 {
+            let (mut reader, ns_buffer) = open(configure);
+            let chunks = reader.split_records(count, &ns_buffer).unwrap();
+            assert!(chunks.len() <= count);
+            assert_eq!(read_chunks(chunks), expected, "split into {} chunks", count);
+
+            // The rest of the document is available
+            assert_eq!(
+                reader.read_event_unbuffered().unwrap(),
+                Event::Text(crate::events::BytesText::from_escaped_str("\n"))
+            );
+        }
+    }
+
+    #[test]
+    fn split() {
+        check(|_| {});
+    }
+
+    #[test]
+    fn configured() {
+        check(|r| {
+            r.expand_empty_elements(true)
+                .track_position(true)
+                .strict(true);
+        });
+    }
+
+    #[test]
+    fn ranges() {
+        let (mut reader, ns_buffer) = open(|_| {});
+        let chunks = reader.split_records(3, &ns_buffer).unwrap();
+        let ranges: Vec<_> = chunks.iter().map(|c| &XML[c.range()]).collect();
+        assert_eq!(ranges.concat(), {
+            let start = XML.find("\n    <p:page").unwrap();
+            &XML[start..XML.find("</dump>").unwrap()]
+        });
+    }
+
+    #[test]
+    fn unclosed() {
+        let mut reader = Reader::from_str("<dump><page></page><page>");
+        reader.trim_text(true);
+        reader.read_event_unbuffered().unwrap();
+        match reader.split_records(2, &[]) {
+            Err(Error::UnexpectedEof(_, span)) => assert_eq!(span, 25..25),
+            x => panic!("Expected `UnexpectedEof`, but got {:?}", x.map(|c| c.len())),
+        }
+    }
+
+    /// Records are not parsed into events when the content is split, so problems
+    /// inside them are found only when chunks are read
+    #[test]
+    fn no_events() {
+        let xml = "<dump><a>1 & 2</a><b x='1' x='2'/><c><![CDATA[</dump>]]><!--</c>--></c></dump>";
+        let mut reader = Reader::from_str(xml);
+        reader.trim_text(true).recover(true);
+        reader.read_event_unbuffered().unwrap();
+        let chunks = reader.split_records(10, &[]).unwrap();
+        let ranges: Vec<_> = chunks.iter().map(|c| &xml[c.range()]).collect();
+        assert_eq!(
+            ranges,
+            vec![
+                "<a>1 & 2</a>",
+                "<b x='1' x='2'/>",
+                "<c><![CDATA[</dump>]]><!--</c>--></c>"
+            ]
+        );
+        // Diagnostics of the bare `&` and the duplicated attribute are not reported
+        assert_eq!(reader.diagnostics(), &[]);
+        assert_eq!(reader.read_event_unbuffered().unwrap(), Event::Eof);
+
+        let (mut chunk, _) = chunks[0].clone().into_reader();
+        while chunk.read_event_unbuffered().unwrap() != Event::Eof {}
+        assert_eq!(chunk.diagnostics().len(), 1);
+    }
+
+    /// Elements closed implicitly in the HTML mode are tracked
+    #[test]
+    fn html() {
+        let xml = "<ul><li>one<li><script>'</li>'</script><li><br>three</ul><p>";
+        let mut reader = Reader::from_str(xml);
+        reader.trim_text(true).html(true);
+        reader.read_event_unbuffered().unwrap();
+        let chunks = reader.split_records(10, &[]).unwrap();
+        let ranges: Vec<_> = chunks.iter().map(|c| &xml[c.range()]).collect();
+        assert_eq!(
+            ranges,
+            vec!["<li>one", "<li><script>'</li>'</script>", "<li><br>three"]
+        );
+        assert_eq!(
+            reader.read_event_unbuffered().unwrap(),
+            Event::Start(crate::events::BytesStart::borrowed_name(b"p"))
+        );
+    }
+
+    /// Unfinished markup is reported with the error of the reader
+    #[test]
+    fn unfinished_markup() {
+        let mut reader = Reader::from_str("<dump><page></page><page id='1>");
+        reader.trim_text(true);
+        reader.read_event_unbuffered().unwrap();
+        match reader.split_records(2, &[]) {
+            Err(Error::UnexpectedEof(kind, _)) => assert_eq!(kind, "Element"),
+            x => panic!("Expected `UnexpectedEof`, but got {:?}", x.map(|c| c.len())),
+        }
+        // The reader is not changed
+        assert_eq!(reader.buffer_position(), 6);
+    }
+
+    #[test]
+    fn parallel() {
+        let (mut reader, ns_buffer) = open(|r| {
+            r.trim_text(true);
+        });
+        let counts = reader
+            .read_records_parallel(3, &ns_buffer, |reader, ns_buffer| {
+                let mut buf = Vec::new();
+                let mut ids = Vec::new();
+                loop {
+                    match reader.read_namespaced_event(&mut buf, ns_buffer)? {
+                        (_, Event::Start(e)) | (_, Event::Empty(e)) => {
+                            if let Some(id) = e.try_get_attribute("id")? {
+                                ids.push(id.unescape_and_decode_value(reader)?);
+                            }
+                        }
+                        (_, Event::Eof) => break,
+                        _ => {}
+                    }
+                    buf.clear();
+                }
+                Ok(ids)
+            })
+            .unwrap();
+        assert_eq!(counts.concat(), vec!["1", "2", "3", "4"]);
+    }
+
+    /// The first error in the document order is returned
+    #[test]
+    fn error() {
+        let mut reader = Reader::from_str("<dump><a/><b/><c/><d/></dump>");
+        reader.trim_text(true);
+        reader.read_event_unbuffered().unwrap();
+        let result = reader.read_records_parallel(4, &[], |reader, _| {
+            match reader.read_event_unbuffered()? {
+                Event::Empty(e) if e.name().as_ref() == b"a" => Ok(()),
+                Event::Empty(e) => Err(Error::UnexpectedToken(
+                    String::from_utf8(e.name().as_ref().to_vec()).unwrap(),
+                    0..0,
+                )),
+                e => panic!("Unexpected event {:?}", e),
+            }
+        });
+        match result {
+            Err(Error::UnexpectedToken(name, _)) => assert_eq!(name, "b"),
+            x => panic!("Expected `UnexpectedToken`, but got {:?}", x),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Access to the stack of elements opened by the [`Reader`].
+//!
+//! [`Reader`]: super::Reader
+
+use crate::name::QName;
+use std::fmt::{self, Display, Formatter};
+use std::iter::FusedIterator;
+
+/// Iterator over the names of currently opened elements, from the root to the
+/// innermost one, created by [`Reader::opened_names()`].
+///
+/// [`Reader::opened_names()`]: super::Reader::opened_names
+#[derive(Debug, Clone)]
+pub struct OpenedNames<'a> {
+    /// Buffer with the names of all opened elements
+    buffer: &'a [u8],
+    /// Start indexes of the names that were not returned yet
+    starts: &'a [usize],
+    /// End of the last name that was not returned yet
+    end: usize,
+}
+
+impl<'a> OpenedNames<'a> {
+    pub(crate) fn new(buffer: &'a [u8], starts: &'a [usize]) -> Self {
+        Self {
+            buffer,
+            starts,
+            end: buffer.len(),
+        }
+    }
+}
+
+impl<'a> Iterator for OpenedNames<'a> {
+    type Item = QName<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, rest) = self.starts.split_first()?;
+        self.starts = rest;
+        let end = rest.first().copied().unwrap_or(self.end);
+        Some(QName(&self.buffer[*start..end]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.starts.len(), Some(self.starts.len()))
+    }
+}
+
+impl<'a> DoubleEndedIterator for OpenedNames<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (start, rest) = self.starts.split_last()?;
+        self.starts = rest;
+        let name = QName(&self.buffer[*start..self.end]);
+        self.end = *start;
+        Some(name)
+    }
+}
+
+impl<'a> ExactSizeIterator for OpenedNames<'a> {}
+
+impl<'a> FusedIterator for OpenedNames<'a> {}
+
+/// The path to the innermost opened element, created by [`Reader::path()`].
+///
+/// Displayed as the names of the opened elements, each preceded by `/`, for
+/// example, `/root/a/b`. The path is `/` if no elements are opened. Names that
+/// are not valid UTF-8 are displayed lossy.
+///
+/// [`Reader::path()`]: super::Reader::path
+#[derive(Debug, Clone)]
+pub struct ElementPath<'a>(pub(crate) OpenedNames<'a>);
+
+impl<'a> ElementPath<'a> {
+    /// Returns an iterator over the names of the elements in this path
+    pub fn names(&self) -> OpenedNames<'a> {
+        self.0.clone()
+    }
+}
+
+impl<'a> Display for ElementPath<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.0.len() == 0 {
+            return f.write_str("/");
+        }
+        for name in self.names() {
+            write!(f, "/{}", String::from_utf8_lossy(name.as_ref()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn names() {
+        let names = OpenedNames::new(b"rootap:b", &[0, 4, 5]);
+        assert_eq!(names.len(), 3);
+        assert_eq!(
+            names.clone().collect::<Vec<_>>(),
+            vec![QName(b"root"), QName(b"a"), QName(b"p:b")]
+        );
+        assert_eq!(
+            names.rev().collect::<Vec<_>>(),
+            vec![QName(b"p:b"), QName(b"a"), QName(b"root")]
+        );
+
+        let mut names = OpenedNames::new(b"rootab", &[0, 4, 5]);
+        assert_eq!(names.next(), Some(QName(b"root")));
+        assert_eq!(names.next_back(), Some(QName(b"b")));
+        assert_eq!(names.next(), Some(QName(b"a")));
+        assert_eq!(names.next_back(), None);
+        assert_eq!(names.next(), None);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(ElementPath(OpenedNames::new(b"", &[])).to_string(), "/");
+        assert_eq!(
+            ElementPath(OpenedNames::new(b"rootap:b", &[0, 4, 5])).to_string(),
+            "/root/a/p:b"
+        );
+    }
+}
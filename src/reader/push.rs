@@ -0,0 +1,424 @@
+//! Push-style reading of documents, which are fed to the [`Reader`] in chunks
+//! instead of being pulled from a [`BufRead`].
+//!
+//! [`Reader`]: super::Reader
+
+use std::io::{self, BufRead, Read};
+
+use crate::errors::{Error, Result};
+use crate::events::Event;
+use crate::reader::{PositionTracker, Reader, TagState};
+
+#[cfg(feature = "encoding")]
+use crate::reader::Transcoder;
+
+/// An input of the [`Reader`] that holds the data fed with [`Reader::feed()`],
+/// but not yet read. Use [`Reader::next_event()`] to read events from it.
+///
+/// When all fed data is read, but the end of input is not [signalled], the
+/// buffer returns an I/O error of kind [`WouldBlock`], which is reported by
+/// [`Reader::next_event()`] as a need for more data.
+///
+/// [`Reader`]: crate::Reader
+/// [`Reader::feed()`]: crate::Reader::feed
+/// [`Reader::next_event()`]: crate::Reader::next_event
+/// [signalled]: crate::Reader::finish
+/// [`WouldBlock`]: io::ErrorKind::WouldBlock
+#[derive(Clone, Debug, Default)]
+pub struct PushBuffer {
+    /// Fed data. Data before `consumed` is already read and is removed on
+    /// the next feed
+    data: Vec<u8>,
+    /// Position of the first unread byte in `data`
+    consumed: usize,
+    /// `true` if the end of input was signalled
+    finished: bool,
+    /// End of `data` searched by the last read that lacked data. The read is
+    /// not repeated until data fed after this position can complete an event
+    searched: Option<usize>,
+}
+
+impl PushBuffer {
+    /// Creates an empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Read for PushBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for PushBuffer {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.consumed == self.data.len() && !self.finished {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        Ok(&self.data[self.consumed..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.consumed = (self.consumed + amt).min(self.data.len());
+    }
+}
+
+/// The number of bytes before [`PushBuffer::searched`] that are searched again
+/// when new data is fed. The reader can look ahead of a `<` to check whether
+/// the next markup is a comment or a CDATA section, so a `<` in the last bytes
+/// can complete an event. Covers `<![CDATA[` encoded in UTF-16
+const LOOKBEHIND: usize = 2 * b"<![CDATA[".len();
+
+/// The state of the reader before reading an event, which is restored if the
+/// event was not complete
+struct Snapshot {
+    tag_state: TagState,
+    buf_position: usize,
+    markup_start: usize,
+    tracker: PositionTracker,
+    consumed: usize,
+    diagnostics: usize,
+    #[cfg(feature = "encoding")]
+    transcoder: Transcoder,
+}
+
+/// Returns `true` if the error is caused by the lack of fed data
+fn is_starved(error: &Error) -> bool {
+    match error {
+        Error::Io(e) => e.kind() == io::ErrorKind::WouldBlock,
+        Error::Positioned { error, .. } => is_starved(error),
+        _ => false,
+    }
+}
+
+impl Reader<PushBuffer> {
+    /// Appends a chunk of the document to the data that will be read by
+    /// [`Self::next_event()`]. The document can be split into chunks at any
+    /// position, even inside of a markup or a multi-byte character.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::{BytesStart, BytesText, Event};
+    /// use quick_xml::{PushBuffer, Reader};
+    ///
+    /// let mut reader = Reader::from_reader(PushBuffer::new());
+    /// reader.trim_text(true);
+    /// let mut buf = Vec::new();
+    ///
+    /// reader.feed(b"<tag att");
+    /// // The start tag is not complete yet
+    /// assert_eq!(reader.next_event(&mut buf).unwrap(), None);
+    ///
+    /// reader.feed(b"r='value'>te");
+    /// assert_eq!(
+    ///     reader.next_event(&mut buf).unwrap(),
+    ///     Some(Event::Start(BytesStart::borrowed(b"tag attr='value'", 3)))
+    /// );
+    /// // Text is complete only when the next markup starts
+    /// assert_eq!(reader.next_event(&mut buf).unwrap(), None);
+    ///
+    /// reader.feed(b"xt</tag>");
+    /// reader.finish();
+    /// assert_eq!(
+    ///     reader.next_event(&mut buf).unwrap(),
+    ///     Some(Event::Text(BytesText::from_escaped_str("text")))
+    /// );
+    /// assert!(matches!(reader.next_event(&mut buf).unwrap(), Some(Event::End(_))));
+    /// assert_eq!(reader.next_event(&mut buf).unwrap(), Some(Event::Eof));
+    /// ```
+    pub fn feed(&mut self, chunk: &[u8]) {
+        let input = &mut self.reader;
+        input.data.drain(..input.consumed);
+        input.searched = input.searched.map(|end| end - input.consumed);
+        input.consumed = 0;
+        input.data.extend_from_slice(chunk);
+    }
+
+    /// Signals that all chunks of the document were [fed]. After that
+    /// [`Self::next_event()`] reads the remaining data as the end of the
+    /// document: returns the last text, reports unclosed markup as
+    /// [`Error::UnexpectedEof`] and finally returns [`Event::Eof`].
+    ///
+    /// [fed]: Self::feed
+    pub fn finish(&mut self) {
+        self.reader.finished = true;
+    }
+
+    /// Reads the next event from the [fed] data. Returns `Ok(None)` if the
+    /// data does not contain a complete event yet. In that case the reader
+    /// is left in the same state as before the call, and the call should be
+    /// repeated after feeding the next chunk or [finishing] the input.
+    ///
+    /// Like in [`Self::read_event()`], the event borrows from the `buf`.
+    /// The reader keeps the incomplete event, so the buffer can be cleared
+    /// when `Ok(None)` is returned.
+    ///
+    /// [fed]: Self::feed
+    /// [finishing]: Self::finish
+    pub fn next_event<'b>(&mut self, buf: &'b mut Vec<u8>) -> Result<Option<Event<'b>>> {
+        if !self.can_complete() {
+            self.reader.searched = Some(self.reader.data.len());
+            return Ok(None);
+        }
+        let snapshot = Snapshot {
+            tag_state: self.tag_state.clone(),
+            buf_position: self.buf_position,
+            markup_start: self.markup_start,
+            tracker: self.tracker.clone(),
+            consumed: self.reader.consumed,
+            diagnostics: self.diagnostics.len(),
+            #[cfg(feature = "encoding")]
+            transcoder: self.transcoder.clone(),
+        };
+        match self.read_event(buf) {
+            Err(e) if is_starved(&e) => {
+                self.tag_state = snapshot.tag_state;
+                self.buf_position = snapshot.buf_position;
+                self.markup_start = snapshot.markup_start;
+                self.tracker = snapshot.tracker;
+                self.reader.consumed = snapshot.consumed;
+                self.reader.searched = Some(self.reader.data.len());
+                self.diagnostics.truncate(snapshot.diagnostics);
+                #[cfg(feature = "encoding")]
+                {
+                    self.transcoder = snapshot.transcoder;
+                }
+                Ok(None)
+            }
+            result => {
+                self.reader.searched = None;
+                result.map(Some)
+            }
+        }
+    }
+
+    /// Checks whether the next read can return an event after the last read
+    /// lacked data. Every event ends with a `<` or `>` or at the end of input,
+    /// so only the data fed after the last read is searched for them. Data
+    /// that can exceed the [limits] of the text or markup length is always read
+    ///
+    /// [limits]: Self::limits
+    fn can_complete(&self) -> bool {
+        let input = &self.reader;
+        let searched = match input.searched {
+            Some(searched) if !input.finished => searched,
+            _ => return true,
+        };
+        let pending = input.data.len() - input.consumed;
+        // Transcoded UTF-16 can be up to 1.5 times longer than the fed data
+        let limit = self.limits.max_markup_len.min(self.limits.max_text_len);
+        if pending.saturating_mul(2) >= limit {
+            return true;
+        }
+        let start = searched.saturating_sub(LOOKBEHIND).max(input.consumed);
+        memchr::memchr2(b'<', b'>', &input.data[start..]).is_some()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::BytesText;
+    use crate::reader::{Diagnostic, ReaderLimits};
+    use pretty_assertions::assert_eq;
+
+    /// Reads all events from the document split into chunks of `size` bytes
+    fn read_chunked(xml: &[u8], size: usize, reader: &mut Reader<PushBuffer>) -> Vec<String> {
+        let mut events = Vec::new();
+        let mut buf = Vec::new();
+        let mut chunks = xml.chunks(size);
+        loop {
+            match reader.next_event(&mut buf) {
+                Ok(Some(Event::Eof)) => break,
+                Ok(Some(event)) => events.push(format!("{:?}", event)),
+                Ok(None) => match chunks.next() {
+                    Some(chunk) => reader.feed(chunk),
+                    None => reader.finish(),
+                },
+                Err(e) => {
+                    events.push(format!("{:?}", e));
+                    break;
+                }
+            }
+            buf.clear();
+        }
+        events
+    }
+
+    /// Checks that events are the same as read from the whole document by the
+    /// buffered reader, regardless of size of chunks
+    fn check(xml: &[u8], configure: fn(&mut Reader<PushBuffer>)) {
+        let mut expected = Vec::new();
+        let mut reader = Reader::from_reader(xml);
+        let mut pushed = Reader::from_reader(PushBuffer::new());
+        configure(&mut pushed);
+        // Copy the configuration
+        reader.trim_text_start = pushed.trim_text_start;
+        reader.trim_text_end = pushed.trim_text_end;
+        reader.expand_empty_elements = pushed.expand_empty_elements;
+        reader.tracker = pushed.tracker.clone();
+        reader.recover = pushed.recover;
+        reader.coalesce_text = pushed.coalesce_text;
+        reader.limits = pushed.limits;
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event(&mut buf) {
+                Ok(Event::Eof) => break,
+                Ok(event) => expected.push(format!("{:?}", event)),
+                Err(e) => {
+                    expected.push(format!("{:?}", e));
+                    break;
+                }
+            }
+            buf.clear();
+        }
+
+        for size in 1..=xml.len() {
+            let mut reader = Reader::from_reader(PushBuffer::new());
+            configure(&mut reader);
+            assert_eq!(
+                read_chunked(xml, size, &mut reader),
+                expected,
+                "chunks of {} bytes",
+                size
+            );
+        }
+    }
+
+    const XML: &[u8] = br#"<?xml version="1.0"?>
+<!DOCTYPE root [<!ENTITY e "entity">]>
+<root attr="a > b">
+    text &e; <![CDATA[<cdata>]]>
+    <!-- comment -->
+    <?pi content?>
+    <empty/>
+</root>
+"#;
+
+    #[test]
+    fn events() {
+        check(XML, |_| {});
+    }
+
+    #[test]
+    fn trimmed() {
+        check(XML, |r| {
+            r.trim_text(true).expand_empty_elements(true);
+        });
+    }
+
+    #[test]
+    fn tracked() {
+        check(XML, |r| {
+            r.track_position(true);
+        });
+    }
+
+    #[test]
+    fn coalesced() {
+        check(XML, |r| {
+            r.coalesce_text(true);
+        });
+        check(b"<root>a<![CDATA[b]]>c<!--d-->e</root>", |r| {
+            r.coalesce_text(true);
+        });
+    }
+
+    #[test]
+    fn limited() {
+        check(b"<root>long text without markup</root>", |r| {
+            r.limits(ReaderLimits {
+                max_text_len: 8,
+                ..ReaderLimits::default()
+            });
+        });
+    }
+
+    /// Diagnostics of an event that lacked data are not reported twice
+    #[test]
+    fn diagnostics() {
+        let mut reader = Reader::from_reader(PushBuffer::new());
+        reader.trim_text(true).recover(true).coalesce_text(true);
+        let mut buf = Vec::new();
+
+        reader.feed(b"<root>a & b<");
+        assert!(matches!(
+            reader.next_event(&mut buf).unwrap(),
+            Some(Event::Start(_))
+        ));
+        // The text is complete, but it can be coalesced with the next CDATA
+        assert_eq!(reader.next_event(&mut buf).unwrap(), None);
+        assert_eq!(reader.next_event(&mut buf).unwrap(), None);
+        assert_eq!(reader.diagnostics(), &[]);
+
+        reader.feed(b"/root>");
+        assert_eq!(
+            reader.next_event(&mut buf).unwrap(),
+            Some(Event::Text(BytesText::from_escaped_str("a &amp; b")))
+        );
+        assert_eq!(reader.diagnostics(), &[Diagnostic::BareAmpersand(8)]);
+    }
+
+    /// The fed data is not read again until it can complete the event
+    #[test]
+    fn resumed() {
+        let mut reader = Reader::from_reader(PushBuffer::new());
+        reader.trim_text(true);
+        let mut buf = Vec::new();
+
+        reader.feed(b"<root>text");
+        assert!(matches!(
+            reader.next_event(&mut buf).unwrap(),
+            Some(Event::Start(_))
+        ));
+        assert_eq!(reader.next_event(&mut buf).unwrap(), None);
+        assert_eq!(reader.reader.searched, Some(10));
+
+        reader.feed(b" more text");
+        assert_eq!(reader.next_event(&mut buf).unwrap(), None);
+        // Only the new data was searched, and the position is adjusted to
+        // the removed consumed data
+        assert_eq!(reader.reader.searched, Some(14));
+        assert_eq!(reader.buffer_position(), 6);
+
+        reader.feed(b"</root>");
+        assert_eq!(
+            reader.next_event(&mut buf).unwrap(),
+            Some(Event::Text(BytesText::from_escaped_str("text more text")))
+        );
+        assert_eq!(reader.reader.searched, None);
+    }
+
+    #[test]
+    fn unclosed() {
+        check(b"<root><unclosed attr='>", |_| {});
+        check(b"<root><!-- unclosed", |_| {});
+        check(b"<root></root", |_| {});
+    }
+
+    #[test]
+    fn empty() {
+        let mut reader = Reader::from_reader(PushBuffer::new());
+        let mut buf = Vec::new();
+        assert_eq!(reader.next_event(&mut buf).unwrap(), None);
+        reader.finish();
+        assert_eq!(reader.next_event(&mut buf).unwrap(), Some(Event::Eof));
+    }
+
+    #[test]
+    #[cfg(feature = "encoding")]
+    fn utf16() {
+        let xml: Vec<u8> = "\u{FEFF}<root>текст</root>"
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes())
+            .collect();
+        check(&xml, |_| {});
+    }
+}
@@ -0,0 +1,329 @@
+//! Helpers for the recovery mode of the [`Reader`], in which the reader fixes
+//! common problems of malformed documents instead of returning an error.
+//!
+//! [`Reader`]: super::Reader
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+use crate::events::attributes::{Attr, AttrError, IterState, Moves};
+
+/// A problem in a malformed document, that was fixed by the [`Reader`] in the
+/// [recovery mode]. All positions are absolute positions in the document.
+///
+/// [`Reader`]: crate::Reader
+/// [recovery mode]: crate::Reader::recover
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// A `&` in the text or in an attribute value, that does not start a character
+    /// or an entity reference. Such `&` is treated as a text and escaped as `&amp;`
+    /// in the [`Text`] event or in the attribute value. Contains the position of `&`.
+    ///
+    /// [`Text`]: crate::events::Event::Text
+    BareAmpersand(usize),
+    /// An end tag that does not match the last opened element, but matches
+    /// one of the elements opened before it. [`End`] events for all elements
+    /// opened after the matched one are generated automatically.
+    ///
+    /// [`End`]: crate::events::Event::End
+    MismatchedEnd {
+        /// The name of the last opened element
+        expected: String,
+        /// The name in the end tag
+        found: String,
+        /// Byte range of the end tag, including `</` and `>`
+        span: Range<usize>,
+    },
+    /// An end tag that does not match any opened element. Such tag is skipped.
+    UnmatchedEnd {
+        /// The name in the end tag
+        found: String,
+        /// Byte range of the end tag, including `</` and `>`
+        span: Range<usize>,
+    },
+    /// A malformed or duplicated attribute. Such attribute is removed from the
+    /// [`Start`] or [`Empty`] event.
+    ///
+    /// [`Start`]: crate::events::Event::Start
+    /// [`Empty`]: crate::events::Event::Empty
+    InvalidAttr(AttrError),
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::BareAmpersand(pos) => {
+                write!(f, "position {}: `&` is not a start of a reference", pos)
+            }
+            Self::MismatchedEnd {
+                expected,
+                found,
+                span,
+            } => write!(
+                f,
+                "position {}: expecting </{}> found </{}>, unclosed elements closed",
+                span.start, expected, found
+            ),
+            Self::UnmatchedEnd { found, span } => write!(
+                f,
+                "position {}: end tag </{}> without start tag skipped",
+                span.start, found
+            ),
+            Self::InvalidAttr(e) => write!(f, "{}, attribute skipped", e),
+        }
+    }
+}
+
+/// Checks that the text after `&` is a character or an entity reference:
+/// `#[0-9]+;`, `#x[0-9a-fA-F]+;` or `Name;`. Names are checked loosely: any
+/// non-ASCII byte is considered as a part of a name.
+fn is_reference(bytes: &[u8]) -> bool {
+    let end = match memchr::memchr(b';', bytes) {
+        Some(end) if end > 0 => end,
+        _ => return false,
+    };
+    match &bytes[..end] {
+        [b'#', b'x', hex @ ..] => !hex.is_empty() && hex.iter().all(u8::is_ascii_hexdigit),
+        [b'#', dec @ ..] => !dec.is_empty() && dec.iter().all(u8::is_ascii_digit),
+        [first, rest @ ..] => {
+            let is_name_start =
+                |b: &u8| b.is_ascii_alphabetic() || matches!(b, b'_' | b':' | 0x80..);
+            is_name_start(first)
+                && rest
+                    .iter()
+                    .all(|b| is_name_start(b) || b.is_ascii_digit() || matches!(b, b'-' | b'.'))
+        }
+        [] => false,
+    }
+}
+
+/// Escapes all `&` in the text which do not start a reference. Returns `None`
+/// if the text does not contain such `&`.
+///
+/// # Parameters
+/// - `text`: the raw (escaped) text
+/// - `offset`: position of the text in the document
+/// - `diagnostics`: list to which diagnostics are added
+pub(crate) fn escape_bare_ampersands(
+    text: &[u8],
+    offset: usize,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<Vec<u8>> {
+    let mut escaped: Option<Vec<u8>> = None;
+    let mut last = 0;
+    for i in memchr::memchr_iter(b'&', text) {
+        if !is_reference(&text[i + 1..]) {
+            let escaped = escaped.get_or_insert_with(|| Vec::with_capacity(text.len() + 4));
+            escaped.extend_from_slice(&text[last..i]);
+            escaped.extend_from_slice(b"&amp;");
+            last = i + 1;
+            diagnostics.push(Diagnostic::BareAmpersand(offset + i));
+        }
+    }
+    if let Some(ref mut escaped) = escaped {
+        escaped.extend_from_slice(&text[last..]);
+    }
+    escaped
+}
+
+/// Removes malformed and duplicated attributes from the content of a start tag
+/// and escapes `&` in attribute values which do not start a reference. Returns
+/// `None` if all attributes are valid, otherwise the repaired content and
+/// positions of its bytes in the original content.
+///
+/// # Parameters
+/// - `content`: the content of the tag between `<` and `>` (or `/>`)
+/// - `name_len`: the length of the element name
+/// - `offset`: position of the content in the document
+/// - `html`: whether unquoted values and attributes without values are allowed
+/// - `diagnostics`: list to which diagnostics are added
+pub(crate) fn repair_attributes(
+    content: &[u8],
+    name_len: usize,
+    offset: usize,
+    html: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<(Vec<u8>, Moves)> {
+    let mut state = IterState::new(name_len, html);
+    // Ranges of valid attributes and their values, escaped values and whether
+    // invalid attributes were removed before them
+    let mut valid = Vec::new();
+    let mut changed = false;
+    let mut after_error = false;
+    while let Some(attr) = state.next(content) {
+        let (range, value) = match attr {
+            // +1 for the closing quote
+            Ok(Attr::DoubleQ(key, value)) | Ok(Attr::SingleQ(key, value)) => {
+                (key.start..value.end + 1, value)
+            }
+            // Only in HTML mode
+            Ok(Attr::Unquoted(key, value)) => (key.start..value.end, value),
+            Ok(Attr::Empty(key)) => (key.clone(), key.end..key.end),
+            Err(e) => {
+                changed = true;
+                after_error = true;
+                diagnostics.push(Diagnostic::InvalidAttr(e.shift(offset)));
+                continue;
+            }
+        };
+        let escaped =
+            escape_bare_ampersands(&content[value.clone()], offset + value.start, diagnostics);
+        changed |= escaped.is_some();
+        valid.push((range, value, escaped, after_error));
+        after_error = false;
+    }
+    if !changed {
+        return None;
+    }
+
+    let mut result = content[..name_len].to_vec();
+    let mut moves = Moves::default();
+    let mut end = name_len;
+    for (range, value, escaped, after_error) in valid {
+        // Spaces between valid attributes are kept, removed attributes are
+        // replaced by one space
+        if after_error {
+            moves.record(result.len(), range.start - 1);
+            result.push(b' ');
+        } else {
+            moves.record(result.len(), end);
+            result.extend_from_slice(&content[end..range.start]);
+        }
+        match escaped {
+            Some(escaped) => {
+                result.extend_from_slice(&content[range.start..value.start]);
+                result.extend_from_slice(&escaped);
+                // The last byte of the escaped value is `;` of the escaped `&`
+                // or the last byte of the value
+                moves.record(result.len() - 1, value.end - 1);
+                result.extend_from_slice(&content[value.end..range.end]);
+            }
+            None => result.extend_from_slice(&content[range.clone()]),
+        }
+        end = range.end;
+    }
+    if !after_error {
+        moves.record(result.len(), end);
+        result.extend_from_slice(&content[end..]);
+    }
+    Some((result, moves))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn references() {
+        assert!(is_reference(b"amp;"));
+        assert!(is_reference(b"custom-entity.1;"));
+        assert!(is_reference(b"\xD1\x8F;"));
+        assert!(is_reference(b"#123;"));
+        assert!(is_reference(b"#x1F;"));
+
+        assert!(!is_reference(b""));
+        assert!(!is_reference(b";"));
+        assert!(!is_reference(b"amp"));
+        assert!(!is_reference(b" amp;"));
+        assert!(!is_reference(b"1a;"));
+        assert!(!is_reference(b"#;"));
+        assert!(!is_reference(b"#x;"));
+        assert!(!is_reference(b"#12a;"));
+        assert!(!is_reference(b"#xZ;"));
+    }
+
+    #[test]
+    fn ampersands() {
+        let mut diagnostics = Vec::new();
+        assert_eq!(
+            escape_bare_ampersands(b"a &amp; b &#32; c", 10, &mut diagnostics),
+            None
+        );
+        assert_eq!(
+            escape_bare_ampersands(b"a & b &amp; c &", 10, &mut diagnostics),
+            Some(b"a &amp; b &amp; c &amp;".to_vec())
+        );
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic::BareAmpersand(12), Diagnostic::BareAmpersand(24)]
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        let mut diagnostics = Vec::new();
+        assert_eq!(
+            repair_attributes(b"tag a='1' b=\"2\"", 3, 1, false, &mut diagnostics),
+            None
+        );
+        let (fixed, moves) = repair_attributes(
+            b"tag a='1' b=2 c a=\"3\" d='\"4\"'",
+            3,
+            1,
+            false,
+            &mut diagnostics,
+        )
+        .unwrap();
+        assert_eq!(fixed, b"tag a='1' d='\"4\"'".to_vec());
+        // ` d='"4"'` is moved
+        assert_eq!(moves.source(8), 8);
+        assert_eq!(moves.source(9), 21);
+        assert_eq!(moves.source(13), 25);
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::InvalidAttr(AttrError::UnquotedValue(13)),
+                Diagnostic::InvalidAttr(AttrError::ExpectedEq(17)),
+                Diagnostic::InvalidAttr(AttrError::Duplicated(17, 5)),
+            ]
+        );
+    }
+
+    /// Unquoted values and attributes without values are kept in the HTML mode
+    #[test]
+    fn html_attributes() {
+        let mut diagnostics = Vec::new();
+        assert_eq!(
+            repair_attributes(b"input disabled value=x", 5, 1, true, &mut diagnostics),
+            None
+        );
+        let (fixed, _) =
+            repair_attributes(b"input a b=1 a=2 c", 5, 1, true, &mut diagnostics).unwrap();
+        assert_eq!(fixed, b"input a b=1 c".to_vec());
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic::InvalidAttr(AttrError::Duplicated(13, 7))]
+        );
+    }
+
+    /// Bare `&` in values are escaped, spaces between attributes are kept
+    #[test]
+    fn attribute_ampersands() {
+        let mut diagnostics = Vec::new();
+        let (fixed, moves) = repair_attributes(
+            b"a href='?a=1&b=2'\n  title=\"&amp;&\" ",
+            1,
+            1,
+            false,
+            &mut diagnostics,
+        )
+        .unwrap();
+        assert_eq!(
+            fixed,
+            b"a href='?a=1&amp;b=2'\n  title=\"&amp;&amp;\" ".to_vec()
+        );
+        // The closing quotes
+        assert_eq!(moves.source(20), 16);
+        assert_eq!(moves.source(41), 33);
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic::BareAmpersand(13), Diagnostic::BareAmpersand(33)]
+        );
+
+        let mut diagnostics = Vec::new();
+        let (fixed, _) = repair_attributes(b"a href=x&y b", 1, 1, true, &mut diagnostics).unwrap();
+        assert_eq!(fixed, b"a href=x&amp;y b".to_vec());
+        assert_eq!(diagnostics, vec![Diagnostic::BareAmpersand(9)]);
+    }
+}
@@ -0,0 +1,203 @@
+//! Scanning of markup a word of bytes at a time.
+//!
+//! The input is loaded as 64-bit words and bytes of a class are found with
+//! bitwise operations over all bytes of a word at once (the "SIMD within a
+//! register" technique). That works on every target without any `unsafe`
+//! code. The remainder of the input which is shorter than a word is scanned
+//! byte by byte.
+//!
+//! Only classes, that cannot be searched with the `memchr` crate, are
+//! classified here: whitespaces and the ends of attribute names. Searches of
+//! `<`, `>`, `&` and quotes are done with `memchr`, which uses vector
+//! instructions of the target when they are available.
+
+use std::convert::TryInto;
+
+use crate::reader::is_whitespace;
+
+/// The number of bytes classified at once
+const WORD: usize = 8;
+
+/// `0x01` in each byte of a word
+const LO: u64 = u64::from_ne_bytes([0x01; WORD]);
+/// `0x7F` in each byte of a word
+const LOW7: u64 = u64::from_ne_bytes([0x7F; WORD]);
+
+/// A class of bytes that can be found in a word
+pub(crate) trait Class: Copy {
+    /// Returns a word with `0x80` in each byte of `word` which belongs to the
+    /// class and zeroes in other bits
+    fn classify(self, word: u64) -> u64;
+
+    /// Returns `true` if the byte belongs to the class
+    fn matches(self, byte: u8) -> bool;
+}
+
+/// Returns a word with `0x80` in each zero byte of `word` and zeroes in other
+/// bits. Unlike the well-known `(x - LO) & !x & HI` expression the result does
+/// not have false positives after the first zero byte
+#[inline(always)]
+fn zero_bytes(word: u64) -> u64 {
+    !(((word & LOW7).wrapping_add(LOW7)) | word | LOW7)
+}
+
+/// Returns a word with `0x80` in each byte of `word` equal to `byte`
+#[inline(always)]
+fn eq(word: u64, byte: u8) -> u64 {
+    zero_bytes(word ^ LO.wrapping_mul(byte as u64))
+}
+
+/// Bytes equal to the specified byte
+#[derive(Clone, Copy)]
+pub(crate) struct Byte(pub u8);
+
+impl Class for Byte {
+    #[inline(always)]
+    fn classify(self, word: u64) -> u64 {
+        eq(word, self.0)
+    }
+    #[inline(always)]
+    fn matches(self, byte: u8) -> bool {
+        byte == self.0
+    }
+}
+
+/// Whitespace bytes: ` `, `\t`, `\r` and `\n`
+#[derive(Clone, Copy)]
+pub(crate) struct Whitespace;
+
+impl Class for Whitespace {
+    #[inline(always)]
+    fn classify(self, word: u64) -> u64 {
+        eq(word, b' ') | eq(word, b'\t') | eq(word, b'\r') | eq(word, b'\n')
+    }
+    #[inline(always)]
+    fn matches(self, byte: u8) -> bool {
+        is_whitespace(byte)
+    }
+}
+
+/// Bytes which do not belong to the class
+#[derive(Clone, Copy)]
+pub(crate) struct Not<C>(pub C);
+
+impl<C: Class> Class for Not<C> {
+    #[inline(always)]
+    fn classify(self, word: u64) -> u64 {
+        !self.0.classify(word) & !LOW7
+    }
+    #[inline(always)]
+    fn matches(self, byte: u8) -> bool {
+        !self.0.matches(byte)
+    }
+}
+
+/// Bytes which belong to any of two classes
+#[derive(Clone, Copy)]
+pub(crate) struct Or<A, B>(pub A, pub B);
+
+impl<A: Class, B: Class> Class for Or<A, B> {
+    #[inline(always)]
+    fn classify(self, word: u64) -> u64 {
+        self.0.classify(word) | self.1.classify(word)
+    }
+    #[inline(always)]
+    fn matches(self, byte: u8) -> bool {
+        self.0.matches(byte) || self.1.matches(byte)
+    }
+}
+
+/// Returns the position of the first byte of the class
+#[inline(always)]
+pub(crate) fn position<C: Class>(bytes: &[u8], class: C) -> Option<usize> {
+    // Short runs, like a single space between attributes, are found without
+    // classification of the whole word
+    match bytes.first() {
+        Some(b) if class.matches(*b) => return Some(0),
+        None => return None,
+        _ => {}
+    }
+    let mut words = bytes.chunks_exact(WORD);
+    for (i, word) in (&mut words).enumerate() {
+        let word = u64::from_le_bytes(word.try_into().unwrap());
+        let found = class.classify(word);
+        if found != 0 {
+            // Bytes are loaded in the little-endian order, so the first
+            // byte is the lowest one
+            return Some(i * WORD + found.trailing_zeros() as usize / 8);
+        }
+    }
+    let remainder = words.remainder();
+    let offset = bytes.len() - remainder.len();
+    remainder
+        .iter()
+        .position(|b| class.matches(*b))
+        .map(|i| offset + i)
+}
+
+/// Returns the position of the first whitespace
+#[inline]
+pub(crate) fn find_whitespace(bytes: &[u8]) -> Option<usize> {
+    position(bytes, Whitespace)
+}
+
+/// Returns the position of the first byte which is not a whitespace
+#[inline]
+pub(crate) fn skip_whitespace(bytes: &[u8]) -> Option<usize> {
+    position(bytes, Not(Whitespace))
+}
+
+/// Returns the position of the first `=` or whitespace, i.e. the end of
+/// an attribute name
+#[inline]
+pub(crate) fn find_name_end(bytes: &[u8]) -> Option<usize> {
+    position(bytes, Or(Byte(b'='), Whitespace))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Each byte value is classified as the scalar check does
+    #[test]
+    fn classify() {
+        for byte in 0..=255u8 {
+            for pos in 0..WORD {
+                let mut bytes = [b'a'; WORD];
+                bytes[pos] = byte;
+                // Bytes with the highest bit set should not affect other bytes
+                bytes[(pos + 1) % WORD] = 0xFF;
+                let word = u64::from_le_bytes(bytes);
+                let expected = |m: bool| if m { 0x80u64 << (pos * 8) } else { 0 };
+
+                let found = Whitespace.classify(word) & (0xFF << (pos * 8));
+                assert_eq!(found, expected(is_whitespace(byte)), "{:#x}", byte);
+                let found = Byte(b'>').classify(word) & (0xFF << (pos * 8));
+                assert_eq!(found, expected(byte == b'>'), "{:#x}", byte);
+                let found = Not(Whitespace).classify(word) & (0xFF << (pos * 8));
+                assert_eq!(found, expected(!is_whitespace(byte)), "{:#x}", byte);
+            }
+        }
+    }
+
+    /// Positions in the first byte, in words and in the remainder are found
+    #[test]
+    fn positions() {
+        for len in 0..100 {
+            for pos in 0..len {
+                let mut bytes = vec![b'a'; len];
+                bytes[pos] = b' ';
+                assert_eq!(find_whitespace(&bytes), Some(pos), "{} in {}", pos, len);
+                bytes[pos] = b'=';
+                assert_eq!(find_name_end(&bytes), Some(pos), "{} in {}", pos, len);
+
+                let mut bytes = vec![b'\n'; len];
+                bytes[pos] = b'a';
+                assert_eq!(skip_whitespace(&bytes), Some(pos), "{} in {}", pos, len);
+            }
+            assert_eq!(find_whitespace(&vec![b'a'; len]), None);
+            assert_eq!(skip_whitespace(&vec![b'\t'; len]), None);
+        }
+    }
+}
@@ -0,0 +1,679 @@
+//! Checkpoints of the [`Reader`] state, that allow to resume reading of a document
+//! from the middle after seeking the input.
+//!
+//! [`Reader`]: super::Reader
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[cfg(feature = "encoding")]
+use encoding_rs::Encoding;
+
+use crate::events::Event;
+use crate::name::NamespaceResolver;
+use crate::reader::html::PendingStart;
+use crate::reader::{Reader, TagState, TextPosition};
+
+#[cfg(feature = "encoding")]
+use crate::reader::Transcoder;
+
+/// A snapshot of the parsing state of a [`Reader`] between two events, created by
+/// [`Reader::state()`].
+///
+/// The state contains everything that the reader has learned about the document
+/// before the snapshot: the position, the stack of opened elements, namespace
+/// bindings, entities and attribute types declared in the DTD and the encoding.
+/// It does not contain the configuration of the reader and [diagnostics].
+///
+/// To continue reading, seek the underlying input to the [`input_position()`],
+/// create and configure a new reader over it and call [`Reader::resume()`].
+/// With the `serialize` feature the state can be stored with any serde format.
+///
+/// [`Reader`]: crate::Reader
+/// [`Reader::state()`]: crate::Reader::state
+/// [`Reader::resume()`]: crate::Reader::resume
+/// [diagnostics]: crate::Reader::diagnostics
+/// [`input_position()`]: Self::input_position
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReaderState {
+    /// Position in the underlying input from which reading should be continued
+    input_position: usize,
+    buf_position: usize,
+    tag_state: TagState,
+    /// Position of the next character, reported by the position tracker
+    text_position: TextPosition,
+    /// Position of the last event, reported by the position tracker
+    event_position: TextPosition,
+    after_cr: bool,
+    root_seen: bool,
+    doctype_seen: bool,
+    bom_len: usize,
+    entities: HashMap<Vec<u8>, Vec<u8>>,
+    /// Names of attributes that are normalized as not `CDATA`, by element names
+    tokenized_attributes: HashMap<Vec<u8>, Vec<Vec<u8>>>,
+    depth: usize,
+    opened_buffer: Vec<u8>,
+    opened_starts: Vec<usize>,
+    ns_resolver: NamespaceResolver,
+    /// Content of the namespace buffer, referenced by `ns_resolver`
+    ns_buffer: Vec<u8>,
+    pending_pop: bool,
+    /// A start tag that should be returned after `End` events in the HTML mode
+    pending_start: Option<PendingStart>,
+    /// An event read after coalesced text, with its span and position
+    lookahead: Option<(Event<'static>, Range<usize>, TextPosition)>,
+    #[cfg(feature = "encoding")]
+    encoding: &'static Encoding,
+    #[cfg(feature = "encoding")]
+    is_encoding_set: bool,
+    /// Bytes read from the input, but not yet consumed by the reader
+    #[cfg(feature = "encoding")]
+    transcoder: Transcoder,
+}
+
+impl ReaderState {
+    /// Returns the position in the underlying input, to which the input should
+    /// be seeked before [resuming] reading. The position is counted in bytes
+    /// from the start of the input the first reader was created from.
+    ///
+    /// The position can differ from the [`buffer_position()`] when UTF-16 or
+    /// UTF-32 documents are transcoded.
+    ///
+    /// [resuming]: crate::Reader::resume
+    /// [`buffer_position()`]: Self::buffer_position
+    pub fn input_position(&self) -> usize {
+        self.input_position
+    }
+
+    /// Returns the position in the document, the same as [`Reader::buffer_position()`]
+    /// returned at the moment of the snapshot.
+    ///
+    /// [`Reader::buffer_position()`]: crate::Reader::buffer_position
+    pub fn buffer_position(&self) -> usize {
+        self.buf_position
+    }
+}
+
+impl<R> Reader<R> {
+    /// Takes a snapshot of the parsing state, which can be used to continue
+    /// reading from the current position by another reader.
+    ///
+    /// `namespace_buffer` is the buffer passed to [`Self::read_namespaced_event()`],
+    /// which is stored in the state with namespace bindings. Pass an empty slice
+    /// if namespaces are not resolved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use std::io::{Cursor, Seek, SeekFrom};
+    /// use quick_xml::events::{BytesStart, BytesEnd, Event};
+    /// use quick_xml::Reader;
+    ///
+    /// let mut input = Cursor::new(b"<root><record id='1'/><record id='2'/></root>");
+    /// let mut buf = Vec::new();
+    ///
+    /// let mut reader = Reader::from_reader(&mut input);
+    /// reader.trim_text(true);
+    /// reader.read_event(&mut buf).unwrap(); // <root>
+    /// reader.read_event(&mut buf).unwrap(); // <record id='1'/>
+    /// let state = reader.state(&[]);
+    ///
+    /// // Later jump back to the second record
+    /// input.seek(SeekFrom::Start(state.input_position() as u64)).unwrap();
+    /// let mut reader = Reader::from_reader(&mut input);
+    /// reader.trim_text(true);
+    /// reader.resume(state, &mut Vec::new());
+    ///
+    /// buf.clear();
+    /// assert_eq!(
+    ///     reader.read_event(&mut buf).unwrap(),
+    ///     Event::Empty(BytesStart::borrowed(b"record id='2'", 6))
+    /// );
+    /// // The reader knows that the `root` element is opened
+    /// assert_eq!(
+    ///     reader.read_event(&mut buf).unwrap(),
+    ///     Event::End(BytesEnd::borrowed(b"root"))
+    /// );
+    /// ```
+    pub fn state(&self, namespace_buffer: &[u8]) -> ReaderState {
+        #[cfg(feature = "encoding")]
+        let input_position = self.transcoder.consumed().unwrap_or(self.buf_position);
+        #[cfg(not(feature = "encoding"))]
+        let input_position = self.buf_position;
+
+        ReaderState {
+            input_position,
+            buf_position: self.buf_position,
+            tag_state: self.tag_state.clone(),
+            text_position: self.tracker.current,
+            event_position: self.tracker.event,
+            after_cr: self.tracker.after_cr,
+            root_seen: self.root_seen,
+            doctype_seen: self.doctype_seen,
+            bom_len: self.bom_len,
+            entities: self.entities.clone(),
+            tokenized_attributes: self.tokenized_attributes.clone(),
+            depth: self.depth,
+            opened_buffer: self.opened_buffer.clone(),
+            opened_starts: self.opened_starts.clone(),
+            ns_resolver: self.ns_resolver.clone(),
+            ns_buffer: namespace_buffer.to_vec(),
+            pending_pop: self.pending_pop,
+            pending_start: self.pending_start.clone(),
+            lookahead: self.lookahead.clone(),
+            #[cfg(feature = "encoding")]
+            encoding: self.encoding,
+            #[cfg(feature = "encoding")]
+            is_encoding_set: self.is_encoding_set,
+            #[cfg(feature = "encoding")]
+            transcoder: self.transcoder.checkpoint(input_position),
+        }
+    }
+
+    /// Restores the parsing state [taken] by another reader. The underlying input
+    /// of this reader should start at the [`ReaderState::input_position()`].
+    ///
+    /// The configuration of this reader is not changed and the `namespace_buffer`
+    /// is replaced with the one stored in the state. See [`Self::state()`] for
+    /// an example.
+    ///
+    /// [taken]: Self::state
+    pub fn resume(&mut self, state: ReaderState, namespace_buffer: &mut Vec<u8>) {
+        self.buf_position = state.buf_position;
+        self.markup_start = state.buf_position;
+        self.event_span = state.buf_position..state.buf_position;
+        self.tag_state = state.tag_state;
+        self.tracker.current = state.text_position;
+        self.tracker.event = state.event_position;
+        self.tracker.after_cr = state.after_cr;
+        self.root_seen = state.root_seen;
+        self.doctype_seen = state.doctype_seen;
+        self.bom_len = state.bom_len;
+        self.entities = state.entities;
+        self.tokenized_attributes = state.tokenized_attributes;
+        self.depth = state.depth;
+        self.opened_buffer = state.opened_buffer;
+        self.opened_starts = state.opened_starts;
+        self.ns_resolver = state.ns_resolver;
+        *namespace_buffer = state.ns_buffer;
+        self.pending_pop = state.pending_pop;
+        self.pending_start = state.pending_start;
+        self.lookahead = state.lookahead;
+        #[cfg(feature = "encoding")]
+        {
+            self.encoding = state.encoding;
+            self.is_encoding_set = state.is_encoding_set;
+            self.transcoder = state.transcoder;
+        }
+    }
+}
+
+#[cfg(feature = "serialize")]
+mod serialize {
+    //! The state is stored as nested tuples of primitive types and byte vectors
+    //! and is validated on deserialization, so a corrupted state can not make
+    //! the reader panic.
+
+    use super::*;
+    use crate::events::{BytesDecl, BytesStart, BytesText};
+    use crate::name::NamespaceEntry;
+    use serde::de::{Deserialize, Deserializer, Error};
+    use serde::ser::{Serialize, Serializer};
+
+    type Tag = (u8, usize);
+    type Tracker = (usize, usize, usize, usize, bool);
+    type Strict = (bool, bool, usize);
+    type Opened = (Vec<u8>, Vec<usize>);
+    type Namespaces = (Vec<(usize, usize, usize, i32)>, i32, bool, Vec<u8>);
+    type EncodingState = (String, bool, u8, Vec<u8>, Vec<u8>, Option<u16>);
+    type Pending = Option<(Vec<u8>, usize, bool, usize, usize)>;
+    type Tokenized = Vec<(Vec<u8>, Vec<Vec<u8>>)>;
+    type Lookahead = Option<(u8, Vec<u8>, usize, usize, usize, usize)>;
+    type Repr = (
+        usize,
+        usize,
+        Tag,
+        Tracker,
+        Strict,
+        Vec<(Vec<u8>, Vec<u8>)>,
+        usize,
+        Opened,
+        Namespaces,
+        EncodingState,
+        Pending,
+        Tokenized,
+        Lookahead,
+    );
+
+    impl Serialize for ReaderState {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let tag = match self.tag_state {
+                TagState::Opened => (0, 0),
+                TagState::Closed => (1, 0),
+                TagState::Empty => (2, 0),
+                TagState::AutoClose(depth) => (3, depth),
+                TagState::Exit => (4, 0),
+                TagState::RawText => (5, 0),
+                TagState::PendingStart => (6, 0),
+            };
+            let mut entities: Vec<_> = self.entities.iter().collect();
+            entities.sort();
+            let mut tokenized: Vec<_> = self.tokenized_attributes.iter().collect();
+            tokenized.sort();
+            let lookahead = self.lookahead.as_ref().map(|(event, span, position)| {
+                let kind = match event {
+                    Event::Comment(_) => 0,
+                    Event::PI(_) => 1,
+                    Event::DocType(_) => 2,
+                    Event::Decl(_) => 3,
+                    Event::Eof => 4,
+                    // Start and end tags are never read ahead and text is coalesced
+                    _ => unreachable!("unexpected event read ahead: {:?}", event),
+                };
+                let content: &[u8] = event;
+                (
+                    kind,
+                    content,
+                    span.start,
+                    span.end,
+                    position.line,
+                    position.column,
+                )
+            });
+            let bindings: Vec<_> = self
+                .ns_resolver
+                .bindings
+                .iter()
+                .map(|e| (e.start, e.prefix_len, e.value_len, e.level))
+                .collect();
+
+            #[cfg(feature = "encoding")]
+            let encoding = {
+                let (kind, raw, utf8, high_surrogate) = self.transcoder.to_parts();
+                (
+                    self.encoding.name(),
+                    self.is_encoding_set,
+                    kind,
+                    raw,
+                    utf8,
+                    high_surrogate,
+                )
+            };
+            #[cfg(not(feature = "encoding"))]
+            let encoding = ("UTF-8", false, 0u8, &[] as &[u8], &[] as &[u8], None::<u16>);
+
+            (
+                self.input_position,
+                self.buf_position,
+                tag,
+                (
+                    self.text_position.line,
+                    self.text_position.column,
+                    self.event_position.line,
+                    self.event_position.column,
+                    self.after_cr,
+                ),
+                (self.root_seen, self.doctype_seen, self.bom_len),
+                entities,
+                self.depth,
+                (&self.opened_buffer, &self.opened_starts),
+                (
+                    bindings,
+                    self.ns_resolver.nesting_level,
+                    self.pending_pop,
+                    &self.ns_buffer,
+                ),
+                encoding,
+                self.pending_start
+                    .as_ref()
+                    .map(|p| (&p.content, p.name_len, p.empty, p.span.start, p.span.end)),
+                tokenized,
+                lookahead,
+            )
+                .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for ReaderState {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let (
+                input_position,
+                buf_position,
+                tag,
+                tracker,
+                strict,
+                entities,
+                depth,
+                opened,
+                namespaces,
+                encoding,
+                pending,
+                tokenized,
+                lookahead,
+            ): Repr = Deserialize::deserialize(deserializer)?;
+
+            let tag_state = match tag {
+                (0, _) => TagState::Opened,
+                (1, _) => TagState::Closed,
+                (2, _) => TagState::Empty,
+                (3, depth) => TagState::AutoClose(depth),
+                (4, _) => TagState::Exit,
+                (5, _) => TagState::RawText,
+                (6, _) if pending.is_some() => TagState::PendingStart,
+                (tag, _) => return Err(D::Error::custom(format!("invalid tag state {}", tag))),
+            };
+            let pending_start = match pending {
+                Some((content, name_len, empty, start, end))
+                    if name_len <= content.len() && start <= end =>
+                {
+                    Some(PendingStart {
+                        content,
+                        name_len,
+                        empty,
+                        span: start..end,
+                    })
+                }
+                Some(_) => return Err(D::Error::custom("invalid pending start tag")),
+                None => None,
+            };
+
+            let lookahead = match lookahead {
+                Some((kind, content, start, end, line, column)) if start <= end => {
+                    let event = match kind {
+                        0 => Event::Comment(BytesText::from_escaped(content)),
+                        1 => Event::PI(BytesText::from_escaped(content)),
+                        2 => Event::DocType(BytesText::from_escaped(content)),
+                        3 if content.len() >= 3 => {
+                            Event::Decl(BytesDecl::from_start(BytesStart::owned(content, 3)))
+                        }
+                        4 => Event::Eof,
+                        _ => return Err(D::Error::custom("invalid event read ahead")),
+                    };
+                    Some((event, start..end, TextPosition { line, column }))
+                }
+                Some(_) => return Err(D::Error::custom("invalid event read ahead")),
+                None => None,
+            };
+
+            let (opened_buffer, opened_starts) = opened;
+            if opened_starts
+                .iter()
+                .any(|start| *start > opened_buffer.len())
+                || opened_starts.windows(2).any(|w| w[0] > w[1])
+            {
+                return Err(D::Error::custom("invalid stack of opened elements"));
+            }
+            // Each `Start` event increments the depth and pushes the element to the stack
+            if depth != opened_starts.len() {
+                return Err(D::Error::custom("depth does not match opened elements"));
+            }
+            // The end of the innermost opened element is pending
+            match tag_state {
+                TagState::Empty if opened_starts.is_empty() => {
+                    return Err(D::Error::custom("no opened element to close"))
+                }
+                TagState::AutoClose(depth) if depth >= opened_starts.len() => {
+                    return Err(D::Error::custom("no opened element to close"))
+                }
+                _ => {}
+            }
+
+            let (bindings, nesting_level, pending_pop, ns_buffer) = namespaces;
+            let bindings = bindings
+                .into_iter()
+                .map(|(start, prefix_len, value_len, level)| {
+                    match start
+                        .checked_add(prefix_len)
+                        .and_then(|end| end.checked_add(value_len))
+                    {
+                        Some(end) if end <= ns_buffer.len() => Ok(NamespaceEntry {
+                            start,
+                            prefix_len,
+                            value_len,
+                            level,
+                        }),
+                        _ => Err(D::Error::custom("invalid namespace binding")),
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+
+            let (name, is_encoding_set, kind, raw, utf8, high_surrogate) = encoding;
+            #[cfg(feature = "encoding")]
+            let (encoding, transcoder) = {
+                let encoding = Encoding::for_label(name.as_bytes())
+                    .ok_or_else(|| D::Error::custom(format!("unknown encoding {}", name)))?;
+                let transcoder =
+                    Transcoder::from_parts(kind, raw, utf8, high_surrogate, input_position)
+                        .ok_or_else(|| D::Error::custom("invalid state of the transcoder"))?;
+                (encoding, transcoder)
+            };
+            #[cfg(not(feature = "encoding"))]
+            {
+                let _ = (name, is_encoding_set, high_surrogate);
+                // Only UTF-8 documents can be read without the `encoding` feature,
+                // bytes of them are never buffered in the state
+                if kind > 1 || !raw.is_empty() || !utf8.is_empty() {
+                    return Err(D::Error::custom(
+                        "the `encoding` feature is required to resume reading of the document",
+                    ));
+                }
+            }
+
+            Ok(ReaderState {
+                input_position,
+                buf_position,
+                tag_state,
+                text_position: TextPosition {
+                    line: tracker.0,
+                    column: tracker.1,
+                },
+                event_position: TextPosition {
+                    line: tracker.2,
+                    column: tracker.3,
+                },
+                after_cr: tracker.4,
+                root_seen: strict.0,
+                doctype_seen: strict.1,
+                bom_len: strict.2,
+                entities: entities.into_iter().collect(),
+                tokenized_attributes: tokenized.into_iter().collect(),
+                depth,
+                opened_buffer,
+                opened_starts,
+                ns_resolver: NamespaceResolver {
+                    bindings,
+                    nesting_level,
+                },
+                ns_buffer,
+                pending_pop,
+                pending_start,
+                lookahead,
+                #[cfg(feature = "encoding")]
+                encoding,
+                #[cfg(feature = "encoding")]
+                is_encoding_set,
+                #[cfg(feature = "encoding")]
+                transcoder,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::Event;
+    use pretty_assertions::assert_eq;
+
+    /// Reads the next event with namespace resolution and formats it together
+    /// with the position and unescaped text
+    fn next<R: std::io::BufRead>(
+        reader: &mut Reader<R>,
+        ns_buffer: &mut Vec<u8>,
+    ) -> Option<String> {
+        let mut buf = Vec::new();
+        let (ns, event) = reader.read_namespaced_event(&mut buf, ns_buffer).unwrap();
+        let text = match &event {
+            Event::Eof => return None,
+            Event::Text(e) => e.unescape_and_decode(reader).unwrap(),
+            _ => String::new(),
+        };
+        Some(format!(
+            "{:?} {:?} {:?} {} {:?}",
+            ns,
+            event,
+            text,
+            reader.buffer_position(),
+            reader.event_position()
+        ))
+    }
+
+    /// Checks that reading resumed after each event returns the same events
+    /// as reading without interruption
+    fn check(input: &[u8], configure: fn(&mut Reader<&[u8]>)) {
+        let mut reader = Reader::from_reader(input);
+        configure(&mut reader);
+        let mut ns_buffer = Vec::new();
+        let mut expected = Vec::new();
+        while let Some(event) = next(&mut reader, &mut ns_buffer) {
+            expected.push(event);
+        }
+
+        for count in 0..=expected.len() {
+            let mut reader = Reader::from_reader(input);
+            configure(&mut reader);
+            let mut ns_buffer = Vec::new();
+            for _ in 0..count {
+                next(&mut reader, &mut ns_buffer).unwrap();
+            }
+            let state = reader.state(&ns_buffer);
+
+            let mut reader = Reader::from_reader(&input[state.input_position()..]);
+            configure(&mut reader);
+            let mut ns_buffer = b"garbage".to_vec();
+            reader.resume(state, &mut ns_buffer);
+            let mut events = Vec::new();
+            while let Some(event) = next(&mut reader, &mut ns_buffer) {
+                events.push(event);
+            }
+            assert_eq!(events, &expected[count..], "resumed after {} events", count);
+        }
+    }
+
+    const XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE root [<!ENTITY e "entity">]>
+<root xmlns="urn:default" xmlns:p="urn:p">
+    <p:child attr="value">text &e;</p:child>
+    <empty xmlns="urn:empty"/>
+    <!-- comment -->
+    <child>текст</child>
+</root>
+"#;
+
+    #[test]
+    fn resume() {
+        check(XML.as_bytes(), |_| {});
+    }
+
+    #[test]
+    fn configured() {
+        check(XML.as_bytes(), |r| {
+            r.trim_text(true)
+                .expand_empty_elements(true)
+                .track_position(true)
+                .strict(true);
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "encoding")]
+    fn utf16() {
+        let input: Vec<u8> = format!("\u{FEFF}{}", XML)
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes())
+            .collect();
+        check(&input, |r| {
+            r.track_position(true);
+        });
+    }
+
+    /// Stored state restores the same state
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn serialize() {
+        use serde::Deserialize;
+
+        let mut reader = Reader::from_str(XML);
+        let mut ns_buffer = Vec::new();
+        for _ in 0..8 {
+            next(&mut reader, &mut ns_buffer).unwrap();
+        }
+        let state = reader.state(&ns_buffer);
+        let value = serde_value::to_value(&state).unwrap();
+        assert_eq!(ReaderState::deserialize(value).unwrap(), state);
+    }
+
+    /// Corrupted state is rejected
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn corrupted() {
+        use serde::Deserialize;
+        use serde_value::Value;
+
+        let reader = Reader::from_str(XML);
+        let mut value = serde_value::to_value(&reader.state(&[])).unwrap();
+        // Replace the tag state
+        if let Value::Seq(fields) = &mut value {
+            fields[2] = Value::Seq(vec![Value::U8(42), Value::U64(0)]);
+        }
+        match ReaderState::deserialize(value) {
+            Err(e) => assert_eq!(e.to_string(), "invalid tag state 42"),
+            x => panic!("Expected error, but got {:?}", x),
+        }
+    }
+
+    /// State that closes an element which is not opened is rejected
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn inconsistent() {
+        use serde::Deserialize;
+        use serde_value::Value;
+
+        let reader = Reader::from_str(XML);
+        let value = serde_value::to_value(&reader.state(&[])).unwrap();
+        let corrupt = |index: usize, field: Value| {
+            let mut value = value.clone();
+            if let Value::Seq(fields) = &mut value {
+                fields[index] = field;
+            }
+            match ReaderState::deserialize(value) {
+                Err(e) => e.to_string(),
+                x => panic!("Expected error, but got {:?}", x),
+            }
+        };
+
+        // `Empty` and `AutoClose` tag states without opened elements
+        assert_eq!(
+            corrupt(2, Value::Seq(vec![Value::U8(2), Value::U64(0)])),
+            "no opened element to close"
+        );
+        assert_eq!(
+            corrupt(2, Value::Seq(vec![Value::U8(3), Value::U64(0)])),
+            "no opened element to close"
+        );
+        // Depth without opened elements
+        assert_eq!(
+            corrupt(6, Value::U64(1)),
+            "depth does not match opened elements"
+        );
+        // Opened element without depth
+        assert_eq!(
+            corrupt(
+                7,
+                Value::Seq(vec![Value::Seq(vec![]), Value::Seq(vec![Value::U64(0)]),])
+            ),
+            "depth does not match opened elements"
+        );
+    }
+}
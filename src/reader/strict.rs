@@ -0,0 +1,369 @@
+//! Checks of the XML 1.0 well-formedness constraints used by the [`Reader`]
+//! in the [strict mode].
+//!
+//! Names and characters are checked against the [`Name`] and [`Char`]
+//! productions of the specification. Characters are checked only in the
+//! documents in UTF-8 (and in UTF-16 or UTF-32, which are transcoded into UTF-8
+//! when the `encoding` feature is enabled), where byte sequences that are not
+//! valid UTF-8 are also rejected. In documents in other encodings, declared when
+//! the `encoding` feature is enabled, only ASCII characters are checked.
+//!
+//! [`Reader`]: super::Reader
+//! [strict mode]: super::Reader::strict
+//! [`Name`]: https://www.w3.org/TR/xml/#NT-Name
+//! [`Char`]: https://www.w3.org/TR/xml/#NT-Char
+
+use std::str::from_utf8;
+
+use crate::errors::{Error, Result};
+use crate::events::attributes::{Attr, IterState};
+use crate::reader::is_whitespace;
+
+/// Checks that the character matches the [`NameStartChar`] production.
+///
+/// [`NameStartChar`]: https://www.w3.org/TR/xml/#NT-NameStartChar
+fn is_name_start_char(ch: char) -> bool {
+    matches!(ch,
+        ':' | 'A'..='Z' | '_' | 'a'..='z'
+        | '\u{C0}'..='\u{D6}'
+        | '\u{D8}'..='\u{F6}'
+        | '\u{F8}'..='\u{2FF}'
+        | '\u{370}'..='\u{37D}'
+        | '\u{37F}'..='\u{1FFF}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}'
+        | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}'
+        | '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+/// Checks that the character matches the [`NameChar`] production.
+///
+/// [`NameChar`]: https://www.w3.org/TR/xml/#NT-NameChar
+fn is_name_char(ch: char) -> bool {
+    is_name_start_char(ch)
+        || matches!(ch,
+            '-' | '.' | '0'..='9'
+            | '\u{B7}'
+            | '\u{300}'..='\u{36F}'
+            | '\u{203F}'..='\u{2040}'
+        )
+}
+
+/// Checks that the code point matches the [`Char`] production.
+///
+/// [`Char`]: https://www.w3.org/TR/xml/#NT-Char
+fn is_xml_char(ch: u32) -> bool {
+    matches!(ch,
+        0x9 | 0xA | 0xD
+        | 0x20..=0xD7FF
+        | 0xE000..=0xFFFD
+        | 0x10000..=0x10FFFF
+    )
+}
+
+/// Checks that `name` matches the [`Name`] production.
+///
+/// # Parameters
+/// - `name`: the name to check
+/// - `offset`: position of the name in the document
+///
+/// [`Name`]: https://www.w3.org/TR/xml/#NT-Name
+pub(crate) fn check_name(name: &[u8], offset: usize) -> Result<()> {
+    let valid = match from_utf8(name) {
+        Ok(s) => {
+            let mut chars = s.chars();
+            matches!(chars.next(), Some(c) if is_name_start_char(c)) && chars.all(is_name_char)
+        }
+        // Not an UTF-8 document, check only ASCII characters
+        Err(_) => name.iter().enumerate().all(|(i, &b)| {
+            !b.is_ascii()
+                || if i == 0 {
+                    is_name_start_char(b as char)
+                } else {
+                    is_name_char(b as char)
+                }
+        }),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidName(
+            String::from_utf8_lossy(name).into_owned(),
+            offset..offset + name.len(),
+        ))
+    }
+}
+
+/// Checks that all characters in `bytes` match the [`Char`] production.
+///
+/// # Parameters
+/// - `bytes`: the content to check
+/// - `offset`: position of the content in the document
+/// - `utf8`: `true` if the document is in UTF-8, otherwise only ASCII characters
+///   are checked
+///
+/// [`Char`]: https://www.w3.org/TR/xml/#NT-Char
+pub(crate) fn check_chars(bytes: &[u8], offset: usize, utf8: bool) -> Result<()> {
+    let (valid, error) = match from_utf8(bytes) {
+        Err(e) if utf8 => (&bytes[..e.valid_up_to()], Some(e)),
+        _ => (bytes, None),
+    };
+    // Valid UTF-8 can not contain surrogates, so only control characters,
+    // U+FFFE and U+FFFF do not match the production
+    for (i, &b) in valid.iter().enumerate() {
+        if b < 0x20 && !is_xml_char(b as u32) {
+            return Err(Error::InvalidChar(b as u32, offset + i));
+        }
+        // U+FFFE and U+FFFF are encoded in UTF-8 as EF BF BE and EF BF BF
+        if utf8 && b == 0xEF && matches!(valid.get(i + 1..i + 3), Some([0xBF, 0xBE..=0xBF])) {
+            return Err(Error::InvalidChar(
+                0xFFF0 | (valid[i + 2] & 0x0F) as u32,
+                offset + i,
+            ));
+        }
+    }
+    let error = match error {
+        Some(error) => error,
+        None => return Ok(()),
+    };
+    let start = error.valid_up_to();
+    match bytes[start..] {
+        // Surrogates U+D800..U+DFFF are encoded as ED A0 80..ED BF BF
+        [0xED, b1 @ 0xA0..=0xBF, b2 @ 0x80..=0xBF, ..] => Err(Error::InvalidChar(
+            0xD000 | ((b1 & 0x3F) as u32) << 6 | (b2 & 0x3F) as u32,
+            offset + start,
+        )),
+        _ => {
+            let end = start + error.error_len().unwrap_or(bytes.len() - start);
+            let token = bytes[start..end]
+                .iter()
+                .map(|b| format!("\\x{:02X}", b))
+                .collect();
+            Err(Error::UnexpectedToken(token, offset + start..offset + end))
+        }
+    }
+}
+
+/// Checks that all `&` in `bytes` start a well-formed entity reference or a
+/// reference to a character that matches the [`Char`] production.
+///
+/// # Parameters
+/// - `bytes`: the escaped content to check
+/// - `offset`: position of the content in the document
+///
+/// [`Char`]: https://www.w3.org/TR/xml/#NT-Char
+pub(crate) fn check_references(bytes: &[u8], offset: usize) -> Result<()> {
+    for start in memchr::memchr_iter(b'&', bytes) {
+        let end = match memchr::memchr(b';', &bytes[start..]) {
+            Some(end) => start + end,
+            None => {
+                return Err(Error::UnexpectedToken(
+                    "&".to_string(),
+                    offset + start..offset + start + 1,
+                ))
+            }
+        };
+        let reference = &bytes[start + 1..end];
+        let code = match reference {
+            [b'#', b'x', hex @ ..] => from_utf8(hex)
+                .ok()
+                .filter(|h| !h.is_empty() && h.bytes().all(|b| b.is_ascii_hexdigit()))
+                .map(|h| u32::from_str_radix(h, 16).unwrap_or(u32::MAX)),
+            [b'#', dec @ ..] => from_utf8(dec)
+                .ok()
+                .filter(|d| !d.is_empty() && d.bytes().all(|b| b.is_ascii_digit()))
+                .map(|d| d.parse().unwrap_or(u32::MAX)),
+            name => {
+                check_name(name, offset + start + 1)?;
+                continue;
+            }
+        };
+        match code {
+            Some(code) if is_xml_char(code) => {}
+            Some(code) => return Err(Error::InvalidChar(code, offset + start)),
+            None => {
+                return Err(Error::UnexpectedToken(
+                    String::from_utf8_lossy(&bytes[start..=end]).into_owned(),
+                    offset + start..offset + end + 1,
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks the character data of a `Text` event: characters, references and
+/// absence of the `]]>` sequence. Text outside of the root element can contain
+/// only whitespaces.
+///
+/// # Parameters
+/// - `bytes`: the escaped text to check
+/// - `offset`: position of the text in the document
+/// - `in_root`: `true` if the text is inside of the root element
+/// - `utf8`: `true` if the document is in UTF-8
+pub(crate) fn check_text(bytes: &[u8], offset: usize, in_root: bool, utf8: bool) -> Result<()> {
+    if !in_root {
+        if let Some(start) = bytes.iter().position(|&b| !is_whitespace(b)) {
+            let end = bytes.iter().rposition(|&b| !is_whitespace(b)).unwrap() + 1;
+            return Err(Error::ContentOutsideRoot(offset + start..offset + end));
+        }
+    }
+    if let Some(p) = memchr::memmem::find(bytes, b"]]>") {
+        return Err(Error::UnexpectedToken(
+            "]]>".to_string(),
+            offset + p..offset + p + 3,
+        ));
+    }
+    check_chars(bytes, offset, utf8)?;
+    check_references(bytes, offset)
+}
+
+/// Checks names, values and uniqueness of attributes of a start tag.
+///
+/// # Parameters
+/// - `content`: the content of the tag between `<` and `>` (or `/>`)
+/// - `name_len`: the length of the element name
+/// - `offset`: position of the content in the document
+/// - `utf8`: `true` if the document is in UTF-8
+pub(crate) fn check_attributes(
+    content: &[u8],
+    name_len: usize,
+    offset: usize,
+    utf8: bool,
+) -> Result<()> {
+    let mut state = IterState::new(name_len, false);
+    while let Some(attr) = state.next(content) {
+        match attr.map_err(|e| e.shift(offset))? {
+            Attr::DoubleQ(key, value) | Attr::SingleQ(key, value) => {
+                check_name(&content[key.clone()], offset + key.start)?;
+                let value_offset = offset + value.start;
+                let value = &content[value];
+                if let Some(p) = memchr::memchr(b'<', value) {
+                    return Err(Error::UnexpectedToken(
+                        "<".to_string(),
+                        value_offset + p..value_offset + p + 1,
+                    ));
+                }
+                check_chars(value, value_offset, utf8)?;
+                check_references(value, value_offset)?;
+            }
+            // Only in HTML mode
+            Attr::Unquoted(..) | Attr::Empty(..) => unreachable!(),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::attributes::AttrError;
+
+    #[test]
+    fn names() {
+        assert!(check_name(b"name", 0).is_ok());
+        assert!(check_name(b"_n:a-m.e1", 0).is_ok());
+        assert!(check_name("имя".as_bytes(), 0).is_ok());
+        // Not an UTF-8: non-ASCII bytes are allowed
+        assert!(check_name(b"\xE8\xEC\xFF", 0).is_ok());
+
+        assert_err!(check_name(b"", 5), Error::InvalidName(_, span) if span == (5..5));
+        assert_err!(check_name(b"1name", 5), Error::InvalidName(_, span) if span == (5..10));
+        assert_err!(check_name(b"-name", 0), Error::InvalidName(..));
+        assert_err!(check_name(b"na%me", 0), Error::InvalidName(..));
+        assert_err!(
+            check_name("na\u{D7}me".as_bytes(), 0),
+            Error::InvalidName(..)
+        );
+    }
+
+    #[test]
+    fn chars() {
+        assert!(check_chars(b"text\t\r\n", 0, true).is_ok());
+        assert!(check_chars("текст \u{FFFD}".as_bytes(), 0, true).is_ok());
+
+        assert_err!(
+            check_chars(b"te\x01xt", 10, true),
+            Error::InvalidChar(1, 12)
+        );
+        assert_err!(
+            check_chars("te\u{FFFE}".as_bytes(), 10, true),
+            Error::InvalidChar(0xFFFE, 12)
+        );
+        assert_err!(
+            check_chars("te\u{FFFF}".as_bytes(), 10, true),
+            Error::InvalidChar(0xFFFF, 12)
+        );
+        // Surrogates and byte sequences that are not UTF-8
+        assert_err!(
+            check_chars(b"te\xED\xA0\x80", 10, true),
+            Error::InvalidChar(0xD800, 12)
+        );
+        assert_err!(
+            check_chars(b"te\xED\xBF\xBFxt", 10, true),
+            Error::InvalidChar(0xDFFF, 12)
+        );
+        assert_err!(
+            check_chars(b"te\xFFxt", 10, true),
+            Error::UnexpectedToken(token, span) if token == "\\xFF" && span == (12..13)
+        );
+        assert_err!(
+            check_chars(b"te\xD0", 10, true),
+            Error::UnexpectedToken(_, span) if span == (12..13)
+        );
+        // Only ASCII characters are checked in other encodings
+        assert!(check_chars(b"te\xED\xA0\x80\xFF\xEF\xBF\xBF", 0, false).is_ok());
+        assert_err!(
+            check_chars(b"te\x01\xFF", 10, false),
+            Error::InvalidChar(1, 12)
+        );
+    }
+
+    #[test]
+    fn references() {
+        assert!(check_references(b"&amp; &#9; &#x10FFFF; &custom;", 0).is_ok());
+
+        assert_err!(check_references(b"a & b", 10), Error::UnexpectedToken(_, span) if span == (12..13));
+        assert_err!(check_references(b"&#1;", 10), Error::InvalidChar(1, 10));
+        assert_err!(
+            check_references(b"&#xFFFE;", 10),
+            Error::InvalidChar(0xFFFE, 10)
+        );
+        assert_err!(
+            check_references(b"&#99999999999;", 10),
+            Error::InvalidChar(..)
+        );
+        assert_err!(check_references(b"&#x;", 10), Error::UnexpectedToken(_, span) if span == (10..14));
+        assert_err!(check_references(b"&1a;", 10), Error::InvalidName(_, span) if span == (11..13));
+    }
+
+    #[test]
+    fn text() {
+        assert!(check_text(b" \r\n\t", 0, false, true).is_ok());
+        assert!(check_text(b"text ]]", 0, true, true).is_ok());
+
+        assert_err!(check_text(b" text ", 10, false, true), Error::ContentOutsideRoot(span) if span == (11..15));
+        assert_err!(check_text(b"a]]>b", 10, true, true), Error::UnexpectedToken(_, span) if span == (11..14));
+    }
+
+    #[test]
+    fn attributes() {
+        assert!(check_attributes(b"tag a='1' b=\"&amp;\"", 3, 0, true).is_ok());
+
+        assert_err!(
+            check_attributes(b"tag a='1' a='2'", 3, 1, true),
+            Error::InvalidAttr(AttrError::Duplicated(11, 5))
+        );
+        assert_err!(check_attributes(b"tag 1a='1'", 3, 1, true), Error::InvalidName(_, span) if span == (5..7));
+        assert_err!(check_attributes(b"tag a='<'", 3, 1, true), Error::UnexpectedToken(_, span) if span == (8..9));
+        assert_err!(
+            check_attributes(b"tag a='&#0;'", 3, 1, true),
+            Error::InvalidChar(0, 8)
+        );
+        assert!(check_attributes(b"tag", 3, 0, true).is_ok());
+    }
+}
@@ -0,0 +1,901 @@
+//! Splitting of documents into raw XML fragments of elements (subtrees) and
+//! reading of the raw content of elements.
+
+use std::borrow::Cow;
+use std::io::{self, BufRead, Read};
+use std::ops::Range;
+
+use crate::errors::{Error, Result};
+use crate::events::Event;
+use crate::reader::{Reader, TagState};
+use crate::writer::Writer;
+
+/// A raw XML fragment of an element: its start tag, content and end tag, as
+/// they are written in the document. Created by [`Subtrees`] and
+/// [`UnbufferedSubtrees`] iterators.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subtree<'a> {
+    bytes: Cow<'a, [u8]>,
+    /// Position of the fragment in the input
+    start: usize,
+}
+
+impl<'a> Subtree<'a> {
+    /// Returns the bytes of the fragment.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the position of the fragment in the input.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.start + self.bytes.len()
+    }
+
+    /// Returns the bytes of the fragment, borrowed from the input of the
+    /// reader, if possible.
+    pub fn into_inner(self) -> Cow<'a, [u8]> {
+        self.bytes
+    }
+
+    /// Converts the fragment into one that owns its bytes.
+    pub fn into_owned(self) -> Subtree<'static> {
+        Subtree {
+            bytes: Cow::Owned(self.bytes.into_owned()),
+            start: self.start,
+        }
+    }
+
+    /// Returns a reader of the fragment, positioned at its start. The reader has the default configuration and its positions
+    /// are relative to the start of the fragment.
+    pub fn reader(&self) -> Reader<&[u8]> {
+        Reader::from_bytes(&self.bytes)
+    }
+}
+
+/// Returns `true` if the event, that was just read, is an `End` event that is
+/// not present in the input. When an end tag or a start tag closes several
+/// elements in the HTML and recovery modes, only the last `End` event
+/// corresponds to the end tag
+fn is_implied_end<R>(reader: &Reader<R>, event: &Event) -> bool {
+    matches!(event, Event::End(_))
+        && matches!(
+            reader.tag_state,
+            TagState::AutoClose(_) | TagState::PendingStart
+        )
+}
+
+/// Tracks the end of a subtree in the input while its events are read
+struct SubtreeEnd {
+    /// The number of opened elements, including the root of the subtree
+    depth: usize,
+    /// The end of the last event of the subtree, that is present in the input
+    end: usize,
+}
+
+impl SubtreeEnd {
+    /// Should be called after the start tag of the subtree is read
+    fn new<R>(reader: &Reader<R>) -> Self {
+        Self {
+            depth: reader.depth(),
+            end: reader.event_span.end,
+        }
+    }
+
+    /// Handles the event of the subtree and returns `true` if the event is
+    /// present in the input
+    fn event<R>(&mut self, reader: &Reader<R>, event: &Event) -> Result<bool> {
+        match event {
+            Event::Eof => {
+                let end = reader.buf_position;
+                Err(Error::UnexpectedEof("Element".to_string(), end..end))
+            }
+            _ if is_implied_end(reader, event) => Ok(false),
+            Event::End(_) => {
+                self.end = reader.buf_position;
+                Ok(true)
+            }
+            _ => {
+                self.end = reader.event_span.end;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Returns `true` if the root of the subtree was closed
+    fn closed<R>(&self, reader: &Reader<R>) -> bool {
+        reader.depth() < self.depth
+    }
+}
+
+/// A [`BufRead`] adapter over the source of the reader, which appends the
+/// consumed bytes to the buffer, if it is given. Used to get the raw input of
+/// subtrees and contents of elements from readers, that copy events into the
+/// user-provided buffer
+pub(crate) struct Recorder<'a, S> {
+    inner: S,
+    recorded: Option<&'a mut Vec<u8>>,
+}
+
+impl<'a, S: BufRead> Recorder<'a, S> {
+    pub fn new(inner: S, recorded: Option<&'a mut Vec<u8>>) -> Self {
+        Recorder { inner, recorded }
+    }
+}
+
+impl<'a, S: BufRead> Read for Recorder<'a, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        if let Some(recorded) = &mut self.recorded {
+            recorded.extend_from_slice(&buf[..len]);
+        }
+        Ok(len)
+    }
+}
+
+impl<'a, S: BufRead> BufRead for Recorder<'a, S> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Some(recorded) = &mut self.recorded {
+            // The consumed bytes are always available in the buffer, so it
+            // is returned without reading
+            if let Ok(available) = self.inner.fill_buf() {
+                recorded.extend_from_slice(&available[..amt]);
+            }
+        }
+        self.inner.consume(amt);
+    }
+}
+
+impl<R> Reader<R> {
+    /// Returns the position of the first recorded byte in the input
+    fn recorded_start(&self) -> usize {
+        self.buf_position - self.recorded.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Returns the recorded input in the range of positions
+    fn recorded(&self, range: Range<usize>) -> &[u8] {
+        let start = self.recorded_start();
+        match &self.recorded {
+            Some(recorded) => &recorded[range.start - start..range.end - start],
+            None => &[],
+        }
+    }
+
+    /// Removes the recorded input before the position
+    fn forget_recorded(&mut self, position: usize) {
+        let start = self.recorded_start();
+        if let Some(recorded) = &mut self.recorded {
+            recorded.drain(..position.saturating_sub(start));
+        }
+    }
+
+    /// Returns the position of the input, which is needed to read the next
+    /// events
+    fn next_input(&self) -> usize {
+        match (&self.pending_start, &self.tag_state) {
+            // The start tag is already consumed
+            (Some(start), _) => start.span.start,
+            // `<` of the next markup is already consumed
+            (None, TagState::Opened) => self.buf_position - 1,
+            _ => self.buf_position,
+        }
+    }
+}
+
+/// Iterator over subtrees of elements with the specified name, which owns the
+/// bytes of subtrees, created by [`Reader::subtrees()`].
+///
+/// The input of the reader is recorded while the iterator exists.
+pub struct Subtrees<'r, R: BufRead> {
+    reader: &'r mut Reader<R>,
+    name: Vec<u8>,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl<'r, R: BufRead> Subtrees<'r, R> {
+    fn read_next(&mut self) -> Result<Option<Subtree<'static>>> {
+        let reader = &mut *self.reader;
+        let (start_tag, empty) = loop {
+            // Only the input of the next events is needed
+            let position = reader.next_input();
+            reader.forget_recorded(position);
+            self.buf.clear();
+            match reader.read_event(&mut self.buf)? {
+                Event::Eof => return Ok(None),
+                event @ Event::Start(_) | event @ Event::Empty(_) => {
+                    let empty = matches!(event, Event::Empty(_));
+                    let matches = match &event {
+                        Event::Start(e) | Event::Empty(e) => e.name().as_ref() == self.name,
+                        _ => false,
+                    };
+                    if matches {
+                        break (event.into_owned(), empty);
+                    }
+                }
+                _ => {}
+            }
+        };
+        let start = reader.event_span.start;
+        let start_end = reader.event_span.end;
+        let mut end = SubtreeEnd::new(reader);
+        while !empty && !end.closed(reader) {
+            self.buf.clear();
+            let event = reader.read_event(&mut self.buf)?;
+            end.event(reader, &event)?;
+        }
+        let bytes = if start >= reader.recorded_start() {
+            reader.recorded(start..end.end).to_vec()
+        } else {
+            // The iterator was created when the start tag was already partially
+            // read, so it is written from its event
+            let mut writer = Writer::new(Vec::new());
+            writer.write_event(&start_tag)?;
+            let mut bytes = writer.into_inner();
+            bytes.extend_from_slice(reader.recorded(start_end..end.end));
+            bytes
+        };
+        // The rest of the recorded input can contain the start of the next subtree
+        reader.forget_recorded(end.end);
+        Ok(Some(Subtree {
+            bytes: Cow::Owned(bytes),
+            start,
+        }))
+    }
+}
+
+impl<'r, R: BufRead> Iterator for Subtrees<'r, R> {
+    type Item = Result<Subtree<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_next().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+impl<'r, R: BufRead> Drop for Subtrees<'r, R> {
+    fn drop(&mut self) {
+        self.reader.recorded = None;
+    }
+}
+
+/// Iterator over subtrees of elements with the specified name, which borrows
+/// the bytes of subtrees from the input, created by [`Reader::subtrees_unbuffered()`].
+pub struct UnbufferedSubtrees<'r, 'a> {
+    reader: &'r mut Reader<&'a [u8]>,
+    name: Vec<u8>,
+    done: bool,
+}
+
+impl<'r, 'a> UnbufferedSubtrees<'r, 'a> {
+    fn read_next(&mut self) -> Result<Option<Subtree<'a>>> {
+        let reader = &mut *self.reader;
+        // The rest of the input, which contains the subtree, and its position.
+        // It is remembered only before a text, because `<` of the next tag is
+        // consumed together with the text
+        let (mut input, mut position) = (reader.reader, reader.buf_position);
+        let (start_tag, empty) = loop {
+            if let TagState::Closed = reader.tag_state {
+                input = reader.reader;
+                position = reader.buf_position;
+            }
+            match reader.read_event_unbuffered()? {
+                Event::Eof => return Ok(None),
+                event @ Event::Start(_) | event @ Event::Empty(_) => {
+                    let empty = matches!(event, Event::Empty(_));
+                    let matches = match &event {
+                        Event::Start(e) | Event::Empty(e) => e.name().as_ref() == self.name,
+                        _ => false,
+                    };
+                    if matches {
+                        break (event, empty);
+                    }
+                }
+                _ => {}
+            }
+        };
+        let start = reader.event_span.start;
+        let start_end = reader.event_span.end;
+        let mut end = SubtreeEnd::new(reader);
+        while !empty && !end.closed(reader) {
+            let event = reader.read_event_unbuffered()?;
+            end.event(reader, &event)?;
+        }
+        let bytes = if start >= position {
+            Cow::Borrowed(&input[start - position..end.end - position])
+        } else {
+            // The iterator was created when the start tag was already partially
+            // read, so it is written from its event
+            let mut writer = Writer::new(Vec::new());
+            writer.write_event(&start_tag)?;
+            let mut bytes = writer.into_inner();
+            bytes.extend_from_slice(&input[start_end - position..end.end - position]);
+            Cow::Owned(bytes)
+        };
+        Ok(Some(Subtree { bytes, start }))
+    }
+}
+
+impl<'r, 'a> Iterator for UnbufferedSubtrees<'r, 'a> {
+    type Item = Result<Subtree<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_next().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Returns an iterator over subtrees of elements with the specified name:
+    /// the raw bytes of their start tags, content and end tags. Subtrees of
+    /// nested elements with the same name are a part of the outer subtree and
+    /// are not returned separately.
+    ///
+    /// The bytes of subtrees are copied from the input as the reader consumes
+    /// it, so options of the reader, that change the content of events, like
+    /// [`Self::trim_text()`], do not change them. Use
+    /// [`Self::subtrees_unbuffered()`] to borrow subtrees from the input.
+    ///
+    /// The iteration stops after the first error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::Reader;
+    ///
+    /// let xml = "<dump><record id='1'>one</record> <record id='2'/></dump>";
+    /// let mut reader = Reader::from_reader(xml.as_bytes());
+    /// reader.trim_text(true);
+    ///
+    /// let subtrees: Vec<_> = reader.subtrees("record").collect::<Result<_, _>>().unwrap();
+    /// assert_eq!(subtrees[0].bytes(), b"<record id='1'>one</record>");
+    /// assert_eq!(&xml[subtrees[1].range()], "<record id='2'/>");
+    ///
+    /// // Each subtree can be read by its own reader
+    /// let mut reader = subtrees[0].reader();
+    /// reader.trim_text(true);
+    /// assert!(matches!(reader.read_event_unbuffered().unwrap(), Event::Start(_)));
+    /// ```
+    pub fn subtrees<K: AsRef<[u8]>>(&mut self, name: K) -> Subtrees<'_, R> {
+        self.recorded = Some(Vec::new());
+        Subtrees {
+            reader: self,
+            name: name.as_ref().to_vec(),
+            buf: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Reads until end element is found, like [`Self::read_to_end()`], and
+    /// returns the raw content of the element between its start and end tags
+    /// (`innerXML`).
+    ///
+    /// The content is copied from the input as the reader consumes it, so
+    /// options of the reader, that change the content of events, like
+    /// [`Self::trim_text()`], do not change it. Use
+    /// [`Self::read_inner_xml_unbuffered()`] to borrow the content from the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_reader(&b"<p>Some <b>bold</b> text</p>"[..]);
+    /// reader.trim_text(true);
+    /// let mut buf = Vec::new();
+    ///
+    /// match reader.read_event(&mut buf).unwrap() {
+    ///     Event::Start(e) => {
+    ///         let name = e.name().as_ref().to_vec();
+    ///         let inner = reader.read_inner_xml(name, &mut Vec::new()).unwrap();
+    ///         assert_eq!(inner, b"Some <b>bold</b> text");
+    ///     }
+    ///     e => panic!("Expected `Start` event, found {:?}", e),
+    /// }
+    /// assert_eq!(reader.read_event(&mut buf).unwrap(), Event::Eof);
+    /// ```
+    pub fn read_inner_xml<K: AsRef<[u8]>>(&mut self, end: K, buf: &mut Vec<u8>) -> Result<Vec<u8>> {
+        self.recorded = Some(Vec::new());
+        let result = self.record_inner_xml(end.as_ref(), buf);
+        self.recorded = None;
+        result
+    }
+
+    fn record_inner_xml(&mut self, end: &[u8], buf: &mut Vec<u8>) -> Result<Vec<u8>> {
+        // The content starts right after the start tag
+        let position = self.buf_position;
+        // The end of the content read so far
+        let mut inner_end = position;
+        let mut depth = 0;
+        let start = self.markup_start;
+        loop {
+            let event = self.read_event(buf)?;
+            // `End` events, that are not present in the input, have an empty span
+            // and end the content at the last read markup
+            let span = self.event_span();
+            if !span.is_empty() {
+                inner_end = match event {
+                    Event::End(_) => span.start,
+                    _ => span.end,
+                };
+            }
+            match event {
+                Event::End(ref e) if e.name().as_ref() == end => {
+                    if depth == 0 {
+                        return Ok(self.recorded(position..inner_end).to_vec());
+                    }
+                    depth -= 1;
+                }
+                Event::Start(ref e) if e.name().as_ref() == end => depth += 1,
+                Event::Eof => {
+                    return Err(Error::UnexpectedEof(
+                        format!("</{:?}>", std::str::from_utf8(end)),
+                        start..self.buf_position,
+                    ));
+                }
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+}
+
+impl<'a> Reader<&'a [u8]> {
+    /// Returns an iterator over subtrees of elements with the specified name,
+    /// which borrow their bytes from the input. See [`Self::subtrees()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use std::borrow::Cow;
+    /// use quick_xml::Reader;
+    ///
+    /// let xml = "<dump><record>one</record><record>two</record></dump>";
+    /// let mut reader = Reader::from_str(xml);
+    /// let mut subtrees = reader.subtrees_unbuffered("record");
+    ///
+    /// let subtree = subtrees.next().unwrap().unwrap();
+    /// assert_eq!(&xml[subtree.range()], "<record>one</record>");
+    /// assert!(matches!(subtree.into_inner(), Cow::Borrowed(_)));
+    /// ```
+    pub fn subtrees_unbuffered<K: AsRef<[u8]>>(&mut self, name: K) -> UnbufferedSubtrees<'_, 'a> {
+        UnbufferedSubtrees {
+            reader: self,
+            name: name.as_ref().to_vec(),
+            done: false,
+        }
+    }
+
+    /// Reads until end element is found, like [`Self::read_to_end_unbuffered()`],
+    /// and returns the raw content of the element between its start and end
+    /// tags (`innerXML`), borrowed from the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str("<p>Some <b>bold</b> text</p>");
+    /// reader.trim_text(true);
+    ///
+    /// match reader.read_event_unbuffered().unwrap() {
+    ///     Event::Start(e) => {
+    ///         let inner = reader.read_inner_xml_unbuffered(e.name()).unwrap();
+    ///         assert_eq!(inner, b"Some <b>bold</b> text");
+    ///     }
+    ///     e => panic!("Expected `Start` event, found {:?}", e),
+    /// }
+    /// assert_eq!(reader.read_event_unbuffered().unwrap(), Event::Eof);
+    /// ```
+    pub fn read_inner_xml_unbuffered<K: AsRef<[u8]>>(&mut self, end: K) -> Result<&'a [u8]> {
+        // The content starts right after the start tag
+        let (input, position) = (self.reader, self.buf_position);
+        // The end of the content read so far
+        let mut inner_end = position;
+        let mut depth = 0;
+        let end = end.as_ref();
+        let start = self.markup_start;
+        loop {
+            let event = self.read_event_unbuffered()?;
+            // `End` events, that are not present in the input, have an empty span
+            // and end the content at the last read markup
+            let span = self.event_span();
+            if !span.is_empty() {
+                inner_end = match event {
+                    Event::End(_) => span.start,
+                    _ => span.end,
+                };
+            }
+            match event {
+                Event::End(ref e) if e.name().as_ref() == end => {
+                    if depth == 0 {
+                        return Ok(&input[..inner_end - position]);
+                    }
+                    depth -= 1;
+                }
+                Event::Start(ref e) if e.name().as_ref() == end => depth += 1,
+                Event::Eof => {
+                    return Err(Error::UnexpectedEof(
+                        format!("</{:?}>", std::str::from_utf8(end)),
+                        start..self.buf_position,
+                    ));
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Returns the subtrees, read by both iterators, and checks that they are equal
+    /// to the input
+    fn subtrees(xml: &str, name: &str, configure: fn(&mut Reader<&[u8]>)) -> Vec<String> {
+        let mut reader = Reader::from_reader(xml.as_bytes());
+        configure(&mut reader);
+        let buffered: Vec<_> = reader.subtrees(name).collect::<Result<_>>().unwrap();
+
+        let mut reader = Reader::from_str(xml);
+        configure(&mut reader);
+        let borrowed: Vec<_> = reader
+            .subtrees_unbuffered(name)
+            .collect::<Result<_>>()
+            .unwrap();
+
+        assert_eq!(buffered, borrowed);
+        for subtree in &borrowed {
+            assert_eq!(subtree.bytes(), xml[subtree.range()].as_bytes());
+        }
+        borrowed
+            .iter()
+            .map(|s| String::from_utf8(s.bytes().to_vec()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn simple() {
+        assert_eq!(
+            subtrees("<a><b x='1'>one</b> <c/> <b/><b>two</b></a>", "b", |_| {}),
+            vec!["<b x='1'>one</b>", "<b/>", "<b>two</b>"]
+        );
+        assert_eq!(subtrees("<a><b/></a>", "c", |_| {}), Vec::<String>::new());
+    }
+
+    #[test]
+    fn nested() {
+        assert_eq!(
+            subtrees("<a><b><b/><c><b>x</b></c></b><b></b></a>", "b", |_| {}),
+            vec!["<b><b/><c><b>x</b></c></b>", "<b></b>"]
+        );
+    }
+
+    #[test]
+    fn markup() {
+        assert_eq!(
+            subtrees(
+                "<a><b> <!-- c --> <![CDATA[<d>]]> &amp; <?pi?> </b></a>",
+                "b",
+                |_| {}
+            ),
+            vec!["<b> <!-- c --> <![CDATA[<d>]]> &amp; <?pi?> </b>"]
+        );
+    }
+
+    #[test]
+    fn root() {
+        assert_eq!(subtrees("<a>x<b/></a>", "a", |_| {}), vec!["<a>x<b/></a>"]);
+    }
+
+    /// Options, that change the content of events, do not change subtrees
+    #[test]
+    fn options() {
+        assert_eq!(
+            subtrees("<a> <b> <c/> </b > <c/> </a>", "b", |r| {
+                r.trim_text(true)
+                    .expand_empty_elements(true)
+                    .trim_markup_names_in_closing_tags(true)
+                    .coalesce_text(true);
+            }),
+            vec!["<b> <c/> </b >"]
+        );
+
+        // The reader continues after the iterator is dropped
+        let xml = "<a> <b> <c/> </b> <c/> </a>";
+        let mut reader = Reader::from_reader(xml.as_bytes());
+        reader.trim_text(true).expand_empty_elements(true);
+
+        let mut subtrees = reader.subtrees("b");
+        assert_eq!(subtrees.next().unwrap().unwrap().bytes(), b"<b> <c/> </b>");
+        drop(subtrees);
+        assert_eq!(reader.recorded, None);
+
+        let mut buf = Vec::new();
+        assert_eq!(
+            reader.read_event(&mut buf).unwrap(),
+            Event::Start(crate::events::BytesStart::borrowed_name(b"c"))
+        );
+        assert_eq!(
+            reader.read_event(&mut buf).unwrap(),
+            Event::End(crate::events::BytesEnd::borrowed(b"c"))
+        );
+    }
+
+    #[test]
+    fn unclosed() {
+        let xml = "<a><b><c></c>";
+
+        let mut reader = Reader::from_reader(xml.as_bytes());
+        let mut subtrees = reader.subtrees("b");
+        match subtrees.next() {
+            Some(Err(Error::UnexpectedEof(kind, span))) => {
+                assert_eq!(kind, "Element");
+                assert_eq!(span, 13..13);
+            }
+            x => panic!("Expected `Err(UnexpectedEof(_, _))`, but got {:?}", x),
+        }
+        assert!(subtrees.next().is_none());
+
+        let mut reader = Reader::from_str(xml);
+        let mut subtrees = reader.subtrees_unbuffered("b");
+        match subtrees.next() {
+            Some(Err(Error::UnexpectedEof(kind, span))) => {
+                assert_eq!(kind, "Element");
+                assert_eq!(span, 13..13);
+            }
+            x => panic!("Expected `Err(UnexpectedEof(_, _))`, but got {:?}", x),
+        }
+        assert!(subtrees.next().is_none());
+    }
+
+    /// End tags, that are implied in the HTML mode, are not a part of subtrees
+    #[test]
+    fn html() {
+        let xml = "<ul><li>one<li>two</ul><p><script>a < b</script ></p>";
+        let html = |r: &mut Reader<&[u8]>| {
+            r.html(true);
+        };
+
+        assert_eq!(subtrees(xml, "li", html), vec!["<li>one", "<li>two"]);
+        assert_eq!(subtrees(xml, "ul", html), vec!["<ul><li>one<li>two</ul>"]);
+        assert_eq!(
+            subtrees(xml, "p", html),
+            vec!["<p><script>a < b</script ></p>"]
+        );
+    }
+
+    /// Bare `&` in the recovery mode are not escaped
+    #[test]
+    fn recover() {
+        assert_eq!(
+            subtrees("<a><b>x & y</b></a>", "b", |r| {
+                r.recover(true);
+            }),
+            vec!["<b>x & y</b>"]
+        );
+    }
+
+    /// End tags with spaces after the name match their start tags
+    #[test]
+    fn trimmed_end_names() {
+        assert_eq!(
+            subtrees("<a><b>x</b ></a >", "a", |r| {
+                r.trim_markup_names_in_closing_tags(true);
+            }),
+            vec!["<a><b>x</b ></a >"]
+        );
+    }
+
+    /// The input is recorded when the underlying reader returns it in small chunks
+    #[test]
+    fn chunks() {
+        let xml = "<a> <b x='>'>one<?pi?></b> <b/>\r\n<b>\u{1F600}</b> </a>";
+        let mut reader = Reader::from_reader(std::io::BufReader::with_capacity(2, xml.as_bytes()));
+        let subtrees: Vec<_> = reader.subtrees("b").collect::<Result<_>>().unwrap();
+        for subtree in &subtrees {
+            assert_eq!(subtree.bytes(), xml[subtree.range()].as_bytes());
+        }
+        assert_eq!(subtrees.len(), 3);
+    }
+
+    mod inner_xml {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        /// Reads the first element with both methods, checks that the results
+        /// are equal and returns them
+        fn inner_xml(xml: &str, configure: fn(&mut Reader<&[u8]>)) -> String {
+            let mut reader = Reader::from_str(xml);
+            configure(&mut reader);
+            let mut buf = Vec::new();
+            let buffered = loop {
+                match reader.read_event(&mut buf).unwrap() {
+                    Event::Start(e) => {
+                        let name = e.name().as_ref().to_vec();
+                        break reader.read_inner_xml(name, &mut Vec::new()).unwrap();
+                    }
+                    Event::Eof => panic!("No elements found"),
+                    _ => buf.clear(),
+                }
+            };
+
+            let mut reader = Reader::from_str(xml);
+            configure(&mut reader);
+            let borrowed = loop {
+                match reader.read_event_unbuffered().unwrap() {
+                    Event::Start(e) => break reader.read_inner_xml_unbuffered(e.name()).unwrap(),
+                    Event::Eof => panic!("No elements found"),
+                    _ => {}
+                }
+            };
+
+            assert_eq!(String::from_utf8(buffered).unwrap(), from_utf8(borrowed));
+            from_utf8(borrowed)
+        }
+
+        fn from_utf8(bytes: &[u8]) -> String {
+            String::from_utf8(bytes.to_vec()).unwrap()
+        }
+
+        #[test]
+        fn content() {
+            assert_eq!(
+                inner_xml(
+                    "<a> <b x='1'>t &amp; <!--c--><![CDATA[<d>]]></b><?pi?> </a>",
+                    |_| {}
+                ),
+                " <b x='1'>t &amp; <!--c--><![CDATA[<d>]]></b><?pi?> "
+            );
+            assert_eq!(inner_xml("<a></a>", |_| {}), "");
+        }
+
+        #[test]
+        fn nested() {
+            assert_eq!(
+                inner_xml("<a><a>x</a><b><a/></b></a><a>y</a>", |_| {}),
+                "<a>x</a><b><a/></b>"
+            );
+        }
+
+        /// Options, that change the content of events, do not change the content
+        #[test]
+        fn options() {
+            assert_eq!(
+                inner_xml("<a> x <b/> </a >", |r| {
+                    r.trim_text(true)
+                        .expand_empty_elements(true)
+                        .trim_markup_names_in_closing_tags(true);
+                }),
+                " x <b/> "
+            );
+            assert_eq!(
+                inner_xml("<a/>", |r| {
+                    r.expand_empty_elements(true);
+                }),
+                ""
+            );
+        }
+
+        #[test]
+        fn html() {
+            assert_eq!(
+                inner_xml("<script>a < b</script>", |r| {
+                    r.html(true);
+                }),
+                "a < b"
+            );
+            assert_eq!(
+                inner_xml("<div><script>a < b</script ></div>", |r| {
+                    r.html(true);
+                }),
+                "<script>a < b</script >"
+            );
+            assert_eq!(
+                inner_xml("<ul><li>one<li>two</ul>", |r| {
+                    r.html(true);
+                }),
+                "<li>one<li>two"
+            );
+        }
+
+        /// `End` events of the elements, that are closed by an end tag of the
+        /// outer element, are not present in the input
+        #[test]
+        fn recover() {
+            assert_eq!(
+                inner_xml("<a>x & <b>y</a>", |r| {
+                    r.recover(true);
+                }),
+                "x & <b>y"
+            );
+        }
+
+        #[test]
+        fn unclosed() {
+            let mut reader = Reader::from_str("<a><b></b>");
+            reader.read_event_unbuffered().unwrap();
+            match reader.read_inner_xml_unbuffered("a") {
+                Err(Error::UnexpectedEof(_, span)) => assert_eq!(span, 0..10),
+                x => panic!("Expected `Err(UnexpectedEof(_, _))`, but got {:?}", x),
+            }
+
+            let mut reader = Reader::from_reader(&b"<a><b></b>"[..]);
+            reader.trim_text(true);
+            reader.read_event(&mut Vec::new()).unwrap();
+            match reader.read_inner_xml("a", &mut Vec::new()) {
+                Err(Error::UnexpectedEof(_, span)) => assert_eq!(span, 0..10),
+                x => panic!("Expected `Err(UnexpectedEof(_, _))`, but got {:?}", x),
+            }
+            // The input is not recorded after an error
+            assert_eq!(reader.recorded, None);
+        }
+    }
+
+    /// The start tag is written from its event if `<` was consumed before
+    /// the iterator was created
+    #[test]
+    fn after_text() {
+        let xml = "x<a>y</a>";
+        let mut reader = Reader::from_str(xml);
+        assert_eq!(
+            reader.read_event_unbuffered().unwrap(),
+            Event::Text(crate::events::BytesText::from_escaped(&b"x"[..]))
+        );
+
+        let subtree = reader.subtrees_unbuffered("a").next().unwrap().unwrap();
+        assert_eq!(subtree.range(), 1..9);
+        assert_eq!(
+            subtree.into_inner(),
+            Cow::<[u8]>::Owned(b"<a>y</a>".to_vec())
+        );
+    }
+
+    #[test]
+    fn reader() {
+        let xml = "<a><b>x</b></a>";
+        let mut reader = Reader::from_str(xml);
+        let subtree = reader.subtrees_unbuffered("b").next().unwrap().unwrap();
+        assert_eq!(subtree.range(), 3..11);
+
+        let mut reader = subtree.reader();
+        reader.trim_text(true);
+        assert!(matches!(
+            reader.read_event_unbuffered().unwrap(),
+            Event::Start(_)
+        ));
+        assert_eq!(
+            reader.read_event_unbuffered().unwrap(),
+            Event::Text(crate::events::BytesText::from_escaped(&b"x"[..]))
+        );
+        assert!(matches!(
+            reader.read_event_unbuffered().unwrap(),
+            Event::End(_)
+        ));
+        assert_eq!(reader.read_event_unbuffered().unwrap(), Event::Eof);
+        assert_eq!(reader.buffer_position(), 8);
+    }
+}
@@ -0,0 +1,390 @@
+//! Transcoding of UTF-16 and UTF-32 documents into UTF-8 before they are
+//! scanned by the [`XmlSource`] implementation for [`BufRead`].
+//!
+//! The markup scanning of the [`Reader`] searches for the ASCII bytes `<`, `>`,
+//! quotes and so on, and therefore works only with ASCII-compatible encodings.
+//! Documents in UTF-16 and UTF-32 are detected using the byte order mark or
+//! the first bytes of the document as described in [Appendix F] of the XML
+//! specification and transcoded on the fly, so the reader always sees UTF-8.
+//!
+//! [`XmlSource`]: super::XmlSource
+//! [`Reader`]: super::Reader
+//! [Appendix F]: https://www.w3.org/TR/xml11/#sec-guessing
+
+use std::io::{self, BufRead, Read};
+
+/// UTF-8 representation of the `U+FFFD REPLACEMENT CHARACTER`
+const REPLACEMENT: &[u8] = "\u{FFFD}".as_bytes();
+
+/// Encoding of the underlying byte stream, as detected by [`Transcoder::detect`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    /// Not yet detected, no bytes were read from the underlying reader
+    Unknown,
+    /// ASCII-compatible encoding, bytes are passed through as is
+    AsciiCompatible,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+/// The state of the transcoding, kept between reads of the events.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Transcoder {
+    kind: Kind,
+    /// Bytes read from the underlying reader which are not yet transcoded: the
+    /// bytes used to detect the encoding or incomplete code units
+    raw: Vec<u8>,
+    /// Transcoded bytes which are not yet consumed
+    utf8: Vec<u8>,
+    /// Position of the first unconsumed byte in `utf8`
+    pos: usize,
+    /// Surrogate from the previous code unit which waits for its pair
+    high_surrogate: Option<u16>,
+    /// Number of bytes consumed from the underlying reader
+    consumed: usize,
+}
+
+impl Transcoder {
+    pub fn new() -> Self {
+        Transcoder {
+            kind: Kind::Unknown,
+            raw: Vec::new(),
+            utf8: Vec::new(),
+            pos: 0,
+            high_surrogate: None,
+            consumed: 0,
+        }
+    }
+
+    /// Returns the number of bytes consumed from the underlying reader, if
+    /// anything was read through the transcoder
+    pub fn consumed(&self) -> Option<usize> {
+        match self.kind {
+            Kind::Unknown => None,
+            _ => Some(self.consumed),
+        }
+    }
+
+    /// Returns a copy of the state without already consumed transcoded bytes.
+    /// `consumed` is the number of bytes consumed from the underlying reader,
+    /// which is used when nothing was read through the transcoder yet
+    pub fn checkpoint(&self, consumed: usize) -> Self {
+        Transcoder {
+            kind: self.kind,
+            raw: self.raw.clone(),
+            utf8: self.utf8.get(self.pos..).unwrap_or_default().to_vec(),
+            pos: 0,
+            high_surrogate: self.high_surrogate,
+            consumed: self.consumed().unwrap_or(consumed),
+        }
+    }
+
+    /// Splits the state into the detected encoding (as a number), bytes waiting
+    /// for transcoding, not consumed transcoded bytes and the pending surrogate
+    #[cfg(feature = "serialize")]
+    pub fn to_parts(&self) -> (u8, &[u8], &[u8], Option<u16>) {
+        let kind = match self.kind {
+            Kind::Unknown => 0,
+            Kind::AsciiCompatible => 1,
+            Kind::Utf16Le => 2,
+            Kind::Utf16Be => 3,
+            Kind::Utf32Le => 4,
+            Kind::Utf32Be => 5,
+        };
+        let utf8 = self.utf8.get(self.pos..).unwrap_or_default();
+        (kind, &self.raw, utf8, self.high_surrogate)
+    }
+
+    /// The reverse of [`Self::to_parts`]. Returns `None` if the encoding
+    /// number is unknown
+    #[cfg(feature = "serialize")]
+    pub fn from_parts(
+        kind: u8,
+        raw: Vec<u8>,
+        utf8: Vec<u8>,
+        high_surrogate: Option<u16>,
+        consumed: usize,
+    ) -> Option<Self> {
+        let kind = match kind {
+            0 => Kind::Unknown,
+            1 => Kind::AsciiCompatible,
+            2 => Kind::Utf16Le,
+            3 => Kind::Utf16Be,
+            4 => Kind::Utf32Le,
+            5 => Kind::Utf32Be,
+            _ => return None,
+        };
+        Some(Transcoder {
+            kind,
+            raw,
+            utf8,
+            pos: 0,
+            high_surrogate,
+            consumed,
+        })
+    }
+
+    /// Returns `true` if the document was detected as UTF-16 or UTF-32 and the
+    /// reader sees it as UTF-8.
+    pub fn is_transcoding(&self) -> bool {
+        !matches!(self.kind, Kind::Unknown | Kind::AsciiCompatible)
+    }
+
+    /// Guesses the encoding from the first (up to) four bytes of the document.
+    fn detect(bytes: &[u8]) -> Kind {
+        match bytes {
+            [0x00, 0x00, 0xFE, 0xFF, ..] | [0x00, 0x00, 0x00, b'<', ..] => Kind::Utf32Be,
+            [0xFF, 0xFE, 0x00, 0x00, ..] | [b'<', 0x00, 0x00, 0x00, ..] => Kind::Utf32Le,
+            [0xFE, 0xFF, ..] | [0x00, b'<', 0x00, b'?', ..] => Kind::Utf16Be,
+            [0xFF, 0xFE, ..] | [b'<', 0x00, b'?', 0x00, ..] => Kind::Utf16Le,
+            _ => Kind::AsciiCompatible,
+        }
+    }
+
+    /// Transcodes all complete code units from `raw` into `utf8`. If `eof` is
+    /// `true`, incomplete code units are replaced with `U+FFFD`.
+    fn transcode(&mut self, eof: bool) {
+        let unit = match self.kind {
+            Kind::Utf16Le | Kind::Utf16Be => 2,
+            _ => 4,
+        };
+        let len = self.raw.len() - self.raw.len() % unit;
+        let mut chunk = [0; 4];
+
+        for i in (0..len).step_by(unit) {
+            let bytes = &self.raw[i..i + unit];
+            let ch = match self.kind {
+                Kind::Utf16Le | Kind::Utf16Be => {
+                    let u = if self.kind == Kind::Utf16Le {
+                        u16::from_le_bytes([bytes[0], bytes[1]])
+                    } else {
+                        u16::from_be_bytes([bytes[0], bytes[1]])
+                    };
+                    match (self.high_surrogate.take(), u) {
+                        (None, 0xD800..=0xDBFF) => {
+                            self.high_surrogate = Some(u);
+                            continue;
+                        }
+                        (Some(high), 0xDC00..=0xDFFF) => {
+                            let c = 0x10000 + ((high as u32 - 0xD800) << 10) + (u as u32 - 0xDC00);
+                            char::from_u32(c)
+                        }
+                        (Some(_), _) => {
+                            // Unpaired high surrogate, the current unit is processed again
+                            self.utf8.extend_from_slice(REPLACEMENT);
+                            match u {
+                                0xD800..=0xDBFF => {
+                                    self.high_surrogate = Some(u);
+                                    continue;
+                                }
+                                _ => char::from_u32(u as u32),
+                            }
+                        }
+                        (None, _) => char::from_u32(u as u32),
+                    }
+                }
+                _ => char::from_u32(if self.kind == Kind::Utf32Le {
+                    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+                } else {
+                    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+                }),
+            };
+            match ch {
+                Some(ch) => self
+                    .utf8
+                    .extend_from_slice(ch.encode_utf8(&mut chunk).as_bytes()),
+                None => self.utf8.extend_from_slice(REPLACEMENT),
+            }
+        }
+        self.raw.drain(..len);
+
+        if eof && (self.high_surrogate.take().is_some() || !self.raw.is_empty()) {
+            self.raw.clear();
+            self.utf8.extend_from_slice(REPLACEMENT);
+        }
+    }
+}
+
+/// A [`BufRead`] adapter over the underlying reader which yields UTF-8 bytes
+/// for UTF-16 and UTF-32 documents and passes other documents through.
+pub(crate) struct Utf8Source<'a, R> {
+    inner: &'a mut R,
+    state: &'a mut Transcoder,
+}
+
+impl<'a, R: BufRead> Utf8Source<'a, R> {
+    pub fn new(inner: &'a mut R, state: &'a mut Transcoder) -> Self {
+        Utf8Source { inner, state }
+    }
+
+    /// Reads the first four bytes of the document (or less, if the document is
+    /// shorter) and detects its encoding.
+    fn detect(&mut self) -> io::Result<()> {
+        while self.state.raw.len() < 4 {
+            let available = match self.inner.fill_buf() {
+                Ok([]) => break,
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let used = available.len().min(4 - self.state.raw.len());
+            self.state.raw.extend_from_slice(&available[..used]);
+            self.inner.consume(used);
+            self.state.consumed += used;
+        }
+        self.state.kind = Transcoder::detect(&self.state.raw);
+        if self.state.kind == Kind::AsciiCompatible {
+            // Give the bytes used for detection back to the caller
+            std::mem::swap(&mut self.state.raw, &mut self.state.utf8);
+            self.state.pos = 0;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, R: BufRead> Read for Utf8Source<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<'a, R: BufRead> BufRead for Utf8Source<'a, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.state.kind == Kind::Unknown {
+            self.detect()?;
+        }
+        if self.state.pos < self.state.utf8.len() {
+            return Ok(&self.state.utf8[self.state.pos..]);
+        }
+        if self.state.kind == Kind::AsciiCompatible {
+            return self.inner.fill_buf();
+        }
+
+        self.state.utf8.clear();
+        self.state.pos = 0;
+        while self.state.utf8.is_empty() {
+            let available = match self.inner.fill_buf() {
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let eof = available.is_empty();
+            let used = available.len();
+            self.state.raw.extend_from_slice(available);
+            self.inner.consume(used);
+            self.state.consumed += used;
+            self.state.transcode(eof);
+            if eof {
+                break;
+            }
+        }
+        Ok(&self.state.utf8)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if self.state.pos < self.state.utf8.len() {
+            self.state.pos += amt;
+        } else {
+            self.inner.consume(amt);
+            self.state.consumed += amt;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Transcoder, Utf8Source};
+    use pretty_assertions::assert_eq;
+    use std::io::{BufRead, BufReader, Read};
+
+    /// Reads all bytes through the `Utf8Source` using a tiny buffer to
+    /// check that incomplete code units are handled
+    fn transcode(input: &[u8]) -> String {
+        let mut inner = BufReader::with_capacity(3, input);
+        let mut state = Transcoder::new();
+        let mut result = Vec::new();
+        Utf8Source::new(&mut inner, &mut state)
+            .read_to_end(&mut result)
+            .unwrap();
+        String::from_utf8(result).unwrap()
+    }
+
+    fn encode_utf16(s: &str, le: bool) -> Vec<u8> {
+        s.encode_utf16()
+            .flat_map(|u| if le { u.to_le_bytes() } else { u.to_be_bytes() })
+            .collect()
+    }
+
+    fn encode_utf32(s: &str, le: bool) -> Vec<u8> {
+        s.chars()
+            .flat_map(|c| {
+                let c = c as u32;
+                if le {
+                    c.to_le_bytes()
+                } else {
+                    c.to_be_bytes()
+                }
+            })
+            .collect()
+    }
+
+    const XML: &str = "<?xml version='1.0'?><root attr='\u{1F600}'>текст</root>";
+    const WITH_BOM: &str = "\u{FEFF}<root attr='\u{1F600}'>текст</root>";
+
+    #[test]
+    fn utf8() {
+        assert_eq!(transcode(XML.as_bytes()), XML);
+        assert_eq!(transcode(WITH_BOM.as_bytes()), WITH_BOM);
+        assert_eq!(transcode(b"<"), "<");
+        assert_eq!(transcode(b""), "");
+    }
+
+    #[test]
+    fn utf16() {
+        assert_eq!(transcode(&encode_utf16(XML, true)), XML);
+        assert_eq!(transcode(&encode_utf16(XML, false)), XML);
+        assert_eq!(transcode(&encode_utf16(WITH_BOM, true)), WITH_BOM);
+        assert_eq!(transcode(&encode_utf16(WITH_BOM, false)), WITH_BOM);
+    }
+
+    #[test]
+    fn utf32() {
+        assert_eq!(transcode(&encode_utf32(XML, true)), XML);
+        assert_eq!(transcode(&encode_utf32(XML, false)), XML);
+        assert_eq!(transcode(&encode_utf32(WITH_BOM, true)), WITH_BOM);
+        assert_eq!(transcode(&encode_utf32(WITH_BOM, false)), WITH_BOM);
+    }
+
+    #[test]
+    fn malformed() {
+        // unpaired surrogates and truncated code unit
+        assert_eq!(
+            transcode(b"\xFF\xFE<\x00\x00\xD8a\x00\x00\xDC\x00"),
+            "\u{FEFF}<\u{FFFD}a\u{FFFD}\u{FFFD}"
+        );
+        // code point out of range
+        assert_eq!(
+            transcode(b"\x00\x00\xFE\xFF\x00\x11\x00\x00"),
+            "\u{FEFF}\u{FFFD}"
+        );
+    }
+
+    #[test]
+    fn fill_buf_returns_utf8() {
+        let input = encode_utf16(WITH_BOM, true);
+        let mut inner = input.as_slice();
+        let mut state = Transcoder::new();
+        let mut source = Utf8Source::new(&mut inner, &mut state);
+
+        assert_eq!(source.fill_buf().unwrap().len(), WITH_BOM.len());
+        source.consume(3);
+        assert_eq!(source.fill_buf().unwrap(), &WITH_BOM.as_bytes()[3..]);
+        assert!(state.is_transcoding());
+    }
+}
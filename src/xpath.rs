@@ -0,0 +1,855 @@
+//! Streaming matcher of a subset of [XPath] expressions, which finds elements
+//! and attributes in the events of the [`Reader`] or the [`NsReader`] as they
+//! are read, without building a tree of the document.
+//!
+//! The supported subset consists of absolute location paths with:
+//! - child (`/name`) and descendant (`//name`) steps;
+//! - name tests `name`, `prefix:name`, `prefix:*` and `*`;
+//! - attribute predicates `[@name]` and `[@name='value']`;
+//! - position predicates like `[2]`, which select the element by its position
+//!   among the children of the same parent, selected by the step;
+//! - an optional last attribute step, like `/@name`, which selects the
+//!   attribute of the matched elements.
+//!
+//! Predicates of a step are applied in order, so `item[@x][2]` selects the
+//! second `item` with the `x` attribute and `item[2][@x]` selects the second
+//! `item`, if it has the `x` attribute.
+//!
+//! # Namespaces
+//!
+//! Events fed with [`Matcher::feed()`] are matched by the names as written,
+//! including prefixes. Events fed with [`Matcher::feed_resolved()`] are matched
+//! by the namespace and the local name, and prefixes of the expression are
+//! resolved using the bindings [added] to the expression. The empty prefix
+//! binds the namespace of the unprefixed element names of the expression.
+//!
+//! # Examples
+//!
+//! ```
+//! # use pretty_assertions::assert_eq;
+//! use quick_xml::events::Event;
+//! use quick_xml::xpath::{Match, Matcher, XPath};
+//! use quick_xml::NsReader;
+//!
+//! let xml = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+//!     <entry><link rel="alternate" href="/1"/><link rel="edit" href="/1/edit"/></entry>
+//!     <entry><link rel="alternate" href="/2"/></entry>
+//! </feed>"#;
+//! let mut path = XPath::parse("/feed/entry/link[@rel='alternate']/@href").unwrap();
+//! path.bind("", "http://www.w3.org/2005/Atom");
+//! let mut matcher = Matcher::new(path);
+//!
+//! let mut reader = NsReader::from_str(xml);
+//! let mut hrefs = Vec::new();
+//! loop {
+//!     let event = reader.read_event_unbuffered().unwrap();
+//!     if let Event::Eof = event {
+//!         break;
+//!     }
+//!     if let Some(Match::Attribute(attr)) = matcher.feed_resolved(&event, reader.resolver()).unwrap() {
+//!         hrefs.push(attr.unescaped_value().unwrap().into_owned());
+//!     }
+//! }
+//! assert_eq!(hrefs, vec![b"/1".to_vec(), b"/2".to_vec()]);
+//! ```
+//!
+//! [XPath]: https://www.w3.org/TR/xpath-10/
+//! [`Reader`]: crate::Reader
+//! [`NsReader`]: crate::NsReader
+//! [added]: XPath::bind
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::events::attributes::Attribute;
+use crate::events::{BytesStart, Event};
+use crate::name::{NamespaceResolver, QName, ResolveResult};
+use crate::reader::is_whitespace;
+
+/// An error returned by [`XPath::parse()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum XPathError {
+    /// The expression ended while the parser expected the specified construct.
+    UnexpectedEnd(&'static str),
+    /// The parser expected the specified construct at the specified position,
+    /// but found something else.
+    Expected(&'static str, usize),
+}
+
+impl Display for XPathError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd(expected) => {
+                write!(f, "unexpected end of expression, expected {}", expected)
+            }
+            Self::Expected(expected, pos) => {
+                write!(f, "position {}: expected {}", pos, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for XPathError {}
+
+type Result<T> = std::result::Result<T, XPathError>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Axis {
+    /// `/name`
+    Child,
+    /// `//name`
+    Descendant,
+}
+
+/// A test of an element or an attribute name. `*` is represented by a test
+/// without a prefix and a local name
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct NameTest {
+    /// The prefix as written in the expression
+    prefix: Option<Vec<u8>>,
+    /// The local name, `None` for `*` and `prefix:*`
+    local: Option<Vec<u8>>,
+}
+
+impl NameTest {
+    fn is_any(&self) -> bool {
+        self.prefix.is_none() && self.local.is_none()
+    }
+
+    fn matches_local(&self, local: &[u8]) -> bool {
+        match &self.local {
+            Some(name) => name == local,
+            None => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Predicate {
+    /// `[@name]` or `[@name='value']`
+    Attribute {
+        name: NameTest,
+        value: Option<Vec<u8>>,
+    },
+    /// `[position]`. `counter` is the index of the counter of selected children
+    /// in a [`Frame`]
+    Position { position: usize, counter: usize },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Step {
+    axis: Axis,
+    name: NameTest,
+    predicates: Vec<Predicate>,
+}
+
+/// A compiled expression. See the [module documentation](self) for the
+/// supported syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XPath {
+    steps: Vec<Step>,
+    /// The name test of the last attribute step
+    attribute: Option<NameTest>,
+    /// Namespace bindings of prefixes used in the expression
+    bindings: Vec<(Vec<u8>, Vec<u8>)>,
+    /// The number of position predicates in all steps
+    counters: usize,
+}
+
+impl XPath {
+    /// Compiles the expression.
+    pub fn parse(expr: &str) -> std::result::Result<XPath, XPathError> {
+        Parser {
+            input: expr.as_bytes(),
+            pos: 0,
+        }
+        .path()
+    }
+
+    /// Binds the prefix of names in the expression to the namespace. The empty
+    /// prefix binds the namespace of unprefixed element names. Bindings are
+    /// used only by [`Matcher::feed_resolved()`].
+    pub fn bind(&mut self, prefix: &str, namespace: &str) -> &mut XPath {
+        let prefix = prefix.as_bytes();
+        self.bindings.retain(|(p, _)| p != prefix);
+        self.bindings
+            .push((prefix.to_vec(), namespace.as_bytes().to_vec()));
+        self
+    }
+}
+
+/// Checks that the byte can not be a part of a name
+#[inline]
+fn is_delimiter(b: u8) -> bool {
+    is_whitespace(b)
+        || matches!(
+            b,
+            b'/' | b'[' | b']' | b'@' | b'=' | b'"' | b'\'' | b'*' | b':' | b'(' | b')'
+        )
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn error(&self, expected: &'static str) -> XPathError {
+        if self.pos >= self.input.len() {
+            XPathError::UnexpectedEnd(expected)
+        } else {
+            XPathError::Expected(expected, self.pos)
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b) if is_whitespace(b)) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consumes `prefix` if the rest of the input starts with it
+    fn eat(&mut self, prefix: &[u8]) -> bool {
+        if self.input[self.pos..].starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, prefix: &'static str) -> Result<()> {
+        if self.eat(prefix.as_bytes()) {
+            Ok(())
+        } else {
+            Err(self.error(prefix))
+        }
+    }
+
+    /// Reads a name without a prefix
+    fn name(&mut self) -> Result<Vec<u8>> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b) if !is_delimiter(b)) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            Err(self.error("name"))
+        } else {
+            Ok(self.input[start..self.pos].to_vec())
+        }
+    }
+
+    fn name_test(&mut self) -> Result<NameTest> {
+        if self.eat(b"*") {
+            return Ok(NameTest {
+                prefix: None,
+                local: None,
+            });
+        }
+        let name = self.name()?;
+        if !self.eat(b":") {
+            return Ok(NameTest {
+                prefix: None,
+                local: Some(name),
+            });
+        }
+        let local = if self.eat(b"*") {
+            None
+        } else {
+            Some(self.name()?)
+        };
+        Ok(NameTest {
+            prefix: Some(name),
+            local,
+        })
+    }
+
+    /// Reads a quoted literal and returns its content without quotes
+    fn literal(&mut self) -> Result<Vec<u8>> {
+        let quote = match self.peek() {
+            Some(q @ b'"') | Some(q @ b'\'') => q,
+            _ => return Err(self.error("quoted literal")),
+        };
+        let start = self.pos + 1;
+        match memchr::memchr(quote, &self.input[start..]) {
+            Some(len) => {
+                self.pos = start + len + 1;
+                Ok(self.input[start..start + len].to_vec())
+            }
+            None => {
+                self.pos = self.input.len();
+                Err(self.error("closing quote"))
+            }
+        }
+    }
+
+    /// Reads a predicate after `[`
+    fn predicate(&mut self, counters: &mut usize) -> Result<Predicate> {
+        self.skip_whitespace();
+        let predicate = if self.eat(b"@") {
+            let name = self.name_test()?;
+            self.skip_whitespace();
+            let value = if self.eat(b"=") {
+                self.skip_whitespace();
+                Some(self.literal()?)
+            } else {
+                None
+            };
+            Predicate::Attribute { name, value }
+        } else {
+            let start = self.pos;
+            while matches!(self.peek(), Some(b'0'..=b'9')) {
+                self.pos += 1;
+            }
+            let position = std::str::from_utf8(&self.input[start..self.pos])
+                .ok()
+                .and_then(|p| p.parse().ok())
+                .filter(|p| *p > 0);
+            match position {
+                Some(position) => {
+                    *counters += 1;
+                    Predicate::Position {
+                        position,
+                        counter: *counters - 1,
+                    }
+                }
+                None => {
+                    self.pos = start;
+                    return Err(self.error("attribute or position"));
+                }
+            }
+        };
+        self.skip_whitespace();
+        self.expect("]")?;
+        Ok(predicate)
+    }
+
+    fn path(mut self) -> Result<XPath> {
+        let mut steps = Vec::new();
+        let mut attribute = None;
+        let mut counters = 0;
+        self.skip_whitespace();
+        loop {
+            let axis = if self.eat(b"//") {
+                Axis::Descendant
+            } else if self.eat(b"/") {
+                Axis::Child
+            } else {
+                return Err(self.error("`/`"));
+            };
+            if axis == Axis::Child && !steps.is_empty() && self.eat(b"@") {
+                attribute = Some(self.name_test()?);
+                break;
+            }
+            let name = self.name_test()?;
+            let mut predicates = Vec::new();
+            while self.eat(b"[") {
+                predicates.push(self.predicate(&mut counters)?);
+            }
+            steps.push(Step {
+                axis,
+                name,
+                predicates,
+            });
+            self.skip_whitespace();
+            if self.pos == self.input.len() {
+                break;
+            }
+        }
+        self.skip_whitespace();
+        if self.pos < self.input.len() {
+            return Err(self.error("end of expression"));
+        }
+        Ok(XPath {
+            steps,
+            attribute,
+            bindings: Vec::new(),
+            counters,
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A match of the expression, returned by [`Matcher::feed()`] and
+/// [`Matcher::feed_resolved()`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Match<'a> {
+    /// The element of the fed `Start` or `Empty` event is selected by the expression
+    Element(&'a BytesStart<'a>),
+    /// The attribute of the element of the fed `Start` or `Empty` event is
+    /// selected by the last step of the expression
+    Attribute(Attribute<'a>),
+}
+
+/// The rules of names comparison, see the [module documentation](self)
+#[derive(Clone, Copy)]
+struct Names<'a> {
+    resolver: Option<(&'a NamespaceResolver, &'a [u8])>,
+    bindings: &'a [(Vec<u8>, Vec<u8>)],
+}
+
+impl<'a> Names<'a> {
+    fn binding(&self, prefix: &[u8]) -> Option<&'a [u8]> {
+        self.bindings
+            .iter()
+            .find(|(p, _)| p == prefix)
+            .map(|(_, ns)| ns.as_slice())
+    }
+
+    fn matches(&self, test: &NameTest, name: QName, element: bool) -> bool {
+        if test.is_any() {
+            return true;
+        }
+        let (resolver, buffer) = match self.resolver {
+            Some(resolver) => resolver,
+            None => {
+                let (local, prefix) = name.decompose();
+                return test.prefix.as_deref() == prefix.as_ref().map(|p| p.as_ref())
+                    && test.matches_local(local.as_ref());
+            }
+        };
+        let (namespace, local) = resolver.resolve(name, buffer, element);
+        let namespace = match namespace {
+            ResolveResult::Bound(ns) => Some(ns.into_inner()),
+            ResolveResult::Unbound => None,
+            ResolveResult::Unknown(_) => return false,
+        };
+        let expected = match &test.prefix {
+            Some(prefix) => match self.binding(prefix) {
+                Some(ns) => Some(ns),
+                None => return false,
+            },
+            None if element => self.binding(b""),
+            None => None,
+        };
+        expected.filter(|ns| !ns.is_empty()) == namespace && test.matches_local(local.as_ref())
+    }
+
+    /// Returns the first attribute of the element, that matches the test.
+    /// Namespace declarations are not attributes
+    fn attribute<'e>(
+        &self,
+        start: &'e BytesStart,
+        test: &NameTest,
+    ) -> crate::Result<Option<Attribute<'e>>> {
+        for attr in start.attributes() {
+            let attr = attr?;
+            if attr.key.as_namespace_binding().is_none() && self.matches(test, attr.key, false) {
+                return Ok(Some(attr));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Checks that the element is selected by the step. `counters` are the
+    /// counters of the element parent
+    fn step(&self, step: &Step, start: &BytesStart, counters: &mut [usize]) -> crate::Result<bool> {
+        if !self.matches(&step.name, start.name(), true) {
+            return Ok(false);
+        }
+        for predicate in &step.predicates {
+            let selected = match predicate {
+                Predicate::Attribute { name, value } => match self.attribute(start, name)? {
+                    Some(attr) => match value {
+                        Some(value) => attr.unescaped_value()?.as_ref() == value.as_slice(),
+                        None => true,
+                    },
+                    None => false,
+                },
+                Predicate::Position { position, counter } => {
+                    counters[*counter] += 1;
+                    counters[*counter] == *position
+                }
+            };
+            if !selected {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// The matching state of the document or an opened element
+#[derive(Clone, Debug, Default)]
+struct Frame {
+    /// Indexes of steps, that are tested against children of this node
+    states: Vec<usize>,
+    /// Counters of children, selected by steps with position predicates
+    counters: Vec<usize>,
+    /// `true` if the element is selected by the expression
+    matched: bool,
+}
+
+/// Matches the events of a document against the [expression](XPath).
+///
+/// Feed all events of the document in order, starting from the first one, to
+/// the matcher. Each `Start` and `Empty` event, selected by the expression, is
+/// reported as a [`Match`].
+#[derive(Clone, Debug)]
+pub struct Matcher {
+    path: XPath,
+    /// Frames of the document and all opened elements
+    frames: Vec<Frame>,
+    /// The number of opened elements selected by the expression
+    matched: usize,
+}
+
+impl Matcher {
+    /// Creates a matcher at the start of a document.
+    pub fn new(path: XPath) -> Self {
+        let mut matcher = Self {
+            path,
+            frames: Vec::new(),
+            matched: 0,
+        };
+        matcher.reset();
+        matcher
+    }
+
+    /// Returns the expression of this matcher.
+    pub fn path(&self) -> &XPath {
+        &self.path
+    }
+
+    /// Resets the matcher to the start of a new document.
+    pub fn reset(&mut self) {
+        self.frames.clear();
+        self.frames.push(Frame {
+            states: vec![0],
+            counters: vec![0; self.path.counters],
+            matched: false,
+        });
+        self.matched = 0;
+    }
+
+    /// Returns `true` if one of the opened elements is selected by the
+    /// expression, so the last fed event is a part of the selected element.
+    pub fn is_inside_match(&self) -> bool {
+        self.matched > 0
+    }
+
+    /// Feeds the next event of the document, comparing names as written.
+    ///
+    /// Returns an error if the attributes of the element, that are tested by
+    /// the expression, are malformed.
+    pub fn feed<'e>(&mut self, event: &'e Event) -> crate::Result<Option<Match<'e>>> {
+        self.handle(event, None)
+    }
+
+    /// Feeds the next event of the document, comparing names by namespace.
+    /// `resolver` is the resolver of namespaces in scope of the event, as
+    /// returned by [`NsReader::resolver()`].
+    ///
+    /// Returns an error if the attributes of the element, that are tested by
+    /// the expression, are malformed.
+    ///
+    /// [`NsReader::resolver()`]: crate::NsReader::resolver
+    pub fn feed_resolved<'e>(
+        &mut self,
+        event: &'e Event,
+        resolver: (&NamespaceResolver, &[u8]),
+    ) -> crate::Result<Option<Match<'e>>> {
+        self.handle(event, Some(resolver))
+    }
+
+    fn handle<'e>(
+        &mut self,
+        event: &'e Event,
+        resolver: Option<(&NamespaceResolver, &[u8])>,
+    ) -> crate::Result<Option<Match<'e>>> {
+        match event {
+            Event::Start(e) => self.open(e, resolver, true),
+            Event::Empty(e) => self.open(e, resolver, false),
+            Event::End(_) => {
+                if self.frames.len() > 1 && self.frames.pop().unwrap().matched {
+                    self.matched -= 1;
+                }
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn open<'e>(
+        &mut self,
+        start: &'e BytesStart,
+        resolver: Option<(&NamespaceResolver, &[u8])>,
+        push: bool,
+    ) -> crate::Result<Option<Match<'e>>> {
+        let names = Names {
+            resolver,
+            bindings: &self.path.bindings,
+        };
+        let steps = &self.path.steps;
+        let parent = self.frames.last_mut().unwrap();
+
+        let mut states = Vec::new();
+        let mut matched = false;
+        for &k in &parent.states {
+            let step = &steps[k];
+            if step.axis == Axis::Descendant && !states.contains(&k) {
+                states.push(k);
+            }
+            if names.step(step, start, &mut parent.counters)? {
+                if k + 1 == steps.len() {
+                    matched = true;
+                } else if !states.contains(&(k + 1)) {
+                    states.push(k + 1);
+                }
+            }
+        }
+
+        let result = match &self.path.attribute {
+            _ if !matched => None,
+            None => Some(Match::Element(start)),
+            Some(test) => names.attribute(start, test)?.map(Match::Attribute),
+        };
+        if push {
+            let counters = if states.is_empty() {
+                Vec::new()
+            } else {
+                vec![0; self.path.counters]
+            };
+            if matched {
+                self.matched += 1;
+            }
+            self.frames.push(Frame {
+                states,
+                counters,
+                matched,
+            });
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{NsReader, Reader};
+    use pretty_assertions::assert_eq;
+
+    /// Returns the content of the selected start tags or the values of the
+    /// selected attributes
+    fn matches(expr: &str, xml: &str) -> Vec<String> {
+        let mut matcher = Matcher::new(XPath::parse(expr).unwrap());
+        let mut reader = Reader::from_str(xml);
+        let mut found = Vec::new();
+        loop {
+            let event = reader.read_event_unbuffered().unwrap();
+            if let Event::Eof = event {
+                break;
+            }
+            let bytes = match matcher.feed(&event).unwrap() {
+                Some(Match::Element(e)) => e.to_vec(),
+                Some(Match::Attribute(a)) => a.value.to_vec(),
+                None => continue,
+            };
+            found.push(String::from_utf8(bytes).unwrap());
+        }
+        found
+    }
+
+    fn name(prefix: Option<&str>, local: Option<&str>) -> NameTest {
+        NameTest {
+            prefix: prefix.map(|p| p.as_bytes().to_vec()),
+            local: local.map(|l| l.as_bytes().to_vec()),
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let path = XPath::parse(" //p:a/*[@x = 'y'][2]/q:*[ 1 ][@*]/@href ").unwrap();
+        assert_eq!(
+            path.steps,
+            vec![
+                Step {
+                    axis: Axis::Descendant,
+                    name: name(Some("p"), Some("a")),
+                    predicates: vec![],
+                },
+                Step {
+                    axis: Axis::Child,
+                    name: name(None, None),
+                    predicates: vec![
+                        Predicate::Attribute {
+                            name: name(None, Some("x")),
+                            value: Some(b"y".to_vec()),
+                        },
+                        Predicate::Position {
+                            position: 2,
+                            counter: 0,
+                        },
+                    ],
+                },
+                Step {
+                    axis: Axis::Child,
+                    name: name(Some("q"), None),
+                    predicates: vec![
+                        Predicate::Position {
+                            position: 1,
+                            counter: 1,
+                        },
+                        Predicate::Attribute {
+                            name: name(None, None),
+                            value: None,
+                        },
+                    ],
+                },
+            ]
+        );
+        assert_eq!(path.attribute, Some(name(None, Some("href"))));
+        assert_eq!(path.counters, 2);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |expr| XPath::parse(expr).unwrap_err();
+        assert_eq!(error(""), XPathError::UnexpectedEnd("`/`"));
+        assert_eq!(error("a"), XPathError::Expected("`/`", 0));
+        assert_eq!(error("/"), XPathError::UnexpectedEnd("name"));
+        assert_eq!(error("/@a"), XPathError::Expected("name", 1));
+        assert_eq!(error("/a//@b"), XPathError::Expected("name", 4));
+        assert_eq!(
+            error("/a/@b/c"),
+            XPathError::Expected("end of expression", 5)
+        );
+        assert_eq!(
+            error("/a[0]"),
+            XPathError::Expected("attribute or position", 3)
+        );
+        assert_eq!(
+            error("/a[@b='c]"),
+            XPathError::UnexpectedEnd("closing quote")
+        );
+        assert_eq!(error("/a[@b"), XPathError::UnexpectedEnd("]"));
+        assert_eq!(error("/a b"), XPathError::Expected("`/`", 3));
+    }
+
+    #[test]
+    fn steps() {
+        let xml = "<a><b id='1'><b id='2'/></b><c><b id='3'/></c></a>";
+        assert_eq!(matches("/a/b", xml), vec!["b id='1'"]);
+        assert_eq!(
+            matches("//b", xml),
+            vec!["b id='1'", "b id='2'", "b id='3'"]
+        );
+        assert_eq!(
+            matches("/a//b", xml),
+            vec!["b id='1'", "b id='2'", "b id='3'"]
+        );
+        assert_eq!(matches("//b/b", xml), vec!["b id='2'"]);
+        assert_eq!(matches("/a/*/b", xml), vec!["b id='2'", "b id='3'"]);
+        assert_eq!(matches("/b", xml), Vec::<String>::new());
+        assert_eq!(matches("/a/b/@id", xml), vec!["1"]);
+        assert_eq!(matches("//c/*/@id", xml), vec!["3"]);
+        assert_eq!(matches("//b/@x", xml), Vec::<String>::new());
+    }
+
+    #[test]
+    fn predicates() {
+        let xml = r#"<a>
+            <b x="1"/><b/><b x="&amp;"/>
+            <c><b/><b x="2"/></c>
+        </a>"#;
+        assert_eq!(matches("//b[@x]/@x", xml), vec!["1", "&amp;", "2"]);
+        assert_eq!(matches("//b[@x='&']/@x", xml), vec!["&amp;"]);
+        assert_eq!(matches("//b[2]", xml), vec!["b", "b x=\"2\""]);
+        assert_eq!(matches("/a/b[@x][2]/@x", xml), vec!["&amp;"]);
+        assert_eq!(matches("/a/b[2][@x]", xml), Vec::<String>::new());
+        assert_eq!(matches("/a/*[4]", xml), vec!["c"]);
+        assert_eq!(matches("/a[1]/c[1]/b[2]/@x", xml), vec!["2"]);
+    }
+
+    /// Without resolver names are compared as written
+    #[test]
+    fn prefixes() {
+        let xml = "<p:a xmlns:p='urn:p'><p:b/><q:b xmlns:q='urn:p'/><b p:x='1'/></p:a>";
+        assert_eq!(matches("/p:a/p:b", xml), vec!["p:b"]);
+        assert_eq!(matches("/p:a/p:*", xml), vec!["p:b"]);
+        assert_eq!(matches("/p:a/b", xml), vec!["b p:x='1'"]);
+        assert_eq!(matches("//b/@x", xml), Vec::<String>::new());
+        assert_eq!(matches("/*/*/@p:x", xml), vec!["1"]);
+        // Namespace declarations are not attributes
+        assert_eq!(matches("/*/@*", xml), Vec::<String>::new());
+    }
+
+    #[test]
+    fn namespaces() {
+        let xml = r#"<root xmlns="urn:default" xmlns:p="urn:p">
+            <item/>
+            <p:item p:attr="1" attr="2"/>
+            <item xmlns="urn:p"/>
+            <item xmlns=""/>
+        </root>"#;
+        let select = |expr: &str, bindings: &[(&str, &str)]| {
+            let mut path = XPath::parse(expr).unwrap();
+            for (prefix, namespace) in bindings {
+                path.bind(prefix, namespace);
+            }
+            let mut matcher = Matcher::new(path);
+            let mut reader = NsReader::from_str(xml);
+            reader.trim_text(true);
+            let mut found = Vec::new();
+            loop {
+                let event = reader.read_event_unbuffered().unwrap();
+                if let Event::Eof = event {
+                    break;
+                }
+                let bytes = match matcher.feed_resolved(&event, reader.resolver()).unwrap() {
+                    Some(Match::Element(e)) => e.to_vec(),
+                    Some(Match::Attribute(a)) => a.value.to_vec(),
+                    None => continue,
+                };
+                found.push(String::from_utf8(bytes).unwrap());
+            }
+            found
+        };
+        let default = ("", "urn:default");
+        let x = ("x", "urn:p");
+
+        let items = [
+            "item",
+            r#"p:item p:attr="1" attr="2""#,
+            r#"item xmlns="urn:p""#,
+            r#"item xmlns="""#,
+        ];
+
+        assert_eq!(select("/root/*", &[default]), items);
+        assert_eq!(select("/root/item", &[default]), items[..1]);
+        assert_eq!(select("/*/x:item", &[x]), items[1..3]);
+        assert_eq!(select("/*/x:*", &[x]), items[1..3]);
+        // Unprefixed names without the default binding are in no namespace
+        assert_eq!(select("/*/item", &[]), items[3..]);
+        // Unbound prefixes do not match
+        assert_eq!(select("/*/p:item", &[]), Vec::<String>::new());
+        // Unprefixed attributes are never in the default namespace
+        assert_eq!(select("/*/*/@x:attr", &[x]), vec!["1"]);
+        assert_eq!(select("/*/*/@attr", &[default]), vec!["2"]);
+    }
+
+    #[test]
+    fn inside_match() {
+        let mut matcher = Matcher::new(XPath::parse("//b").unwrap());
+        let mut reader = Reader::from_str("<a><b><c/>text</b><b/></a>");
+        reader.trim_text(true);
+        let mut inside = Vec::new();
+        loop {
+            let event = reader.read_event_unbuffered().unwrap();
+            if let Event::Eof = event {
+                break;
+            }
+            matcher.feed(&event).unwrap();
+            inside.push(matcher.is_inside_match());
+        }
+        // <a>, <b>, <c/>, text, </b>, <b/>, </a>
+        assert_eq!(inside, vec![false, true, true, true, false, false, false]);
+
+        matcher.reset();
+        assert_eq!(matcher.is_inside_match(), false);
+    }
+}
@@ -0,0 +1,106 @@
+use pretty_assertions::assert_eq;
+use quick_xml::events::{BytesStart, Event::*};
+use quick_xml::{Error, Reader};
+use tokio::io::BufReader;
+
+#[tokio::test]
+async fn test_sample() {
+    let src = include_str!("sample_rss.xml");
+    let mut reader = Reader::from_reader(src.as_bytes());
+    let mut buf = Vec::new();
+    let mut count = 0;
+    loop {
+        match reader.read_event_async(&mut buf).await.unwrap() {
+            Start(_) => count += 1,
+            Decl(e) => assert_eq!(e.version().unwrap().as_ref(), b"1.0"),
+            Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+    assert_eq!(count, 1247);
+}
+
+/// Checks that asynchronous reader produces the same events as the synchronous one
+#[tokio::test]
+async fn same_as_sync() {
+    let src = include_str!("documents/sample_ns.xml");
+
+    let mut sync_reader = Reader::from_reader(src.as_bytes());
+    sync_reader.trim_text(true);
+    // Use small internal buffer to be sure that markup will be split between chunks
+    let mut async_reader = Reader::from_reader(BufReader::with_capacity(3, src.as_bytes()));
+    async_reader.trim_text(true);
+
+    let mut sync_buf = Vec::new();
+    let mut async_buf = Vec::new();
+    loop {
+        let expected = sync_reader.read_event(&mut sync_buf).unwrap();
+        let actual = async_reader.read_event_async(&mut async_buf).await.unwrap();
+        assert_eq!(actual, expected);
+        assert_eq!(
+            async_reader.buffer_position(),
+            sync_reader.buffer_position()
+        );
+        if expected == Eof {
+            break;
+        }
+        sync_buf.clear();
+        async_buf.clear();
+    }
+}
+
+#[tokio::test]
+async fn read_to_end() {
+    let mut reader = Reader::from_reader(BufReader::new(
+        "<outer><inner><outer/><outer></outer></inner></outer><next/>".as_bytes(),
+    ));
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+
+    assert_eq!(
+        reader.read_event_async(&mut buf).await.unwrap(),
+        Start(BytesStart::borrowed_name(b"outer"))
+    );
+    reader.read_to_end_async("outer", &mut buf).await.unwrap();
+    assert_eq!(
+        reader.read_event_async(&mut buf).await.unwrap(),
+        Empty(BytesStart::borrowed_name(b"next"))
+    );
+    assert_eq!(reader.read_event_async(&mut buf).await.unwrap(), Eof);
+}
+
+#[tokio::test]
+async fn read_to_end_eof() {
+    let mut reader = Reader::from_reader("<outer><inner>".as_bytes());
+    let mut buf = Vec::new();
+
+    reader.read_event_async(&mut buf).await.unwrap();
+    match reader.read_to_end_async("outer", &mut buf).await {
+        Err(Error::UnexpectedEof(_, _)) => {}
+        x => panic!("Expected `Err(UnexpectedEof(_, _))`, but got `{:?}`", x),
+    }
+}
+
+#[tokio::test]
+async fn mismatched_end() {
+    let mut reader = Reader::from_reader("<a></b>".as_bytes());
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+
+    reader.read_event_async(&mut buf).await.unwrap();
+    match reader.read_event_async(&mut buf).await {
+        Err(Error::EndEventMismatch {
+            expected,
+            found,
+            span,
+        }) => {
+            assert_eq!(expected, "a");
+            assert_eq!(found, "b");
+            assert_eq!(span, 3..7);
+        }
+        x => panic!("Expected `Err(EndEventMismatch)`, but got `{:?}`", x),
+    }
+    // After an error the reader always returns `Eof`
+    assert_eq!(reader.read_event_async(&mut buf).await.unwrap(), Eof);
+}
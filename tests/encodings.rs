@@ -0,0 +1,145 @@
+use std::io::BufReader;
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use pretty_assertions::assert_eq;
+
+/// Document in UTF-8 which is encoded into other encodings by the tests
+const XML: &str = r#"<?xml version="1.0" encoding="UTF-16"?>
+<root attr="значение">
+    <!-- 😀 -->
+    <child>текст &amp; 😀</child>
+    <![CDATA[<данные>]]>
+    <empty/>
+</root>"#;
+
+fn encode_utf16(s: &str, le: bool) -> Vec<u8> {
+    s.encode_utf16()
+        .flat_map(|u| if le { u.to_le_bytes() } else { u.to_be_bytes() })
+        .collect()
+}
+
+fn encode_utf32(s: &str, le: bool) -> Vec<u8> {
+    s.chars()
+        .flat_map(|c| {
+            let c = c as u32;
+            if le {
+                c.to_le_bytes()
+            } else {
+                c.to_be_bytes()
+            }
+        })
+        .collect()
+}
+
+/// Reads all events from the reader, converting them into owned events
+fn events(input: &[u8]) -> Vec<Event<'static>> {
+    let mut reader = Reader::from_reader(BufReader::new(input));
+    let mut buf = Vec::new();
+    let mut events = Vec::new();
+    loop {
+        match reader.read_event(&mut buf).unwrap() {
+            Event::Eof => break,
+            e => events.push(e.into_owned()),
+        }
+        buf.clear();
+    }
+    events
+}
+
+mod utf16 {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn le() {
+        assert_eq!(events(&encode_utf16(XML, true)), events(XML.as_bytes()));
+    }
+
+    #[test]
+    fn be() {
+        assert_eq!(events(&encode_utf16(XML, false)), events(XML.as_bytes()));
+    }
+
+    #[test]
+    fn le_with_bom() {
+        let xml = format!("\u{FEFF}{}", XML);
+        assert_eq!(events(&encode_utf16(&xml, true)), events(xml.as_bytes()));
+    }
+
+    #[test]
+    fn be_with_bom() {
+        let xml = format!("\u{FEFF}{}", XML);
+        assert_eq!(events(&encode_utf16(&xml, false)), events(xml.as_bytes()));
+    }
+}
+
+mod utf32 {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn le() {
+        assert_eq!(events(&encode_utf32(XML, true)), events(XML.as_bytes()));
+    }
+
+    #[test]
+    fn be() {
+        assert_eq!(events(&encode_utf32(XML, false)), events(XML.as_bytes()));
+    }
+
+    #[test]
+    fn le_with_bom() {
+        let xml = format!("\u{FEFF}{}", XML);
+        assert_eq!(events(&encode_utf32(&xml, true)), events(xml.as_bytes()));
+    }
+
+    #[test]
+    fn be_with_bom() {
+        let xml = format!("\u{FEFF}{}", XML);
+        assert_eq!(events(&encode_utf32(&xml, false)), events(xml.as_bytes()));
+    }
+}
+
+/// Declared encoding should not be used for decoding of transcoded documents
+#[test]
+fn decode_ignores_declaration() {
+    let input = encode_utf16(XML, true);
+    let mut reader = Reader::from_reader(input.as_slice());
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+
+    match reader.read_event(&mut buf).unwrap() {
+        Event::Decl(e) => assert_eq!(e.encoding().unwrap().unwrap().as_ref(), b"UTF-16"),
+        e => panic!("Expected `Decl`, but got `{:?}`", e),
+    }
+    assert_eq!(reader.encoding(), encoding_rs::UTF_8);
+
+    match reader.read_event(&mut buf).unwrap() {
+        Event::Start(e) => {
+            let attr = e.attributes().next().unwrap().unwrap();
+            assert_eq!(attr.unescape_and_decode_value(&reader).unwrap(), "значение");
+        }
+        e => panic!("Expected `Start`, but got `{:?}`", e),
+    }
+}
+
+#[test]
+fn from_file() {
+    let mut reader = Reader::from_file("tests/documents/utf16le.xml").unwrap();
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut names = Vec::new();
+    loop {
+        match reader.read_event(&mut buf).unwrap() {
+            Event::Start(e) | Event::Empty(e) => {
+                names.push(reader.decode(e.name().as_ref()).into_owned())
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    assert_eq!(names, vec!["project"]);
+}
@@ -375,3 +375,139 @@ fn reserved_name() {
         ),
     }
 }
+
+/// Tests of the `NsReader` that owns the buffer with namespace bindings
+mod ns_reader {
+    use pretty_assertions::assert_eq;
+    use quick_xml::events::Event::*;
+    use quick_xml::name::ResolveResult::*;
+    use quick_xml::name::{Namespace, QName};
+    use quick_xml::NsReader;
+
+    #[test]
+    fn elements() {
+        let mut r =
+            NsReader::from_str("<a xmlns='www1' xmlns:p='www2'><p:b><c xmlns=''/></p:b><d/></a>");
+        r.trim_text(true);
+
+        let mut buf = Vec::new();
+        let mut events = Vec::new();
+        loop {
+            match r.read_resolved_event(&mut buf).unwrap() {
+                (_, Eof) => break,
+                (Bound(ns), _) => events.push(Some(ns.as_ref().to_vec())),
+                (ns, _) => {
+                    assert_eq!(ns, Unbound);
+                    events.push(None);
+                }
+            }
+            buf.clear();
+        }
+        assert_eq!(
+            events,
+            vec![
+                Some(b"www1".to_vec()), // <a>
+                Some(b"www2".to_vec()), // <p:b>
+                None,                   // <c/>
+                Some(b"www2".to_vec()), // </p:b>
+                Some(b"www1".to_vec()), // <d/>
+                Some(b"www1".to_vec()), // </a>
+            ]
+        );
+    }
+
+    /// Namespaces declared on an empty element are in scope until the next event
+    #[test]
+    fn empty_scope() {
+        let mut r = NsReader::from_str("<a><b xmlns:x='www1' x:attr='1'/><x:c/></a>");
+        r.trim_text(true);
+
+        assert!(matches!(r.read_event_unbuffered(), Ok(Start(_))));
+        match r.read_event_unbuffered() {
+            Ok(Empty(e)) => {
+                let attrs = r
+                    .resolve_attributes(e.attributes())
+                    .map(|a| {
+                        let (ns, a) = a.unwrap();
+                        (ns, a.key)
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    attrs,
+                    vec![
+                        (Unknown(b"xmlns".to_vec()), QName(b"xmlns:x")),
+                        (Bound(Namespace(b"www1")), QName(b"x:attr")),
+                    ]
+                );
+            }
+            e => panic!("Expected Empty event, got {:?}", e),
+        }
+        match r.read_resolved_event_unbuffered() {
+            Ok((ns, Empty(e))) => {
+                assert_eq!(ns, Unknown(b"x".to_vec()));
+                assert_eq!(e.name(), QName(b"x:c"));
+            }
+            e => panic!("Expected Empty event, got {:?}", e),
+        }
+    }
+
+    /// Unprefixed attributes are not in the default namespace
+    #[test]
+    fn attributes() {
+        let mut r = NsReader::from_str("<a xmlns='www1' xmlns:p='www2' b='1' p:c='2'/>");
+        r.trim_text(true);
+
+        match r.read_event_unbuffered() {
+            Ok(Empty(e)) => {
+                assert_eq!(r.resolve_element(e.name()).0, Bound(Namespace(b"www1")));
+                assert_eq!(r.resolve_attribute(QName(b"b")).0, Unbound);
+                assert_eq!(
+                    r.resolve_attribute(QName(b"p:c")),
+                    (Bound(Namespace(b"www2")), QName(b"p:c").local_name())
+                );
+            }
+            e => panic!("Expected Empty event, got {:?}", e),
+        }
+    }
+
+    /// Namespaces declared on skipped elements are not in scope after skipping
+    #[test]
+    fn read_to_end() {
+        let mut r =
+            NsReader::from_str("<a xmlns:x='www1'><x:b xmlns:x='www2'><x:c/></x:b><x:d/></a>");
+        r.trim_text(true);
+        let mut buf = Vec::new();
+
+        assert!(matches!(r.read_event(&mut buf), Ok(Start(_))));
+        match r.read_resolved_event(&mut buf) {
+            Ok((ns, Start(_))) => assert_eq!(ns, Bound(Namespace(b"www2"))),
+            e => panic!("Expected Start event, got {:?}", e),
+        }
+        r.read_to_end("x:b", &mut buf).unwrap();
+        match r.read_resolved_event(&mut buf) {
+            Ok((ns, Empty(_))) => assert_eq!(ns, Bound(Namespace(b"www1"))),
+            e => panic!("Expected Empty event, got {:?}", e),
+        }
+        match r.read_resolved_event(&mut buf) {
+            Ok((ns, End(_))) => assert_eq!(ns, Unbound),
+            e => panic!("Expected End event, got {:?}", e),
+        }
+    }
+
+    /// Namespaces declared on elements, which content was read, are not in
+    /// scope after reading
+    #[test]
+    fn read_inner_xml() {
+        let mut r =
+            NsReader::from_str("<a xmlns:x='www1'><x:b xmlns:x='www2'><x:c/></x:b><x:d/></a>");
+        r.trim_text(true);
+
+        assert!(matches!(r.read_event_unbuffered(), Ok(Start(_))));
+        assert!(matches!(r.read_event_unbuffered(), Ok(Start(_))));
+        assert_eq!(r.read_inner_xml_unbuffered("x:b").unwrap(), b"<x:c/>");
+        match r.read_resolved_event_unbuffered() {
+            Ok((ns, Empty(_))) => assert_eq!(ns, Bound(Namespace(b"www1"))),
+            e => panic!("Expected Empty event, got {:?}", e),
+        }
+    }
+}
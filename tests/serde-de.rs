@@ -225,6 +225,52 @@ fn ignored_any() {
 
 /// Tests for trivial XML documents: empty or contains only primitive type
 /// on a top level; all of them should be considered invalid
+/// Entities declared in the DTD should be expanded in text and attribute values
+#[test]
+fn dtd_entities() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Root {
+        attr: String,
+        element: String,
+    }
+
+    let data: Root = from_str(
+        r#"<!DOCTYPE root [
+            <!ENTITY name "World">
+            <!ENTITY greeting "Hello, &name;!">
+        ]>
+        <root attr="&greeting;"><element>&lt;&greeting;&gt;</element></root>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        data,
+        Root {
+            attr: "Hello, World!".into(),
+            element: "<Hello, World!>".into(),
+        }
+    );
+}
+
+/// Text mixed with CDATA sections and comments should be deserialized as one string
+#[test]
+fn mixed_cdata() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Root {
+        element: String,
+    }
+
+    let data: Root = from_str(
+        r#"<root><element>a &lt; b<![CDATA[ && <c>]]><!-- comment -->&amp; d</element></root>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        data,
+        Root {
+            element: "a < b && <c>& d".into(),
+        }
+    );
+}
+
 mod trivial {
     use super::*;
 
@@ -569,9 +615,10 @@ mod seq {
             assert_eq!(data, vec![(), (), ()]);
         }
 
+        /// Consecutive text and CDATA are merged into one item
         #[test]
         fn mixed_content() {
-            from_str::<[(); 3]>(
+            from_str::<[(); 2]>(
                 r#"
                 <element/>
                 text
@@ -588,7 +635,7 @@ mod seq {
                 "#,
             )
             .unwrap();
-            assert_eq!(data, vec![(), (), ()]);
+            assert_eq!(data, vec![(), ()]);
         }
     }
 
@@ -1728,12 +1775,13 @@ mod seq {
                 .unwrap_err();
             }
 
+            /// Consecutive text and CDATA are merged into one item
             #[test]
             fn mixed_content() {
                 #[derive(Debug, PartialEq, Deserialize)]
                 struct List {
                     #[serde(rename = "$value")]
-                    item: [(); 3],
+                    item: [(); 2],
                 }
 
                 from_str::<List>(
@@ -2416,6 +2464,7 @@ mod seq {
                 );
             }
 
+            /// Consecutive text and CDATA are merged into one item
             #[test]
             fn mixed_content() {
                 #[derive(Debug, PartialEq, Deserialize)]
@@ -2435,12 +2484,7 @@ mod seq {
                 )
                 .unwrap();
 
-                assert_eq!(
-                    data,
-                    List {
-                        item: vec![(), (), ()],
-                    }
-                );
+                assert_eq!(data, List { item: vec![(), ()] });
             }
 
             // There cannot be unknown items, because any tag name is accepted
@@ -137,9 +137,10 @@ fn encoded_characters() {
 // }
 
 #[test]
-// FIXME: Trips on the first byte-order-mark byte
+// FIXME: The byte-order-mark is reported as a text event
 // Expected: StartDocument(1.0, utf-16)
-// Found: InvalidUtf8([255, 254]; invalid utf-8 sequence of 1 bytes from index 0)
+// Found: Characters()
+// Without the `encoding` feature the UTF-16 document cannot be read at all
 #[ignore]
 fn sample_5_short() {
     test_bytes(
@@ -229,7 +230,7 @@ fn issue_83_duplicate_attributes() {
         "
             |StartElement(hello)
             |1:30 EmptyElement(some-tag, attr-error: \
-                  position 16: duplicated attribute, previous declaration at position 9)
+                  position 24: duplicated attribute, previous declaration at position 17)
             |EndElement(hello)
             |EndDocument
         ",